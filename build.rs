@@ -0,0 +1,17 @@
+fn main() {
+    // Regenerates the C header for the `ffi` feature; other builds have
+    // nothing to do.
+    #[cfg(feature = "ffi")]
+    {
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+        let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir)).expect("valid cbindgen.toml");
+        cbindgen::Builder::new()
+            .with_src(format!("{}/src/ffi.rs", crate_dir))
+            .with_config(config)
+            .generate()
+            .expect("C header generation failed")
+            .write_to_file(format!("{}/include/twenty_forty_eight.h", crate_dir));
+    }
+}
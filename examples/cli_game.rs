@@ -1,3 +1,4 @@
+use twenty_forty_eight::utils::render;
 use twenty_forty_eight::{GameBoard, Direction};
 
 fn main() {
@@ -7,7 +8,7 @@ fn main() {
     let mut game = GameBoard::new();
     
     println!("Initial board:");
-    print_board(&game.get_board());
+    println!("{}", render::unicode(&game.get_board()));
     
     // Play a few moves
     let moves = [Direction::Right, Direction::Down, Direction::Left, Direction::Up];
@@ -15,12 +16,13 @@ fn main() {
     for (i, &direction) in moves.iter().enumerate() {
         println!("\nMove {}: {:?}", i + 1, direction);
         
-        if game.move_tiles(direction) {
-            game.add_random_tile_self();
-            print_board(&game.get_board());
-            println!("Score: {}, Max tile: {}", game.get_score(), game.get_max_tile());
-        } else {
-            println!("Invalid move!");
+        match game.play_move(direction) {
+            Ok(result) => {
+                println!("{}", render::unicode(&game.get_board()));
+                println!("Merges: {}, +{}", result.merged.len(), result.score_gained);
+                println!("Score: {}, Max tile: {}", game.get_score(), game.get_max_tile());
+            }
+            Err(err) => println!("Invalid move: {}", err),
         }
     }
     
@@ -31,8 +33,3 @@ fn main() {
     println!("Game over: {}", game.is_game_over());
 }
 
-fn print_board(board: &[[u32; 4]; 4]) {
-    for row in board {
-        println!("{:>4} {:>4} {:>4} {:>4}", row[0], row[1], row[2], row[3]);
-    }
-} 
\ No newline at end of file
@@ -0,0 +1,68 @@
+/* Generated by cbindgen from src/ffi.rs; do not edit. */
+
+#ifndef TWENTY_FORTY_EIGHT_H
+#define TWENTY_FORTY_EIGHT_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Direction codes: the values taken by [`tfe_game_move`] and returned by
+// [`tfe_game_hint`].
+#define TFE_UP 0
+
+#define TFE_DOWN 1
+
+#define TFE_LEFT 2
+
+#define TFE_RIGHT 3
+
+// A game owned by the caller.
+struct TfeGame;
+
+// Starts a game whose spawns are fully determined by `seed`. Release it
+// with [`tfe_game_free`].
+struct TfeGame *tfe_game_new(uint64_t seed);
+
+// Releases a game. Null is ignored.
+//
+// # Safety
+// `game` must be null or a handle from [`tfe_game_new`] not yet freed.
+void tfe_game_free(struct TfeGame *game);
+
+// Copies the 16 cell values, row by row (0 = empty), into `out`.
+//
+// # Safety
+// `game` must be a live handle and `out` must point to 16 writable
+// `uint32_t`s.
+void tfe_game_cells(const struct TfeGame *game, uint32_t *out);
+
+// Plays `direction` (a `TFE_*` code) and spawns a tile. Returns the score
+// gained, or -1 if the move does not change the board or the code is not
+// a direction.
+//
+// # Safety
+// `game` must be a live handle.
+int32_t tfe_game_move(struct TfeGame *game, int32_t direction);
+
+// Best move found within `time_limit_ms` milliseconds as a `TFE_*` code,
+// or -1 when the game is over.
+//
+// # Safety
+// `game` must be a live handle.
+int32_t tfe_game_hint(struct TfeGame *game, uint32_t time_limit_ms);
+
+// # Safety
+// `game` must be a live handle.
+uint32_t tfe_game_score(const struct TfeGame *game);
+
+// # Safety
+// `game` must be a live handle.
+uint32_t tfe_game_max_tile(const struct TfeGame *game);
+
+// # Safety
+// `game` must be a live handle.
+bool tfe_game_is_over(const struct TfeGame *game);
+
+#endif  /* TWENTY_FORTY_EIGHT_H */
@@ -0,0 +1,127 @@
+//! Evaluation weights that adapt between games.
+//!
+//! Each game is played with the current weight multipliers jittered a
+//! little. A game that beats the running baseline score pulls the
+//! multipliers towards the jitter it was played with, and one that falls
+//! short pushes them away: a simple evolution strategy, slow but steady
+//! over a long autoplay session. The adapter's whole state serializes, so
+//! a session can stop and pick up where it left off.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use super::optimized_evaluation::OptimizedEvaluationWeights;
+
+/// Multipliers are kept within this range, so a run of bad luck cannot
+/// switch a term off or let it swamp the rest.
+const MIN_SCALE: f32 = 0.1;
+const MAX_SCALE: f32 = 10.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeightAdapter {
+    /// Multipliers on the built-in evaluation weights.
+    pub weights: OptimizedEvaluationWeights,
+    /// Share of the way towards (or away from) a game's jitter that its
+    /// result moves the weights, at a score twice (or zero times) the
+    /// baseline.
+    pub learning_rate: f32,
+    /// Largest relative change jitter makes to any one weight.
+    pub exploration: f32,
+    /// Share of each new score mixed into the baseline.
+    pub baseline_rate: f64,
+    /// Exponential moving average of game scores; `None` before the first.
+    pub baseline: Option<f64>,
+    /// Games recorded so far.
+    pub games: u64,
+    /// Seeds the jitter, together with `games`.
+    pub seed: u64,
+}
+
+impl WeightAdapter {
+    /// Starts adapting from `weights`.
+    pub fn new(weights: OptimizedEvaluationWeights, seed: u64) -> Self {
+        Self {
+            weights,
+            learning_rate: 0.2,
+            exploration: 0.1,
+            baseline_rate: 0.1,
+            baseline: None,
+            games: 0,
+            seed,
+        }
+    }
+
+    /// Weights to play the next game with: the current ones, jittered.
+    /// The same until [`WeightAdapter::record`] is called.
+    pub fn propose(&self) -> OptimizedEvaluationWeights {
+        let mut rng = StdRng::seed_from_u64(self.seed.wrapping_add(self.games));
+        let exploration = self.exploration;
+        OptimizedEvaluationWeights::from_array(self.weights.to_array().map(|weight| {
+            (weight * (1.0 + rng.gen_range(-exploration..=exploration))).clamp(MIN_SCALE, MAX_SCALE)
+        }))
+    }
+
+    /// Learns from a game scoring `score` with weights `played` (as
+    /// proposed), and updates the baseline.
+    pub fn record(&mut self, played: &OptimizedEvaluationWeights, score: u32) {
+        let score = score as f64;
+        if let Some(baseline) = self.baseline {
+            let advantage = ((score - baseline) / baseline.max(1.0)).clamp(-1.0, 1.0) as f32;
+            let step = self.learning_rate * advantage;
+            let current = self.weights.to_array();
+            let played = played.to_array();
+            self.weights = OptimizedEvaluationWeights::from_array(std::array::from_fn(|i| {
+                (current[i] + step * (played[i] - current[i])).clamp(MIN_SCALE, MAX_SCALE)
+            }));
+        }
+        self.baseline = Some(match self.baseline {
+            Some(baseline) => baseline + self.baseline_rate * (score - baseline),
+            None => score,
+        });
+        self.games += 1;
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("an adapter serializes")
+    }
+
+    pub fn from_json(text: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weights_move_towards_winning_jitter_and_away_from_losing() {
+        let mut adapter = WeightAdapter::new(OptimizedEvaluationWeights::uniform(1.0), 4);
+        let first = adapter.propose();
+        assert_eq!(adapter.propose(), first);
+        adapter.record(&first, 10_000);
+        // The first game only sets the baseline.
+        assert_eq!(adapter.weights, OptimizedEvaluationWeights::uniform(1.0));
+        assert_eq!(adapter.baseline, Some(10_000.0));
+
+        let good = adapter.propose();
+        assert_ne!(good, first);
+        adapter.record(&good, 15_000);
+        for (weight, tried) in adapter.weights.to_array().into_iter().zip(good.to_array()) {
+            assert!((weight - 1.0) * (tried - 1.0) >= 0.0);
+            assert!((weight - 1.0).abs() < (tried - 1.0).abs() || tried == 1.0);
+        }
+
+        let before = adapter.weights.to_array();
+        let bad = adapter.propose();
+        adapter.record(&bad, 1_000);
+        for ((weight, was), tried) in adapter.weights.to_array().into_iter().zip(before).zip(bad.to_array()) {
+            assert!((weight - was) * (tried - was) <= 0.0);
+        }
+
+        let restored = WeightAdapter::from_json(&adapter.to_json()).unwrap();
+        assert_eq!(restored, adapter);
+        assert_eq!(restored.propose(), adapter.propose());
+    }
+}
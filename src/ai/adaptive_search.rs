@@ -1,4 +1,19 @@
+use arrayvec::ArrayVec;
+
 use crate::game::{GameBoard, Direction};
+use super::move_ordering::CLONES_PER_ORDERED_MOVE;
+use super::search::{SearchContext, ORDERING_DEPTHS};
+use super::trace::TraceEvent;
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+use crate::cache::TranspositionState;
+
+/// A chance node's spawn, (row, col, value), with its probability from the
+/// root.
+type Placement = ((usize, usize, u32), f32);
+
+/// Evaluation cache size of each parallel subtree's short-lived table.
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+const FORK_EVAL_ENTRIES: usize = 1 << 12;
 
 impl GameBoard {
     // Smarter adaptive depth calculation
@@ -28,15 +43,13 @@ impl GameBoard {
         // Adjust based on board complexity
         let complexity_adjustment = if board_complexity > 0.7 {
             1  // Complex board: deeper search
-        } else if board_complexity < 0.3 {
-            0  // Simple board: normal depth
         } else {
-            0  // Normal complexity: no adjustment
+            0  // Simple or normal complexity: no adjustment
         };
         
         // Ensure depth is within reasonable bounds (allow up to 12 when building 2048)
         let total_depth = base_depth + tile_bonus + complexity_adjustment;
-        total_depth.max(4).min(12)
+        total_depth.clamp(4, 12)
     }
     
     // Calculate board complexity (0.0 = simple, 1.0 = complex)
@@ -102,44 +115,57 @@ impl GameBoard {
     }
     
     // Optimized expectimax with early termination
-    pub fn expectimax_optimized(
+    pub(crate) fn expectimax_optimized(
         &mut self,
         depth: u32,
         is_maximizing: bool,
         alpha: f32,
         beta: f32,
-        tt: &mut crate::cache::TranspositionState,
+        ctx: &mut SearchContext,
     ) -> f32 {
+        // Unwinding after a timeout: the value is discarded by the caller.
+        if ctx.visit() {
+            return 0.0;
+        }
+
         if depth == 0 {
-            return self.evaluate_board_optimized();
+            ctx.count(|p| p.leaf_nodes += 1);
+            return ctx.evaluate(self);
         }
         
         if self.is_game_over() {
+            ctx.count(|p| p.terminal_nodes += 1);
             return -100000.0;
         }
         
         let hash = self.board_hash();
-        if let Some(cached_score) = tt.probe(hash, depth, is_maximizing) {
+        if let Some(cached_score) = ctx.probe(hash, depth, is_maximizing) {
             return cached_score;
         }
+        let mark = ctx.prune_mark();
         
         if is_maximizing {
+            ctx.count(|p| p.max_nodes += 1);
             let mut best_score = f32::NEG_INFINITY;
             let mut alpha = alpha;
             
             // Use optimized move ordering
-            let ordered_moves = self.order_moves();
+            let ordered_moves = ctx.order_moves(self, hash);
+            ctx.count(|p| p.board_clones += CLONES_PER_ORDERED_MOVE * ordered_moves.len() as u64);
             
+            // Move-ordering quality: which legal move scored best or cut off.
+            let (mut searched, mut best_index, mut cutoff) = (0, 0, None);
+            let mut best_move = None;
             for direction in ordered_moves {
-                let mut new_board = self.clone();
-                if new_board.move_tiles(direction) {
-                    new_board.empty_mask = GameBoard::calculate_empty_mask(&new_board.board);
-                    new_board.max_tile = GameBoard::calculate_max_tile(&new_board.board);
-                    
-                    let score = new_board.expectimax_optimized(depth - 1, false, alpha, beta, tt);
+                if let Some(undo) = self.apply(direction) {
+                    let score = self.expectimax_optimized(depth - 1, false, alpha, beta, ctx);
+                    self.undo(undo);
+                    searched += 1;
                     
                     if score > best_score {
                         best_score = score;
+                        best_index = searched - 1;
+                        best_move = Some(direction);
                         
                         // Early termination check
                         if self.should_terminate_early(depth, score, best_score) {
@@ -149,59 +175,161 @@ impl GameBoard {
                     
                     alpha = alpha.max(score);
                     if alpha >= beta {
+                        cutoff = Some(searched - 1);
+                        ctx.trace(|| TraceEvent::Cutoff { hash, depth, index: (searched - 1) as u32 });
                         break; // Alpha-beta cutoff
                     }
                 }
             }
+            if let Some(best) = best_move {
+                ctx.remember_best_move(hash, depth, best);
+            }
+            if searched > 1 && !ctx.aborted() {
+                ctx.count(|p| p.ordering[(depth as usize).min(ORDERING_DEPTHS - 1)].record(best_index, cutoff));
+            }
             
             if best_score == f32::NEG_INFINITY {
-                best_score = self.evaluate_board_optimized();
+                best_score = ctx.evaluate(self);
             }
             
-            tt.store(hash, depth, is_maximizing, best_score);
+            if !ctx.aborted() {
+                ctx.store(hash, depth, is_maximizing, best_score, mark);
+            }
             best_score
         } else {
-            // Chance node - use strategic empty cell selection
-            let empty_cells = self.get_strategic_empty_cells();
-            if empty_cells.is_empty() {
-                return self.evaluate_board_optimized();
+            ctx.count(|p| p.chance_nodes += 1);
+            // Chance node - average over the cells the sampling picks
+            let empty_cells = self.chance_cells(ctx.sampling);
+            if empty_cells.len() == 0 {
+                return ctx.evaluate(self);
             }
             
+            let spawns = ctx.spawns.clone();
+            let spawn_weight: u32 = spawns.iter().map(|&(_, weight)| weight).sum();
+            let cell_probability = ctx.probability / empty_cells.len() as f32;
+            // Each spawnable tile on each cell, at its chance under the
+            // game's rules and the probability of reaching it from the root.
+            let placements: ArrayVec<Placement, 48> = empty_cells
+                .flat_map(|(i, j)| spawns.iter().map(move |&(value, weight)| (i, j, value, weight)))
+                .map(|(i, j, value, weight)| ((i, j, value), cell_probability * weight as f32 / spawn_weight as f32))
+                .collect();
+            let scores = if ctx.parallel_chance(depth) {
+                self.expand_parallel(&placements, depth, alpha, beta, ctx)
+            } else {
+                placements
+                    .iter()
+                    .map(|&(placement, probability)| self.chance_child(placement, probability, depth, alpha, beta, ctx))
+                    .collect()
+            };
+            
             let mut total_score = 0.0;
             let mut total_weight = 0.0;
-            
-            for &(i, j) in &empty_cells {
-                // Try placing a 2 (90% probability)
-                let mut new_board_2 = self.clone();
-                new_board_2.board[i][j] = 2;
-                new_board_2.empty_mask = GameBoard::calculate_empty_mask(&new_board_2.board);
-                new_board_2.max_tile = GameBoard::calculate_max_tile(&new_board_2.board);
-                
-                let score_2 = new_board_2.expectimax_optimized(depth - 1, true, alpha, beta, tt);
-                total_score += score_2 * 0.9;
-                total_weight += 0.9;
-                
-                // Try placing a 4 (10% probability)
-                let mut new_board_4 = self.clone();
-                new_board_4.board[i][j] = 4;
-                new_board_4.empty_mask = GameBoard::calculate_empty_mask(&new_board_4.board);
-                new_board_4.max_tile = GameBoard::calculate_max_tile(&new_board_4.board);
-                
-                let score_4 = new_board_4.expectimax_optimized(depth - 1, true, alpha, beta, tt);
-                total_score += score_4 * 0.1;
-                total_weight += 0.1;
+            // Probability-weighted sum of squares, for the spread of outcomes.
+            let mut total_square = 0.0f64;
+            // Fixed point: integer spawn weights, divided out at the end.
+            let mut total_fixed = 0i64;
+            let cells = empty_cells.len() as i64;
+            for (k, &score) in scores.iter().enumerate() {
+                let weight = spawns[k % spawns.len()].1;
+                let probability = weight as f32 / spawn_weight as f32;
+                total_score += score * probability;
+                total_square += (probability * score) as f64 * score as f64;
+                total_weight += probability;
+                total_fixed += weight as i64 * score as i64;
             }
             
-            let avg_score = if total_weight > 0.0 {
+            let avg_score = if ctx.fixed_point() {
+                total_fixed.div_euclid(spawn_weight as i64 * cells) as f32
+            } else if total_weight > 0.0 {
                 total_score / total_weight
             } else {
-                self.evaluate_board_optimized()
+                ctx.evaluate(self)
             };
             
-            tt.store(hash, depth, is_maximizing, avg_score);
+            // Risk-averse play: mean minus a multiple of the standard deviation.
+            let avg_score = if ctx.risk_aversion > 0.0 && total_weight > 0.0 {
+                let mean = (total_score / total_weight) as f64;
+                let deviation = (total_square / total_weight as f64 - mean * mean).max(0.0).sqrt() as f32;
+                let penalty = ctx.risk_aversion * deviation;
+                avg_score - if ctx.fixed_point() { penalty.round() } else { penalty }
+            } else {
+                avg_score
+            };
+            
+            if !ctx.aborted() {
+                ctx.store(hash, depth, is_maximizing, avg_score, mark);
+            }
             avg_score
         }
     }
+    
+    /// One spawn of a chance node, reached with `probability` from the
+    /// root: the position after it searched `depth - 1` deep.
+    fn chance_child(
+        &mut self,
+        (row, col, value): (usize, usize, u32),
+        probability: f32,
+        depth: u32,
+        alpha: f32,
+        beta: f32,
+        ctx: &mut SearchContext,
+    ) -> f32 {
+        let undo = self.place_tile(row, col, value);
+        let parent = std::mem::replace(&mut ctx.probability, probability);
+        // Too unlikely to be worth searching: take the static value.
+        let score = if probability < ctx.probability_threshold() {
+            ctx.record_pruned();
+            ctx.evaluate(self)
+        } else {
+            self.expectimax_optimized(depth - 1, true, alpha, beta, ctx)
+        };
+        ctx.probability = parent;
+        self.undo(undo);
+        score
+    }
+    
+    /// [`GameBoard::chance_child`] for every placement at once on the rayon
+    /// pool, each with its own board, table and forked context.
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    fn expand_parallel(
+        &self,
+        placements: &[Placement],
+        depth: u32,
+        alpha: f32,
+        beta: f32,
+        ctx: &mut SearchContext,
+    ) -> ArrayVec<f32, 48> {
+        use rayon::prelude::*;
+        
+        let shared = &*ctx;
+        let results: Vec<_> = placements
+            .par_iter()
+            .map(|&(placement, probability)| {
+                let mut tt = TranspositionState::with_eval_entries(FORK_EVAL_ENTRIES);
+                let mut fork = shared.fork(&mut tt);
+                let mut board = self.clone();
+                let score = board.chance_child(placement, probability, depth, alpha, beta, &mut fork);
+                (score, fork.into_counts())
+            })
+            .collect();
+        ctx.count(|p| {
+            p.board_clones += placements.len() as u64;
+            p.allocations += placements.len() as u64;
+        });
+        results
+            .into_iter()
+            .map(|(score, counts)| {
+                ctx.join(counts);
+                score
+            })
+            .collect()
+    }
+    
+    #[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+    fn expand_parallel(&mut self, _: &[Placement], _: u32, _: f32, _: f32, _: &mut SearchContext) -> ArrayVec<f32, 48> {
+        unreachable!("parallel expansion needs the `parallel` feature")
+    }
+
 }
 
 #[cfg(test)]
@@ -257,4 +385,4 @@ mod tests {
         
         assert!(complex_complexity > simple_complexity);
     }
-} 
\ No newline at end of file
+}
\ No newline at end of file
@@ -0,0 +1,174 @@
+//! Engine-versus-engine matches: two self-play configurations play the same
+//! seeded games, and the per-seed score differences are tested for
+//! significance. Pairing removes the spawn luck both players share, so far
+//! fewer games separate two engines than comparing independent runs would.
+
+use serde::Serialize;
+
+use super::selfplay::{run_games_with, GameRecord, SelfPlayConfig};
+use crate::stats::GameStats;
+
+/// Paired comparison of per-seed score differences (A minus B).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PairedTest {
+    pub mean_diff: f64,
+    pub std_err: f64,
+    pub t: f64,
+    /// Two-sided p-value from the normal approximation to the t statistic.
+    pub p_value: f64,
+}
+
+impl PairedTest {
+    /// Half-width of the 95% confidence interval of `mean_diff`.
+    pub fn ci95(&self) -> f64 {
+        1.96 * self.std_err
+    }
+}
+
+/// Abramowitz & Stegun 7.1.26; absolute error below 1.5e-7.
+fn erf(x: f64) -> f64 {
+    let sign = if x < 0.0 { -1.0 } else { 1.0 };
+    let x = x.abs();
+    let t = 1.0 / (1.0 + 0.327_591_1 * x);
+    let poly = t * (0.254_829_592 + t * (-0.284_496_736 + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    sign * (1.0 - poly * (-x * x).exp())
+}
+
+fn normal_cdf(x: f64) -> f64 {
+    0.5 * (1.0 + erf(x / std::f64::consts::SQRT_2))
+}
+
+pub fn paired_test(diffs: &[f64]) -> PairedTest {
+    let n = diffs.len() as f64;
+    let mean_diff = diffs.iter().sum::<f64>() / n.max(1.0);
+    let variance = if diffs.len() > 1 {
+        diffs.iter().map(|d| (d - mean_diff).powi(2)).sum::<f64>() / (n - 1.0)
+    } else {
+        0.0
+    };
+    let std_err = (variance / n.max(1.0)).sqrt();
+    let (t, p_value) = if std_err > 0.0 {
+        let t = mean_diff / std_err;
+        (t, 2.0 * (1.0 - normal_cdf(t.abs())))
+    } else {
+        (0.0, 1.0)
+    };
+    PairedTest { mean_diff, std_err, t, p_value }
+}
+
+/// Which configuration of a [`Match`] played a game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Side {
+    A,
+    B,
+}
+
+/// Two configurations to compare. Both play A's seeds and spawn rules, so
+/// game `i` deals the same spawns to both as long as they play the same
+/// moves.
+#[derive(Debug, Clone)]
+pub struct Match {
+    pub a: SelfPlayConfig,
+    pub b: SelfPlayConfig,
+}
+
+/// Every game of a match, each side's statistics and the paired test.
+#[derive(Debug, Clone)]
+pub struct MatchResult {
+    /// Game records of each side, ordered by seed, so they pair up.
+    pub records_a: Vec<GameRecord>,
+    pub records_b: Vec<GameRecord>,
+    pub stats_a: GameStats,
+    pub stats_b: GameStats,
+    /// Seeds where A outscored B, B outscored A, and neither.
+    pub wins: usize,
+    pub losses: usize,
+    pub ties: usize,
+    pub test: PairedTest,
+}
+
+impl MatchResult {
+    /// The side that scores more, if the difference is significant at
+    /// `level` (e.g. 0.05).
+    pub fn stronger(&self, level: f64) -> Option<Side> {
+        (self.test.p_value < level).then_some(if self.test.mean_diff > 0.0 { Side::A } else { Side::B })
+    }
+}
+
+impl Match {
+    pub fn new(a: SelfPlayConfig, b: SelfPlayConfig) -> Self {
+        Self { a, b }
+    }
+
+    /// Plays `games` seeds with each side.
+    pub fn play(&self, games: usize) -> MatchResult {
+        self.play_with(games, |_, _| {})
+    }
+
+    /// [`Match::play`], calling `on_game` as each game finishes. All of A's
+    /// games are played before B's.
+    pub fn play_with<F>(&self, games: usize, on_game: F) -> MatchResult
+    where
+        F: Fn(Side, &GameRecord) + Sync,
+    {
+        let b = SelfPlayConfig { seed: self.a.seed, spawn: self.a.spawn, ..self.b.clone() };
+        let records_a = run_games_with(&self.a, games, |record| on_game(Side::A, record)).records;
+        let records_b = run_games_with(&b, games, |record| on_game(Side::B, record)).records;
+        let diffs: Vec<f64> = records_a
+            .iter()
+            .zip(&records_b)
+            .map(|(a, b)| a.score as f64 - b.score as f64)
+            .collect();
+        let wins = diffs.iter().filter(|&&d| d > 0.0).count();
+        let losses = diffs.iter().filter(|&&d| d < 0.0).count();
+        MatchResult {
+            stats_a: GameStats::from_records(&records_a),
+            stats_b: GameStats::from_records(&records_b),
+            wins,
+            losses,
+            ties: diffs.len() - wins - losses,
+            test: paired_test(&diffs),
+            records_a,
+            records_b,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::search::SearchConfig;
+
+    #[test]
+    fn test_paired_test_detects_consistent_difference() {
+        let diffs: Vec<f64> = (0..50).map(|i| 100.0 + (i % 5) as f64 * 10.0).collect();
+        let test = paired_test(&diffs);
+        assert!(test.mean_diff > 100.0);
+        assert!(test.p_value < 0.001);
+    }
+
+    #[test]
+    fn test_paired_test_symmetric_noise_is_not_significant() {
+        let diffs: Vec<f64> = (0..50).map(|i| if i % 2 == 0 { 500.0 } else { -500.0 }).collect();
+        let test = paired_test(&diffs);
+        assert!(test.p_value > 0.5);
+    }
+
+    #[test]
+    fn test_identical_configs_tie_every_seed() {
+        let config = SelfPlayConfig {
+            search: SearchConfig::with_depth(1),
+            max_moves: 60,
+            seed: 3,
+            threads: Some(2),
+            ..SelfPlayConfig::default()
+        };
+        let result = Match::new(config.clone(), SelfPlayConfig { seed: 99, ..config }).play(4);
+        assert_eq!(result.ties, 4);
+        for (a, b) in result.records_a.iter().zip(&result.records_b) {
+            assert_eq!((a.seed, a.score, a.moves), (b.seed, b.score, b.moves));
+        }
+        assert_eq!(result.stronger(0.05), None);
+        assert_eq!(result.stats_a.games(), 4);
+    }
+}
@@ -0,0 +1,76 @@
+use crate::cache::TranspositionState;
+use crate::game::{BattleGame, Direction, GameBoard, GARBAGE_TILE};
+use super::search::SearchConfig;
+
+/// Weight of the damage garbage does to the opponent against the value of
+/// the player's own board.
+const ATTACK_WEIGHT: f32 = 1.0;
+
+impl BattleGame {
+    /// Best move for the player to move, judged on both boards: the
+    /// expectimax value of the move on the player's own board, plus how
+    /// much the garbage it sends lowers the opponent's evaluation.
+    pub fn best_move(&self, config: &SearchConfig, tt: &mut TranspositionState) -> Option<Direction> {
+        let own = &self.boards[self.to_move];
+        let opponent = &self.boards[self.opponent()];
+        let before = opponent.evaluate_board_optimized();
+        own.search_with(config, tt)
+            .move_scores
+            .into_iter()
+            .map(|(direction, score)| {
+                let mut child = own.clone();
+                let garbage = child
+                    .play_move_without_spawn(direction)
+                    .map_or(0, |result| Self::garbage_sent(&result, self.garbage_threshold));
+                let damage = before - with_garbage(opponent, garbage).evaluate_board_optimized();
+                (direction, score + ATTACK_WEIGHT * damage)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(direction, _)| direction)
+    }
+}
+
+/// `board` with `count` garbage tiles, each on the empty cell where it
+/// hurts most.
+fn with_garbage(board: &GameBoard, count: usize) -> GameBoard {
+    let mut board = board.clone();
+    for _ in 0..count {
+        let worst = board.empty_cells().min_by(|&a, &b| {
+            garbage_eval(&board, a).total_cmp(&garbage_eval(&board, b))
+        });
+        let Some((row, col)) = worst else { break };
+        board.place_tile(row, col, GARBAGE_TILE);
+    }
+    board
+}
+
+fn garbage_eval(board: &GameBoard, (row, col): (usize, usize)) -> f32 {
+    let mut child = board.clone();
+    child.place_tile(row, col, GARBAGE_TILE);
+    child.evaluate_board_optimized()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn test_ai_battle_plays_until_one_side_loses() {
+        let mut rng = StdRng::seed_from_u64(9);
+        let mut battle = BattleGame::new_with_rng(&mut rng);
+        battle.garbage_threshold = 8;
+        let config = SearchConfig::with_depth(1);
+        let mut tt = TranspositionState::new();
+        let mut garbage = 0;
+        while battle.loser().is_none() {
+            let direction = battle.best_move(&config, &mut tt).unwrap();
+            garbage += battle.play_move_with(direction, &mut rng).unwrap().garbage.len();
+            tt.clear();
+        }
+        assert!(garbage > 0);
+        assert!(battle.boards[battle.loser().unwrap()].is_game_over());
+    }
+}
@@ -1,30 +1,41 @@
-use crate::game::GameBoard;
+use arrayvec::ArrayVec;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::search::ChanceSampling;
+use crate::game::{EmptyCells, GameBoard};
 
 impl GameBoard {
-    // Optimized empty cell selection for chance nodes
-    pub(crate) fn get_strategic_empty_cells(&self) -> Vec<(usize, usize)> {
-        let mut all_empty = self.get_empty_cells();
-        
-        // If few empty cells, consider them all
-        if all_empty.len() <= 4 {
-            return all_empty;
-        }
-        
-        // If many empty cells, prioritize strategic positions
-        if all_empty.len() > 8 {
-            all_empty = self.prioritize_empty_cells(all_empty);
+    // Empty cells a chance node averages over
+    pub(crate) fn chance_cells(&self, sampling: ChanceSampling) -> EmptyCells {
+        let all_empty = self.empty_cells();
+        match sampling {
+            ChanceSampling::Strategic if all_empty.len() > 8 => {
+                let limit = (all_empty.len() / 2).clamp(6, 8);
+                self.prioritize_empty_cells(all_empty, limit)
+            }
+            ChanceSampling::TopK(k) if all_empty.len() > k => self.prioritize_empty_cells(all_empty, k.max(1)),
+            ChanceSampling::RandomK { k, seed } if all_empty.len() > k => {
+                // Seeded by the position too, so every visit of a
+                // transposition draws the same cells.
+                let mut rng = StdRng::seed_from_u64(seed ^ self.board_hash());
+                let cells: ArrayVec<(usize, usize), 16> = all_empty.collect();
+                let mask = rand::seq::index::sample(&mut rng, cells.len(), k.max(1))
+                    .into_iter()
+                    .fold(0u16, |mask, index| mask | 1 << (cells[index].0 * 4 + cells[index].1));
+                EmptyCells(mask)
+            }
+            _ => all_empty,
         }
-        
-        all_empty
     }
     
     // Prioritize empty cells based on strategic value
-    fn prioritize_empty_cells(&self, empty_cells: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    fn prioritize_empty_cells(&self, empty_cells: EmptyCells, limit: usize) -> EmptyCells {
         let max_tile = self.get_max_tile();
         
         // Score each empty cell
-        let mut cell_scores: Vec<((usize, usize), f32)> = empty_cells.iter()
-            .map(|&(row, col)| {
+        let mut cell_scores: ArrayVec<((usize, usize), f32), 16> = empty_cells
+            .map(|(row, col)| {
                 let score = self.score_empty_cell(row, col, max_tile);
                 ((row, col), score)
             })
@@ -33,12 +44,11 @@ impl GameBoard {
         // Sort by score (best first)
         cell_scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
         
-        // Return top 6-8 cells
-        let limit = (empty_cells.len() / 2).max(6).min(8);
-        cell_scores.into_iter()
+        // Keep the top `limit` cells
+        let mask = cell_scores.into_iter()
             .take(limit)
-            .map(|(pos, _)| pos)
-            .collect()
+            .fold(0u16, |mask, ((row, col), _)| mask | 1 << (row * 4 + col));
+        EmptyCells(mask)
     }
     
     // Score an empty cell based on strategic factors
@@ -160,9 +170,39 @@ mod tests {
             [0, 0, 0, 0]
         ]);
         
-        let strategic_cells = board.get_strategic_empty_cells();
-        assert!(!strategic_cells.is_empty());
-        assert!(strategic_cells.len() <= 8);
+        let mut strategic_cells = board.chance_cells(ChanceSampling::TopK(8));
+        assert_eq!(strategic_cells.len(), 8);
+        assert!(strategic_cells.all(|(row, col)| board.board[row][col] == 0));
+        assert_eq!(board.chance_cells(ChanceSampling::All).len(), 12);
+        assert_eq!(board.chance_cells(ChanceSampling::ProbabilityThreshold(0.01)).len(), 12);
+        assert_eq!(board.chance_cells(ChanceSampling::TopK(16)).len(), 12);
+    }
+
+    #[test]
+    fn test_default_sampling_keeps_half_the_cells_between_six_and_eight() {
+        let kept = |empty: usize| {
+            let mut board = GameBoard::new();
+            board.set_board(std::array::from_fn(|i| std::array::from_fn(|j| if i * 4 + j < 16 - empty { 2 << ((i * 4 + j) % 11) } else { 0 })));
+            board.chance_cells(ChanceSampling::default()).len()
+        };
+        assert_eq!([4, 8, 9, 12, 13, 14, 16].map(kept), [4, 8, 6, 6, 6, 7, 8]);
+    }
+    
+    #[test]
+    fn test_random_cells_are_reproducible() {
+        let mut board = GameBoard::new();
+        board.set_board([
+            [2, 4, 8, 16],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0]
+        ]);
+        
+        let sampling = ChanceSampling::RandomK { k: 5, seed: 3 };
+        let cells = board.chance_cells(sampling);
+        assert_eq!(cells.len(), 5);
+        assert_eq!(cells.0 & 0xF, 0);
+        assert_eq!(board.chance_cells(sampling).0, cells.0);
     }
     
     #[test]
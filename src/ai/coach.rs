@@ -0,0 +1,152 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::Serialize;
+
+use super::search::SearchConfig;
+use crate::cache::TranspositionState;
+use crate::game::replay::{replay_positions, ReplayError, ReplayHeader, ReplayStep};
+use crate::game::{Direction, GameBoard, SpawnRules};
+
+/// The engine's verdict on one played move.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MoveReview {
+    /// 1 for the first move of the game.
+    pub move_number: u32,
+    /// The position the move was played from, in compact notation.
+    pub position: String,
+    pub played: Direction,
+    pub best: Direction,
+    pub played_eval: f32,
+    pub best_eval: f32,
+    /// Evaluation given up by the played move, as a fraction of the best
+    /// move's (the scale of [`crate::SearchResult::score_gap`]); 0 for the
+    /// best move.
+    pub loss: f32,
+    pub blunder: bool,
+}
+
+/// Every move of a game reviewed, with a summary.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GameReview {
+    pub moves: Vec<MoveReview>,
+    /// Mean of `1 - loss` over the moves, from 0 to 1.
+    pub accuracy: f32,
+    /// Share of moves that were the engine's choice.
+    pub best_move_rate: f32,
+    pub blunders: usize,
+}
+
+impl GameReview {
+    pub fn blunder_moves(&self) -> impl Iterator<Item = &MoveReview> {
+        self.moves.iter().filter(|review| review.blunder)
+    }
+}
+
+/// Annotates a finished game with the engine's best move at each position
+/// and how much each played move lost against it.
+#[derive(Debug, Clone)]
+pub struct Coach {
+    pub search: SearchConfig,
+    /// Loss above which a move is flagged as a blunder.
+    pub blunder_threshold: f32,
+}
+
+impl Default for Coach {
+    fn default() -> Self {
+        Self {
+            search: SearchConfig::with_depth(3),
+            blunder_threshold: 0.1,
+        }
+    }
+}
+
+impl Coach {
+    /// Reviews `moves`, where move `i` was played from `positions[i]`.
+    pub fn review(&self, positions: &[GameBoard], moves: &[Direction]) -> GameReview {
+        let mut tt = TranspositionState::new();
+        let reviews: Vec<_> = positions
+            .iter()
+            .zip(moves)
+            .enumerate()
+            .filter_map(|(index, (board, &played))| {
+                let result = board.search_with(&self.search, &mut tt);
+                let &(best, best_eval) = result.move_scores.first()?;
+                let &(_, played_eval) = result.move_scores.iter().find(|&&(direction, _)| direction == played)?;
+                let loss = ((best_eval - played_eval) / best_eval.abs().max(1.0)).clamp(0.0, 1.0);
+                Some(MoveReview {
+                    move_number: index as u32 + 1,
+                    position: board.to_compact(),
+                    played,
+                    best,
+                    played_eval,
+                    best_eval,
+                    loss,
+                    blunder: loss > self.blunder_threshold,
+                })
+            })
+            .collect();
+        let count = reviews.len().max(1) as f32;
+        GameReview {
+            accuracy: reviews.iter().map(|review| 1.0 - review.loss).sum::<f32>() / count,
+            best_move_rate: reviews.iter().filter(|review| review.played == review.best).count() as f32 / count,
+            blunders: reviews.iter().filter(|review| review.blunder).count(),
+            moves: reviews,
+        }
+    }
+
+    /// Reviews a recorded game.
+    pub fn review_replay(&self, header: &ReplayHeader, steps: &[ReplayStep]) -> Result<GameReview, ReplayError> {
+        let positions = replay_positions(header, steps)?;
+        let moves: Vec<_> = steps.iter().map(|step| step.direction).collect();
+        Ok(self.review(&positions, &moves))
+    }
+
+    /// Reviews a seeded classic game from its move history, as kept by a
+    /// save file.
+    pub fn review_history(&self, seed: u64, moves: &[Direction], rules: &SpawnRules) -> Result<GameReview, ReplayError> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut board = GameBoard::new_with_rules(&mut rng, rules);
+        let mut positions = Vec::with_capacity(moves.len());
+        for (index, &direction) in moves.iter().enumerate() {
+            positions.push(board.clone());
+            board
+                .play_move_with_rules(direction, &mut rng, rules)
+                .map_err(|_| ReplayError::IllegalMove { index, direction })?;
+        }
+        Ok(self.review(&positions, moves))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_engine_moves_review_as_perfect_and_bad_moves_as_blunders() {
+        let coach = Coach::default();
+        let mut rng = StdRng::seed_from_u64(12);
+        let mut board = GameBoard::new_with_rng(&mut rng);
+        let mut moves = Vec::new();
+        for _ in 0..20 {
+            let best = board.search_with(&coach.search, &mut TranspositionState::new()).best_move.unwrap();
+            board.play_move_with(best, &mut rng).unwrap();
+            moves.push(best);
+        }
+        let review = coach.review_history(12, &moves, &SpawnRules::default()).unwrap();
+        assert_eq!(review.moves.len(), 20);
+        assert_eq!(review.best_move_rate, 1.0);
+        assert_eq!(review.accuracy, 1.0);
+        assert_eq!(review.blunders, 0);
+
+        // The engine's least favourite move everywhere.
+        let positions = review.moves.iter().map(|m| GameBoard::from_compact(&m.position).unwrap()).collect::<Vec<_>>();
+        let worst: Vec<_> = positions
+            .iter()
+            .map(|board| board.search_with(&coach.search, &mut TranspositionState::new()).move_scores.last().unwrap().0)
+            .collect();
+        let review = coach.review(&positions, &worst);
+        assert!(review.accuracy < 1.0);
+        assert!(review.blunders > 0);
+        assert_eq!(review.blunder_moves().count(), review.blunders);
+    }
+}
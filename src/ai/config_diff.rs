@@ -0,0 +1,152 @@
+//! Two search configurations side by side on one position: how each scores
+//! the moves, and where their principal variations part. For checking what
+//! a weight change or a pruning option actually changes.
+
+use serde::Serialize;
+
+use super::search::SearchConfig;
+use crate::cache::TranspositionState;
+use crate::game::{Direction, GameBoard};
+
+/// One move of a principal variation.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PvStep {
+    pub direction: Direction,
+    /// The move's search score from the position before it.
+    pub score: f32,
+    /// Assumed spawn after the move: a 2 at (row, col).
+    pub spawn: Option<(usize, usize)>,
+}
+
+/// A root move's score under each configuration; `None` when that one
+/// did not search it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct MoveDelta {
+    pub direction: Direction,
+    pub a: Option<f32>,
+    pub b: Option<f32>,
+}
+
+impl MoveDelta {
+    /// `b - a`, when both scored the move.
+    pub fn delta(&self) -> Option<f32> {
+        Some(self.b? - self.a?)
+    }
+}
+
+/// Outcome of [`GameBoard::compare_configs`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigDiff {
+    pub best_a: Option<Direction>,
+    pub best_b: Option<Direction>,
+    /// Every legal move, in [`Direction::all`] order.
+    pub moves: Vec<MoveDelta>,
+    pub depth_a: u32,
+    pub depth_b: u32,
+    pub nodes_a: u64,
+    pub nodes_b: u64,
+    pub pv_a: Vec<PvStep>,
+    pub pv_b: Vec<PvStep>,
+    /// Index of the first move where the principal variations differ;
+    /// `None` when one is a prefix of the other.
+    pub first_divergence: Option<usize>,
+}
+
+impl GameBoard {
+    /// Best moves from here searched with `config` to `depth`, assuming
+    /// after each one the 2-spawn that leaves the worst static evaluation.
+    /// Each ply of search depth covers a move or a spawn, so the line holds
+    /// about `depth / 2` moves. The assumed spawns depend on the position
+    /// only, so lines from different configurations stay comparable.
+    pub fn principal_variation(&self, config: &SearchConfig, depth: u32) -> Vec<PvStep> {
+        let mut tt = TranspositionState::new();
+        let mut line = Vec::new();
+        let mut board = self.clone();
+        let mut remaining = depth;
+        while remaining >= 1 {
+            let config = SearchConfig {
+                max_depth: Some(remaining),
+                time_limit: None,
+                ..config.clone()
+            };
+            let result = board.search_with(&config, &mut tt);
+            let Some(direction) = result.best_move else { break };
+            if !board.move_tiles(direction) {
+                break;
+            }
+            let spawn = board
+                .empty_cells()
+                .min_by(|&a, &b| spawn_eval(&board, a).total_cmp(&spawn_eval(&board, b)));
+            if let Some((row, col)) = spawn {
+                board.place_tile(row, col, 2);
+            }
+            line.push(PvStep { direction, score: result.score, spawn });
+            remaining = remaining.saturating_sub(2);
+        }
+        line
+    }
+
+    /// Searches this position with `a` and with `b` and lines up the
+    /// results.
+    pub fn compare_configs(&self, a: &SearchConfig, b: &SearchConfig) -> ConfigDiff {
+        let result_a = self.search_with(a, &mut TranspositionState::new());
+        let result_b = self.search_with(b, &mut TranspositionState::new());
+        let score = |scores: &[(Direction, f32)], direction| scores.iter().find(|&&(d, _)| d == direction).map(|&(_, s)| s);
+        let moves = Direction::all()
+            .into_iter()
+            .filter(|&direction| self.can_move(direction))
+            .map(|direction| MoveDelta {
+                direction,
+                a: score(&result_a.move_scores, direction),
+                b: score(&result_b.move_scores, direction),
+            })
+            .collect();
+        let pv_a = self.principal_variation(a, result_a.depth);
+        let pv_b = self.principal_variation(b, result_b.depth);
+        let first_divergence = pv_a.iter().zip(&pv_b).position(|(x, y)| x.direction != y.direction);
+        ConfigDiff {
+            best_a: result_a.best_move,
+            best_b: result_b.best_move,
+            moves,
+            depth_a: result_a.depth,
+            depth_b: result_b.depth,
+            nodes_a: result_a.nodes,
+            nodes_b: result_b.nodes,
+            pv_a,
+            pv_b,
+            first_divergence,
+        }
+    }
+}
+
+fn spawn_eval(board: &GameBoard, (row, col): (usize, usize)) -> f32 {
+    let mut child = board.clone();
+    child.place_tile(row, col, 2);
+    child.evaluate_board_optimized()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::OptimizedEvaluationWeights;
+
+    #[test]
+    fn test_identical_configs_agree_and_different_weights_show_deltas() {
+        let board = GameBoard::from_compact("512,256,128,64/8,16,32,4/2,0,0,2/0,0,0,0").unwrap();
+        let config = SearchConfig::with_depth(4);
+        let same = board.compare_configs(&config, &config);
+        assert_eq!(same.best_a, same.best_b);
+        assert_eq!(same.first_divergence, None);
+        assert_eq!(same.pv_a, same.pv_b);
+        assert_eq!(same.pv_a.len(), 2);
+        assert!(same.moves.iter().all(|m| m.delta() == Some(0.0)));
+
+        let corner_only = SearchConfig {
+            weight_scale: Some(OptimizedEvaluationWeights::from_array([0.0, 0.0, 0.0, 1.0, 0.0, 0.0])),
+            ..config.clone()
+        };
+        let diff = board.compare_configs(&config, &corner_only);
+        assert!(diff.moves.iter().any(|m| m.delta().is_some_and(|d| d != 0.0)));
+        assert_eq!(diff.pv_a, same.pv_a);
+    }
+}
@@ -0,0 +1,338 @@
+//! Self-play training data for external value and policy networks.
+//!
+//! A dataset file is a 16-byte header followed by fixed-size little-endian
+//! records, one per position played:
+//!
+//! | offset | size | field                                              |
+//! |-------:|-----:|----------------------------------------------------|
+//! |      0 |    8 | magic `b"2048DSET"`                                |
+//! |      8 |    4 | format version (`u32`, currently 1)                |
+//! |     12 |    4 | record size in bytes (`u32`, currently 48)         |
+//!
+//! | offset | size | record field                                       |
+//! |-------:|-----:|----------------------------------------------------|
+//! |      0 |   16 | cells row by row as tile ranks (`u8`, 0 = empty, 1 = 2, 2 = 4, ...) |
+//! |     16 |   16 | search policy over Up, Down, Left, Right (`f32` x 4) |
+//! |     32 |    1 | move played (`u8`, index into the same order)      |
+//! |     33 |    3 | padding                                            |
+//! |     36 |    4 | final score of the game (`u32`)                    |
+//! |     40 |    4 | final max tile of the game (`u32`)                 |
+//! |     44 |    4 | moves left until the game ended (`u32`)            |
+//!
+//! With NumPy:
+//!
+//! ```text
+//! dtype = np.dtype([("cells", "u1", 16), ("policy", "<f4", 4), ("move", "u1"), ("pad", "u1", 3),
+//!                   ("final_score", "<u4"), ("final_max_tile", "<u4"), ("moves_left", "<u4")])
+//! data = np.fromfile("games.bin", dtype=dtype, offset=16)
+//! ```
+
+use std::io::{self, Read, Write};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use super::search::{SearchConfig, SearchResult};
+use crate::cache::TranspositionState;
+use crate::game::{Direction, GameBoard};
+
+pub const DATASET_MAGIC: [u8; 8] = *b"2048DSET";
+pub const DATASET_VERSION: u32 = 1;
+pub const RECORD_SIZE: usize = 48;
+
+/// How dataset games are played.
+#[derive(Debug, Clone)]
+pub struct DatasetConfig {
+    pub search: SearchConfig,
+    /// Game `i` is played with seed `seed + i`.
+    pub seed: u64,
+    /// Games stop after this many moves.
+    pub max_moves: u32,
+    /// Softness of the policy: 0 puts all weight on the best move and
+    /// always plays it; higher values spread the policy and sample the
+    /// move from it, for more varied games.
+    pub temperature: f32,
+    /// Also emit the seven rotations and reflections of every position.
+    pub augment: bool,
+}
+
+impl Default for DatasetConfig {
+    fn default() -> Self {
+        Self {
+            search: SearchConfig::with_depth(3),
+            seed: 0,
+            max_moves: 5000,
+            temperature: 0.0,
+            augment: false,
+        }
+    }
+}
+
+/// One training example.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DatasetRecord {
+    /// Tile rank of each cell, row by row.
+    pub cells: [u8; 16],
+    /// Probability of each move in [`Direction::all`] order.
+    pub policy: [f32; 4],
+    pub played: Direction,
+    pub final_score: u32,
+    pub final_max_tile: u32,
+    pub moves_left: u32,
+}
+
+fn direction_index(direction: Direction) -> usize {
+    Direction::all().iter().position(|&d| d == direction).unwrap()
+}
+
+/// Search scores turned into move probabilities. Scores are scaled by the
+/// best one so the temperature means the same early and late in a game.
+fn policy(result: &SearchResult, temperature: f32) -> [f32; 4] {
+    let mut policy = [0.0; 4];
+    let Some(&(best_move, best)) = result.move_scores.first() else {
+        return policy;
+    };
+    if temperature <= 0.0 {
+        policy[direction_index(best_move)] = 1.0;
+        return policy;
+    }
+    let scale = temperature * best.abs().max(1.0);
+    for &(direction, score) in &result.move_scores {
+        policy[direction_index(direction)] = ((score - best) / scale).exp();
+    }
+    let total: f32 = policy.iter().sum();
+    policy.map(|p| p / total)
+}
+
+fn sample(policy: &[f32; 4], rng: &mut impl Rng) -> Direction {
+    let mut pick = rng.gen::<f32>();
+    for (direction, &p) in Direction::all().into_iter().zip(policy) {
+        if pick < p {
+            return direction;
+        }
+        pick -= p;
+    }
+    // Rounding left a sliver past the last move; take the likeliest.
+    let best = (0..4).max_by(|&a, &b| policy[a].total_cmp(&policy[b])).unwrap();
+    Direction::all()[best]
+}
+
+/// The eight rotations and reflections of a 4x4 board, as the cell each
+/// position moves to and the direction each move turns into.
+fn symmetries() -> impl Iterator<Item = (impl Fn(usize, usize) -> (usize, usize), impl Fn(Direction) -> Direction)> {
+    (0..8).map(|symmetry: u32| {
+        let (turns, mirror) = (symmetry % 4, symmetry >= 4);
+        let cell = move |row: usize, col: usize| {
+            let (mut row, mut col) = if mirror { (row, 3 - col) } else { (row, col) };
+            for _ in 0..turns {
+                (row, col) = (col, 3 - row);
+            }
+            (row, col)
+        };
+        let direction = move |direction: Direction| {
+            let mut direction = match (mirror, direction) {
+                (true, Direction::Left) => Direction::Right,
+                (true, Direction::Right) => Direction::Left,
+                (_, direction) => direction,
+            };
+            for _ in 0..turns {
+                direction = match direction {
+                    Direction::Up => Direction::Right,
+                    Direction::Right => Direction::Down,
+                    Direction::Down => Direction::Left,
+                    Direction::Left => Direction::Up,
+                };
+            }
+            direction
+        };
+        (cell, direction)
+    })
+}
+
+/// `record` and, when `augment` is set, its seven symmetric copies.
+fn augmented(record: DatasetRecord, augment: bool) -> Vec<DatasetRecord> {
+    if !augment {
+        return vec![record];
+    }
+    symmetries()
+        .map(|(cell, direction)| {
+            let mut copy = record;
+            for (index, &rank) in record.cells.iter().enumerate() {
+                let (row, col) = cell(index / 4, index % 4);
+                copy.cells[row * 4 + col] = rank;
+            }
+            for (from, &p) in Direction::all().into_iter().zip(&record.policy) {
+                copy.policy[direction_index(direction(from))] = p;
+            }
+            copy.played = direction(record.played);
+            copy
+        })
+        .collect()
+}
+
+/// Plays one game with seed `seed` and returns a record for every move,
+/// labelled with how the game ended.
+pub fn play_game(seed: u64, config: &DatasetConfig) -> Vec<DatasetRecord> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut board = GameBoard::new_with_rng(&mut rng);
+    let mut tt = TranspositionState::new();
+    let mut score = 0;
+    let mut positions = Vec::new();
+    while !board.is_game_over() && board.get_move_count() < config.max_moves {
+        let result = board.search_with(&config.search, &mut tt);
+        if result.best_move.is_none() {
+            break;
+        }
+        let policy = policy(&result, config.temperature);
+        let played = if config.temperature > 0.0 { sample(&policy, &mut rng) } else { result.best_move.unwrap() };
+        let mut cells = [0; 16];
+        for (index, &value) in board.board.iter().flatten().enumerate() {
+            cells[index] = board.rules().rank(value) as u8;
+        }
+        positions.push((cells, policy, played));
+        match board.play_move_with(played, &mut rng) {
+            Ok(result) => score += result.score_gained,
+            Err(_) => break,
+        }
+    }
+    let total = positions.len() as u32;
+    positions
+        .into_iter()
+        .enumerate()
+        .flat_map(|(index, (cells, policy, played))| {
+            let record = DatasetRecord {
+                cells,
+                policy,
+                played,
+                final_score: score,
+                final_max_tile: board.get_max_tile(),
+                moves_left: total - index as u32,
+            };
+            augmented(record, config.augment)
+        })
+        .collect()
+}
+
+/// Plays `count` games, game `i` with seed `config.seed + i`, yielding
+/// each game's records as it finishes.
+pub fn play_games(config: &DatasetConfig, count: u64) -> impl Iterator<Item = Vec<DatasetRecord>> + '_ {
+    (0..count).map(move |game| play_game(config.seed.wrapping_add(game), config))
+}
+
+/// Writes a dataset file: the header, then records as they come.
+pub struct DatasetWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> DatasetWriter<W> {
+    pub fn new(mut out: W) -> io::Result<Self> {
+        out.write_all(&DATASET_MAGIC)?;
+        out.write_all(&DATASET_VERSION.to_le_bytes())?;
+        out.write_all(&(RECORD_SIZE as u32).to_le_bytes())?;
+        Ok(Self { out })
+    }
+
+    pub fn write(&mut self, record: &DatasetRecord) -> io::Result<()> {
+        let mut bytes = [0u8; RECORD_SIZE];
+        bytes[..16].copy_from_slice(&record.cells);
+        for (i, p) in record.policy.iter().enumerate() {
+            bytes[16 + 4 * i..20 + 4 * i].copy_from_slice(&p.to_le_bytes());
+        }
+        bytes[32] = direction_index(record.played) as u8;
+        bytes[36..40].copy_from_slice(&record.final_score.to_le_bytes());
+        bytes[40..44].copy_from_slice(&record.final_max_tile.to_le_bytes());
+        bytes[44..48].copy_from_slice(&record.moves_left.to_le_bytes());
+        self.out.write_all(&bytes)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+/// Reads a dataset file written by [`DatasetWriter`].
+pub fn read_dataset<R: Read>(mut input: R) -> io::Result<Vec<DatasetRecord>> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let mut header = [0u8; 16];
+    input.read_exact(&mut header)?;
+    if header[..8] != DATASET_MAGIC {
+        return Err(invalid("not a dataset file"));
+    }
+    let word = |bytes: &[u8]| u32::from_le_bytes(bytes.try_into().unwrap());
+    if word(&header[8..12]) != DATASET_VERSION || word(&header[12..16]) as usize != RECORD_SIZE {
+        return Err(invalid("unsupported dataset version"));
+    }
+    let mut data = Vec::new();
+    input.read_to_end(&mut data)?;
+    if data.len() % RECORD_SIZE != 0 {
+        return Err(invalid("truncated dataset record"));
+    }
+    data.chunks_exact(RECORD_SIZE)
+        .map(|bytes| {
+            let played = *Direction::all().get(bytes[32] as usize).ok_or_else(|| invalid("invalid move index"))?;
+            Ok(DatasetRecord {
+                cells: bytes[..16].try_into().unwrap(),
+                policy: [0, 1, 2, 3].map(|i| f32::from_le_bytes(bytes[16 + 4 * i..20 + 4 * i].try_into().unwrap())),
+                played,
+                final_score: word(&bytes[36..40]),
+                final_max_tile: word(&bytes[40..44]),
+                moves_left: word(&bytes[44..48]),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dataset_round_trips_with_augmentation() {
+        let config = DatasetConfig {
+            search: SearchConfig::with_depth(1),
+            max_moves: 20,
+            temperature: 0.5,
+            augment: true,
+            ..DatasetConfig::default()
+        };
+        let records = play_game(3, &config);
+        assert_eq!(records.len(), 8 * 20);
+        assert!(records.iter().all(|r| (r.policy.iter().sum::<f32>() - 1.0).abs() < 1e-5));
+        assert_eq!(records[0].moves_left, 20);
+
+        let mut writer = DatasetWriter::new(Vec::new()).unwrap();
+        for record in &records {
+            writer.write(record).unwrap();
+        }
+        let bytes = writer.into_inner();
+        assert_eq!(bytes.len(), 16 + RECORD_SIZE * records.len());
+        assert_eq!(read_dataset(&bytes[..]).unwrap(), records);
+    }
+
+    #[test]
+    fn test_symmetric_copies_turn_moves_with_the_board() {
+        // Right is legal here and Left is not.
+        let record = DatasetRecord {
+            cells: [1, 2, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            policy: [0.0, 0.0, 0.25, 0.75],
+            played: Direction::Right,
+            final_score: 0,
+            final_max_tile: 8,
+            moves_left: 1,
+        };
+        let copies = augmented(record, true);
+        assert_eq!(copies.len(), 8);
+        for copy in copies {
+            let mut board = GameBoard::new();
+            board.set_board([0, 1, 2, 3].map(|row| {
+                [0, 1, 2, 3].map(|col| match copy.cells[row * 4 + col] {
+                    0 => 0,
+                    rank => 1 << rank,
+                })
+            }));
+            assert!(board.can_move(copy.played));
+            let illegal = Direction::all()[copy.policy.iter().position(|&p| p == 0.25).unwrap()];
+            assert!(!board.can_move(illegal));
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use serde::Serialize;
+
+use super::search::SearchConfig;
+use crate::cache::TranspositionState;
+use crate::game::{Direction, GameBoard, SpawnRules};
+
+/// Moves ahead the survival chance looks.
+const SURVIVAL_PLIES: u32 = 3;
+
+/// Depth of the search that measures the gap between the best moves.
+const GAP_DEPTH: u32 = 2;
+
+/// Share of the rating from how full the board is, how narrow the best
+/// move's lead makes the choice, and the chance of dying soon.
+const CROWDING_WEIGHT: f32 = 0.3;
+const GAP_WEIGHT: f32 = 0.3;
+const DANGER_WEIGHT: f32 = 0.4;
+
+/// How critical a position is, as one number and the parts it combines.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Difficulty {
+    /// From 0 (anything goes) to 1 (one slip loses the game).
+    pub rating: f32,
+    pub empty_cells: usize,
+    /// Lead of the best move over the runner-up, see
+    /// [`crate::SearchResult::score_gap`]. A large lead means only one move
+    /// is any good.
+    pub eval_gap: f32,
+    /// Chance the best play still has a move after the next few spawns.
+    pub survival: f32,
+}
+
+/// Rates `board` for the time manager, puzzles and move feedback alike.
+pub fn rate_difficulty(board: &GameBoard) -> Difficulty {
+    rate_difficulty_with(board, &SpawnRules::default())
+}
+
+/// [`rate_difficulty`] for a game spawning by `rules`.
+pub fn rate_difficulty_with(board: &GameBoard, rules: &SpawnRules) -> Difficulty {
+    let empty_cells = board.count_empty_cells();
+    let search = SearchConfig { spawn: *rules, ..SearchConfig::with_depth(GAP_DEPTH) };
+    let eval_gap = board
+        .search_with(&search, &mut TranspositionState::new())
+        .score_gap()
+        .clamp(0.0, 1.0);
+    let spawns = rules.weights(board.variant);
+    let survival = survival(board, &spawns, SURVIVAL_PLIES);
+    let crowding = 1.0 - empty_cells as f32 / 16.0;
+    Difficulty {
+        rating: CROWDING_WEIGHT * crowding + GAP_WEIGHT * eval_gap + DANGER_WEIGHT * (1.0 - survival),
+        empty_cells,
+        eval_gap,
+        survival,
+    }
+}
+
+/// Chance that best play on `board` survives the next few spawns of a
+/// game spawning by `rules`.
+pub(crate) fn survival_chance(board: &GameBoard, rules: &SpawnRules) -> f32 {
+    survival(board, &rules.weights(board.variant), SURVIVAL_PLIES)
+}
+
+/// Chance that best play on `board` is still alive after `plies` moves and
+/// their spawns. A spawn takes one cell and a move frees at least none, so
+/// a board with more empty cells than plies cannot die in time.
+fn survival(board: &GameBoard, spawns: &[(u32, u32)], plies: u32) -> f32 {
+    if board.is_game_over() {
+        return 0.0;
+    }
+    if plies == 0 || board.count_empty_cells() > plies as usize {
+        return 1.0;
+    }
+    let total_weight: u32 = spawns.iter().map(|&(_, weight)| weight).sum();
+    Direction::all()
+        .into_iter()
+        .filter_map(|direction| board.child(direction))
+        .map(|child| {
+            let cells = child.empty_cells();
+            let mut alive = 0.0;
+            for (row, col) in cells {
+                for &(value, weight) in spawns {
+                    let mut next = child.clone();
+                    next.place_tile(row, col, value);
+                    alive += weight as f32 * survival(&next, spawns, plies - 1);
+                }
+            }
+            alive / (total_weight as usize * cells.len()) as f32
+        })
+        .fold(0.0, f32::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(cells: [[u32; 4]; 4]) -> GameBoard {
+        let mut game = GameBoard::new();
+        game.set_board(cells);
+        game
+    }
+
+    #[test]
+    fn test_crowded_positions_rate_harder() {
+        let open = rate_difficulty(&board([
+            [2, 0, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 2],
+        ]));
+        assert_eq!(open.survival, 1.0);
+
+        // Only the top row can merge, and most spawns in the freed cell end the game.
+        let cornered = rate_difficulty(&board([
+            [2, 2, 8, 16],
+            [32, 64, 128, 256],
+            [512, 8, 2, 8],
+            [4, 2, 4, 2],
+        ]));
+        assert!(cornered.survival < 1.0);
+        assert!(cornered.rating > open.rating);
+        assert!((0.0..=1.0).contains(&cornered.rating));
+    }
+}
@@ -1,4 +1,5 @@
 use crate::game::GameBoard;
+use super::row_tables::{BoardLines, ROW_TABLES};
 
 #[derive(Debug, Clone)]
 pub struct EvaluationWeights {
@@ -116,117 +117,15 @@ impl GameBoard {
     }
 
     pub(crate) fn calculate_monotonicity(&self) -> f32 {
-        let mut monotonicity = 0.0;
-        for i in 0..4 {
-            let mut current = 0;
-            let mut next = current + 1;
-            let mut current_direction = 0;
-            let mut score = 0.0;
-            while next < 4 {
-                while next < 4 && self.board[i][next] == 0 {
-                    next += 1;
-                }
-                if next >= 4 {
-                    break;
-                }
-                let current_value = self.board[i][current] as f32;
-                let next_value = self.board[i][next] as f32;
-                if current_value > next_value {
-                    match current_direction.cmp(&0) {
-                        std::cmp::Ordering::Greater => score = 0.0,
-                        std::cmp::Ordering::Less => score += next_value,
-                        std::cmp::Ordering::Equal => score = next_value,
-                    }
-                    current_direction = -1;
-                } else if next_value > current_value {
-                    match current_direction.cmp(&0) {
-                        std::cmp::Ordering::Less => score = 0.0,
-                        std::cmp::Ordering::Greater => score += current_value,
-                        std::cmp::Ordering::Equal => score = current_value,
-                    }
-                    current_direction = 1;
-                }
-                current = next;
-                next += 1;
-            }
-            monotonicity += score;
-        }
-        for j in 0..4 {
-            let mut current = 0;
-            let mut next = current + 1;
-            let mut current_direction = 0;
-            let mut score = 0.0;
-            while next < 4 {
-                while next < 4 && self.board[next][j] == 0 {
-                    next += 1;
-                }
-                if next >= 4 {
-                    break;
-                }
-                let current_value = self.board[current][j] as f32;
-                let next_value = self.board[next][j] as f32;
-                if current_value > next_value {
-                    match current_direction.cmp(&0) {
-                        std::cmp::Ordering::Greater => score = 0.0,
-                        std::cmp::Ordering::Less => score += next_value,
-                        std::cmp::Ordering::Equal => score = next_value,
-                    }
-                    current_direction = -1;
-                } else if next_value > current_value {
-                    match current_direction.cmp(&0) {
-                        std::cmp::Ordering::Less => score = 0.0,
-                        std::cmp::Ordering::Greater => score += current_value,
-                        std::cmp::Ordering::Equal => score = current_value,
-                    }
-                    current_direction = 1;
-                }
-                current = next;
-                next += 1;
-            }
-            monotonicity += score;
-        }
-        monotonicity
+        ROW_TABLES.monotonicity(&BoardLines::of(self))
     }
 
     pub(crate) fn calculate_smoothness(&self) -> f32 {
-        let mut smoothness = 0.0;
-        for i in 0..4 {
-            for j in 0..4 {
-                if self.board[i][j] != 0 {
-                    let current_value = self.board[i][j] as f32;
-                    if j < 3 && self.board[i][j + 1] != 0 {
-                        smoothness -= (current_value - self.board[i][j + 1] as f32).abs();
-                    }
-                    if i < 3 && self.board[i + 1][j] != 0 {
-                        smoothness -= (current_value - self.board[i + 1][j] as f32).abs();
-                    }
-                }
-            }
-        }
-        smoothness
+        ROW_TABLES.smoothness(&BoardLines::of(self))
     }
 
     pub(crate) fn calculate_merge_potential(&self) -> f32 {
-        let mut potential = 0.0;
-        for i in 0..4 {
-            for j in 0..4 {
-                if self.board[i][j] != 0 {
-                    let value = self.board[i][j];
-                    let adjacent_positions = [
-                        (i.wrapping_sub(1), j),
-                        (i + 1, j),
-                        (i, j.wrapping_sub(1)),
-                        (i, j + 1),
-                    ];
-                    for (ni, nj) in adjacent_positions {
-                        if ni < 4 && nj < 4 && self.board[ni][nj] == value {
-                            potential += value as f32;
-                        }
-                    }
-                }
-            }
-        }
-        potential
+        ROW_TABLES.merge_potential(&BoardLines::of(self))
     }
 
     pub(crate) fn calculate_snake_pattern(&self) -> f32 {
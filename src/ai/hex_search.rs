@@ -0,0 +1,130 @@
+use crate::game::{HexBoard, HexDirection};
+
+/// Weights of the hex evaluation's terms.
+const EMPTY_WEIGHT: f32 = 20.0;
+const MONOTONICITY_WEIGHT: f32 = 6.0;
+const SMOOTHNESS_WEIGHT: f32 = 2.0;
+const MERGE_WEIGHT: f32 = 6.0;
+const CORNER_WEIGHT: f32 = 10.0;
+
+/// The two acute corners of the rhombus, with two neighbours each: the
+/// hex grid's best anchors for the largest tile.
+const ANCHORS: [(usize, usize); 2] = [(0, 0), (3, 3)];
+
+impl HexBoard {
+    /// Static score of a hex position. There is no snake to follow on six
+    /// axes, so this is the rank-based variant evaluation carried over to
+    /// the hex grid: empty cells, monotonicity along all three axes,
+    /// smoothness and merges between the six neighbours of each cell, and
+    /// the largest tile in an acute corner.
+    pub fn evaluate(&self) -> f32 {
+        let rules = self.rules();
+        let rank = |(row, col): (usize, usize)| rules.rank(self.cells[row][col]) as i32;
+
+        let mut monotonicity = 0;
+        let mut smoothness = 0;
+        let mut merges = 0;
+        for direction in [HexDirection::Left, HexDirection::UpLeft, HexDirection::UpRight] {
+            for line in HexBoard::lines(direction) {
+                let (mut rising, mut falling) = (0, 0);
+                for pair in line.windows(2) {
+                    let step = rank(pair[1]) - rank(pair[0]);
+                    if step > 0 {
+                        rising += step;
+                    } else {
+                        falling -= step;
+                    }
+                    let [(r0, c0), (r1, c1)] = [pair[0], pair[1]];
+                    let (first, second) = (self.cells[r0][c0], self.cells[r1][c1]);
+                    if first == 0 || second == 0 {
+                        continue;
+                    }
+                    match rules.merge(first, second) {
+                        Some(merged) => merges += rules.rank(merged) as i32,
+                        None => smoothness -= step.abs(),
+                    }
+                }
+                monotonicity -= rising.min(falling);
+            }
+        }
+
+        let top_rank = rules.rank(self.get_max_tile()) as i32;
+        let corner = if ANCHORS.iter().any(|&cell| rank(cell) == top_rank) { top_rank } else { 0 };
+
+        let terms = [
+            (self.empty_cells().count() as i32, EMPTY_WEIGHT),
+            (monotonicity, MONOTONICITY_WEIGHT),
+            (smoothness, SMOOTHNESS_WEIGHT),
+            (merges, MERGE_WEIGHT),
+            (corner, CORNER_WEIGHT),
+        ];
+        terms.iter().map(|&(value, weight)| value as f32 * weight).sum()
+    }
+
+    /// Best move by a plain expectimax `depth` player moves deep, or `None`
+    /// once the game is over.
+    pub fn best_move(&self, depth: u32) -> Option<HexDirection> {
+        self.legal_moves()
+            .filter_map(|direction| {
+                let (cells, _) = self.slide(direction)?;
+                let child = HexBoard { cells, ..self.clone() };
+                Some((direction, child.chance_value(depth.max(1) - 1)))
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(direction, _)| direction)
+    }
+
+    fn max_value(&self, depth: u32) -> f32 {
+        if depth == 0 {
+            return self.evaluate();
+        }
+        self.legal_moves()
+            .filter_map(|direction| self.slide(direction))
+            .map(|(cells, _)| HexBoard { cells, ..self.clone() }.chance_value(depth - 1))
+            .max_by(f32::total_cmp)
+            .unwrap_or(-100000.0)
+    }
+
+    /// Expected value over every spawn on every empty cell.
+    fn chance_value(&self, depth: u32) -> f32 {
+        let spawns = self.rules().spawns();
+        let spawn_weight: u32 = spawns.iter().map(|&(_, weight)| weight).sum();
+        let empty: Vec<_> = self.empty_cells().collect();
+        if empty.is_empty() {
+            return self.max_value(depth);
+        }
+        let mut total = 0.0;
+        for &(row, col) in &empty {
+            for &(value, weight) in spawns {
+                let mut child = self.clone();
+                child.cells[row][col] = value;
+                total += child.max_value(depth) * weight as f32 / spawn_weight as f32;
+            }
+        }
+        total / empty.len() as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::game::{HexBoard, Variant};
+
+    #[test]
+    fn test_search_plays_hex_games_of_each_variant() {
+        for variant in Variant::ALL {
+            let mut rng = StdRng::seed_from_u64(5);
+            let mut game = HexBoard::new_with_variant(&mut rng, variant);
+            while let Some(direction) = game.best_move(1) {
+                game.play_move_with(direction, &mut rng).unwrap();
+                if game.move_count == 40 {
+                    break;
+                }
+            }
+            assert!(game.move_count > 0);
+            assert!(game.cells.iter().flatten().all(|&v| v == 0 || variant.rules().is_tile(v)));
+        }
+    }
+}
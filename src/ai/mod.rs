@@ -1,9 +1,44 @@
 mod solver;
+mod search;
 mod evaluation;
 mod optimized_evaluation;
 mod move_ordering;
 mod chance_node_optimization;
+mod coach;
+mod config_diff;
+pub mod dataset;
 mod adaptive_search;
+mod battle;
+mod difficulty;
+mod row_tables;
+mod variant_evaluation;
+mod hex_search;
+mod puzzle;
+mod puzzle_generator;
+mod time_attack;
+mod adaptation;
+mod preview;
+mod power_ups;
+mod rollout;
+pub mod score_model;
+pub mod trace;
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+pub mod selfplay;
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+pub mod arena;
 
+pub use adaptation::WeightAdapter;
+pub use coach::{Coach, GameReview, MoveReview};
+pub use config_diff::{ConfigDiff, MoveDelta, PvStep};
+pub use difficulty::{rate_difficulty, rate_difficulty_with, Difficulty};
 pub use evaluation::EvaluationWeights;
-pub use optimized_evaluation::OptimizedEvaluationWeights; 
\ No newline at end of file
+pub use optimized_evaluation::{EvaluationTerm, OptimizedEvaluationWeights, FIXED_SCORE_LIMIT};
+pub use power_ups::{PowerUpPlayer, PowerUpTurn, UNDO_MARGIN};
+pub use preview::Preview;
+pub use puzzle::{PuzzleGoal, PuzzleSolution};
+pub use puzzle_generator::{MovePuzzle, PuzzleGenerator, PuzzleGrade};
+pub use rollout::{MoveEstimate, RolloutConfig, RolloutResult};
+pub use score_model::ScoreModel;
+pub use search::{ChanceSampling, OrderingStats, SearchConfig, SearchProfile, SearchResult, ORDERING_DEPTHS, POWER_UP_MARGIN};
+pub use time_attack::{Budget, TimeAttack};
+pub use trace::{SearchTrace, TraceEvent};
@@ -1,10 +1,16 @@
+use arrayvec::ArrayVec;
+
 use crate::game::{GameBoard, Direction};
 
+/// Boards `order_moves` clones per legal move: the child scored by
+/// `fast_move_score` and the one `count_merges_after_move` inspects.
+pub(crate) const CLONES_PER_ORDERED_MOVE: u64 = 2;
+
 impl GameBoard {
     // Enhanced move ordering with multiple heuristics
-    pub(crate) fn order_moves(&self) -> Vec<Direction> {
+    pub(crate) fn order_moves(&self) -> ArrayVec<Direction, 4> {
         let directions = Direction::all();
-        let mut move_scores: Vec<(Direction, f32)> = directions.iter()
+        let mut move_scores: ArrayVec<(Direction, f32), 4> = directions.iter()
             .map(|&direction| {
                 let score = self.fast_move_score(direction);
                 (direction, score)
@@ -20,10 +26,9 @@ impl GameBoard {
     
     // Ultra-fast move scoring for ordering (no deep evaluation)
     fn fast_move_score(&self, direction: Direction) -> f32 {
-        let mut new_board = self.clone();
-        if !new_board.move_tiles(direction) {
+        let Some(new_board) = self.child(direction) else {
             return f32::NEG_INFINITY;
-        }
+        };
         
         let mut score = 0.0;
         
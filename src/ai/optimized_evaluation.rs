@@ -1,6 +1,17 @@
+use serde::{Deserialize, Serialize};
+
 use crate::game::GameBoard;
+use super::row_tables::{BoardLines, FIXED_ROW_TABLES, ROW_TABLES};
+
+/// Fixed-point weights are in 1/256ths.
+const WEIGHT_ONE: i64 = 256;
 
-#[derive(Debug, Clone)]
+/// Largest fixed-point evaluation magnitude: every whole number up to 2^24
+/// is exact in f32, so fixed-point scores survive the search's f32 values
+/// and expectations unchanged.
+pub const FIXED_SCORE_LIMIT: i32 = (1 << 24) - 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct OptimizedEvaluationWeights {
     pub monotonicity: f32,
     pub smoothness: f32,
@@ -10,6 +21,20 @@ pub struct OptimizedEvaluationWeights {
     pub position: f32,
 }
 
+/// One weighted term of the score-optimized evaluation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EvaluationTerm {
+    pub name: &'static str,
+    pub value: f32,
+    pub weight: f32,
+}
+
+impl EvaluationTerm {
+    pub fn contribution(&self) -> f32 {
+        self.weight * self.value
+    }
+}
+
 impl OptimizedEvaluationWeights {
     // Score-optimized weights - prioritize moves that create higher scores
     pub fn for_game_state(max_tile: u32, empty_cells: usize) -> Self {
@@ -30,6 +55,48 @@ impl OptimizedEvaluationWeights {
             position: 1.2 + progress * 0.8,          // Better tile positioning
         }
     }
+
+    /// All weights set to `value`; `uniform(1.0)` is the identity scale.
+    pub fn uniform(value: f32) -> Self {
+        Self {
+            monotonicity: value,
+            smoothness: value,
+            empty: value,
+            corner: value,
+            merge: value,
+            position: value,
+        }
+    }
+
+    /// The weights in field order.
+    pub fn to_array(&self) -> [f32; 6] {
+        [self.monotonicity, self.smoothness, self.empty, self.corner, self.merge, self.position]
+    }
+
+    pub fn from_array([monotonicity, smoothness, empty, corner, merge, position]: [f32; 6]) -> Self {
+        Self { monotonicity, smoothness, empty, corner, merge, position }
+    }
+
+    /// Identifies these weights for caches of evaluations made with them;
+    /// never 0, which stands for the built-in weights.
+    pub fn fingerprint(&self) -> u64 {
+        self.to_array()
+            .iter()
+            .fold(0xcbf2_9ce4_8422_2325_u64, |hash, w| (hash ^ w.to_bits() as u64).wrapping_mul(0x0100_0000_01b3))
+            .max(1)
+    }
+
+    /// Multiplies each weight by the matching entry of `scale`.
+    pub fn scaled_by(&self, scale: &Self) -> Self {
+        Self {
+            monotonicity: self.monotonicity * scale.monotonicity,
+            smoothness: self.smoothness * scale.smoothness,
+            empty: self.empty * scale.empty,
+            corner: self.corner * scale.corner,
+            merge: self.merge * scale.merge,
+            position: self.position * scale.position,
+        }
+    }
 }
 
 impl GameBoard {
@@ -40,46 +107,115 @@ impl GameBoard {
 
     // Score-optimized evaluation - specifically designed to maximize score
     pub fn evaluate_board_for_score(&self) -> f32 {
-        let max_tile = self.get_max_tile();
+        let weights = OptimizedEvaluationWeights::for_game_state(self.get_max_tile(), self.count_empty_cells());
+        self.evaluate_with_weights(&weights)
+    }
+
+    /// Score-optimized evaluation with the stage-adaptive weights multiplied
+    /// term by term by `scale` (e.g. from a solver config file).
+    pub fn evaluate_board_scaled(&self, scale: &OptimizedEvaluationWeights) -> f32 {
+        let weights = OptimizedEvaluationWeights::for_game_state(self.get_max_tile(), self.count_empty_cells());
+        self.evaluate_with_weights(&weights.scaled_by(scale))
+    }
+
+    /// Every term of the score-optimized evaluation with its raw value and
+    /// weight; the evaluation is the sum of their contributions. `scale` is
+    /// applied as in [`GameBoard::evaluate_board_scaled`].
+    pub fn evaluation_breakdown(&self, scale: Option<&OptimizedEvaluationWeights>) -> [EvaluationTerm; 9] {
+        let mut weights = OptimizedEvaluationWeights::for_game_state(self.get_max_tile(), self.count_empty_cells());
+        if let Some(scale) = scale {
+            weights = weights.scaled_by(scale);
+        }
+        self.evaluation_terms(&weights)
+    }
+
+    /// The score-optimized evaluation in whole points, computed entirely in
+    /// integers: weights are rounded to 1/256ths and every term is exact in
+    /// tenths of a point, so the result is the same on every platform.
+    pub fn evaluate_board_fixed(&self, scale: Option<&OptimizedEvaluationWeights>) -> i32 {
+        let mut weights = OptimizedEvaluationWeights::for_game_state(self.get_max_tile(), self.count_empty_cells());
+        if let Some(scale) = scale {
+            weights = weights.scaled_by(scale);
+        }
+        let fixed = |weight: f32| (weight * WEIGHT_ONE as f32).round() as i64;
+        let lines = BoardLines::of(self);
+        let tables = &*FIXED_ROW_TABLES;
+        // Position scores are whole numbers below 2^24, so exact in f32.
+        let position = self.calculate_position_score() as i64 * 10;
+
+        let terms = [
+            (tables.monotonicity(&lines) as i64, fixed(weights.monotonicity)),
+            (tables.smoothness(&lines) as i64, fixed(weights.smoothness)),
+            (self.count_empty_cells() as i64 * 10, fixed(weights.empty)),
+            (self.get_max_tile() as i64 * self.corner_factor() * 10, fixed(weights.corner)),
+            (tables.merge_potential(&lines) as i64, fixed(weights.merge)),
+            (position, fixed(weights.position)),
+            (self.score_potential_tenths(), 2 * WEIGHT_ONE),
+            (tables.chain_merge(&lines) as i64, 3 * WEIGHT_ONE / 2),
+            (tables.edge_control(&lines) as i64, fixed(0.8)),
+        ];
+        let total: i64 = terms.iter().map(|&(value, weight)| value * weight).sum();
+        total
+            .div_euclid(10 * WEIGHT_ONE)
+            .clamp(-FIXED_SCORE_LIMIT as i64, FIXED_SCORE_LIMIT as i64) as i32
+    }
+
+    fn evaluate_with_weights(&self, weights: &OptimizedEvaluationWeights) -> f32 {
+        self.evaluation_terms(weights)
+            .iter()
+            .map(EvaluationTerm::contribution)
+            .sum()
+    }
+
+    fn evaluation_terms(&self, weights: &OptimizedEvaluationWeights) -> [EvaluationTerm; 9] {
         let empty_cells = self.count_empty_cells();
-        let weights = OptimizedEvaluationWeights::for_game_state(max_tile, empty_cells);
+        // Line terms are table lookups on the packed rows and columns.
+        let lines = BoardLines::of(self);
 
         // Base score components
-        let monotonicity = self.calculate_monotonicity();
-        let smoothness = self.calculate_smoothness();
+        let monotonicity = ROW_TABLES.monotonicity(&lines);
+        let smoothness = ROW_TABLES.smoothness(&lines);
         let empty_score = empty_cells as f32;
         let corner_bonus = self.calculate_corner_bonus_optimized();
-        let merge_potential = self.calculate_merge_potential();
+        let merge_potential = ROW_TABLES.merge_potential(&lines);
         let position_score = self.calculate_position_score();
 
         // Score-specific bonuses
         let score_bonus = self.calculate_score_potential_bonus();
-        let chain_bonus = self.calculate_chain_merge_bonus();
-        let edge_control = self.calculate_edge_control_bonus();
+        let chain_bonus = ROW_TABLES.chain_merge(&lines);
+        let edge_control = ROW_TABLES.edge_control(&lines);
 
-        weights.monotonicity * monotonicity
-            + weights.smoothness * smoothness
-            + weights.empty * empty_score
-            + weights.corner * corner_bonus
-            + weights.merge * merge_potential
-            + weights.position * position_score
-            + score_bonus * 2.0           // High weight for score potential
-            + chain_bonus * 1.5           // Chain merges are great for score
-            + edge_control * 0.8          // Edge control helps maintain structure
+        let term = |name, value, weight| EvaluationTerm { name, value, weight };
+        [
+            term("monotonicity", monotonicity, weights.monotonicity),
+            term("smoothness", smoothness, weights.smoothness),
+            term("empty", empty_score, weights.empty),
+            term("corner", corner_bonus, weights.corner),
+            term("merge", merge_potential, weights.merge),
+            term("position", position_score, weights.position),
+            term("score_potential", score_bonus, 2.0),   // High weight for score potential
+            term("chain_merge", chain_bonus, 1.5),       // Chain merges are great for score
+            term("edge_control", edge_control, 0.8),     // Edge control helps maintain structure
+        ]
     }
 
     // One-corner bias: strong preference for max tile in top-left (0,0); other corners weaker.
     fn calculate_corner_bonus_optimized(&self) -> f32 {
+        self.get_max_tile() as f32 * self.corner_factor() as f32
+    }
+
+    /// Multiple of the max tile awarded for where it sits.
+    fn corner_factor(&self) -> i64 {
         let max_tile = self.get_max_tile();
 
         if self.board[0][0] == max_tile {
-            return max_tile as f32 * 20.0; // Primary corner: top-left
+            return 20; // Primary corner: top-left
         }
 
         let other_corners = [(0, 3), (3, 0), (3, 3)];
         for &(row, col) in &other_corners {
             if self.board[row][col] == max_tile {
-                return max_tile as f32 * 8.0; // Secondary corners
+                return 8; // Secondary corners
             }
         }
 
@@ -87,20 +223,37 @@ impl GameBoard {
             for col in 0..4 {
                 if self.board[row][col] == max_tile {
                     if row == 0 || row == 3 || col == 0 || col == 3 {
-                        return max_tile as f32 * 2.0;
+                        return 2;
                     }
-                    return -(max_tile as f32 * 2.0);
+                    return -2;
                 }
             }
         }
 
-        -(max_tile as f32)
+        -1
     }
 
     // Calculate bonus for moves that create scoring opportunities
     fn calculate_score_potential_bonus(&self) -> f32 {
         let mut bonus = 0.0;
-        
+        self.for_each_high_pair(|value, adjacent| {
+            bonus += value as f32 * if adjacent { 0.5 } else { 0.2 };
+        });
+        bonus
+    }
+
+    /// [`Self::calculate_score_potential_bonus`] in tenths of a point.
+    fn score_potential_tenths(&self) -> i64 {
+        let mut bonus = 0;
+        self.for_each_high_pair(|value, adjacent| {
+            bonus += value as i64 * if adjacent { 5 } else { 2 };
+        });
+        bonus
+    }
+
+    /// Calls `f(value, adjacent)` for every pair of equal tiles of 64 or
+    /// more at most three steps apart.
+    fn for_each_high_pair(&self, mut f: impl FnMut(u32, bool)) {
         // Bonus for having multiple high tiles that can merge
         let mut high_tiles = Vec::new();
         for i in 0..4 {
@@ -118,59 +271,13 @@ impl GameBoard {
                     let distance = (high_tiles[i].0 as i32 - high_tiles[j].0 as i32).abs() 
                                 + (high_tiles[i].1 as i32 - high_tiles[j].1 as i32).abs();
                     if distance == 1 {
-                        bonus += high_tiles[i].2 as f32 * 0.5; // Adjacent same tiles
+                        f(high_tiles[i].2, true); // Adjacent same tiles
                     } else if distance <= 3 {
-                        bonus += high_tiles[i].2 as f32 * 0.2; // Close same tiles
+                        f(high_tiles[i].2, false); // Close same tiles
                     }
                 }
             }
         }
-        
-        bonus
-    }
-
-    // Calculate bonus for chain merge opportunities
-    fn calculate_chain_merge_bonus(&self) -> f32 {
-        let mut bonus = 0.0;
-        
-        // Look for patterns like 2-4-8-16 that can chain merge
-        for i in 0..4 {
-            for j in 0..3 {
-                let current = self.board[i][j];
-                let next = self.board[i][j+1];
-                if current > 0 && next > 0 && (current == next/2 || next == current/2) {
-                    bonus += (current + next) as f32 * 0.3;
-                }
-            }
-        }
-        
-        for i in 0..3 {
-            for j in 0..4 {
-                let current = self.board[i][j];
-                let next = self.board[i+1][j];
-                if current > 0 && next > 0 && (current == next/2 || next == current/2) {
-                    bonus += (current + next) as f32 * 0.3;
-                }
-            }
-        }
-        
-        bonus
-    }
-
-    // Calculate bonus for controlling edges (helps maintain structure)
-    fn calculate_edge_control_bonus(&self) -> f32 {
-        let mut bonus = 0.0;
-        
-        // Bonus for having high tiles on edges
-        for i in 0..4 {
-            for j in 0..4 {
-                if self.board[i][j] > 0 && (i == 0 || i == 3 || j == 0 || j == 3) {
-                    bonus += self.board[i][j] as f32 * 0.1;
-                }
-            }
-        }
-        
-        bonus
     }
 }
 
@@ -209,4 +316,49 @@ mod tests {
         // Should complete in under 1ms
         assert!(duration.as_millis() < 1);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_fixed_evaluation_tracks_float_evaluation() {
+        let mut board = GameBoard::new();
+        board.set_board([
+            [1024, 512, 256, 128],
+            [64, 32, 16, 8],
+            [4, 2, 4, 2],
+            [0, 0, 64, 2]
+        ]);
+        let float = board.evaluate_board_optimized();
+        let fixed = board.evaluate_board_fixed(None);
+        assert!((fixed as f32 - float).abs() < 1e-3 * float.abs() + 1.0);
+
+        let scale = OptimizedEvaluationWeights::uniform(0.5);
+        assert!(board.evaluate_board_fixed(Some(&scale)) < fixed);
+
+        // A 65536 no longer fits the packed tables; both paths read the cells.
+        board.set_board([
+            [65536, 32768, 256, 128],
+            [64, 32, 16, 8],
+            [4, 2, 4, 2],
+            [0, 0, 64, 2]
+        ]);
+        let float = board.evaluate_board_optimized();
+        let fixed = board.evaluate_board_fixed(None);
+        assert!((fixed as f32 - float).abs() < 1e-3 * float.abs() + 1.0, "{} vs {}", fixed, float);
+    }
+
+    #[test]
+    fn test_breakdown_sums_to_evaluation() {
+        let mut board = GameBoard::new();
+        board.set_board([
+            [1024, 512, 256, 128],
+            [64, 32, 16, 8],
+            [4, 2, 4, 2],
+            [0, 0, 0, 2]
+        ]);
+        let total: f32 = board.evaluation_breakdown(None).iter().map(EvaluationTerm::contribution).sum();
+        assert_eq!(total, board.evaluate_board_optimized());
+
+        let scale = OptimizedEvaluationWeights::uniform(2.0);
+        let scaled = board.evaluation_breakdown(Some(&scale));
+        assert_eq!(scaled[0].weight, 2.0 * board.evaluation_breakdown(None)[0].weight);
+    }
+}
@@ -0,0 +1,93 @@
+//! Playing with power-ups. Swaps and deletes are root choices of the
+//! search itself (see [`SearchResult::power_up`]); undo is decided here,
+//! once the spawn after a move is known: when the position it left
+//! searches well below what the move promised, the spawn was unlucky and
+//! the move is taken back to be played again with a fresh spawn.
+
+use super::search::{SearchConfig, SearchResult};
+use crate::cache::TranspositionState;
+use crate::game::{PowerUp, PowerUpGame};
+
+/// Shortfall against the score the last move promised, relative to it,
+/// past which the player undoes the move.
+pub const UNDO_MARGIN: f32 = 0.1;
+
+/// The search's pick for a turn of a [`PowerUpGame`].
+#[derive(Debug, Clone)]
+pub struct PowerUpTurn {
+    /// Power-ups used this turn, already applied to the game.
+    pub used: Vec<PowerUp>,
+    /// The search of the position to move from, after them; play its
+    /// `best_move`.
+    pub search: SearchResult,
+}
+
+/// Chooses the power-ups and moves of a [`PowerUpGame`].
+#[derive(Debug, Clone)]
+pub struct PowerUpPlayer {
+    pub search: SearchConfig,
+    /// The score the search gave the move about to be played.
+    promised: Option<f32>,
+}
+
+impl PowerUpPlayer {
+    pub fn new(search: SearchConfig) -> Self {
+        Self { search, promised: None }
+    }
+
+    /// Uses whatever power-ups pay off on `game`'s position and searches
+    /// the move to play from the result. The caller plays
+    /// `search.best_move`.
+    pub fn choose(&mut self, game: &mut PowerUpGame, tt: &mut TranspositionState) -> PowerUpTurn {
+        let mut used = Vec::new();
+        let mut search = self.search_game(game, tt);
+        if let Some(promised) = self.promised.filter(|_| game.can_undo()) {
+            if search.score < promised - UNDO_MARGIN * promised.abs().max(1.0) && game.use_power_up(PowerUp::Undo).is_ok() {
+                used.push(PowerUp::Undo);
+                search = self.search_game(game, tt);
+            }
+        }
+        if let Some((power_up, _)) = search.power_up {
+            if game.use_power_up(power_up).is_ok() {
+                used.push(power_up);
+                search = self.search_game(game, tt);
+            }
+        }
+        self.promised = search.best_move.map(|_| search.score);
+        PowerUpTurn { used, search }
+    }
+
+    fn search_game(&self, game: &PowerUpGame, tt: &mut TranspositionState) -> SearchResult {
+        let config = SearchConfig { power_ups: *game.charges(), ..self.search.clone() };
+        game.board().search_with(&config, tt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GameBoard, PowerUps};
+
+    #[test]
+    fn test_power_ups_open_a_locked_board_and_are_kept_otherwise() {
+        let locked = GameBoard::from_compact("2,4,2,4/4,2,4,2/2,4,2,4/4,2,4,2").unwrap();
+        let charges = PowerUps { swap: 1, delete: 1, undo: 0 };
+        let config = SearchConfig { power_ups: charges, ..SearchConfig::with_depth(2) };
+        let result = locked.search_with(&config, &mut TranspositionState::new());
+        assert!(result.best_move.is_none());
+        // A delete empties a cell; a swap lines up two equal tiles.
+        let (power_up, score) = result.power_up.expect("a power-up opens the board");
+        assert!(score.is_finite());
+
+        let mut game = PowerUpGame::new(locked, charges);
+        let mut player = PowerUpPlayer::new(SearchConfig::with_depth(2));
+        let turn = player.choose(&mut game, &mut TranspositionState::new());
+        assert_eq!(turn.used, vec![power_up]);
+        assert_eq!(game.charges().charges(&power_up), 0);
+        assert!(turn.search.best_move.is_some());
+
+        // Early on nothing is worth a charge.
+        let open = GameBoard::from_compact("2,0,0,0/0,0,0,0/0,0,0,0/0,0,0,2").unwrap();
+        assert!(open.search_with(&config, &mut TranspositionState::new()).power_up.is_none());
+    }
+}
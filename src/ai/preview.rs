@@ -0,0 +1,90 @@
+use super::search::SearchConfig;
+use crate::cache::TranspositionState;
+use crate::game::{Direction, GameBoard, SpawnedTile, Variant};
+
+/// Where the engine expects the game to stand a few moves from now.
+#[derive(Debug, Clone)]
+pub struct Preview {
+    /// The engine's moves, in order; fewer than asked when the game ends
+    /// first.
+    pub moves: Vec<Direction>,
+    /// The spawn assumed after each move.
+    pub spawns: Vec<SpawnedTile>,
+    /// The position after the last move and spawn.
+    pub board: GameBoard,
+}
+
+impl GameBoard {
+    /// Plays `moves` of the engine's own moves from here, each searched with
+    /// `config`, assuming after each the most typical spawn: the likeliest
+    /// tile under `config.spawn`, on the cell whose placement evaluates to
+    /// the median of all placements. Every empty cell is equally likely, so
+    /// the median stands in for a spawn that is neither lucky nor unlucky.
+    pub fn preview(&self, config: &SearchConfig, moves: usize) -> Preview {
+        let mut tt = TranspositionState::new();
+        let mut board = self.clone();
+        let mut preview = Preview { moves: Vec::new(), spawns: Vec::new(), board: self.clone() };
+        let value = config
+            .spawn
+            .weights(self.variant)
+            .iter()
+            .max_by_key(|&&(_, weight)| weight)
+            .map_or(2, |&(value, _)| value);
+        while preview.moves.len() < moves {
+            let Some(direction) = board.search_with(config, &mut tt).best_move else { break };
+            if !board.move_tiles(direction) {
+                break;
+            }
+            let mut placements: Vec<_> = board
+                .empty_cells()
+                .map(|(row, col)| {
+                    let mut child = board.clone();
+                    child.place_tile(row, col, value);
+                    let eval = if child.variant == Variant::Classic {
+                        child.evaluate_board_optimized()
+                    } else {
+                        child.evaluate_variant(config.weight_scale.as_ref())
+                    };
+                    (eval, SpawnedTile { row, col, value })
+                })
+                .collect();
+            placements.sort_by(|a, b| a.0.total_cmp(&b.0));
+            preview.moves.push(direction);
+            if let Some(&(_, spawn)) = placements.get(placements.len() / 2) {
+                board.place_tile(spawn.row, spawn.col, spawn.value);
+                preview.spawns.push(spawn);
+            }
+        }
+        preview.board = board;
+        preview
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_replays_to_its_board() {
+        let mut board = GameBoard::new();
+        board.set_board([
+            [4, 2, 0, 0],
+            [2, 0, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 2]
+        ]);
+        let config = SearchConfig::with_depth(2);
+        let preview = board.preview(&config, 5);
+        assert_eq!(preview.moves.len(), 5);
+        assert_eq!(preview.spawns.len(), 5);
+        assert!(preview.spawns.iter().all(|spawn| spawn.value == 2));
+
+        let mut replayed = board.clone();
+        for (&direction, spawn) in preview.moves.iter().zip(&preview.spawns) {
+            assert!(replayed.move_tiles(direction));
+            replayed.place_tile(spawn.row, spawn.col, spawn.value);
+        }
+        assert_eq!(replayed.get_board(), preview.board.get_board());
+        assert_eq!(board.preview(&config, 0).board.get_board(), board.get_board());
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{Direction, GameBoard};
+
+/// What a puzzle asks for. Puzzles play without spawns
+/// ([`GameBoard::play_move_without_spawn`]), so each is solved exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PuzzleGoal {
+    /// The most points the starting tiles can still earn.
+    MaxScore,
+    /// A tile of at least this value, in as few moves as possible.
+    Tile(u32),
+}
+
+/// An optimal move sequence for a puzzle and where it leads.
+#[derive(Debug, Clone)]
+pub struct PuzzleSolution {
+    pub moves: Vec<Direction>,
+    pub score: u32,
+    pub final_board: GameBoard,
+}
+
+type Cells = [[u32; 4]; 4];
+
+impl GameBoard {
+    /// Solves the position as a puzzle for `goal`. `None` only when a
+    /// [`PuzzleGoal::Tile`] cannot be reached. Ties on score go to the
+    /// shorter sequence.
+    pub fn solve_puzzle(&self, goal: PuzzleGoal) -> Option<PuzzleSolution> {
+        let moves = match goal {
+            PuzzleGoal::MaxScore => best_score(self, &mut HashMap::new()).1,
+            PuzzleGoal::Tile(target) => shortest_to_tile(self, target)?,
+        };
+        let mut final_board = self.clone();
+        let mut score = 0;
+        for &direction in &moves {
+            score += final_board.play_move_without_spawn(direction).ok()?.score_gained;
+        }
+        Some(PuzzleSolution { moves, score, final_board })
+    }
+}
+
+/// Most points reachable from `board` and the moves that earn them. Every
+/// merge removes a tile, so the search ends; between merges, the positions
+/// reachable by plain slides are explored breadth-first, since they can
+/// cycle.
+fn best_score(board: &GameBoard, memo: &mut HashMap<Cells, (u32, Vec<Direction>)>) -> (u32, Vec<Direction>) {
+    if let Some(known) = memo.get(&board.board) {
+        return known.clone();
+    }
+    let mut best = (0, Vec::new());
+    let mut paths: HashMap<Cells, Vec<Direction>> = HashMap::from([(board.board, Vec::new())]);
+    let mut queue = VecDeque::from([board.clone()]);
+    while let Some(position) = queue.pop_front() {
+        let path = paths[&position.board].clone();
+        for direction in Direction::all() {
+            let mut next = position.clone();
+            let Ok(result) = next.play_move_without_spawn(direction) else { continue };
+            let mut moves = path.clone();
+            moves.push(direction);
+            if result.merged.is_empty() {
+                if let Entry::Vacant(entry) = paths.entry(next.board) {
+                    entry.insert(moves);
+                    queue.push_back(next);
+                }
+                continue;
+            }
+            let (rest_score, rest) = best_score(&next, memo);
+            let score = result.score_gained + rest_score;
+            if score > best.0 || (score == best.0 && moves.len() + rest.len() < best.1.len()) {
+                moves.extend(rest);
+                best = (score, moves);
+            }
+        }
+    }
+    memo.insert(board.board, best.clone());
+    best
+}
+
+/// Fewest moves from `board` to a tile of at least `target`, breadth-first.
+fn shortest_to_tile(board: &GameBoard, target: u32) -> Option<Vec<Direction>> {
+    let mut seen = HashSet::from([board.board]);
+    let mut queue = VecDeque::from([(board.clone(), Vec::new())]);
+    while let Some((position, path)) = queue.pop_front() {
+        if position.get_max_tile() >= target {
+            return Some(path);
+        }
+        for direction in Direction::all() {
+            let mut next = position.clone();
+            if next.play_move_without_spawn(direction).is_ok() && seen.insert(next.board) {
+                let mut moves = path.clone();
+                moves.push(direction);
+                queue.push_back((next, moves));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn puzzle(board: Cells) -> GameBoard {
+        let mut game = GameBoard::new();
+        game.set_board(board);
+        game
+    }
+
+    #[test]
+    fn test_puzzle_solver_finds_best_score_through_plain_slides() {
+        // The 2s only meet after a slide that merges nothing.
+        let scattered = puzzle([
+            [2, 0, 0, 0],
+            [0, 2, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0],
+        ]);
+        let solution = scattered.solve_puzzle(PuzzleGoal::MaxScore).unwrap();
+        assert_eq!((solution.score, solution.moves.len()), (4, 2));
+        assert_eq!(solution.final_board.count_empty_cells(), 15);
+
+        let chain = puzzle([
+            [2, 2, 4, 8],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0],
+        ]);
+        let solution = chain.solve_puzzle(PuzzleGoal::MaxScore).unwrap();
+        assert_eq!(solution.score, 4 + 8 + 16);
+        assert_eq!(solution.moves.len(), 3);
+    }
+
+    #[test]
+    fn test_puzzle_tile_goal_is_shortest_or_unreachable() {
+        let chain = puzzle([
+            [2, 2, 4, 8],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0],
+        ]);
+        let solution = chain.solve_puzzle(PuzzleGoal::Tile(16)).unwrap();
+        assert_eq!(solution.moves.len(), 3);
+        assert_eq!(solution.final_board.get_max_tile(), 16);
+        assert!(chain.solve_puzzle(PuzzleGoal::Tile(32)).is_none());
+    }
+}
@@ -0,0 +1,173 @@
+use std::fmt;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::Serialize;
+
+use super::difficulty::rate_difficulty;
+use super::search::SearchConfig;
+use crate::cache::TranspositionState;
+use crate::game::{Direction, GameBoard};
+
+/// How hard a "find the best move" puzzle is: the shallowest search depth
+/// from which every deeper search agrees on the answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PuzzleGrade {
+    /// A one-ply search already finds it.
+    Easy,
+    /// Found from two or three plies.
+    Medium,
+    /// Only deeper searches find it.
+    Hard,
+}
+
+impl PuzzleGrade {
+    fn from_depth(depth: u32) -> Self {
+        match depth {
+            0..=1 => PuzzleGrade::Easy,
+            2..=3 => PuzzleGrade::Medium,
+            _ => PuzzleGrade::Hard,
+        }
+    }
+}
+
+impl fmt::Display for PuzzleGrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PuzzleGrade::Easy => "easy",
+            PuzzleGrade::Medium => "medium",
+            PuzzleGrade::Hard => "hard",
+        })
+    }
+}
+
+/// A position where one move is clearly better than every other.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MovePuzzle {
+    /// The position in compact notation ([`GameBoard::from_compact`]).
+    pub position: String,
+    pub best_move: Direction,
+    /// How far the best move's score is ahead of the runner-up, as a
+    /// fraction of the best score, at the verifying depth.
+    pub gap: f32,
+    pub grade: PuzzleGrade,
+    /// [`crate::Difficulty::rating`] of the position.
+    pub difficulty: f32,
+    /// Move of its game the position arose at.
+    pub move_number: u32,
+}
+
+impl fmt::Display for MovePuzzle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {:?} ({}, gap {:.1}%)", self.position, self.best_move, self.grade, self.gap * 100.0)
+    }
+}
+
+/// Finds "find the best move" puzzles in AI self-play. Each game is played
+/// with the `play` search; a position whose best move leads the runner-up by
+/// at least `min_gap` is searched again at `verify_depth` and kept only if
+/// the deep search still has one move that far ahead.
+#[derive(Debug, Clone)]
+pub struct PuzzleGenerator {
+    /// Game `i` is played with seed `seed + i`.
+    pub seed: u64,
+    pub play: SearchConfig,
+    pub verify_depth: u32,
+    pub min_gap: f32,
+    /// Games to search before giving up on finding enough puzzles.
+    pub max_games: u64,
+    /// Games stop after this many moves.
+    pub max_moves: u32,
+}
+
+impl Default for PuzzleGenerator {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            play: SearchConfig::with_depth(2),
+            verify_depth: 5,
+            min_gap: 0.05,
+            max_games: 100,
+            max_moves: 2000,
+        }
+    }
+}
+
+impl PuzzleGenerator {
+    /// Up to `count` puzzles, in the order they were found.
+    pub fn generate(&self, count: usize) -> Vec<MovePuzzle> {
+        let mut puzzles = Vec::new();
+        let mut tt = TranspositionState::new();
+        for game in 0..self.max_games {
+            let mut rng = StdRng::seed_from_u64(self.seed.wrapping_add(game));
+            let mut board = GameBoard::new_with_rng(&mut rng);
+            while puzzles.len() < count && !board.is_game_over() && board.get_move_count() < self.max_moves {
+                let result = board.search_with(&self.play, &mut tt);
+                let Some(direction) = result.best_move else { break };
+                if result.score_gap() >= self.min_gap {
+                    puzzles.extend(self.verify(&board, &mut tt));
+                }
+                if board.play_move_with(direction, &mut rng).is_err() {
+                    break;
+                }
+            }
+            tt.clear();
+            if puzzles.len() >= count {
+                break;
+            }
+        }
+        puzzles
+    }
+
+    /// The puzzle at `board`, if a `verify_depth` search confirms one move
+    /// leads by `min_gap`.
+    pub fn verify(&self, board: &GameBoard, tt: &mut TranspositionState) -> Option<MovePuzzle> {
+        let deep = board.search_with(&SearchConfig::with_depth(self.verify_depth), tt);
+        let best_move = deep.best_move?;
+        let gap = deep.score_gap();
+        if gap < self.min_gap {
+            return None;
+        }
+        let mut solved_at = self.verify_depth;
+        while solved_at > 1
+            && board.search_with(&SearchConfig::with_depth(solved_at - 1), tt).best_move == Some(best_move)
+        {
+            solved_at -= 1;
+        }
+        Some(MovePuzzle {
+            position: board.to_compact(),
+            best_move,
+            gap,
+            grade: PuzzleGrade::from_depth(solved_at),
+            difficulty: rate_difficulty(board).rating,
+            move_number: board.get_move_count(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_puzzles_have_one_clear_best_move() {
+        let generator = PuzzleGenerator {
+            seed: 4,
+            play: SearchConfig::with_depth(1),
+            verify_depth: 3,
+            min_gap: 0.02,
+            max_games: 2,
+            max_moves: 150,
+        };
+        let puzzles = generator.generate(3);
+        assert!(!puzzles.is_empty());
+        for puzzle in &puzzles {
+            let board = GameBoard::from_compact(&puzzle.position).unwrap();
+            let deep = board.search_with(&SearchConfig::with_depth(3), &mut TranspositionState::new());
+            assert_eq!(deep.best_move, Some(puzzle.best_move));
+            assert!(deep.score_gap() >= generator.min_gap);
+            assert!(puzzle.grade <= PuzzleGrade::Medium);
+        }
+    }
+}
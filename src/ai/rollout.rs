@@ -0,0 +1,206 @@
+//! Monte Carlo move values: each root move scored by the points random
+//! playouts go on to make after it, with a confidence interval, sampled
+//! until the best move is statistically separated from the rest.
+//!
+//! Rollout `i` of every move draws from the same seed, so the moves are
+//! compared on the same luck and their differences settle sooner than the
+//! intervals alone suggest.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::game::{Direction, GameBoard, SpawnRules};
+
+/// How many rollouts to play and when to stop.
+#[derive(Debug, Clone)]
+pub struct RolloutConfig {
+    /// Rollouts of every move before separation is first checked.
+    pub min_rollouts: u32,
+    /// Rollouts of every move at most.
+    pub max_rollouts: u32,
+    /// Half-width of the confidence intervals in standard errors: 1.96
+    /// for 95%.
+    pub z: f64,
+    /// A playout stops after this many moves even if the game goes on.
+    pub max_moves: u32,
+    pub spawn: SpawnRules,
+    /// Rollout `i` of every move uses seed `seed + i`.
+    pub seed: u64,
+}
+
+impl Default for RolloutConfig {
+    fn default() -> Self {
+        Self {
+            min_rollouts: 20,
+            max_rollouts: 1000,
+            z: 1.96,
+            max_moves: 2000,
+            spawn: SpawnRules::default(),
+            seed: 0,
+        }
+    }
+}
+
+/// The value of one root move.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveEstimate {
+    pub direction: Direction,
+    pub rollouts: u32,
+    /// Mean points scored from the move to the end of the playout.
+    pub mean: f64,
+    /// Standard error of `mean`.
+    pub std_error: f64,
+}
+
+impl MoveEstimate {
+    /// `mean` plus and minus `z` standard errors.
+    pub fn interval(&self, z: f64) -> (f64, f64) {
+        (self.mean - z * self.std_error, self.mean + z * self.std_error)
+    }
+}
+
+/// Outcome of [`GameBoard::rollout_values`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RolloutResult {
+    /// Every legal move, best mean first.
+    pub moves: Vec<MoveEstimate>,
+    /// The best move's interval lies wholly above every other move's;
+    /// false when the rollout budget ran out first.
+    pub separated: bool,
+}
+
+impl RolloutResult {
+    pub fn best_move(&self) -> Option<Direction> {
+        self.moves.first().map(|estimate| estimate.direction)
+    }
+
+    /// Rollouts played across all moves.
+    pub fn rollouts(&self) -> u32 {
+        self.moves.iter().map(|estimate| estimate.rollouts).sum()
+    }
+}
+
+/// Running mean and variance (Welford).
+#[derive(Default)]
+struct Running {
+    n: u32,
+    mean: f64,
+    m2: f64,
+}
+
+impl Running {
+    fn add(&mut self, x: f64) {
+        self.n += 1;
+        let delta = x - self.mean;
+        self.mean += delta / self.n as f64;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    fn std_error(&self) -> f64 {
+        if self.n < 2 {
+            return f64::INFINITY;
+        }
+        (self.m2 / (self.n - 1) as f64 / self.n as f64).sqrt()
+    }
+}
+
+impl GameBoard {
+    /// Points from playing `direction` here and then uniformly random legal
+    /// moves until the game ends or `config.max_moves` moves are played.
+    fn rollout(&self, direction: Direction, rng: &mut StdRng, config: &RolloutConfig) -> f64 {
+        let mut game = self.clone();
+        let Ok(first) = game.play_move_with_rules(direction, rng, &config.spawn) else {
+            return 0.0;
+        };
+        let mut points = first.score_gained as u64;
+        for _ in 1..config.max_moves {
+            let legal: Vec<_> = Direction::all().into_iter().filter(|&d| game.can_move(d)).collect();
+            if legal.is_empty() {
+                break;
+            }
+            let direction = legal[rng.gen_range(0..legal.len())];
+            if let Ok(result) = game.play_move_with_rules(direction, rng, &config.spawn) {
+                points += result.score_gained as u64;
+            }
+        }
+        points as f64
+    }
+
+    /// Estimates every legal move's value by random playouts, one more of
+    /// each move per round, until the best move is separated at
+    /// `config.z` or every move has had `config.max_rollouts`.
+    pub fn rollout_values(&self, config: &RolloutConfig) -> RolloutResult {
+        let moves: Vec<_> = Direction::all().into_iter().filter(|&d| self.can_move(d)).collect();
+        let mut stats: Vec<Running> = moves.iter().map(|_| Running::default()).collect();
+        let mut separated = false;
+        for i in 0..config.max_rollouts.max(1) {
+            for (&direction, running) in moves.iter().zip(&mut stats) {
+                let mut rng = StdRng::seed_from_u64(config.seed.wrapping_add(i as u64));
+                running.add(self.rollout(direction, &mut rng, config));
+            }
+            if i + 1 >= config.min_rollouts.max(2) {
+                separated = Self::separated(&stats, config.z);
+                if separated {
+                    break;
+                }
+            }
+        }
+        let mut estimates: Vec<_> = moves
+            .into_iter()
+            .zip(&stats)
+            .map(|(direction, running)| MoveEstimate {
+                direction,
+                rollouts: running.n,
+                mean: running.mean,
+                std_error: running.std_error(),
+            })
+            .collect();
+        estimates.sort_by(|a, b| b.mean.total_cmp(&a.mean));
+        RolloutResult {
+            // A lone legal move needs no comparison.
+            separated: separated || estimates.len() == 1,
+            moves: estimates,
+        }
+    }
+
+    /// Whether the best mean's lower bound clears every other upper bound.
+    fn separated(stats: &[Running], z: f64) -> bool {
+        let Some(best) = (0..stats.len()).max_by(|&a, &b| stats[a].mean.total_cmp(&stats[b].mean)) else {
+            return false;
+        };
+        let lower = stats[best].mean - z * stats[best].std_error();
+        stats
+            .iter()
+            .enumerate()
+            .all(|(i, other)| i == best || other.mean + z * other.std_error() < lower)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rollouts_stop_once_the_best_move_is_separated() {
+        let board = GameBoard::from_compact("2,4,8,16/0,0,2,4/0,0,0,2/0,0,0,0").unwrap();
+        let config = RolloutConfig { min_rollouts: 5, max_rollouts: 40, max_moves: 50, ..RolloutConfig::default() };
+
+        // No interval at all: separated as soon as checking starts.
+        let eager = board.rollout_values(&RolloutConfig { z: 0.0, ..config.clone() });
+        assert!(eager.separated);
+        assert!(eager.moves.iter().all(|estimate| estimate.rollouts == 5));
+
+        // Intervals too wide to ever separate: the whole budget.
+        let never = board.rollout_values(&RolloutConfig { z: 1e6, ..config.clone() });
+        assert!(!never.separated);
+        assert_eq!(never.rollouts(), 40 * never.moves.len() as u32);
+        assert!(never.moves.windows(2).all(|pair| pair[0].mean >= pair[1].mean));
+
+        let result = board.rollout_values(&config);
+        if result.separated {
+            let (lower, _) = result.moves[0].interval(config.z);
+            assert!(result.moves[1..].iter().all(|estimate| estimate.interval(config.z).1 < lower));
+        }
+        assert_eq!(result, board.rollout_values(&config));
+    }
+}
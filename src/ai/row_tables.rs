@@ -0,0 +1,331 @@
+use crate::game::lines::{unpack, LineKeys, LINES, MAX_PACKED_TILE};
+use crate::game::GameBoard;
+
+/// Per-line evaluation terms for every possible row or column, indexed by
+/// the packed line (see [`LineKeys`]), so a full-board term is the sum of
+/// eight lookups.
+pub(crate) struct RowTables {
+    monotonicity: Box<[f32]>,
+    smoothness: Box<[f32]>,
+    merge: Box<[f32]>,
+    chain: Box<[f32]>,
+    /// Edge-control bonus of every tile in the line.
+    edge_full: Box<[f32]>,
+    /// Edge-control bonus of the two end tiles only (inner rows).
+    edge_ends: Box<[f32]>,
+}
+
+/// The same line terms as integers in tenths of a point, exact for every
+/// line, for the fixed-point evaluation.
+pub(crate) struct FixedRowTables {
+    monotonicity: Box<[i32]>,
+    smoothness: Box<[i32]>,
+    merge: Box<[i32]>,
+    chain: Box<[i32]>,
+    edge_full: Box<[i32]>,
+    edge_ends: Box<[i32]>,
+}
+
+/// A board's rows and columns as the line terms read them: packed keys to
+/// look up, or the cells themselves once a tile is too large to pack.
+pub(crate) enum BoardLines<'a> {
+    Packed(LineKeys),
+    Direct(&'a [[u32; 4]; 4]),
+}
+
+impl<'a> BoardLines<'a> {
+    pub fn of(board: &'a GameBoard) -> Self {
+        if board.get_max_tile() > MAX_PACKED_TILE {
+            BoardLines::Direct(&board.board)
+        } else {
+            BoardLines::Packed(board.line_keys())
+        }
+    }
+
+    /// Sums `term` over the rows and columns, reading `table` when packed.
+    fn sum<T: Copy + std::iter::Sum>(&self, table: &[T], term: impl Fn([u32; 4]) -> T) -> T {
+        match self {
+            BoardLines::Packed(keys) => keys.rows.iter().chain(&keys.columns).map(|&key| table[key as usize]).sum(),
+            BoardLines::Direct(board) => (0..4).map(|i| board[i]).chain((0..4).map(|j| board.map(|row| row[j]))).map(term).sum(),
+        }
+    }
+
+    /// `full` of the top and bottom rows plus `ends` of the two inner ones,
+    /// each read from its table when packed.
+    fn edge<T: Copy + std::ops::Add<Output = T>>(&self, full: &[T], full_term: fn([u32; 4]) -> T, ends: &[T], ends_term: fn([u32; 4]) -> T) -> T {
+        match self {
+            BoardLines::Packed(keys) => {
+                let [top, upper, lower, bottom] = keys.rows.map(usize::from);
+                full[top] + ends[upper] + ends[lower] + full[bottom]
+            }
+            BoardLines::Direct(board) => {
+                let [top, upper, lower, bottom] = **board;
+                full_term(top) + ends_term(upper) + ends_term(lower) + full_term(bottom)
+            }
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    pub(crate) static ref ROW_TABLES: RowTables = RowTables::build();
+    pub(crate) static ref FIXED_ROW_TABLES: FixedRowTables = FixedRowTables::build();
+}
+
+/// Rewards runs that keep increasing or decreasing, skipping empty cells.
+fn line_monotonicity(line: [u32; 4]) -> f32 {
+    let mut current = 0;
+    let mut next = current + 1;
+    let mut current_direction = 0;
+    let mut score = 0.0;
+    while next < 4 {
+        while next < 4 && line[next] == 0 {
+            next += 1;
+        }
+        if next >= 4 {
+            break;
+        }
+        let current_value = line[current] as f32;
+        let next_value = line[next] as f32;
+        if current_value > next_value {
+            match current_direction.cmp(&0) {
+                std::cmp::Ordering::Greater => score = 0.0,
+                std::cmp::Ordering::Less => score += next_value,
+                std::cmp::Ordering::Equal => score = next_value,
+            }
+            current_direction = -1;
+        } else if next_value > current_value {
+            match current_direction.cmp(&0) {
+                std::cmp::Ordering::Less => score = 0.0,
+                std::cmp::Ordering::Greater => score += current_value,
+                std::cmp::Ordering::Equal => score = current_value,
+            }
+            current_direction = 1;
+        }
+        current = next;
+        next += 1;
+    }
+    score
+}
+
+/// Minus the difference between each pair of adjacent occupied cells.
+fn line_smoothness(line: [u32; 4]) -> f32 {
+    line.windows(2)
+        .filter(|pair| pair[0] != 0 && pair[1] != 0)
+        .map(|pair| -(pair[0] as f32 - pair[1] as f32).abs())
+        .sum()
+}
+
+/// Each equal adjacent pair counts once from each side.
+fn line_merge(line: [u32; 4]) -> f32 {
+    line.windows(2)
+        .filter(|pair| pair[0] != 0 && pair[0] == pair[1])
+        .map(|pair| 2.0 * pair[0] as f32)
+        .sum()
+}
+
+/// Adjacent tiles one doubling apart, which can chain into a merge.
+fn line_chain(line: [u32; 4]) -> f32 {
+    line.windows(2)
+        .filter(|pair| {
+            let (current, next) = (pair[0], pair[1]);
+            current > 0 && next > 0 && (current == next / 2 || next == current / 2)
+        })
+        .map(|pair| (pair[0] + pair[1]) as f32 * 0.3)
+        .sum()
+}
+
+fn line_edge_full(line: [u32; 4]) -> f32 {
+    line.iter().map(|&v| v as f32 * 0.1).sum()
+}
+
+fn line_edge_ends(line: [u32; 4]) -> f32 {
+    line[0] as f32 * 0.1 + line[3] as f32 * 0.1
+}
+
+impl RowTables {
+    fn build() -> Self {
+        let table = |term: fn([u32; 4]) -> f32| (0..LINES).map(|key| term(unpack(key))).collect();
+        RowTables {
+            monotonicity: table(line_monotonicity),
+            smoothness: table(line_smoothness),
+            merge: table(line_merge),
+            chain: table(line_chain),
+            edge_full: table(line_edge_full),
+            edge_ends: table(line_edge_ends),
+        }
+    }
+
+    pub fn monotonicity(&self, lines: &BoardLines) -> f32 {
+        lines.sum(&self.monotonicity, line_monotonicity)
+    }
+
+    pub fn smoothness(&self, lines: &BoardLines) -> f32 {
+        lines.sum(&self.smoothness, line_smoothness)
+    }
+
+    pub fn merge_potential(&self, lines: &BoardLines) -> f32 {
+        lines.sum(&self.merge, line_merge)
+    }
+
+    pub fn chain_merge(&self, lines: &BoardLines) -> f32 {
+        lines.sum(&self.chain, line_chain)
+    }
+
+    /// Bonus for every tile on the outer ring: the top and bottom rows in
+    /// full plus the end cells of the two inner rows.
+    pub fn edge_control(&self, lines: &BoardLines) -> f32 {
+        lines.edge(&self.edge_full, line_edge_full, &self.edge_ends, line_edge_ends)
+    }
+}
+
+/// [`line_chain`] in tenths of a point.
+fn line_chain_tenths(line: [u32; 4]) -> i32 {
+    line.windows(2)
+        .filter(|pair| pair[0] > 0 && pair[1] > 0 && (pair[0] == pair[1] / 2 || pair[1] == pair[0] / 2))
+        .map(|pair| 3 * (pair[0] + pair[1]) as i32)
+        .sum()
+}
+
+fn line_edge_full_tenths(line: [u32; 4]) -> i32 {
+    line.iter().map(|&v| v as i32).sum()
+}
+
+fn line_edge_ends_tenths(line: [u32; 4]) -> i32 {
+    (line[0] + line[3]) as i32
+}
+
+/// `term` in tenths of a point; the three terms it wraps are whole numbers
+/// below 2^24, so exact in f32.
+fn tenths(term: fn([u32; 4]) -> f32) -> impl Fn([u32; 4]) -> i32 {
+    move |line| term(line) as i32 * 10
+}
+
+impl FixedRowTables {
+    fn build() -> Self {
+        let table = |term: &dyn Fn([u32; 4]) -> i32| (0..LINES).map(|key| term(unpack(key))).collect();
+        FixedRowTables {
+            monotonicity: table(&tenths(line_monotonicity)),
+            smoothness: table(&tenths(line_smoothness)),
+            merge: table(&tenths(line_merge)),
+            chain: table(&line_chain_tenths),
+            edge_full: table(&line_edge_full_tenths),
+            edge_ends: table(&line_edge_ends_tenths),
+        }
+    }
+
+    pub fn monotonicity(&self, lines: &BoardLines) -> i32 {
+        lines.sum(&self.monotonicity, tenths(line_monotonicity))
+    }
+
+    pub fn smoothness(&self, lines: &BoardLines) -> i32 {
+        lines.sum(&self.smoothness, tenths(line_smoothness))
+    }
+
+    pub fn merge_potential(&self, lines: &BoardLines) -> i32 {
+        lines.sum(&self.merge, tenths(line_merge))
+    }
+
+    pub fn chain_merge(&self, lines: &BoardLines) -> i32 {
+        lines.sum(&self.chain, line_chain_tenths)
+    }
+
+    pub fn edge_control(&self, lines: &BoardLines) -> i32 {
+        lines.edge(&self.edge_full, line_edge_full_tenths, &self.edge_ends, line_edge_ends_tenths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    /// Per-cell smoothness, merge and edge terms the tables replace.
+    fn reference_terms(board: &[[u32; 4]; 4]) -> (f32, f32, f32) {
+        let (mut smoothness, mut merge, mut edge) = (0.0, 0.0, 0.0);
+        for i in 0..4 {
+            for j in 0..4 {
+                let value = board[i][j];
+                if value == 0 {
+                    continue;
+                }
+                for (ni, nj) in [(i + 1, j), (i, j + 1)] {
+                    if ni < 4 && nj < 4 && board[ni][nj] != 0 {
+                        smoothness -= (value as f32 - board[ni][nj] as f32).abs();
+                        if board[ni][nj] == value {
+                            merge += 2.0 * value as f32;
+                        }
+                    }
+                }
+                if i == 0 || i == 3 || j == 0 || j == 3 {
+                    edge += value as f32 * 0.1;
+                }
+            }
+        }
+        (smoothness, merge, edge)
+    }
+
+    #[test]
+    fn test_tables_match_per_cell_terms() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..200 {
+            let mut board = GameBoard::new();
+            board.set_board(std::array::from_fn(|_| {
+                std::array::from_fn(|_| match rng.gen_range(0..12) {
+                    0 => 0,
+                    e => 1 << e,
+                })
+            }));
+            let lines = BoardLines::Packed(board.line_keys());
+            let direct = BoardLines::Direct(&board.board);
+            let (smoothness, merge, edge) = reference_terms(&board.board);
+            assert_eq!(ROW_TABLES.smoothness(&lines), smoothness);
+            assert_eq!(ROW_TABLES.merge_potential(&lines), merge);
+            assert!((ROW_TABLES.edge_control(&lines) - edge).abs() < 1e-2);
+            assert_eq!(ROW_TABLES.monotonicity(&lines), ROW_TABLES.monotonicity(&direct));
+            assert_eq!(ROW_TABLES.chain_merge(&lines), ROW_TABLES.chain_merge(&direct));
+        }
+    }
+
+    #[test]
+    fn test_tiles_past_the_packed_range_fall_back_to_direct_terms() {
+        let mut board = GameBoard::new();
+        board.set_board([
+            [65536, 65536, 32768, 2],
+            [131072, 0, 4, 2],
+            [65536, 0, 0, 4],
+            [2, 4, 8, 16]
+        ]);
+        let (smoothness, merge, _) = reference_terms(&board.board);
+        assert_eq!(board.calculate_smoothness(), smoothness);
+        assert_eq!(board.calculate_merge_potential(), merge);
+        let lines = (0..4).map(|i| board.board[i]).chain((0..4).map(|j| board.board.map(|row| row[j])));
+        assert_eq!(board.calculate_monotonicity(), lines.map(line_monotonicity).sum::<f32>());
+        // Packed, the 65536s spill into their neighbours' cells.
+        assert_ne!(ROW_TABLES.merge_potential(&BoardLines::Packed(board.line_keys())), merge);
+    }
+
+    #[test]
+    fn test_fixed_tables_are_tenths_of_float_tables() {
+        let mut board = GameBoard::new();
+        board.set_board([
+            [1024, 512, 256, 128],
+            [64, 32, 16, 8],
+            [4, 2, 4, 2],
+            [0, 0, 0, 2]
+        ]);
+        let lines = BoardLines::Packed(board.line_keys());
+        let fixed = &*FIXED_ROW_TABLES;
+        assert_eq!(fixed.monotonicity(&lines) as f32, ROW_TABLES.monotonicity(&lines) * 10.0);
+        assert_eq!(fixed.smoothness(&lines) as f32, ROW_TABLES.smoothness(&lines) * 10.0);
+        assert!((fixed.chain_merge(&lines) as f32 / 10.0 - ROW_TABLES.chain_merge(&lines)).abs() < 1e-2);
+        assert!((fixed.edge_control(&lines) as f32 / 10.0 - ROW_TABLES.edge_control(&lines)).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_monotonicity_of_lines() {
+        assert_eq!(line_monotonicity([2, 4, 8, 16]), 14.0);
+        assert_eq!(line_monotonicity([16, 8, 0, 2]), 10.0);
+        assert_eq!(line_monotonicity([2, 8, 4, 0]), 0.0);
+    }
+}
@@ -0,0 +1,221 @@
+//! A learned leaf value: the expected final score of the game from a
+//! position, fitted by regression on self-play datasets (see
+//! [`super::dataset`]).
+//!
+//! The model is an n-tuple network over the board's eight lines. Each row
+//! and column, as four tile ranks capped at 15, indexes a table of values;
+//! outer lines and inner lines have a table each, and a line shares its
+//! entry with its reverse. The prediction is the sum of the eight entries
+//! and a bias, in points.
+//!
+//! A model file is a 16-byte header (magic `b"2048SMDL"`, format version
+//! `u32`, 4 bytes of padding), the examples trained on (`u64`), the bias
+//! (`f32`) and the two tables of `f32`, all little-endian.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use super::dataset::DatasetRecord;
+use crate::game::GameBoard;
+
+pub const MODEL_MAGIC: [u8; 8] = *b"2048SMDL";
+pub const MODEL_VERSION: u32 = 1;
+/// Entries in each line table: four ranks of four bits.
+pub const LINE_KEYS: usize = 1 << 16;
+
+/// The cells of each row and column, in reading order.
+const LINES: [[usize; 4]; 8] = [
+    [0, 1, 2, 3],
+    [4, 5, 6, 7],
+    [8, 9, 10, 11],
+    [12, 13, 14, 15],
+    [0, 4, 8, 12],
+    [1, 5, 9, 13],
+    [2, 6, 10, 14],
+    [3, 7, 11, 15],
+];
+
+/// Predicts a game's final score from a position.
+#[derive(Clone, PartialEq)]
+pub struct ScoreModel {
+    /// Outer-line values, then inner-line values.
+    tables: Vec<f32>,
+    bias: f32,
+    examples: u64,
+    fingerprint: u64,
+}
+
+impl fmt::Debug for ScoreModel {
+    /// A summary; the tables are far too large to print.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScoreModel")
+            .field("examples", &self.examples)
+            .field("fingerprint", &format_args!("{:016x}", self.fingerprint))
+            .finish()
+    }
+}
+
+impl Default for ScoreModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Table index of every line of `ranks` (tile ranks, row by row).
+fn features(ranks: &[u8; 16]) -> [usize; 8] {
+    std::array::from_fn(|line| {
+        let rank = |k: usize| ranks[LINES[line][k]].min(15) as usize;
+        let forward = rank(0) << 12 | rank(1) << 8 | rank(2) << 4 | rank(3);
+        let backward = rank(3) << 12 | rank(2) << 8 | rank(1) << 4 | rank(0);
+        // Rows 0 and 3 and columns 0 and 3 are the outer lines.
+        let outer = matches!(line % 4, 0 | 3);
+        forward.min(backward) + if outer { 0 } else { LINE_KEYS }
+    })
+}
+
+impl ScoreModel {
+    /// An untrained model, predicting 0 everywhere.
+    pub fn new() -> Self {
+        let mut model = Self {
+            tables: vec![0.0; 2 * LINE_KEYS],
+            bias: 0.0,
+            examples: 0,
+            fingerprint: 0,
+        };
+        model.refresh_fingerprint();
+        model
+    }
+
+    /// Examples seen in training, counting each epoch.
+    pub fn examples(&self) -> u64 {
+        self.examples
+    }
+
+    /// Identifies the model for caches of evaluations made with it; never
+    /// 0.
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
+    fn refresh_fingerprint(&mut self) {
+        self.fingerprint = std::iter::once(self.bias)
+            .chain(self.tables.iter().copied())
+            .fold(0xcbf2_9ce4_8422_2325_u64, |hash, w| (hash ^ w.to_bits() as u64).wrapping_mul(0x0100_0000_01b3))
+            .max(1);
+    }
+
+    /// Expected final score from a position given as tile ranks, row by
+    /// row, as in a [`DatasetRecord`].
+    pub fn predict_ranks(&self, ranks: &[u8; 16]) -> f32 {
+        self.bias + features(ranks).iter().map(|&index| self.tables[index]).sum::<f32>()
+    }
+
+    /// Expected final score of a classic game from `board`.
+    pub fn predict(&self, board: &GameBoard) -> f32 {
+        let ranks = std::array::from_fn(|cell| board.rules().rank(board.board[cell / 4][cell % 4]).min(15) as u8);
+        self.predict_ranks(&ranks)
+    }
+
+    /// Fits the model to `records` by stochastic gradient descent on the
+    /// squared error, `epochs` passes in a seeded random order. Returns the
+    /// mean absolute error, in points, during each pass.
+    pub fn train(&mut self, records: &[DatasetRecord], epochs: u32, learning_rate: f32) -> Vec<f32> {
+        let mut order: Vec<usize> = (0..records.len()).collect();
+        let mut errors = Vec::with_capacity(epochs as usize);
+        for epoch in 0..epochs {
+            order.shuffle(&mut StdRng::seed_from_u64(epoch as u64));
+            let mut total = 0.0f64;
+            for &i in &order {
+                let record = &records[i];
+                let error = record.final_score as f32 - self.predict_ranks(&record.cells);
+                total += error.abs() as f64;
+                // Shared between the eight lines and the bias.
+                let step = learning_rate * error / 9.0;
+                for index in features(&record.cells) {
+                    self.tables[index] += step;
+                }
+                self.bias += step;
+            }
+            self.examples += records.len() as u64;
+            errors.push((total / records.len().max(1) as f64) as f32);
+        }
+        self.refresh_fingerprint();
+        errors
+    }
+
+    pub fn write_to<W: Write>(&self, mut out: W) -> io::Result<()> {
+        out.write_all(&MODEL_MAGIC)?;
+        out.write_all(&MODEL_VERSION.to_le_bytes())?;
+        out.write_all(&[0; 4])?;
+        out.write_all(&self.examples.to_le_bytes())?;
+        out.write_all(&self.bias.to_le_bytes())?;
+        let bytes: Vec<u8> = self.tables.iter().flat_map(|value| value.to_le_bytes()).collect();
+        out.write_all(&bytes)
+    }
+
+    pub fn read_from<R: Read>(mut input: R) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+        let mut header = [0u8; 28];
+        input.read_exact(&mut header)?;
+        if header[..8] != MODEL_MAGIC {
+            return Err(invalid("not a score model file"));
+        }
+        if u32::from_le_bytes(header[8..12].try_into().unwrap()) != MODEL_VERSION {
+            return Err(invalid("unsupported score model version"));
+        }
+        let mut data = vec![0u8; 2 * LINE_KEYS * 4];
+        input.read_exact(&mut data).map_err(|_| invalid("truncated score model"))?;
+        let mut model = Self {
+            tables: data.chunks_exact(4).map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap())).collect(),
+            bias: f32::from_le_bytes(header[24..28].try_into().unwrap()),
+            examples: u64::from_le_bytes(header[16..24].try_into().unwrap()),
+            fingerprint: 0,
+        };
+        model.refresh_fingerprint();
+        Ok(model)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::ai::dataset::{play_game, DatasetConfig};
+    use crate::ai::search::SearchConfig;
+    use crate::cache::TranspositionState;
+
+    #[test]
+    fn test_model_learns_final_scores_and_drives_the_search() {
+        let config = DatasetConfig {
+            search: SearchConfig::with_depth(1),
+            max_moves: 300,
+            ..DatasetConfig::default()
+        };
+        let records: Vec<_> = (0..4).flat_map(|seed| play_game(seed, &config)).collect();
+        let mut model = ScoreModel::new();
+        let untrained = model.fingerprint();
+        let errors = model.train(&records, 4, 0.05);
+        assert!(errors.last().unwrap() < &errors[0], "{:?}", errors);
+        assert_eq!(model.examples(), 4 * records.len() as u64);
+        assert_ne!(model.fingerprint(), untrained);
+
+        let mut file = Vec::new();
+        model.write_to(&mut file).unwrap();
+        let read = ScoreModel::read_from(&file[..]).unwrap();
+        assert_eq!(read, model);
+
+        let board = GameBoard::from_compact("2,4,8,16/0,0,2,4/0,0,0,2/0,0,0,0").unwrap();
+        let search = SearchConfig {
+            score_model: Some(Arc::new(read)),
+            ..SearchConfig::with_depth(2)
+        };
+        let result = board.search_with(&search, &mut TranspositionState::new());
+        assert!(result.best_move.is_some());
+        assert!(result.score.is_finite());
+    }
+}
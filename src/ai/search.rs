@@ -0,0 +1,1130 @@
+use std::collections::HashMap;
+use std::ops::AddAssign;
+use std::sync::Arc;
+use std::time::Duration;
+
+use arrayvec::ArrayVec;
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+use crate::cache::{EntryQuality, TranspositionState};
+use super::difficulty::survival_chance;
+use super::move_ordering::CLONES_PER_ORDERED_MOVE;
+use super::optimized_evaluation::OptimizedEvaluationWeights;
+use super::score_model::ScoreModel;
+use super::time_attack::{self, TimeAttack};
+use super::trace::{self, SearchTrace, TraceEvent};
+use crate::game::{Direction, GameBoard, PowerUp, PowerUps, SpawnRules, Variant};
+
+/// How deep and how long a single move search may run.
+#[derive(Debug, Clone, Default)]
+pub struct SearchConfig {
+    /// Fixed search depth in plies; `None` uses `calculate_smart_depth`.
+    pub max_depth: Option<u32>,
+    /// Wall-clock budget. When set, the search deepens one ply at a time
+    /// and returns the best move of the deepest completed iteration.
+    pub time_limit: Option<Duration>,
+    /// Per-term multipliers on the stage-adaptive evaluation weights;
+    /// `None` evaluates with the built-in weights.
+    pub weight_scale: Option<OptimizedEvaluationWeights>,
+    /// Value classic leaves by this model's expected final score instead
+    /// of the heuristic evaluation (other variants keep theirs).
+    pub score_model: Option<Arc<ScoreModel>>,
+    /// Evaluate in integer fixed point and take chance-node expectations
+    /// with integer arithmetic, so scores and chosen moves are bit-for-bit
+    /// reproducible across platforms.
+    pub fixed_point: bool,
+    /// Collect a [`SearchProfile`] into [`SearchResult::profile`].
+    pub profile: bool,
+    /// Record every step of the search into [`SearchResult::trace`], see
+    /// [`super::trace`]. Slow and memory-hungry; for debugging.
+    pub trace: bool,
+    /// Budget of a time-attack game: paces the time limit, caps the depth
+    /// at the last budgeted move and weighs points more as it runs out.
+    pub time_attack: Option<TimeAttack>,
+    /// Which spawn cells a chance node averages over.
+    pub chance_sampling: ChanceSampling,
+    /// Spawn rules of the game being played; chance nodes weigh each
+    /// spawned tile by its chance under them.
+    pub spawn: SpawnRules,
+    /// Expand the spawns of chance nodes with at least this much depth
+    /// left in parallel, each on its own thread and table. Deep searches
+    /// on many cores gain; shallow nodes cost more to hand out than they
+    /// save. Needs the `parallel` feature; ignored without it.
+    pub parallel_chance_depth: Option<u32>,
+    /// Standard deviations of its outcomes taken off every chance node's
+    /// expectation. 0 plays for the expected value; higher values steer
+    /// away from lines where a few unlucky spawns lock the board, at some
+    /// cost to the average score.
+    pub risk_aversion: f32,
+    /// Drop root moves whose static evaluation after the move falls more
+    /// than this below the best one's, as a fraction of the best (the scale
+    /// of [`SearchResult::score_gap`]), and spend the whole search on the
+    /// rest. Moves are only ordered, never dropped, when `None`.
+    pub root_prefilter: Option<f32>,
+    /// Flag the position [`SearchResult::hopeless`] when best play's chance
+    /// of surviving the next three spawns is below this.
+    pub resign_threshold: Option<f32>,
+    /// Power-up charges the player holds. Swaps and deletes join the
+    /// root's choices, see [`SearchResult::power_up`]; undo is decided
+    /// after the spawn, see [`super::power_ups::PowerUpPlayer`].
+    pub power_ups: PowerUps,
+}
+
+/// Relative gain over the best plain move a swap or delete must promise
+/// before the search spends a charge on it: a charge used now is gone
+/// when the board locks up later.
+pub const POWER_UP_MARGIN: f32 = 0.1;
+/// Power-ups searched in full, after ranking every usable one by the
+/// static evaluation of the position it leaves.
+const POWER_UP_CANDIDATES: usize = 4;
+
+/// How a chance node picks the empty cells it averages over: fewer cells
+/// search faster and estimate the expectation less exactly.
+///
+/// In a config file, `chance_sampling = "strategic"`, `"all"`,
+/// `{ top_k = 8 }`, `{ random_k = { k = 6, seed = 1 } }` or
+/// `{ probability_threshold = 0.0001 }`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChanceSampling {
+    /// The default: every cell while at most 8 are empty, otherwise the
+    /// most important half of them (as [`ChanceSampling::TopK`] ranks
+    /// them), but between 6 and 8.
+    #[default]
+    Strategic,
+    /// Every empty cell: the exact expectation.
+    All,
+    /// The `k` cells that matter most to the position (corners, edges,
+    /// next to the largest tile and to small tiles).
+    TopK(usize),
+    /// `k` cells drawn at random. The draw is seeded by `seed` and the
+    /// position, so a search is reproducible and transpositions agree.
+    RandomK { k: usize, seed: u64 },
+    /// Every empty cell, but a spawn whose probability along the path
+    /// from the root falls below the threshold is scored by the static
+    /// evaluation instead of searched further.
+    ProbabilityThreshold(f32),
+}
+
+impl SearchConfig {
+    pub fn with_depth(depth: u32) -> Self {
+        Self {
+            max_depth: Some(depth),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_time_limit(time_limit: Duration) -> Self {
+        Self {
+            time_limit: Some(time_limit),
+            ..Self::default()
+        }
+    }
+}
+
+/// Outcome of one move search.
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub best_move: Option<Direction>,
+    /// Expectimax score of `best_move` (`NEG_INFINITY` when there is none).
+    pub score: f32,
+    /// Every legal root move with its score from the deepest completed
+    /// iteration, best first.
+    pub move_scores: Vec<(Direction, f32)>,
+    /// Deepest fully completed search depth.
+    pub depth: u32,
+    /// Depth the search was aiming for (configured or adaptive).
+    pub target_depth: u32,
+    /// The time limit ran out before `target_depth` was completed.
+    pub timed_out: bool,
+    /// Nodes visited across all iterations.
+    pub nodes: u64,
+    /// Static (leaf) evaluations performed across all iterations.
+    pub evaluations: u64,
+    pub elapsed: Duration,
+    /// Engine counters, when [`SearchConfig::profile`] was set.
+    pub profile: Option<SearchProfile>,
+    /// The game is as good as lost (see [`SearchConfig::resign_threshold`]);
+    /// always false without a threshold.
+    pub hopeless: bool,
+    /// Every step of the search, when [`SearchConfig::trace`] was set.
+    pub trace: Option<SearchTrace>,
+    /// Legal moves left unsearched by [`SearchConfig::root_prefilter`];
+    /// they have no entry in `move_scores`.
+    pub filtered_moves: Vec<Direction>,
+    /// A swap or delete worth using before `best_move`, with the score of
+    /// the position it leaves, when [`SearchConfig::power_ups`] holds
+    /// charges and one beats `score` by [`POWER_UP_MARGIN`]. Search again
+    /// after using it: the best move may change.
+    pub power_up: Option<(PowerUp, f32)>,
+}
+
+impl SearchResult {
+    /// Lead of the best root move over the runner-up, relative to the best
+    /// score; 0 when there is no choice to make.
+    pub fn score_gap(&self) -> f32 {
+        match self.move_scores[..] {
+            [(_, best), (_, second), ..] => (best - second) / best.abs().max(1.0),
+            _ => 0.0,
+        }
+    }
+}
+
+/// Work done by one search, broken down for comparing engine changes by
+/// more than wall-clock time. Counts cover every deepening iteration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SearchProfile {
+    /// Player-move nodes that were expanded.
+    pub max_nodes: u64,
+    /// Tile-spawn nodes that were expanded.
+    pub chance_nodes: u64,
+    /// Nodes at the depth limit, scored by static evaluation.
+    pub leaf_nodes: u64,
+    /// Nodes where no move was left.
+    pub terminal_nodes: u64,
+    /// Static evaluations requested, including ones served from the cache.
+    pub evaluations: u64,
+    pub eval_cache_hits: u64,
+    pub tt_probes: u64,
+    pub tt_hits: u64,
+    pub tt_stores: u64,
+    /// `GameBoard` copies: the root's working board and the children move
+    /// ordering scores.
+    pub board_clones: u64,
+    /// Heap allocations by the search: transposition-table growth and the
+    /// root move list of each completed iteration.
+    pub allocations: u64,
+    /// Move-ordering quality at player-move nodes (the root included) by
+    /// remaining depth; the last entry also counts every deeper node.
+    pub ordering: [OrderingStats; ORDERING_DEPTHS],
+}
+
+/// Remaining depths [`SearchProfile::ordering`] tells apart.
+pub const ORDERING_DEPTHS: usize = 16;
+
+/// How often the move `order_moves` put first was the one that mattered,
+/// at player-move nodes with more than one legal move.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct OrderingStats {
+    pub nodes: u64,
+    /// Nodes whose first-ordered move scored best among the moves searched.
+    pub first_best: u64,
+    /// Nodes cut off by alpha-beta.
+    pub cutoffs: u64,
+    /// Cutoffs caused by the first-ordered move.
+    pub first_cutoffs: u64,
+}
+
+impl OrderingStats {
+    /// Counts a node where move `best` (0 = first ordered) scored best and
+    /// move `cutoff`, if any, caused a cutoff.
+    pub(crate) fn record(&mut self, best: usize, cutoff: Option<usize>) {
+        self.nodes += 1;
+        self.first_best += (best == 0) as u64;
+        self.cutoffs += cutoff.is_some() as u64;
+        self.first_cutoffs += (cutoff == Some(0)) as u64;
+    }
+
+    /// Share of nodes where the first-ordered move was best.
+    pub fn first_best_rate(&self) -> f64 {
+        self.first_best as f64 / self.nodes.max(1) as f64
+    }
+
+    /// Share of cutoffs the first-ordered move caused.
+    pub fn first_cutoff_rate(&self) -> f64 {
+        self.first_cutoffs as f64 / self.cutoffs.max(1) as f64
+    }
+}
+
+impl AddAssign for OrderingStats {
+    fn add_assign(&mut self, other: Self) {
+        self.nodes += other.nodes;
+        self.first_best += other.first_best;
+        self.cutoffs += other.cutoffs;
+        self.first_cutoffs += other.first_cutoffs;
+    }
+}
+
+impl AddAssign for SearchProfile {
+    fn add_assign(&mut self, other: Self) {
+        self.max_nodes += other.max_nodes;
+        self.chance_nodes += other.chance_nodes;
+        self.leaf_nodes += other.leaf_nodes;
+        self.terminal_nodes += other.terminal_nodes;
+        self.evaluations += other.evaluations;
+        self.eval_cache_hits += other.eval_cache_hits;
+        self.tt_probes += other.tt_probes;
+        self.tt_hits += other.tt_hits;
+        self.tt_stores += other.tt_stores;
+        self.board_clones += other.board_clones;
+        self.allocations += other.allocations;
+        for (total, counts) in self.ordering.iter_mut().zip(other.ordering) {
+            *total += counts;
+        }
+    }
+}
+
+/// Per-search state threaded through the recursion: the transposition
+/// table, node counter, optional profile and the deadline for
+/// time-limited searches.
+pub(crate) struct SearchContext<'a> {
+    tt: &'a mut TranspositionState,
+    pub(crate) nodes: u64,
+    pub(crate) evaluations: u64,
+    pub(crate) profile: Option<SearchProfile>,
+    deadline: Option<Instant>,
+    weight_scale: Option<&'a OptimizedEvaluationWeights>,
+    score_model: Option<&'a ScoreModel>,
+    fixed_point: bool,
+    /// Share of each classic leaf's value given to points (time attack).
+    urgency: f32,
+    pub(crate) sampling: ChanceSampling,
+    /// Probability of reaching the current node from the root through
+    /// the spawns chosen so far.
+    pub(crate) probability: f32,
+    /// Spawned tiles and their integer weights, see [`SpawnRules::weights`].
+    pub(crate) spawns: ArrayVec<(u32, u32), 3>,
+    pub(crate) risk_aversion: f32,
+    /// Best move found at each interior player-move position, kept across
+    /// deepening iterations and tried first when the position comes up
+    /// again, so every iteration starts down the last one's variation.
+    best_moves: HashMap<u64, Direction>,
+    /// See [`SearchConfig::parallel_chance_depth`]; `None` in forks.
+    parallel_chance_depth: Option<u32>,
+    /// Spawns evaluated statically under the probability threshold so far,
+    /// counting those behind pruned table entries. A node whose subtree
+    /// adds to it stores a pruned entry, see [`EntryQuality`].
+    pruned: u64,
+    /// Events so far, when tracing.
+    trace: Option<Vec<TraceEvent>>,
+    /// When replaying a trace: the node count at which the recording hit
+    /// its deadline, reached in place of reading the clock.
+    scripted_deadline: Option<u64>,
+    aborted: bool,
+}
+
+/// What a forked context did, for merging into its parent.
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+pub(crate) struct ForkCounts {
+    nodes: u64,
+    evaluations: u64,
+    profile: Option<SearchProfile>,
+    pruned: u64,
+    aborted: bool,
+}
+
+impl<'a> SearchContext<'a> {
+    pub(crate) fn new(tt: &'a mut TranspositionState, deadline: Option<Instant>) -> Self {
+        Self {
+            tt,
+            nodes: 0,
+            evaluations: 0,
+            profile: None,
+            deadline,
+            weight_scale: None,
+            score_model: None,
+            fixed_point: false,
+            urgency: 0.0,
+            sampling: ChanceSampling::default(),
+            probability: 1.0,
+            spawns: SpawnRules::default().weights(Variant::Classic),
+            risk_aversion: 0.0,
+            best_moves: HashMap::new(),
+            parallel_chance_depth: None,
+            pruned: 0,
+            trace: None,
+            scripted_deadline: None,
+            aborted: false,
+        }
+    }
+
+    /// Counts a node and reports whether the search must unwind. The clock
+    /// is only read every 1024 nodes to keep the check cheap.
+    pub(crate) fn visit(&mut self) -> bool {
+        self.nodes += 1;
+        if !self.aborted && self.nodes & 1023 == 0 {
+            self.aborted = self.deadline_passed();
+        }
+        self.aborted
+    }
+
+    /// Whether the time is up, recorded in the trace when it is.
+    fn deadline_passed(&mut self) -> bool {
+        let passed = match self.scripted_deadline {
+            Some(nodes) => self.nodes >= nodes,
+            None => self.deadline.is_some_and(|deadline| Instant::now() >= deadline),
+        };
+        if passed {
+            let nodes = self.nodes;
+            self.trace(|| TraceEvent::Deadline { nodes });
+        }
+        passed
+    }
+
+    /// Appends to the trace, if one is being recorded.
+    pub(crate) fn trace(&mut self, event: impl FnOnce() -> TraceEvent) {
+        if let Some(trace) = &mut self.trace {
+            trace.push(event());
+        }
+    }
+
+    /// Uses `scale` (and fixed point, if set) for every evaluation,
+    /// dropping cached evaluations made any other way.
+    pub(crate) fn set_evaluation(&mut self, scale: Option<&'a OptimizedEvaluationWeights>, model: Option<&'a ScoreModel>, fixed_point: bool) {
+        let tag = scale.map_or(0, OptimizedEvaluationWeights::fingerprint) ^ model.map_or(0, |model| model.fingerprint().rotate_left(1));
+        self.tt.eval.retag(if fixed_point { !tag } else { tag });
+        self.weight_scale = scale;
+        self.score_model = model;
+        self.fixed_point = fixed_point;
+    }
+
+    /// Moves `urgency` of every classic leaf's value to points. Applied
+    /// after the evaluation cache, which keeps the plain evaluations.
+    pub(crate) fn set_urgency(&mut self, urgency: f32) {
+        self.urgency = urgency;
+    }
+
+    /// Move ordering at a player-move node: `order_moves`, with the best
+    /// move found for this position before, if any, tried first.
+    pub(crate) fn order_moves(&self, board: &GameBoard, hash: u64) -> ArrayVec<Direction, 4> {
+        let mut moves = board.order_moves();
+        if let Some(&best) = self.best_moves.get(&hash) {
+            if let Some(index) = moves.iter().position(|&direction| direction == best) {
+                moves[..=index].rotate_right(1);
+            }
+        }
+        moves
+    }
+
+    /// Remembers `best` as the move to try first at this position. Only
+    /// nodes with at least `depth` 2 are kept: below that the ordering
+    /// saves little and the table would grow with every leaf parent.
+    pub(crate) fn remember_best_move(&mut self, hash: u64, depth: u32, best: Direction) {
+        if depth >= 2 && !self.aborted {
+            self.best_moves.insert(hash, best);
+        }
+    }
+
+    /// Spawns below this probability from the root are not searched.
+    pub(crate) fn probability_threshold(&self) -> f32 {
+        match self.sampling {
+            ChanceSampling::ProbabilityThreshold(threshold) => threshold,
+            _ => 0.0,
+        }
+    }
+
+    /// Whether a chance node with `depth` left expands its spawns in
+    /// parallel.
+    pub(crate) fn parallel_chance(&self, depth: u32) -> bool {
+        cfg!(all(feature = "parallel", not(target_arch = "wasm32")))
+            && self.trace.is_none()
+            && self.parallel_chance_depth.is_some_and(|min| depth >= min)
+    }
+
+    /// A context searching one subtree on another thread with its own
+    /// table `tt`: the same settings and deadline, fresh counters, and no
+    /// parallel expansion of its own.
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    pub(crate) fn fork<'b>(&self, tt: &'b mut TranspositionState) -> SearchContext<'b>
+    where
+        'a: 'b,
+    {
+        let mut fork = SearchContext::new(tt, self.deadline);
+        fork.set_evaluation(self.weight_scale, self.score_model, self.fixed_point);
+        fork.profile = self.profile.map(|_| SearchProfile::default());
+        fork.urgency = self.urgency;
+        fork.sampling = self.sampling;
+        fork.probability = self.probability;
+        fork.spawns = self.spawns.clone();
+        fork.risk_aversion = self.risk_aversion;
+        fork
+    }
+
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    pub(crate) fn into_counts(self) -> ForkCounts {
+        ForkCounts {
+            nodes: self.nodes,
+            evaluations: self.evaluations,
+            profile: self.profile,
+            pruned: self.pruned,
+            aborted: self.aborted,
+        }
+    }
+
+    /// Adds a finished fork's work, and its timeout, to this context.
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    pub(crate) fn join(&mut self, counts: ForkCounts) {
+        self.nodes += counts.nodes;
+        self.evaluations += counts.evaluations;
+        if let (Some(total), Some(profile)) = (&mut self.profile, counts.profile) {
+            *total += profile;
+        }
+        self.pruned += counts.pruned;
+        self.aborted |= counts.aborted;
+    }
+
+    pub(crate) fn fixed_point(&self) -> bool {
+        self.fixed_point
+    }
+
+    /// Runs `count` on the profile, if one is being collected.
+    pub(crate) fn count(&mut self, count: impl FnOnce(&mut SearchProfile)) {
+        if let Some(profile) = &mut self.profile {
+            count(profile);
+        }
+    }
+
+    /// Transposition-table lookup, counted in the profile. Entries pruned
+    /// at a likelier reach than the current node's are passed over.
+    pub(crate) fn probe(&mut self, hash: u64, depth: u32, max_node: bool) -> Option<f32> {
+        let entry = self.tt.probe_at(hash, depth, max_node, self.probability);
+        self.count(|p| {
+            p.tt_probes += 1;
+            p.tt_hits += entry.is_some() as u64;
+        });
+        self.trace(|| TraceEvent::Probe { hash, depth, max_node, hit: entry.map(|(score, _)| score) });
+        let (score, quality) = entry?;
+        if quality != EntryQuality::Exact {
+            self.pruned += 1;
+        }
+        Some(score)
+    }
+
+    /// Marks the start of a node's subtree, for [`SearchContext::store`].
+    pub(crate) fn prune_mark(&self) -> u64 {
+        self.pruned
+    }
+
+    /// Counts a spawn evaluated statically instead of searched.
+    pub(crate) fn record_pruned(&mut self) {
+        self.pruned += 1;
+    }
+
+    /// Transposition-table store, counted in the profile along with any
+    /// growth of the table it causes. The entry is exact unless spawns
+    /// were pruned since `mark` was taken at the node.
+    pub(crate) fn store(&mut self, hash: u64, depth: u32, max_node: bool, score: f32, mark: u64) {
+        let quality = if self.pruned == mark {
+            EntryQuality::Exact
+        } else {
+            EntryQuality::Pruned { reach: self.probability }
+        };
+        let capacity = self.tt.capacity();
+        self.tt.store_with(hash, depth, max_node, score, quality);
+        self.trace(|| TraceEvent::Store { hash, depth, max_node, score });
+        let grew = self.tt.capacity() != capacity;
+        self.count(|p| {
+            p.tt_stores += 1;
+            p.allocations += grew as u64;
+        });
+    }
+
+    /// Static evaluation of a leaf, counted for throughput statistics and
+    /// memoized in the table's evaluation cache.
+    pub(crate) fn evaluate(&mut self, board: &GameBoard) -> f32 {
+        self.evaluations += 1;
+        self.count(|p| p.evaluations += 1);
+        let hash = board.board_hash();
+        let score = match self.tt.eval.probe(hash) {
+            Some(score) => {
+                self.count(|p| p.eval_cache_hits += 1);
+                score
+            }
+            None => {
+                let score = self.evaluate_uncached(board);
+                self.tt.eval.store(hash, score);
+                score
+            }
+        };
+        let score = if self.urgency > 0.0 && board.variant == Variant::Classic {
+            time_attack::blend(score, board, self.urgency)
+        } else {
+            score
+        };
+        self.trace(|| TraceEvent::Evaluate { hash, score });
+        score
+    }
+
+    fn evaluate_uncached(&self, board: &GameBoard) -> f32 {
+        if board.variant != Variant::Classic {
+            board.evaluate_variant(self.weight_scale)
+        } else if let Some(model) = self.score_model {
+            let score = model.predict(board);
+            if self.fixed_point { score.round() } else { score }
+        } else if self.fixed_point {
+            board.evaluate_board_fixed(self.weight_scale) as f32
+        } else {
+            match self.weight_scale {
+                Some(scale) => board.evaluate_board_scaled(scale),
+                None => board.evaluate_board_optimized(),
+            }
+        }
+    }
+
+    pub(crate) fn aborted(&self) -> bool {
+        self.aborted
+    }
+}
+
+impl GameBoard {
+    /// Searches with the solver's per-thread transposition table.
+    pub fn search(&self, config: &SearchConfig) -> SearchResult {
+        crate::cache::with_thread_tt(|tt| self.search_with(config, tt))
+    }
+
+    /// Searches with a caller-owned transposition table, for embedders that
+    /// cannot rely on thread-local state (e.g. WASM or one table per worker).
+    pub fn search_with(&self, config: &SearchConfig, tt: &mut TranspositionState) -> SearchResult {
+        self.search_scripted(config, tt, None)
+    }
+
+    /// [`GameBoard::search_with`], hitting the deadline after
+    /// `scripted_deadline` nodes instead of by the clock when given (see
+    /// [`SearchTrace::replay`]).
+    pub(crate) fn search_scripted(&self, config: &SearchConfig, tt: &mut TranspositionState, scripted_deadline: Option<u64>) -> SearchResult {
+        let start = Instant::now();
+        let time_attack = config.time_attack.as_ref();
+        let target_depth = config
+            .max_depth
+            .unwrap_or_else(|| self.calculate_smart_depth())
+            .min(time_attack.and_then(TimeAttack::horizon).unwrap_or(u32::MAX))
+            .max(1);
+        let time_limit = match (config.time_limit, time_attack.and_then(TimeAttack::move_time)) {
+            (Some(limit), Some(paced)) => Some(limit.min(paced)),
+            (limit, paced) => limit.or(paced),
+        };
+        let deadline = time_limit.map(|limit| start + limit);
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("search", target_depth, time_limit_ms = time_limit.map(|limit| limit.as_millis() as u64))
+            .entered();
+        let table_entries = tt.stats().2;
+        let mut ctx = SearchContext::new(tt, deadline);
+        ctx.scripted_deadline = scripted_deadline;
+        if config.trace {
+            ctx.trace = Some(Vec::new());
+        }
+        ctx.set_evaluation(config.weight_scale.as_ref(), config.score_model.as_deref(), config.fixed_point);
+        ctx.set_urgency(time_attack.map_or(0.0, TimeAttack::urgency));
+        ctx.sampling = config.chance_sampling;
+        ctx.spawns = config.spawn.weights(self.variant);
+        ctx.risk_aversion = config.risk_aversion;
+        ctx.parallel_chance_depth = config.parallel_chance_depth;
+        if config.profile {
+            ctx.profile = Some(SearchProfile::default());
+        }
+
+        let mut result = SearchResult {
+            best_move: None,
+            score: f32::NEG_INFINITY,
+            move_scores: Vec::new(),
+            depth: 0,
+            target_depth,
+            timed_out: false,
+            nodes: 0,
+            evaluations: 0,
+            elapsed: Duration::ZERO,
+            profile: None,
+            hopeless: false,
+            trace: None,
+            filtered_moves: Vec::new(),
+            power_up: None,
+        };
+        let candidates = self.root_candidates(config.root_prefilter, &mut ctx);
+        result.filtered_moves = self.order_moves().into_iter().filter(|direction| !candidates.contains(direction)).collect();
+
+        // Without a time limit there is nothing to gain from shallower passes.
+        let first_depth = if deadline.is_some() { 1 } else { target_depth };
+        for depth in first_depth..=target_depth {
+            #[cfg(feature = "tracing")]
+            let _iteration = tracing::trace_span!("iteration", depth).entered();
+            let move_scores = self.search_root(depth, &candidates, &result.move_scores, &mut ctx);
+            if ctx.aborted() {
+                #[cfg(feature = "tracing")]
+                tracing::trace!(nodes = ctx.nodes, "deadline hit mid-iteration");
+                result.timed_out = true;
+                break;
+            }
+            #[cfg(feature = "tracing")]
+            tracing::trace!(nodes = ctx.nodes, best_move = ?move_scores.first(), elapsed_us = start.elapsed().as_micros() as u64, "iteration done");
+            let best_move = move_scores.first().map(|&(direction, _)| direction);
+            result.best_move = best_move;
+            result.score = move_scores.first().map_or(f32::NEG_INFINITY, |&(_, score)| score);
+            result.move_scores = move_scores.to_vec();
+            ctx.count(|p| p.allocations += !move_scores.is_empty() as u64);
+            result.depth = depth;
+            if best_move.is_none() {
+                break;
+            }
+            if depth < target_depth && deadline.is_some() && ctx.deadline_passed() {
+                result.timed_out = true;
+                break;
+            }
+        }
+
+        if result.depth > 0 {
+            self.pin_root(result.depth, &result.move_scores, &mut ctx);
+        }
+
+        // A search cut off before depth 1 finished still needs a legal answer.
+        if result.best_move.is_none() {
+            result.best_move = candidates.first().copied();
+        }
+
+        if result.depth > 0 && (config.power_ups.swap > 0 || config.power_ups.delete > 0) {
+            result.power_up = self.best_power_up(result.depth, result.score, &config.power_ups, &mut ctx);
+        }
+
+        if let Some(threshold) = config.resign_threshold {
+            result.hopeless = survival_chance(self, &config.spawn) < threshold;
+        }
+
+        result.nodes = ctx.nodes;
+        result.evaluations = ctx.evaluations;
+        result.profile = ctx.profile;
+        result.trace = ctx.trace.map(|events| SearchTrace {
+            position: self.to_compact(),
+            settings: trace::settings(config),
+            table_entries,
+            events,
+        });
+        result.elapsed = start.elapsed();
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            depth = result.depth,
+            nodes = result.nodes,
+            evaluations = result.evaluations,
+            timed_out = result.timed_out,
+            elapsed_us = result.elapsed.as_micros() as u64,
+            "search done"
+        );
+        result
+    }
+
+    /// Pins, in place of the previous search's pins, the table entries the
+    /// next move will start from: each root move's chance node, and along
+    /// the best move every position its spawns leave, so cache maintenance
+    /// between moves cannot evict them.
+    fn pin_root(&self, depth: u32, move_scores: &[(Direction, f32)], ctx: &mut SearchContext) {
+        ctx.tt.unpin_all();
+        if depth < 2 {
+            return;
+        }
+        for (rank, &(direction, _)) in move_scores.iter().enumerate() {
+            let Some(mut child) = self.child(direction) else { continue };
+            ctx.tt.pin(child.board_hash(), depth - 1, false);
+            if rank > 0 || depth < 3 {
+                continue;
+            }
+            let cells: ArrayVec<(usize, usize), 16> = child.empty_cells().collect();
+            for (row, col) in cells {
+                for &(value, _) in &ctx.spawns {
+                    let undo = child.place_tile(row, col, value);
+                    ctx.tt.pin(child.board_hash(), depth - 2, true);
+                    child.undo(undo);
+                }
+            }
+        }
+    }
+
+    /// The legal moves in move-ordering order, less those whose static
+    /// evaluation falls more than `margin` (relative) below the best one.
+    fn root_candidates(&self, margin: Option<f32>, ctx: &mut SearchContext) -> ArrayVec<Direction, 4> {
+        let moves = self.order_moves();
+        ctx.count(|p| p.board_clones += CLONES_PER_ORDERED_MOVE * moves.len() as u64);
+        let Some(margin) = margin else {
+            return moves;
+        };
+        let quick: ArrayVec<f32, 4> = moves
+            .iter()
+            .map(|&direction| {
+                ctx.count(|p| p.board_clones += 1);
+                let child = self.child(direction).expect("ordered moves are legal");
+                ctx.evaluate(&child)
+            })
+            .collect();
+        let best = quick.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        moves
+            .into_iter()
+            .zip(quick)
+            .filter(|&(_, score)| (best - score) / best.abs().max(1.0) <= margin)
+            .map(|(direction, _)| direction)
+            .collect()
+    }
+
+    /// The swap or delete whose position searches best at `depth`, if it
+    /// beats `baseline`, the best plain move, by [`POWER_UP_MARGIN`]. Only
+    /// the few that evaluate best statically are searched.
+    fn best_power_up(&self, depth: u32, baseline: f32, charges: &PowerUps, ctx: &mut SearchContext) -> Option<(PowerUp, f32)> {
+        let mut options: Vec<(PowerUp, GameBoard, f32)> = self
+            .power_up_options(charges)
+            .into_iter()
+            .filter_map(|power_up| {
+                let board = self.with_power_up(&power_up).ok()?;
+                let quick = ctx.evaluate(&board);
+                Some((power_up, board, quick))
+            })
+            .collect();
+        options.sort_by(|a, b| b.2.total_cmp(&a.2));
+        let mut best = None;
+        // With no plain move left, anything that opens the board will do.
+        let mut best_score = if baseline.is_finite() { baseline + POWER_UP_MARGIN * baseline.abs().max(1.0) } else { baseline };
+        for (power_up, board, _) in options.into_iter().take(POWER_UP_CANDIDATES) {
+            let moves = board.order_moves();
+            let score = board.search_root(depth, &moves, &[], ctx).first().map_or(f32::NEG_INFINITY, |&(_, score)| score);
+            if ctx.aborted() {
+                break;
+            }
+            if score > best_score {
+                best = Some((power_up, score));
+                best_score = score;
+            }
+        }
+        best
+    }
+
+    /// Scores every move in `moves` at `depth`, best first. Moves are
+    /// searched in the order of `previous`, the last iteration's scores,
+    /// then the order of `moves`; ties keep that order, so the preferred
+    /// move wins them.
+    fn search_root(&self, depth: u32, moves: &[Direction], previous: &[(Direction, f32)], ctx: &mut SearchContext) -> ArrayVec<(Direction, f32), 4> {
+        let mut move_scores = ArrayVec::new();
+        // One working copy for the whole tree; children are made and
+        // unmade in place.
+        let mut board = self.clone();
+        let mut moves: ArrayVec<Direction, 4> = moves.iter().copied().collect();
+        moves.sort_by_key(|&direction| previous.iter().position(|&(d, _)| d == direction).unwrap_or(previous.len()));
+        ctx.count(|p| p.board_clones += 1);
+
+        for direction in moves {
+            if let Some(undo) = board.apply(direction) {
+                let score = board.expectimax_optimized(
+                    depth - 1,
+                    false,
+                    f32::NEG_INFINITY,
+                    f32::INFINITY,
+                    ctx,
+                );
+                board.undo(undo);
+                if ctx.aborted() {
+                    break;
+                }
+                move_scores.push((direction, score));
+            }
+        }
+
+        if move_scores.len() > 1 && !ctx.aborted() {
+            // Scores are in move order here; the first is the one ordered first.
+            let best = (1..move_scores.len()).fold(0, |best, i| if move_scores[i].1 > move_scores[best].1 { i } else { best });
+            ctx.count(|p| p.ordering[(depth as usize).min(ORDERING_DEPTHS - 1)].record(best, None));
+        }
+        move_scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+        move_scores
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_depth_search_reports_depth() {
+        let mut board = GameBoard::new();
+        board.set_board([
+            [2, 2, 0, 0],
+            [0, 4, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0]
+        ]);
+        let mut tt = TranspositionState::new();
+        let result = board.search_with(&SearchConfig::with_depth(3), &mut tt);
+        assert_eq!(result.depth, 3);
+        assert_eq!(result.target_depth, 3);
+        assert!(!result.timed_out);
+        assert!(result.best_move.is_some());
+        assert!(result.nodes > 0);
+        assert_eq!(result.move_scores.first().map(|&(d, _)| d), result.best_move);
+        assert!(result.move_scores.windows(2).all(|w| w[0].1 >= w[1].1));
+    }
+
+    #[test]
+    fn test_search_pins_what_the_next_move_needs() {
+        let board = GameBoard::from_compact("2,2,0,0/0,4,0,0/0,0,0,0/0,0,0,0").unwrap();
+        let mut tt = TranspositionState::new();
+        let result = board.search_with(&SearchConfig::with_depth(3), &mut tt);
+        let best = board.child(result.best_move.unwrap()).unwrap();
+        assert_eq!(tt.pinned(), result.move_scores.len() + 2 * best.count_empty_cells());
+
+        // Maintenance far below the table's size keeps only pinned entries.
+        tt.maintain(1);
+        tt.maintain(1);
+        for &(direction, score) in &result.move_scores {
+            let child = board.child(direction).unwrap();
+            assert_eq!(tt.probe(child.board_hash(), 2, false), Some(score));
+        }
+        assert!(tt.stats().2 <= tt.pinned());
+    }
+
+    #[test]
+    fn test_time_limited_search_always_returns_a_move() {
+        let mut board = GameBoard::new();
+        board.set_board([
+            [2, 4, 8, 16],
+            [32, 64, 128, 256],
+            [2, 4, 8, 16],
+            [0, 0, 0, 0]
+        ]);
+        let mut tt = TranspositionState::new();
+        let config = SearchConfig {
+            max_depth: Some(8),
+            ..SearchConfig::with_time_limit(Duration::ZERO)
+        };
+        let result = board.search_with(&config, &mut tt);
+        assert!(result.best_move.is_some());
+        assert!(result.timed_out);
+    }
+
+    #[test]
+    fn test_fixed_point_search_scores_are_whole_points() {
+        let mut board = GameBoard::new();
+        board.set_board([
+            [64, 32, 16, 8],
+            [4, 8, 2, 0],
+            [2, 0, 0, 0],
+            [0, 0, 0, 0]
+        ]);
+        let mut tt = TranspositionState::new();
+        let config = SearchConfig {
+            fixed_point: true,
+            ..SearchConfig::with_depth(3)
+        };
+        let result = board.search_with(&config, &mut tt);
+        assert!(result.best_move.is_some());
+        assert!(result.move_scores.iter().all(|&(_, score)| score.fract() == 0.0));
+    }
+
+    #[test]
+    fn test_profile_is_opt_in_and_accounts_for_every_node() {
+        let mut board = GameBoard::new();
+        board.set_board([
+            [4, 2, 0, 0],
+            [2, 0, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 2]
+        ]);
+        let plain = board.search_with(&SearchConfig::with_depth(3), &mut TranspositionState::new());
+        assert_eq!(plain.profile, None);
+
+        let config = SearchConfig {
+            profile: true,
+            ..SearchConfig::with_depth(3)
+        };
+        let result = board.search_with(&config, &mut TranspositionState::new());
+        let profile = result.profile.unwrap();
+        assert_eq!(result.best_move, plain.best_move);
+        assert_eq!(profile.evaluations, result.evaluations);
+        assert!(profile.max_nodes > 0 && profile.chance_nodes > 0 && profile.leaf_nodes > 0);
+        assert!(profile.max_nodes + profile.chance_nodes + profile.leaf_nodes + profile.terminal_nodes + profile.tt_hits <= result.nodes);
+        assert!(profile.tt_hits <= profile.tt_probes);
+        assert!(profile.eval_cache_hits <= profile.evaluations);
+        assert!(profile.board_clones > 0);
+        assert!(profile.allocations > 0);
+        let ordering = profile.ordering[3];
+        assert!(ordering.nodes > 0);
+        assert_eq!(ordering.cutoffs, 0);
+        assert!(ordering.first_best <= ordering.nodes);
+        assert!(profile.ordering[1].nodes > 0);
+        assert!(profile.ordering[1].first_cutoffs <= profile.ordering[1].cutoffs);
+    }
+
+    #[test]
+    fn test_narrower_chance_sampling_visits_fewer_nodes() {
+        let mut board = GameBoard::new();
+        board.set_board([
+            [4, 2, 0, 0],
+            [2, 0, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 2]
+        ]);
+        let nodes = |chance_sampling| {
+            let config = SearchConfig { chance_sampling, ..SearchConfig::with_depth(3) };
+            let result = board.search_with(&config, &mut TranspositionState::new());
+            assert!(result.best_move.is_some());
+            result.nodes
+        };
+        let all = nodes(ChanceSampling::All);
+        assert!(nodes(ChanceSampling::TopK(4)) < all);
+        assert!(nodes(ChanceSampling::RandomK { k: 4, seed: 1 }) < all);
+        assert!(nodes(ChanceSampling::ProbabilityThreshold(0.05)) < all);
+        assert_eq!(nodes(ChanceSampling::ProbabilityThreshold(0.0)), all);
+    }
+
+    #[test]
+    fn test_chance_nodes_follow_the_configured_spawns() {
+        let mut board = GameBoard::new();
+        board.set_board([
+            [2, 4, 0, 0],
+            [4, 2, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 2]
+        ]);
+        let score = |four_probability| {
+            let config = SearchConfig {
+                spawn: SpawnRules { four_probability, ..SpawnRules::default() },
+                ..SearchConfig::with_depth(2)
+            };
+            board.search_with(&config, &mut TranspositionState::new()).score
+        };
+        assert_eq!(score(0.1), board.search_with(&SearchConfig::with_depth(2), &mut TranspositionState::new()).score);
+        assert_ne!(score(0.0), score(1.0));
+    }
+
+    #[test]
+    fn test_risk_aversion_discounts_uncertain_moves() {
+        let mut board = GameBoard::new();
+        board.set_board([
+            [64, 32, 16, 8],
+            [4, 8, 2, 4],
+            [2, 4, 8, 2],
+            [0, 2, 0, 4]
+        ]);
+        let scores = |risk_aversion| {
+            let config = SearchConfig { risk_aversion, ..SearchConfig::with_depth(3) };
+            let mut scores = board.search_with(&config, &mut TranspositionState::new()).move_scores;
+            scores.sort_by_key(|&(direction, _)| direction as u8);
+            scores
+        };
+        let expected = scores(0.0);
+        let averse = scores(1.0);
+        assert_eq!(expected.len(), averse.len());
+        for ((direction, expected), (_, averse)) in expected.iter().zip(&averse) {
+            assert!(averse <= expected, "{:?}: {} > {}", direction, averse, expected);
+        }
+        assert!(expected.iter().zip(&averse).any(|(a, b)| b.1 < a.1));
+    }
+
+    #[test]
+    fn test_positions_one_spawn_from_death_are_hopeless() {
+        let mut board = GameBoard::new();
+        board.set_board([
+            [2, 4, 8, 16],
+            [32, 64, 128, 256],
+            [512, 1024, 2048, 4096],
+            [8192, 16384, 32768, 0]
+        ]);
+        let config = SearchConfig { resign_threshold: Some(0.5), ..SearchConfig::with_depth(2) };
+        assert!(board.search_with(&config, &mut TranspositionState::new()).hopeless);
+        assert!(!board.search_with(&SearchConfig::with_depth(2), &mut TranspositionState::new()).hopeless);
+
+        board.set_board([
+            [2, 4, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0]
+        ]);
+        assert!(!board.search_with(&config, &mut TranspositionState::new()).hopeless);
+    }
+
+    #[test]
+    fn test_deepening_from_the_last_variation_matches_a_fixed_depth_search() {
+        let mut board = GameBoard::new();
+        board.set_board([
+            [64, 32, 16, 8],
+            [4, 8, 2, 0],
+            [2, 0, 0, 0],
+            [0, 0, 0, 0]
+        ]);
+        let fixed = board.search_with(&SearchConfig::with_depth(4), &mut TranspositionState::new());
+        let config = SearchConfig {
+            time_limit: Some(Duration::from_secs(60)),
+            profile: true,
+            ..SearchConfig::with_depth(4)
+        };
+        let deepened = board.search_with(&config, &mut TranspositionState::new());
+        assert_eq!(deepened.depth, 4);
+        assert_eq!(deepened.best_move, fixed.best_move);
+        // Each iteration after the first starts from the last one's best move.
+        let root = deepened.profile.unwrap().ordering[4];
+        assert_eq!((root.nodes, root.first_best), (1, 1));
+    }
+
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    #[test]
+    fn test_parallel_chance_expansion_scores_like_the_serial_search() {
+        let mut board = GameBoard::new();
+        board.set_board([
+            [64, 32, 16, 8],
+            [4, 8, 2, 0],
+            [2, 0, 0, 0],
+            [0, 0, 0, 0]
+        ]);
+        let serial = board.search_with(&SearchConfig::with_depth(4), &mut TranspositionState::new());
+        let config = SearchConfig {
+            parallel_chance_depth: Some(3),
+            profile: true,
+            ..SearchConfig::with_depth(4)
+        };
+        let parallel = board.search_with(&config, &mut TranspositionState::new());
+        assert_eq!(parallel.move_scores, serial.move_scores);
+        assert!(parallel.nodes >= serial.nodes);
+        assert_eq!(parallel.profile.unwrap().evaluations, parallel.evaluations);
+    }
+
+    #[test]
+    fn test_pruned_table_entries_do_not_leak_into_likelier_searches() {
+        let mut board = GameBoard::new();
+        board.set_board([
+            [32, 16, 8, 4],
+            [2, 4, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 2]
+        ]);
+        let config = |depth| SearchConfig {
+            chance_sampling: ChanceSampling::ProbabilityThreshold(0.02),
+            ..SearchConfig::with_depth(depth)
+        };
+        // Every position a move and a spawn on, searched first deep inside
+        // the board's own search and then as a root of its own.
+        let mut tt = TranspositionState::new();
+        board.search_with(&config(5), &mut tt);
+        for direction in Direction::all() {
+            let Some(child) = board.child(direction) else { continue };
+            for (row, col) in child.empty_cells() {
+                let mut position = child.clone();
+                position.place_tile(row, col, 2);
+                let fresh = position.search_with(&config(3), &mut TranspositionState::new());
+                let shared = position.search_with(&config(3), &mut tt);
+                assert_eq!(shared.move_scores, fresh.move_scores);
+            }
+        }
+    }
+
+    #[test]
+    fn test_root_prefilter_searches_only_plausible_moves() {
+        let mut board = GameBoard::new();
+        board.set_board([
+            [1024, 512, 256, 128],
+            [8, 16, 32, 64],
+            [4, 2, 0, 0],
+            [0, 0, 0, 2]
+        ]);
+        let full = board.search_with(&SearchConfig::with_depth(3), &mut TranspositionState::new());
+        assert!(full.filtered_moves.is_empty());
+        let config = SearchConfig {
+            root_prefilter: Some(0.05),
+            ..SearchConfig::with_depth(3)
+        };
+        let filtered = board.search_with(&config, &mut TranspositionState::new());
+        assert!(!filtered.filtered_moves.is_empty());
+        assert_eq!(filtered.move_scores.len() + filtered.filtered_moves.len(), full.move_scores.len());
+        assert!(filtered.nodes < full.nodes);
+        assert_eq!(filtered.best_move, full.best_move);
+
+        let all = SearchConfig { root_prefilter: Some(f32::INFINITY), ..config };
+        assert_eq!(board.search_with(&all, &mut TranspositionState::new()).move_scores, full.move_scores);
+    }
+}
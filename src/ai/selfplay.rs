@@ -0,0 +1,224 @@
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rayon::prelude::*;
+
+use super::adaptation::WeightAdapter;
+use super::power_ups::PowerUpPlayer;
+use super::search::SearchConfig;
+use crate::cache::{clear_cache, maintain_cache, with_thread_tt};
+use crate::game::{GameBoard, PowerUpGame, PowerUps, SpawnRules};
+pub use crate::stats::GameRecord;
+
+/// Settings shared by every game of a self-play run.
+#[derive(Debug, Clone)]
+pub struct SelfPlayConfig {
+    pub search: SearchConfig,
+    pub spawn: SpawnRules,
+    /// Games stop after this many moves even if they are not over.
+    pub max_moves: u32,
+    /// Entries a worker's transposition table is kept under (see
+    /// [`crate::maintain_cache`]).
+    pub max_cache_entries: usize,
+    /// Power-up charges each game starts with.
+    pub power_ups: PowerUps,
+    /// Game `i` is played with seed `seed + i`.
+    pub seed: u64,
+    /// Worker threads; `None` uses every core, as does a count whose
+    /// threads cannot be started.
+    pub threads: Option<usize>,
+}
+
+impl Default for SelfPlayConfig {
+    fn default() -> Self {
+        Self {
+            search: SearchConfig::default(),
+            spawn: SpawnRules::default(),
+            max_moves: u32::MAX,
+            max_cache_entries: 1_000_000,
+            power_ups: PowerUps::default(),
+            seed: 0,
+            threads: None,
+        }
+    }
+}
+
+/// Running depth statistics for one game.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DepthStats {
+    sum: u64,
+    count: u32,
+    pub max: u32,
+}
+
+impl DepthStats {
+    pub fn record(&mut self, depth: u32) {
+        self.sum += depth as u64;
+        self.count += 1;
+        self.max = self.max.max(depth);
+    }
+
+    /// Searches recorded.
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.sum as f64 / self.count as f64
+        }
+    }
+}
+
+/// Every game of a run, ordered by seed, and the wall time it took.
+#[derive(Debug, Clone)]
+pub struct SelfPlayResults {
+    pub records: Vec<GameRecord>,
+    pub elapsed: Duration,
+}
+
+impl SelfPlayResults {
+    pub fn mean_score(&self) -> f64 {
+        self.records.iter().map(|r| r.score as f64).sum::<f64>() / self.records.len().max(1) as f64
+    }
+
+    /// Fraction of games that reached `tile`.
+    pub fn reach_rate(&self, tile: u32) -> f64 {
+        let reached = self.records.iter().filter(|r| r.max_tile >= tile).count();
+        reached as f64 / self.records.len().max(1) as f64
+    }
+}
+
+/// Plays one AI game whose spawns are fully determined by `seed`.
+pub fn play_game(seed: u64, config: &SelfPlayConfig) -> GameRecord {
+    let start = Instant::now();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut game = PowerUpGame::new(GameBoard::new_with_rules(&mut rng, &config.spawn), config.power_ups);
+    let mut depth = DepthStats::default();
+    // The search expects the spawns the game is played with.
+    let mut player = PowerUpPlayer::new(SearchConfig { spawn: config.spawn, ..config.search.clone() });
+    // A locked board is not over while a power-up can open it, so the game
+    // ends when the player finds no move.
+    while game.board().get_move_count() < config.max_moves {
+        if game.board().is_game_over() && game.charges().is_empty() {
+            break;
+        }
+        let result = with_thread_tt(|tt| player.choose(&mut game, tt)).search;
+        let Some(direction) = result.best_move else { break };
+        depth.record(result.depth);
+        // Resign rather than grind out a lost position.
+        if result.hopeless {
+            break;
+        }
+        if game.play_move_with_rules(direction, &mut rng, &config.spawn).is_err() {
+            break;
+        }
+        maintain_cache(game.board().get_move_count(), config.max_cache_entries);
+    }
+    // Each game starts from an empty table so results do not depend on
+    // which games a worker thread happened to play before.
+    clear_cache();
+    GameRecord {
+        seed,
+        score: game.score(),
+        max_tile: game.board().get_max_tile(),
+        moves: game.board().get_move_count(),
+        time_secs: start.elapsed().as_secs_f64(),
+        mean_depth: depth.mean(),
+        max_depth: depth.max,
+        merges: game.merges().clone(),
+    }
+}
+
+/// Plays `games` games in parallel. Each worker searches with its own
+/// thread-local table and each game with its own seeded RNG, so a run is
+/// reproducible regardless of the thread count.
+pub fn run_games(config: &SelfPlayConfig, games: usize) -> SelfPlayResults {
+    run_games_with(config, games, |_| {})
+}
+
+/// [`run_games`], calling `on_game` from the worker thread as each game
+/// finishes (e.g. to drive a progress bar).
+pub fn run_games_with<F>(config: &SelfPlayConfig, games: usize, on_game: F) -> SelfPlayResults
+where
+    F: Fn(&GameRecord) + Sync,
+{
+    let start = Instant::now();
+    let play = || {
+        (0..games)
+            .into_par_iter()
+            .map(|index| {
+                let record = play_game(config.seed.wrapping_add(index as u64), config);
+                on_game(&record);
+                record
+            })
+            .collect()
+    };
+    // A pool of the requested size that cannot be started (e.g. threads
+    // are exhausted) falls back to the global one; results are the same.
+    let pool = config.threads.and_then(|threads| rayon::ThreadPoolBuilder::new().num_threads(threads.max(1)).build().ok());
+    let records = match pool {
+        Some(pool) => pool.install(play),
+        None => play(),
+    };
+    SelfPlayResults {
+        records,
+        elapsed: start.elapsed(),
+    }
+}
+
+/// Plays `games` games one after another, each with the weights `adapter`
+/// proposes, teaching it every result. `on_game` sees each finished game
+/// and the adapter after learning from it (e.g. to save it); an error from
+/// it ends the run.
+pub fn run_adaptive_games<F, E>(config: &SelfPlayConfig, games: usize, adapter: &mut WeightAdapter, mut on_game: F) -> Result<SelfPlayResults, E>
+where
+    F: FnMut(&GameRecord, &WeightAdapter) -> Result<(), E>,
+{
+    let start = Instant::now();
+    let mut records = Vec::with_capacity(games);
+    for index in 0..games {
+        let weights = adapter.propose();
+        let config = SelfPlayConfig {
+            search: SearchConfig { weight_scale: Some(weights.clone()), ..config.search.clone() },
+            ..config.clone()
+        };
+        let record = play_game(config.seed.wrapping_add(index as u64), &config);
+        adapter.record(&weights, record.score);
+        on_game(&record, adapter)?;
+        records.push(record);
+    }
+    Ok(SelfPlayResults {
+        records,
+        elapsed: start.elapsed(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_games_is_independent_of_thread_count() {
+        let config = SelfPlayConfig {
+            search: SearchConfig::with_depth(1),
+            max_moves: 40,
+            seed: 9,
+            threads: Some(1),
+            ..SelfPlayConfig::default()
+        };
+        let serial = run_games(&config, 3);
+        let parallel = run_games(&SelfPlayConfig { threads: Some(3), ..config }, 3);
+        let scores = |results: &SelfPlayResults| results.records.iter().map(|r| (r.seed, r.score)).collect::<Vec<_>>();
+        assert_eq!(scores(&serial), scores(&parallel));
+        assert_eq!(serial.records.iter().map(|r| r.seed).collect::<Vec<_>>(), vec![9, 10, 11]);
+        // Classic merges earn their tile's value, so the breakdown adds up
+        // to the score.
+        for record in &serial.records {
+            assert_eq!(record.merges.iter().map(|(tile, count)| tile as u64 * count).sum::<u64>(), record.score as u64);
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::GameBoard;
+
+/// Weight of the points estimate against the static evaluation once the
+/// budget is spent.
+const POINTS_WEIGHT: f32 = 10.0;
+
+/// Moves a time budget is assumed to still have to cover when pacing the
+/// next search.
+const PACING_MOVES: u32 = 30;
+
+/// Shortest search a nearly spent time budget still allows.
+const MIN_MOVE_TIME: Duration = Duration::from_millis(1);
+
+/// What a time-attack game may spend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Budget {
+    Moves(u32),
+    Time(Duration),
+}
+
+/// A time-attack game: the most points within a budget of moves or
+/// wall-clock time. The game loop calls [`TimeAttack::record_move`] after
+/// every move; set in [`crate::SearchConfig::time_attack`], it paces each
+/// search, stops the search planning past the last budgeted move, and
+/// shifts the objective from board quality to points as the budget runs
+/// out, since a position that would collapse later no longer costs anything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeAttack {
+    pub budget: Budget,
+    pub moves_played: u32,
+    pub elapsed: Duration,
+}
+
+impl TimeAttack {
+    pub fn new(budget: Budget) -> Self {
+        Self {
+            budget,
+            moves_played: 0,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Counts a played move that took `took` of wall-clock time.
+    pub fn record_move(&mut self, took: Duration) {
+        self.moves_played += 1;
+        self.elapsed += took;
+    }
+
+    /// Share of the budget still left, from 1 down to 0.
+    pub fn remaining(&self) -> f32 {
+        match self.budget {
+            Budget::Moves(0) => 0.0,
+            Budget::Moves(moves) => moves.saturating_sub(self.moves_played) as f32 / moves as f32,
+            Budget::Time(time) if time.is_zero() => 0.0,
+            Budget::Time(time) => time.saturating_sub(self.elapsed).as_secs_f32() / time.as_secs_f32(),
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.remaining() <= 0.0
+    }
+
+    /// Moves still allowed by a move budget.
+    pub fn moves_left(&self) -> Option<u32> {
+        match self.budget {
+            Budget::Moves(moves) => Some(moves.saturating_sub(self.moves_played)),
+            Budget::Time(_) => None,
+        }
+    }
+
+    /// Deepest search worth running: the plies up to the last budgeted move.
+    pub(crate) fn horizon(&self) -> Option<u32> {
+        self.moves_left().map(|left| (2 * left).saturating_sub(1).max(1))
+    }
+
+    /// Time for the next search under a time budget: an even share of what
+    /// is left over the moves it is paced for.
+    pub(crate) fn move_time(&self) -> Option<Duration> {
+        match self.budget {
+            Budget::Time(time) => Some((time.saturating_sub(self.elapsed) / PACING_MOVES).max(MIN_MOVE_TIME)),
+            Budget::Moves(_) => None,
+        }
+    }
+
+    /// How far the objective has moved from board quality to points.
+    pub(crate) fn urgency(&self) -> f32 {
+        1.0 - self.remaining()
+    }
+}
+
+impl GameBoard {
+    /// Points the tiles on the board are worth if each was built from 2s:
+    /// a 2^k tile earns (k - 1) * 2^k. A merge raises this by exactly the
+    /// points it scores, so the search uses it for the points a line earns.
+    pub fn points_estimate(&self) -> u32 {
+        self.board
+            .iter()
+            .flatten()
+            .filter(|&&value| value > 2)
+            .map(|&value| (value.trailing_zeros() - 1) * value)
+            .sum()
+    }
+}
+
+/// `score` of a classic leaf with `urgency` of its weight moved to points.
+pub(crate) fn blend(score: f32, board: &GameBoard, urgency: f32) -> f32 {
+    (1.0 - urgency) * score + urgency * POINTS_WEIGHT * board.points_estimate() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::SearchConfig;
+    use crate::cache::TranspositionState;
+    use crate::game::Direction;
+
+    #[test]
+    fn test_points_estimate_rises_by_the_merge_score() {
+        let mut board = GameBoard::new();
+        board.set_board([
+            [8, 8, 4, 4],
+            [2, 2, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0]
+        ]);
+        let before = board.points_estimate();
+        let result = board.play_move_without_spawn(Direction::Left).unwrap();
+        assert_eq!(board.points_estimate() - before, result.score_gained);
+    }
+
+    #[test]
+    fn test_move_budget_caps_the_search_and_raises_urgency() {
+        let mut attack = TimeAttack::new(Budget::Moves(10));
+        assert_eq!(attack.urgency(), 0.0);
+        for _ in 0..9 {
+            attack.record_move(Duration::ZERO);
+        }
+        assert_eq!(attack.moves_left(), Some(1));
+        assert!(attack.urgency() > 0.8);
+
+        let mut board = GameBoard::new();
+        board.set_board([
+            [4, 2, 0, 0],
+            [2, 0, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 2]
+        ]);
+        let config = SearchConfig {
+            time_attack: Some(attack),
+            ..SearchConfig::with_depth(5)
+        };
+        let result = board.search_with(&config, &mut TranspositionState::new());
+        assert_eq!(result.target_depth, 1);
+        assert!(result.best_move.is_some());
+
+        attack.record_move(Duration::ZERO);
+        assert!(attack.is_over());
+    }
+
+    #[test]
+    fn test_time_budget_paces_each_search() {
+        let mut attack = TimeAttack::new(Budget::Time(Duration::from_secs(3)));
+        assert_eq!(attack.move_time(), Some(Duration::from_millis(100)));
+        attack.record_move(Duration::from_secs(4));
+        assert!(attack.is_over());
+        assert_eq!(attack.move_time(), Some(MIN_MOVE_TIME));
+    }
+}
@@ -0,0 +1,300 @@
+//! Search traces, for chasing nondeterminism in time-limited searches.
+//!
+//! With [`SearchConfig::trace`] set, a search records every table probe
+//! and store, cutoff, leaf evaluation and deadline hit, in order, into
+//! [`SearchResult::trace`]. [`SearchTrace::replay`] searches the position
+//! again with a fresh table, stopping at the recorded deadline's node
+//! instead of reading the clock, and [`SearchTrace::first_divergence`]
+//! finds the first event where the two runs part. A search that started
+//! on a warm table can only be replayed up to its first hit on an entry
+//! the fresh table lacks; record with a fresh table to replay it all.
+//! Parallel chance expansion is off while tracing.
+//!
+//! A trace file is a 16-byte header, two length-prefixed UTF-8 strings
+//! (the position in compact notation and the settings), the table's entry
+//! count when the search started (`u64`), the event count (`u64`) and the
+//! events, all little-endian. Each event is a tag byte and its fields:
+//!
+//! | tag  | event                     | fields                                   |
+//! |-----:|---------------------------|------------------------------------------|
+//! | 0, 1 | probe miss (1 = player)   | hash `u64`, depth `u8`                   |
+//! | 2, 3 | probe hit (3 = player)    | hash `u64`, depth `u8`, score `f32`      |
+//! | 4, 5 | store (5 = player)        | hash `u64`, depth `u8`, score `f32`      |
+//! |    6 | cutoff                    | hash `u64`, depth `u8`, move index `u8`  |
+//! |    7 | leaf evaluation           | hash `u64`, score `f32`                  |
+//! |    8 | deadline hit              | nodes visited `u64`                      |
+//!
+//! [`SearchResult::trace`]: super::SearchResult::trace
+
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use super::search::SearchConfig;
+use crate::cache::TranspositionState;
+use crate::game::GameBoard;
+
+pub const TRACE_MAGIC: [u8; 8] = *b"2048TRCE";
+pub const TRACE_VERSION: u32 = 1;
+
+/// One step of a traced search. Depths are remaining plies; `max_node`
+/// is true at player-move nodes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TraceEvent {
+    Probe { hash: u64, depth: u32, max_node: bool, hit: Option<f32> },
+    Store { hash: u64, depth: u32, max_node: bool, score: f32 },
+    /// The move at `index` in search order cut off the rest.
+    Cutoff { hash: u64, depth: u32, index: u32 },
+    Evaluate { hash: u64, score: f32 },
+    /// The deadline was found passed after visiting `nodes` nodes.
+    Deadline { nodes: u64 },
+}
+
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let node = |max_node: bool| if max_node { "player" } else { "chance" };
+        match *self {
+            TraceEvent::Probe { hash, depth, max_node, hit: Some(score) } => {
+                write!(f, "probe {:016x} depth {} ({}): hit {}", hash, depth, node(max_node), score)
+            }
+            TraceEvent::Probe { hash, depth, max_node, hit: None } => {
+                write!(f, "probe {:016x} depth {} ({}): miss", hash, depth, node(max_node))
+            }
+            TraceEvent::Store { hash, depth, max_node, score } => {
+                write!(f, "store {:016x} depth {} ({}): {}", hash, depth, node(max_node), score)
+            }
+            TraceEvent::Cutoff { hash, depth, index } => write!(f, "cutoff {:016x} depth {} at move {}", hash, depth, index + 1),
+            TraceEvent::Evaluate { hash, score } => write!(f, "evaluate {:016x}: {}", hash, score),
+            TraceEvent::Deadline { nodes } => write!(f, "deadline hit after {} nodes", nodes),
+        }
+    }
+}
+
+/// Everything one search did, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchTrace {
+    /// The searched position in compact notation.
+    pub position: String,
+    /// The search's settings as [`SearchConfig`]'s `Debug` output, with
+    /// `trace` and `profile` cleared; a replay must match them.
+    pub settings: String,
+    /// Entries in the table when the search started.
+    pub table_entries: usize,
+    pub events: Vec<TraceEvent>,
+}
+
+/// Where a replay first did something else than the recording.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub index: usize,
+    /// `None` when that run had already finished.
+    pub expected: Option<TraceEvent>,
+    pub actual: Option<TraceEvent>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayError {
+    /// The trace's position is not valid compact notation.
+    Position(String),
+    /// `config` is not the configuration the trace was recorded with.
+    Settings { recorded: String, given: String },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Position(err) => write!(f, "trace position: {}", err),
+            ReplayError::Settings { recorded, given } => {
+                write!(f, "the trace was recorded with other settings:\n  recorded: {}\n  given:    {}", recorded, given)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+/// `config` as recorded in a trace.
+pub(crate) fn settings(config: &SearchConfig) -> String {
+    format!("{:?}", SearchConfig { trace: false, profile: false, ..config.clone() })
+}
+
+impl SearchTrace {
+    /// Node count at which the recorded search hit its deadline, if it did.
+    fn deadline(&self) -> Option<u64> {
+        self.events.iter().rev().find_map(|event| match *event {
+            TraceEvent::Deadline { nodes } => Some(nodes),
+            _ => None,
+        })
+    }
+
+    /// Searches the position again with `config`, which must be the one
+    /// the trace was recorded with, and a fresh table. The deadline is hit
+    /// where the recording hit it, whatever the clock says.
+    pub fn replay(&self, config: &SearchConfig) -> Result<SearchTrace, ReplayError> {
+        let given = settings(config);
+        if given != self.settings {
+            return Err(ReplayError::Settings { recorded: self.settings.clone(), given });
+        }
+        let board = GameBoard::from_compact(&self.position).map_err(|err| ReplayError::Position(err.to_string()))?;
+        let config = SearchConfig { trace: true, ..config.clone() };
+        let result = board.search_scripted(&config, &mut TranspositionState::new(), Some(self.deadline().unwrap_or(u64::MAX)));
+        Ok(result.trace.expect("tracing was on"))
+    }
+
+    /// The first event at which `other` differs from this trace, if any.
+    pub fn first_divergence(&self, other: &SearchTrace) -> Option<Divergence> {
+        let index = (0..self.events.len().max(other.events.len()))
+            .find(|&i| self.events.get(i) != other.events.get(i))?;
+        Some(Divergence {
+            index,
+            expected: self.events.get(index).copied(),
+            actual: other.events.get(index).copied(),
+        })
+    }
+
+    pub fn write_to<W: Write>(&self, mut out: W) -> io::Result<()> {
+        out.write_all(&TRACE_MAGIC)?;
+        out.write_all(&TRACE_VERSION.to_le_bytes())?;
+        out.write_all(&[0; 4])?;
+        for text in [&self.position, &self.settings] {
+            out.write_all(&(text.len() as u32).to_le_bytes())?;
+            out.write_all(text.as_bytes())?;
+        }
+        out.write_all(&(self.table_entries as u64).to_le_bytes())?;
+        out.write_all(&(self.events.len() as u64).to_le_bytes())?;
+        let mut bytes = Vec::with_capacity(16);
+        for event in &self.events {
+            bytes.clear();
+            let mut node = |tag: u8, hash: u64, depth: u32| {
+                bytes.push(tag);
+                bytes.extend_from_slice(&hash.to_le_bytes());
+                bytes.push(depth.min(u8::MAX as u32) as u8);
+            };
+            match *event {
+                TraceEvent::Probe { hash, depth, max_node, hit } => {
+                    node(if hit.is_some() { 2 } else { 0 } + max_node as u8, hash, depth);
+                    if let Some(score) = hit {
+                        bytes.extend_from_slice(&score.to_le_bytes());
+                    }
+                }
+                TraceEvent::Store { hash, depth, max_node, score } => {
+                    node(4 + max_node as u8, hash, depth);
+                    bytes.extend_from_slice(&score.to_le_bytes());
+                }
+                TraceEvent::Cutoff { hash, depth, index } => {
+                    node(6, hash, depth);
+                    bytes.push(index as u8);
+                }
+                TraceEvent::Evaluate { hash, score } => {
+                    bytes.push(7);
+                    bytes.extend_from_slice(&hash.to_le_bytes());
+                    bytes.extend_from_slice(&score.to_le_bytes());
+                }
+                TraceEvent::Deadline { nodes } => {
+                    bytes.push(8);
+                    bytes.extend_from_slice(&nodes.to_le_bytes());
+                }
+            }
+            out.write_all(&bytes)?;
+        }
+        Ok(())
+    }
+
+    pub fn read_from<R: Read>(mut input: R) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+        let mut header = [0u8; 16];
+        input.read_exact(&mut header)?;
+        if header[..8] != TRACE_MAGIC {
+            return Err(invalid("not a trace file"));
+        }
+        if u32::from_le_bytes(header[8..12].try_into().unwrap()) != TRACE_VERSION {
+            return Err(invalid("unsupported trace version"));
+        }
+        let mut data = Vec::new();
+        input.read_to_end(&mut data)?;
+        let mut rest = &data[..];
+        let mut take = |n: usize| -> io::Result<&[u8]> {
+            if rest.len() < n {
+                return Err(invalid("truncated trace"));
+            }
+            let (head, tail) = rest.split_at(n);
+            rest = tail;
+            Ok(head)
+        };
+        let mut text = || -> io::Result<String> {
+            let len = u32::from_le_bytes(take(4)?.try_into().unwrap()) as usize;
+            String::from_utf8(take(len)?.to_vec()).map_err(|_| invalid("trace text is not UTF-8"))
+        };
+        let position = text()?;
+        let settings = text()?;
+        let table_entries = u64::from_le_bytes(take(8)?.try_into().unwrap()) as usize;
+        let count = u64::from_le_bytes(take(8)?.try_into().unwrap());
+        let mut events = Vec::new();
+        for _ in 0..count {
+            let tag = take(1)?[0];
+            let hash = |bytes: &[u8]| u64::from_le_bytes(bytes[..8].try_into().unwrap());
+            let score = |bytes: &[u8]| f32::from_le_bytes(bytes.try_into().unwrap());
+            let event = match tag {
+                0..=5 => {
+                    let node = take(9)?;
+                    let (hash, depth, max_node) = (hash(node), node[8] as u32, tag & 1 == 1);
+                    match tag {
+                        0 | 1 => TraceEvent::Probe { hash, depth, max_node, hit: None },
+                        2 | 3 => TraceEvent::Probe { hash, depth, max_node, hit: Some(score(take(4)?)) },
+                        _ => TraceEvent::Store { hash, depth, max_node, score: score(take(4)?) },
+                    }
+                }
+                6 => {
+                    let bytes = take(10)?;
+                    TraceEvent::Cutoff { hash: hash(bytes), depth: bytes[8] as u32, index: bytes[9] as u32 }
+                }
+                7 => {
+                    let bytes = take(12)?;
+                    TraceEvent::Evaluate { hash: hash(bytes), score: score(&bytes[8..]) }
+                }
+                8 => TraceEvent::Deadline { nodes: hash(take(8)?) },
+                _ => return Err(invalid("unknown trace event")),
+            };
+            events.push(event);
+        }
+        Ok(SearchTrace { position, settings, table_entries, events })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_timed_out_search_replays_event_for_event() {
+        let board = GameBoard::from_compact("2,4,8,16/0,0,2,4/0,0,0,2/0,0,0,0").unwrap();
+        let config = SearchConfig {
+            time_limit: Some(Duration::from_millis(20)),
+            trace: true,
+            ..SearchConfig::with_depth(12)
+        };
+        let result = board.search_with(&config, &mut TranspositionState::new());
+        let trace = result.trace.unwrap();
+        assert!(result.timed_out);
+        assert!(matches!(trace.events.last(), Some(TraceEvent::Deadline { .. })));
+
+        let mut file = Vec::new();
+        trace.write_to(&mut file).unwrap();
+        let read = SearchTrace::read_from(&file[..]).unwrap();
+        assert_eq!(read, trace);
+
+        // Whatever the clock does this time.
+        let again = read.replay(&config).unwrap();
+        assert_eq!(read.first_divergence(&again), None);
+
+        let mut tampered = read.clone();
+        tampered.events.truncate(5);
+        let divergence = tampered.first_divergence(&again).unwrap();
+        assert_eq!((divergence.index, divergence.expected), (5, None));
+        assert!(matches!(
+            read.replay(&SearchConfig::with_depth(12)),
+            Err(ReplayError::Settings { .. })
+        ));
+    }
+}
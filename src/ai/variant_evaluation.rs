@@ -0,0 +1,99 @@
+use crate::game::GameBoard;
+use super::optimized_evaluation::OptimizedEvaluationWeights;
+
+/// Base weights of the variant evaluation's terms.
+const EMPTY_WEIGHT: f32 = 20.0;
+const MONOTONICITY_WEIGHT: f32 = 8.0;
+const SMOOTHNESS_WEIGHT: f32 = 2.0;
+const MERGE_WEIGHT: f32 = 6.0;
+const CORNER_WEIGHT: f32 = 10.0;
+
+impl GameBoard {
+    /// Evaluation for boards that are not classic 2048. The classic terms
+    /// assume tiles double (log2 smoothness, the one-doubling chain bonus,
+    /// equal-tile merges), which is wrong for Threes or Fibonacci tiles, so
+    /// this one works on [`crate::GameRules::rank`] and asks the variant
+    /// which neighbours merge. `scale` multiplies the terms it shares with
+    /// the classic weights; the score is always a whole number.
+    pub fn evaluate_variant(&self, scale: Option<&OptimizedEvaluationWeights>) -> f32 {
+        let ranks = self.board.map(|row| row.map(|value| self.rules().rank(value) as i32));
+        let lines = (0..4).flat_map(|i| [ranks[i], ranks.map(|row| row[i])]);
+
+        let mut monotonicity = 0;
+        let mut smoothness = 0;
+        for line in lines {
+            // Cost of the steps against the line's better direction.
+            let (mut rising, mut falling) = (0, 0);
+            for pair in line.windows(2) {
+                let step = pair[1] - pair[0];
+                if step > 0 {
+                    rising += step;
+                } else {
+                    falling -= step;
+                }
+            }
+            monotonicity -= rising.min(falling);
+        }
+
+        let mut merges = 0;
+        for i in 0..4 {
+            for j in 0..4 {
+                let value = self.board[i][j];
+                for (ni, nj) in [(i + 1, j), (i, j + 1)] {
+                    if ni == 4 || nj == 4 || value == 0 || self.board[ni][nj] == 0 {
+                        continue;
+                    }
+                    match self.rules().merge(value, self.board[ni][nj]) {
+                        Some(merged) => merges += self.rules().rank(merged) as i32,
+                        None => smoothness -= (ranks[i][j] - ranks[ni][nj]).abs(),
+                    }
+                }
+            }
+        }
+
+        let top_rank = ranks.iter().flatten().copied().max().unwrap_or(0);
+        let corners = [ranks[0][0], ranks[0][3], ranks[3][0], ranks[3][3]];
+        let corner = if corners.contains(&top_rank) { top_rank } else { 0 };
+
+        let scale = scale.cloned().unwrap_or_else(|| OptimizedEvaluationWeights::uniform(1.0));
+        let terms = [
+            (self.count_empty_cells() as i32, EMPTY_WEIGHT * scale.empty),
+            (monotonicity, MONOTONICITY_WEIGHT * scale.monotonicity),
+            (smoothness, SMOOTHNESS_WEIGHT * scale.smoothness),
+            (merges, MERGE_WEIGHT * scale.merge),
+            (corner, CORNER_WEIGHT * scale.corner),
+        ];
+        terms.iter().map(|&(value, weight)| value as f32 * weight).sum::<f32>().round()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{GameBoard, Variant};
+
+    fn fibonacci(board: [[u32; 4]; 4]) -> GameBoard {
+        let mut game = GameBoard::new();
+        game.set_board(board);
+        game.set_variant(Variant::Fibonacci);
+        game
+    }
+
+    #[test]
+    fn test_fibonacci_neighbours_score_as_merges() {
+        // Same tiles; only the first keeps Fibonacci neighbours side by side.
+        let neighbours = fibonacci([
+            [13, 8, 5, 3],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0]
+        ]);
+        let apart = fibonacci([
+            [13, 5, 8, 3],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0]
+        ]);
+        assert!(neighbours.evaluate_variant(None) > apart.evaluate_variant(None));
+        assert_eq!(neighbours.evaluate_variant(None).fract(), 0.0);
+    }
+}
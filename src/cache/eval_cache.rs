@@ -0,0 +1,97 @@
+/// Slots in the default evaluation cache: small enough to stay in the CPU
+/// cache, since a miss to main memory costs as much as evaluating.
+pub const DEFAULT_EVAL_CACHE_ENTRIES: usize = 1 << 16;
+
+/// Marks a slot that has never been written.
+const EMPTY_SLOT: (u64, f32) = (0, 0.0);
+
+/// Memoized static evaluations keyed by board hash alone: a leaf is worth
+/// the same at every depth, so unlike search scores these are shared by
+/// every iteration and every move. Direct-mapped: each hash has one slot
+/// and a newer board simply replaces the one there. Entries are only valid
+/// for one set of evaluation weights, identified by `tag`.
+pub struct EvalCache {
+    slots: Vec<(u64, f32)>,
+    tag: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl EvalCache {
+    /// Cache of `max_entries` slots, rounded up to a power of two.
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            slots: vec![EMPTY_SLOT; max_entries.max(1).next_power_of_two()],
+            tag: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    fn slot(&self, hash: u64) -> usize {
+        hash as usize & (self.slots.len() - 1)
+    }
+
+    /// Drops every entry if they were computed under a different `tag`.
+    pub fn retag(&mut self, tag: u64) {
+        if tag != self.tag {
+            self.slots.fill(EMPTY_SLOT);
+            self.tag = tag;
+        }
+    }
+
+    pub fn probe(&mut self, hash: u64) -> Option<f32> {
+        let (key, score) = self.slots[self.slot(hash)];
+        if key == hash && hash != 0 {
+            self.hits += 1;
+            Some(score)
+        } else {
+            self.misses += 1;
+            None
+        }
+    }
+
+    pub fn store(&mut self, hash: u64, score: f32) {
+        let slot = self.slot(hash);
+        self.slots[slot] = (hash, score);
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.fill(EMPTY_SLOT);
+        self.hits = 0;
+        self.misses = 0;
+    }
+
+    /// Hits, misses and occupied slots.
+    pub fn stats(&self) -> (u64, u64, usize) {
+        let used = self.slots.iter().filter(|&&(key, _)| key != 0).count();
+        (self.hits, self.misses, used)
+    }
+}
+
+impl Default for EvalCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVAL_CACHE_ENTRIES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eval_cache_replaces_colliding_boards_and_retags() {
+        let mut cache = EvalCache::new(3);
+        cache.store(1, 10.0);
+        cache.store(2, 20.0);
+        assert_eq!(cache.probe(1), Some(10.0));
+        // 4 slots, so 5 lands where 1 was.
+        cache.store(5, 50.0);
+        assert_eq!(cache.probe(1), None);
+        assert_eq!(cache.probe(5), Some(50.0));
+
+        cache.retag(7);
+        assert_eq!(cache.probe(5), None);
+        assert_eq!(cache.stats(), (2, 2, 0));
+    }
+}
@@ -0,0 +1,60 @@
+/// A Bloom filter over transposition-table keys: two bits per key, so a
+/// probe that finds either bit unset is a guaranteed miss and skips the
+/// hash-map lookup. It never forgets a key until cleared, so it only pays
+/// while the table is small against it: about 10 bits per stored entry
+/// keeps false positives near 5%.
+pub struct MissFilter {
+    words: Vec<u64>,
+    /// Probes answered as misses without a table lookup.
+    skipped: u64,
+}
+
+impl MissFilter {
+    /// A filter of `bits` bits, rounded up to a power of two (at least 64).
+    pub fn new(bits: usize) -> Self {
+        Self {
+            words: vec![0; bits.max(64).next_power_of_two() / 64],
+            skipped: 0,
+        }
+    }
+
+    /// The two bit positions of `key`, from its mixed high and low halves.
+    fn positions(&self, key: u64) -> [usize; 2] {
+        // SplitMix64 finalizer: neighbouring keys land far apart.
+        let mut mixed = key;
+        mixed = (mixed ^ (mixed >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        mixed = (mixed ^ (mixed >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        mixed ^= mixed >> 31;
+        let mask = self.words.len() * 64 - 1;
+        [mixed as usize & mask, (mixed >> 32) as usize & mask]
+    }
+
+    pub fn bits(&self) -> usize {
+        self.words.len() * 64
+    }
+
+    pub fn insert(&mut self, key: u64) {
+        for bit in self.positions(key) {
+            self.words[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    /// False only if `key` was never inserted; counts those probes.
+    pub fn may_contain(&mut self, key: u64) -> bool {
+        let present = self.positions(key).iter().all(|&bit| self.words[bit / 64] & (1 << (bit % 64)) != 0);
+        if !present {
+            self.skipped += 1;
+        }
+        present
+    }
+
+    /// Probes answered without a table lookup since the last clear.
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+        self.skipped = 0;
+    }
+}
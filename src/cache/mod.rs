@@ -1,3 +1,7 @@
+mod eval_cache;
+mod miss_filter;
 mod transposition;
 
-pub use transposition::{clear_cache, get_cache_stats, with_thread_tt, TranspositionState}; 
\ No newline at end of file
+pub use eval_cache::{EvalCache, DEFAULT_EVAL_CACHE_ENTRIES};
+pub use miss_filter::MissFilter;
+pub use transposition::{clear_cache, get_cache_stats, get_eval_cache_stats, maintain_cache, with_thread_tt, EntryQuality, Maintenance, TranspositionState, MAINTENANCE_INTERVAL};
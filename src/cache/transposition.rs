@@ -1,5 +1,8 @@
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+use super::eval_cache::EvalCache;
+use super::miss_filter::MissFilter;
 
 /// Lookup key: same board can have different values depending on how much
 /// lookahead remains and whether the next event is a player move or a spawn.
@@ -11,12 +14,81 @@ struct TtKey {
     max_node: bool,
 }
 
+impl TtKey {
+    /// The key folded to one word for the [`MissFilter`].
+    fn filter_key(&self) -> u64 {
+        let phase = ((self.depth as u64) << 1) | self.max_node as u64;
+        self.hash ^ phase.wrapping_mul(0x9e37_79b9_7f4a_7c15)
+    }
+}
+
+/// How far a stored score can be trusted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntryQuality {
+    /// The full search to the entry's depth.
+    Exact,
+    /// Some spawns below the entry were too unlikely to search and were
+    /// evaluated statically instead (see
+    /// [`crate::ChanceSampling::ProbabilityThreshold`]). The position was
+    /// reached with probability `reach`; reached more likely, the search
+    /// would have pruned less, so the score only stands in for searches
+    /// reaching it at most this likely.
+    Pruned { reach: f32 },
+}
+
+#[derive(Clone, Copy)]
+struct TtEntry {
+    score: f32,
+    quality: EntryQuality,
+    /// Maintenance generation of the entry's last store or hit.
+    generation: u32,
+}
+
+/// Moves between [`maintain_cache`] checks.
+pub const MAINTENANCE_INTERVAL: u32 = 200;
+
+/// Generations an entry may go unused before a drop in hit rate evicts it.
+const STALE_GENERATIONS: u32 = 2;
+
+/// A check's hit rate below this share of the previous check's counts as
+/// the table losing effectiveness.
+const HIT_RATE_DROP: f64 = 0.75;
+
+/// Fill below which the table is left alone however its hit rate moves.
+const EVICTION_FILL: f64 = 0.5;
+
+/// Share of capacity an over-full table is evicted down to.
+const EVICTION_TARGET: f64 = 0.75;
+
+/// What one [`TranspositionState::maintain`] check saw and did.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Maintenance {
+    /// Entries before eviction.
+    pub entries: usize,
+    /// Entries evicted.
+    pub evicted: usize,
+    /// Share of probes since the previous check that hit.
+    pub hit_rate: f64,
+    /// Entries over capacity.
+    pub fill: f64,
+}
+
 /// Transposition table for expectimax. Pass `&mut TranspositionState` through
 /// the search so probes/stores avoid global synchronization.
 pub struct TranspositionState {
-    map: HashMap<TtKey, f32>,
+    map: HashMap<TtKey, TtEntry>,
     hits: u64,
     misses: u64,
+    /// Keys maintenance never evicts, whether or not they are stored yet.
+    pinned: HashSet<TtKey>,
+    /// Maintenance checks so far; stamps entries as they are used.
+    generation: u32,
+    /// Hits and misses at the previous check, and its hit rate.
+    checked: (u64, u64, Option<f64>),
+    /// Answers guaranteed misses before the map is looked up, when set.
+    filter: Option<MissFilter>,
+    /// Static leaf evaluations, kept apart from the depth-keyed scores.
+    pub eval: EvalCache,
 }
 
 impl TranspositionState {
@@ -25,42 +97,192 @@ impl TranspositionState {
             map: HashMap::new(),
             hits: 0,
             misses: 0,
+            pinned: HashSet::new(),
+            generation: 0,
+            checked: (0, 0, None),
+            filter: None,
+            eval: EvalCache::default(),
         }
     }
 
+    /// A table whose evaluation cache holds about `entries` evaluations
+    /// instead of the default, for short-lived tables.
+    pub fn with_eval_entries(entries: usize) -> Self {
+        Self {
+            eval: EvalCache::new(entries),
+            ..Self::new()
+        }
+    }
+
+    /// Puts a [`MissFilter`] of `bits` bits in front of the table, so
+    /// probes for positions never stored skip the lookup; `0` removes it.
+    /// Entries already stored are added to it.
+    pub fn set_miss_filter(&mut self, bits: usize) {
+        self.filter = (bits > 0).then(|| {
+            let mut filter = MissFilter::new(bits);
+            for key in self.map.keys() {
+                filter.insert(key.filter_key());
+            }
+            filter
+        });
+    }
+
+    /// Keeps the entry for the position from being evicted by
+    /// [`TranspositionState::maintain`] until it is unpinned or the table
+    /// cleared. The search pins what the next move will need.
+    pub fn pin(&mut self, hash: u64, depth: u32, max_node: bool) {
+        self.pinned.insert(TtKey { hash, depth, max_node });
+    }
+
+    pub fn unpin_all(&mut self) {
+        self.pinned.clear();
+    }
+
+    /// Keys pinned.
+    pub fn pinned(&self) -> usize {
+        self.pinned.len()
+    }
+
+    /// Probes the miss filter answered without a lookup since the last
+    /// clear; 0 without a filter.
+    pub fn filtered_misses(&self) -> u64 {
+        self.filter.as_ref().map_or(0, MissFilter::skipped)
+    }
+
+    /// The score of a full search of the position, if one is stored.
     pub fn probe(&mut self, hash: u64, depth: u32, max_node: bool) -> Option<f32> {
+        self.probe_at(hash, depth, max_node, f32::INFINITY).map(|(score, _)| score)
+    }
+
+    /// A stored score good enough for a search reaching the position with
+    /// probability `reach`: exact, or pruned from at least as likely a
+    /// reach. Entries pruned more than the search would prune count as
+    /// misses.
+    pub fn probe_at(&mut self, hash: u64, depth: u32, max_node: bool, reach: f32) -> Option<(f32, EntryQuality)> {
         let key = TtKey {
             hash,
             depth,
             max_node,
         };
-        if let Some(&score) = self.map.get(&key) {
+        if let Some(filter) = &mut self.filter {
+            if !filter.may_contain(key.filter_key()) {
+                self.misses += 1;
+                return None;
+            }
+        }
+        let entry = self.map.get_mut(&key).filter(|entry| match entry.quality {
+            EntryQuality::Exact => true,
+            EntryQuality::Pruned { reach: stored } => reach <= stored,
+        });
+        if let Some(entry) = entry {
+            entry.generation = self.generation;
             self.hits += 1;
-            Some(score)
+            Some((entry.score, entry.quality))
         } else {
             self.misses += 1;
             None
         }
     }
 
+    /// Stores the score of a full search.
     pub fn store(&mut self, hash: u64, depth: u32, max_node: bool, score: f32) {
+        self.store_with(hash, depth, max_node, score, EntryQuality::Exact);
+    }
+
+    pub fn store_with(&mut self, hash: u64, depth: u32, max_node: bool, score: f32, quality: EntryQuality) {
         let key = TtKey {
             hash,
             depth,
             max_node,
         };
-        self.map.insert(key, score);
+        if let Some(filter) = &mut self.filter {
+            filter.insert(key.filter_key());
+        }
+        self.map.insert(key, TtEntry { score, quality, generation: self.generation });
+    }
+
+    /// Entries the table can hold before it has to reallocate.
+    pub(crate) fn capacity(&self) -> usize {
+        self.map.capacity()
     }
 
     pub fn clear(&mut self) {
         self.map.clear();
         self.hits = 0;
         self.misses = 0;
+        self.checked = (0, 0, None);
+        self.pinned.clear();
+        if let Some(filter) = &mut self.filter {
+            filter.clear();
+        }
+        self.eval.clear();
     }
 
     pub fn stats(&self) -> (u64, u64, usize) {
         (self.hits, self.misses, self.map.len())
     }
+
+    /// Keeps the table under `max_entries` without wiping it: an over-full
+    /// table loses its longest-unused entries, then its shallowest, down to
+    /// three quarters of capacity; a table at least half full whose hit
+    /// rate has fallen by a quarter since the previous check loses the
+    /// entries no probe has used for two checks. Pinned entries always stay.
+    /// Starts a new generation.
+    pub fn maintain(&mut self, max_entries: usize) -> Maintenance {
+        let probes = (self.hits - self.checked.0) + (self.misses - self.checked.1);
+        let hit_rate = if probes == 0 { 0.0 } else { (self.hits - self.checked.0) as f64 / probes as f64 };
+        let entries = self.map.len();
+        let fill = entries as f64 / max_entries.max(1) as f64;
+        let evicted = if entries > max_entries {
+            self.evict_to((max_entries as f64 * EVICTION_TARGET) as usize)
+        } else if fill >= EVICTION_FILL && self.checked.2.is_some_and(|previous| hit_rate < previous * HIT_RATE_DROP) {
+            self.evict_older_than(STALE_GENERATIONS)
+        } else {
+            0
+        };
+        if evicted > 0 {
+            if let Some(filter) = &self.filter {
+                self.set_miss_filter(filter.bits());
+            }
+        }
+        self.checked = (self.hits, self.misses, (probes > 0).then_some(hit_rate));
+        self.generation = self.generation.wrapping_add(1);
+        Maintenance { entries, evicted, hit_rate, fill }
+    }
+
+    /// Evicts entries unused for at least `age` generations; returns how many.
+    fn evict_older_than(&mut self, age: u32) -> usize {
+        let (before, generation, pinned) = (self.map.len(), self.generation, &self.pinned);
+        self.map.retain(|key, entry| generation.wrapping_sub(entry.generation) < age || pinned.contains(key));
+        before - self.map.len()
+    }
+
+    /// Evicts the longest-unused entries, then the shallowest, until at most
+    /// `target` are left; returns how many went.
+    fn evict_to(&mut self, target: usize) -> usize {
+        let before = self.map.len();
+        for age in (1..=STALE_GENERATIONS).rev() {
+            if self.map.len() <= target {
+                return before - self.map.len();
+            }
+            self.evict_older_than(age);
+        }
+        let deepest = self.map.keys().map(|key| key.depth).max().unwrap_or(0);
+        for depth in 0..=deepest {
+            if self.map.len() <= target {
+                break;
+            }
+            let pinned = &self.pinned;
+            self.map.retain(|key, _| key.depth > depth || pinned.contains(key));
+        }
+        before - self.map.len()
+    }
+}
+
+impl Default for TranspositionState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 thread_local! {
@@ -74,7 +296,7 @@ pub fn with_thread_tt<F, R>(f: F) -> R
 where
     F: FnOnce(&mut TranspositionState) -> R,
 {
-    THREAD_TT.with(|cell| f(&mut *cell.borrow_mut()))
+    THREAD_TT.with(|cell| f(&mut cell.borrow_mut()))
 }
 
 pub fn get_cache_stats() -> (u64, u64, usize) {
@@ -84,12 +306,30 @@ pub fn get_cache_stats() -> (u64, u64, usize) {
     })
 }
 
+/// Hits, misses and entries of the current thread's static-evaluation cache.
+pub fn get_eval_cache_stats() -> (u64, u64, usize) {
+    THREAD_TT.with(|cell| cell.borrow().eval.stats())
+}
+
 pub fn clear_cache() {
     THREAD_TT.with(|cell| {
         cell.borrow_mut().clear();
     });
 }
 
+/// Runs [`TranspositionState::maintain`] on the current thread's table every
+/// [`MAINTENANCE_INTERVAL`] moves, keeping it under `max_entries`. Returns
+/// what the check did when one ran.
+pub fn maintain_cache(moves: u32, max_entries: usize) -> Option<Maintenance> {
+    if moves == 0 || !moves.is_multiple_of(MAINTENANCE_INTERVAL) {
+        return None;
+    }
+    let report = with_thread_tt(|tt| tt.maintain(max_entries));
+    #[cfg(feature = "tracing")]
+    tracing::debug!(moves, entries = report.entries, evicted = report.evicted, hit_rate = report.hit_rate, max_entries, "transposition table maintenance");
+    Some(report)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -107,4 +347,99 @@ mod tests {
         assert_eq!(tt.probe(h, 5, false), Some(30.0));
         assert_eq!(tt.probe(h, 4, true), None);
     }
+
+    #[test]
+    fn pruned_entries_only_serve_less_likely_positions() {
+        let mut tt = TranspositionState::new();
+        let h = 0x7e57_7e57_0000_0002_u64;
+        tt.store_with(h, 4, false, 50.0, EntryQuality::Pruned { reach: 0.01 });
+
+        assert_eq!(tt.probe(h, 4, false), None);
+        assert_eq!(tt.probe_at(h, 4, false, 0.1), None);
+        assert_eq!(tt.probe_at(h, 4, false, 0.001), Some((50.0, EntryQuality::Pruned { reach: 0.01 })));
+
+        tt.store(h, 4, false, 60.0);
+        assert_eq!(tt.probe(h, 4, false), Some(60.0));
+    }
+
+    #[test]
+    fn miss_filter_skips_unstored_keys_and_never_hides_stored_ones() {
+        let mut tt = TranspositionState::new();
+        tt.store(1, 2, true, 5.0);
+        tt.set_miss_filter(1 << 12);
+        for hash in 0..200 {
+            tt.store(hash * 7919, 3, hash % 2 == 0, hash as f32);
+        }
+
+        assert_eq!(tt.probe(1, 2, true), Some(5.0));
+        for hash in 0..200 {
+            assert_eq!(tt.probe(hash * 7919, 3, hash % 2 == 0), Some(hash as f32));
+        }
+        for hash in 1000..1200 {
+            assert_eq!(tt.probe(hash * 7919, 3, true), None);
+        }
+        // 201 keys in 4096 bits: nearly every unstored key is filtered.
+        assert!(tt.filtered_misses() > 150, "{}", tt.filtered_misses());
+        assert_eq!(tt.stats().1, 200);
+
+        tt.clear();
+        assert_eq!(tt.filtered_misses(), 0);
+        assert_eq!(tt.probe(1, 2, true), None);
+        assert_eq!(tt.filtered_misses(), 1);
+    }
+
+    #[test]
+    fn maintenance_evicts_unused_and_shallow_entries_instead_of_wiping() {
+        let mut tt = TranspositionState::new();
+        let depth = |hash: u64| if hash < 10 { 5 } else { 2 };
+        for hash in 0..40 {
+            tt.store(hash, depth(hash), true, 1.0);
+        }
+        let report = tt.maintain(100);
+        assert_eq!((report.entries, report.evicted), (40, 0));
+
+        // Ten entries stay in use while new ones keep arriving.
+        for hash in 40..120 {
+            tt.store(hash, 1 + hash as u32 % 4, true, 1.0);
+            assert!(tt.probe(hash % 10, 5, true).is_some());
+        }
+        // Over capacity: the 30 unused old entries go, then the 20 shallowest.
+        let report = tt.maintain(100);
+        assert_eq!((report.entries, report.evicted, report.hit_rate), (120, 50, 1.0));
+        assert_eq!(tt.stats().2, 70);
+        assert!((0..10).all(|hash| tt.probe(hash, 5, true).is_some()));
+        assert!((10..40).all(|hash| tt.probe(hash, 2, true).is_none()));
+
+        // Probes that stop hitting evict entries unused for two checks.
+        tt.maintain(100);
+        for hash in 1000..1100 {
+            tt.probe(hash, 1, true);
+        }
+        let report = tt.maintain(100);
+        assert_eq!((report.hit_rate, report.evicted), (0.0, 60));
+        assert_eq!(tt.stats().2, 10);
+    }
+
+    #[test]
+    fn pinned_entries_survive_eviction_until_unpinned() {
+        let mut tt = TranspositionState::new();
+        for hash in 0..100 {
+            tt.store(hash, 1, false, 1.0);
+        }
+        tt.pin(7, 1, false);
+        tt.pin(8, 2, true);
+        tt.maintain(100);
+        for hash in 100..200 {
+            tt.store(hash, 1, false, 1.0);
+        }
+        // Everything is as shallow as the pinned entry; it alone stays.
+        assert_eq!(tt.maintain(10).evicted, 199);
+        assert_eq!(tt.probe(7, 1, false), Some(1.0));
+        assert_eq!(tt.pinned(), 2);
+
+        tt.unpin_all();
+        tt.store(0, 1, false, 1.0);
+        tt.maintain(1);
+        assert_eq!(tt.stats().2, 0);
+    }
 }
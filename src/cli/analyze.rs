@@ -0,0 +1,239 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::Serialize;
+use twenty_forty_eight::utils::render;
+use twenty_forty_eight::{clear_cache, rate_difficulty_with, Difficulty, Direction, GameBoard, PvStep, RolloutConfig, SearchConfig};
+
+use super::config::{GameSettings, SolverConfig};
+use super::{print_json, AnalyzeArgs, OutputFormat};
+
+/// Fixed depth for win-probability rollouts: a few hundred microseconds per
+/// move, and still reaches 2048 in most games from the opening.
+const ROLLOUT_DEPTH: u32 = 3;
+/// Rollouts give up after this many moves.
+const ROLLOUT_MAX_MOVES: u32 = 20_000;
+
+#[derive(Serialize)]
+struct TermReport {
+    name: &'static str,
+    value: f32,
+    weight: f32,
+    contribution: f32,
+}
+
+#[derive(Serialize)]
+struct MoveReport {
+    direction: Direction,
+    /// `None` when the move does not change the board.
+    score: Option<f32>,
+}
+
+#[derive(Serialize)]
+struct MonteCarloMove {
+    direction: Direction,
+    rollouts: u32,
+    mean: f64,
+    /// 95% confidence interval of `mean`.
+    low: f64,
+    high: f64,
+}
+
+#[derive(Serialize)]
+struct MonteCarloReport {
+    moves: Vec<MonteCarloMove>,
+    separated: bool,
+}
+
+/// `--output json` document of `analyze`.
+#[derive(Serialize)]
+struct AnalyzeReport {
+    position: String,
+    evaluation: f32,
+    terms: Vec<TermReport>,
+    depth: u32,
+    best_move: Option<Direction>,
+    moves: Vec<MoveReport>,
+    principal_variation: Vec<PvStep>,
+    win_tile: u32,
+    rollouts: usize,
+    win_probability: f64,
+    difficulty: Difficulty,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    monte_carlo: Option<MonteCarloReport>,
+}
+
+/// Reads the position from a file when `position` names one, otherwise
+/// parses it as compact notation.
+pub(super) fn load_position(position: &str) -> io::Result<GameBoard> {
+    let path = Path::new(position);
+    let text = if path.is_file() { fs::read_to_string(path)? } else { position.to_string() };
+    GameBoard::from_compact(&text).map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))
+}
+
+/// [`load_position`], rejecting a position no legal game reaches unless
+/// `allow_illegal` is set.
+pub(super) fn load_legal_position(position: &str, allow_illegal: bool) -> io::Result<GameBoard> {
+    let board = load_position(position)?;
+    if !allow_illegal {
+        board.check_legality().map_err(|reason| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("illegal position: {} (pass --allow-illegal to search it anyway)", reason))
+        })?;
+    }
+    Ok(board)
+}
+
+/// Share of seeded rollouts from `board` that reach `win_tile`.
+fn win_probability(board: &GameBoard, settings: &GameSettings, win_tile: u32, rollouts: usize, seed: u64) -> f64 {
+    if board.get_max_tile() >= win_tile {
+        return 1.0;
+    }
+    let search = SearchConfig {
+        weight_scale: settings.search.weight_scale.clone(),
+        spawn: settings.spawn,
+        ..SearchConfig::with_depth(ROLLOUT_DEPTH)
+    };
+    let next = AtomicUsize::new(0);
+    let wins = AtomicUsize::new(0);
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                if index >= rollouts {
+                    break;
+                }
+                let mut rng = StdRng::seed_from_u64(seed.wrapping_add(index as u64));
+                let mut game = board.clone();
+                let mut moves = 0;
+                while game.get_max_tile() < win_tile && !game.is_game_over() && moves < ROLLOUT_MAX_MOVES {
+                    let Some(direction) = game.search(&search).best_move else { break };
+                    if game.play_move_with_rules(direction, &mut rng, &settings.spawn).is_err() {
+                        break;
+                    }
+                    moves += 1;
+                }
+                if game.get_max_tile() >= win_tile {
+                    wins.fetch_add(1, Ordering::Relaxed);
+                }
+                clear_cache();
+            });
+        }
+    });
+    wins.into_inner() as f64 / rollouts.max(1) as f64
+}
+
+pub fn run(args: &AnalyzeArgs, config: &SolverConfig, format: OutputFormat) -> io::Result<()> {
+    let board = load_legal_position(&args.position, args.allow_illegal)?;
+    let settings = config.game_settings();
+    let mut search = settings.search.clone();
+    if args.depth.is_some() {
+        search.max_depth = args.depth;
+    }
+    let seed = args.seed.unwrap_or_else(rand::random);
+
+    let terms = board.evaluation_breakdown(search.weight_scale.as_ref());
+    let evaluation: f32 = terms.iter().map(|t| t.contribution()).sum();
+    let result = board.search(&search);
+    let moves: Vec<MoveReport> = Direction::all()
+        .into_iter()
+        .map(|direction| MoveReport {
+            direction,
+            score: result
+                .move_scores
+                .iter()
+                .find(|&&(d, _)| d == direction)
+                .map(|&(_, score)| score),
+        })
+        .collect();
+    let pv = board.principal_variation(&search, result.depth);
+    let probability = win_probability(&board, &settings, args.win_tile, args.rollouts, seed);
+    let monte_carlo = args.monte_carlo.map(|max_rollouts| {
+        let config = RolloutConfig { max_rollouts, spawn: settings.spawn, seed, ..RolloutConfig::default() };
+        let values = board.rollout_values(&config);
+        MonteCarloReport {
+            moves: values
+                .moves
+                .iter()
+                .map(|estimate| {
+                    let (low, high) = estimate.interval(config.z);
+                    MonteCarloMove { direction: estimate.direction, rollouts: estimate.rollouts, mean: estimate.mean, low, high }
+                })
+                .collect(),
+            separated: values.separated,
+        }
+    });
+
+    let report = AnalyzeReport {
+        position: board.to_compact(),
+        evaluation,
+        terms: terms
+            .iter()
+            .map(|t| TermReport {
+                name: t.name,
+                value: t.value,
+                weight: t.weight,
+                contribution: t.contribution(),
+            })
+            .collect(),
+        depth: result.depth,
+        best_move: result.best_move,
+        moves,
+        principal_variation: pv,
+        win_tile: args.win_tile,
+        rollouts: args.rollouts,
+        win_probability: probability,
+        difficulty: rate_difficulty_with(&board, &settings.spawn),
+        monte_carlo,
+    };
+    if format == OutputFormat::Json {
+        return print_json(&report);
+    }
+
+    println!("Position: {}", report.position);
+    println!("{}", render::ascii(&board.get_board()));
+    println!("\nEvaluation: {:.1}", report.evaluation);
+    println!("  {:<16} {:>12} {:>8} {:>14}", "term", "value", "weight", "contribution");
+    for term in &report.terms {
+        println!("  {:<16} {:>12.2} {:>8.2} {:>14.1}", term.name, term.value, term.weight, term.contribution);
+    }
+    println!("\nMoves (depth {}):", report.depth);
+    for m in &report.moves {
+        match m.score {
+            Some(score) if Some(m.direction) == report.best_move => println!("  {:<6} {:>12.1}  best", format!("{:?}", m.direction), score),
+            Some(score) => println!("  {:<6} {:>12.1}", format!("{:?}", m.direction), score),
+            None => println!("  {:<6} {:>12}", format!("{:?}", m.direction), "illegal"),
+        }
+    }
+    let line: Vec<String> = report
+        .principal_variation
+        .iter()
+        .map(|step| match step.spawn {
+            Some((row, col)) => format!("{:?} (2 at {},{})", step.direction, row, col),
+            None => format!("{:?}", step.direction),
+        })
+        .collect();
+    println!("\nPrincipal variation: {}", if line.is_empty() { "-".to_string() } else { line.join(", ") });
+    println!("Win probability ({}): {:.0}% over {} rollouts (seed {})",
+             report.win_tile,
+             report.win_probability * 100.0,
+             report.rollouts,
+             seed);
+    println!("Difficulty: {:.2} ({} empty cells, best move leads by {:.0}%, {:.0}% survival over the next moves)",
+             report.difficulty.rating,
+             report.difficulty.empty_cells,
+             report.difficulty.eval_gap * 100.0,
+             report.difficulty.survival * 100.0);
+    if let Some(monte_carlo) = &report.monte_carlo {
+        println!("\nRandom playouts ({}):", if monte_carlo.separated { "best move separated" } else { "not separated" });
+        for m in &monte_carlo.moves {
+            println!("  {:<6} {:>10.0}  [{:.0}, {:.0}] over {} rollouts", format!("{:?}", m.direction), m.mean, m.low, m.high, m.rollouts);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use std::hint::black_box;
+use std::io;
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::Serialize;
+use twenty_forty_eight::{Direction, GameBoard, SearchConfig, SearchProfile, TranspositionState};
+
+use super::{print_json, BenchArgs, OutputFormat};
+
+/// Seed for the benchmark positions; fixed so runs stay comparable.
+const BENCH_SEED: u64 = 0x2048_BE4C;
+
+/// `--output json` document of `bench`.
+#[derive(Serialize)]
+struct BenchReport {
+    moves_per_sec: f64,
+    evals_per_sec: f64,
+    nodes_per_sec: f64,
+    leaf_evals_per_sec: f64,
+    positions: usize,
+    depth: u32,
+    nodes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    profile: Option<SearchProfile>,
+}
+
+/// Mid-game positions reached by a fixed move cycle from seeded openings.
+fn bench_positions(count: usize) -> Vec<GameBoard> {
+    (0..count as u64)
+        .map(|i| {
+            let mut rng = StdRng::seed_from_u64(BENCH_SEED + i);
+            let mut game = GameBoard::new_with_rng(&mut rng);
+            let plies = 40 + (i as usize * 7) % 60;
+            for turn in 0..plies {
+                let moved = (0..4).any(|offset| {
+                    let direction = Direction::all()[(turn + offset) % 4];
+                    game.play_move_with(direction, &mut rng).is_ok()
+                });
+                if !moved {
+                    break;
+                }
+            }
+            game
+        })
+        .collect()
+}
+
+/// Runs `f` over the positions until `budget` has elapsed; returns
+/// operations per second given how many operations one pass performs.
+fn throughput<F: FnMut(&GameBoard)>(positions: &[GameBoard], ops_per_pass: usize, budget: Duration, mut f: F) -> f64 {
+    let start = Instant::now();
+    let mut passes = 0u64;
+    while passes == 0 || start.elapsed() < budget {
+        for board in positions {
+            f(board);
+        }
+        passes += 1;
+    }
+    (passes * ops_per_pass as u64) as f64 / start.elapsed().as_secs_f64()
+}
+
+pub fn run(args: &BenchArgs, format: OutputFormat) -> io::Result<()> {
+    let positions = bench_positions(args.positions);
+    let budget = Duration::from_millis(args.millis);
+
+    let moves_per_sec = throughput(&positions, positions.len() * 4, budget, |board| {
+        for direction in Direction::all() {
+            black_box(board.child(direction));
+        }
+    });
+
+    let evals_per_sec = throughput(&positions, positions.len(), budget, |board| {
+        black_box(board.evaluate_board_optimized());
+    });
+
+    // Fresh table per position so every run searches the same tree.
+    let config = SearchConfig {
+        profile: args.profile,
+        ..SearchConfig::with_depth(args.depth)
+    };
+    let (mut nodes, mut leaves, mut search_time) = (0u64, 0u64, Duration::ZERO);
+    let mut profile = args.profile.then(SearchProfile::default);
+    for board in &positions {
+        let mut tt = TranspositionState::new();
+        let result = board.search_with(&config, &mut tt);
+        nodes += result.nodes;
+        leaves += result.evaluations;
+        search_time += result.elapsed;
+        if let (Some(total), Some(counts)) = (&mut profile, result.profile) {
+            *total += counts;
+        }
+    }
+    let search_secs = search_time.as_secs_f64().max(f64::EPSILON);
+    let report = BenchReport {
+        moves_per_sec,
+        evals_per_sec,
+        nodes_per_sec: nodes as f64 / search_secs,
+        leaf_evals_per_sec: leaves as f64 / search_secs,
+        positions: positions.len(),
+        depth: args.depth,
+        nodes,
+        profile,
+    };
+
+    if format == OutputFormat::Json {
+        return print_json(&report);
+    }
+    println!(
+        "bench: moves/s={:.0} evals/s={:.0} nodes/s={:.0} leaf-evals/s={:.0} (positions={}, depth={}, nodes={})",
+        report.moves_per_sec,
+        report.evals_per_sec,
+        report.nodes_per_sec,
+        report.leaf_evals_per_sec,
+        report.positions,
+        report.depth,
+        report.nodes
+    );
+    if let Some(p) = report.profile {
+        println!(
+            "profile: max={} chance={} leaf={} terminal={} evals={} (cached {}) tt probes={} hits={} stores={} clones={} allocs={}",
+            p.max_nodes,
+            p.chance_nodes,
+            p.leaf_nodes,
+            p.terminal_nodes,
+            p.evaluations,
+            p.eval_cache_hits,
+            p.tt_probes,
+            p.tt_hits,
+            p.tt_stores,
+            p.board_clones,
+            p.allocations
+        );
+        for (depth, ordering) in p.ordering.iter().enumerate().filter(|(_, o)| o.nodes > 0) {
+            println!(
+                "ordering at depth {}: nodes={} first move best {:.1}%, cutoffs={} by first move {:.1}%",
+                depth,
+                ordering.nodes,
+                ordering.first_best_rate() * 100.0,
+                ordering.cutoffs,
+                ordering.first_cutoff_rate() * 100.0
+            );
+        }
+    }
+    Ok(())
+}
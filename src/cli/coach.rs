@@ -0,0 +1,56 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+
+use twenty_forty_eight::game::replay::read_replay;
+use twenty_forty_eight::game::savegame::SaveGame;
+use twenty_forty_eight::{Coach, GameReview, SearchConfig};
+
+use super::progress::Verbosity;
+use super::{print_json, CoachArgs, OutputFormat};
+
+fn invalid(args: &CoachArgs, err: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", args.game.display(), err))
+}
+
+/// Reviews a save file, or a replay when the file is not a save.
+fn review(args: &CoachArgs, coach: &Coach) -> io::Result<GameReview> {
+    if let Ok(save) = SaveGame::from_json(&fs::read_to_string(&args.game)?) {
+        let moves = save.directions().map_err(|err| invalid(args, err))?;
+        return coach.review_history(save.seed, &moves, &save.spawn_rules()).map_err(|err| invalid(args, err));
+    }
+    let (header, steps) = read_replay(BufReader::new(File::open(&args.game)?))?;
+    coach.review_replay(&header, &steps).map_err(|err| invalid(args, err))
+}
+
+pub fn run(args: &CoachArgs, verbosity: Verbosity, format: OutputFormat) -> io::Result<()> {
+    let coach = Coach {
+        search: SearchConfig::with_depth(args.depth),
+        blunder_threshold: args.threshold,
+    };
+    let review = review(args, &coach)?;
+    if format == OutputFormat::Json {
+        return print_json(&review);
+    }
+    for reviewed in &review.moves {
+        if reviewed.blunder || verbosity == Verbosity::Verbose {
+            println!(
+                "move {:>4}: played {:?}, best {:?} (loss {:.1}%){}  {}",
+                reviewed.move_number,
+                reviewed.played,
+                reviewed.best,
+                reviewed.loss * 100.0,
+                if reviewed.blunder { " BLUNDER" } else { "" },
+                reviewed.position
+            );
+        }
+    }
+    println!(
+        "{} moves: accuracy {:.1}%, engine's choice {:.1}%, {} blunders (loss over {:.0}%)",
+        review.moves.len(),
+        review.accuracy * 100.0,
+        review.best_move_rate * 100.0,
+        review.blunders,
+        args.threshold * 100.0
+    );
+    Ok(())
+}
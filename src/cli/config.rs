@@ -0,0 +1,400 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Deserialize;
+use twenty_forty_eight::ai::selfplay::SelfPlayConfig;
+use twenty_forty_eight::{ChanceSampling, OptimizedEvaluationWeights, PowerUps, ScoreModel, SearchConfig, SpawnRules};
+
+use super::keys::{KeyBindings, KeyScheme};
+use super::progress::Verbosity;
+use super::results::ResultsFormat;
+
+/// Entries periodic maintenance keeps the transposition table under.
+pub const DEFAULT_MAX_CACHE_ENTRIES: usize = 1_000_000;
+
+/// Built-in profiles usable as `--config <name>` in place of a path.
+const PRESETS: [(&str, &str); 3] = [
+    ("fast", include_str!("../../presets/fast.toml")),
+    ("strong", include_str!("../../presets/strong.toml")),
+    ("tuning", include_str!("../../presets/tuning.toml")),
+];
+
+/// Solver settings read from a TOML file, e.g.
+///
+/// ```toml
+/// [search]
+/// max_depth = 4
+/// time_limit_ms = 50
+/// fixed_point = true
+/// chance_sampling = { top_k = 6 }
+///
+/// [evaluation]   # multipliers on the built-in weights
+/// corner = 1.5
+///
+/// [cache]
+/// max_entries = 2000000
+///
+/// [spawn]
+/// four_probability = 0.1
+///
+/// [power_ups]    # charges per game; none when omitted
+/// swap = 1
+/// delete = 1
+/// undo = 2
+///
+/// [output]
+/// verbosity = "quiet"
+/// results = "games.csv"
+///
+/// [tui]
+/// keys = ["vim", "arrows"]
+/// heatmap = false
+/// ```
+///
+/// Every section is optional; command-line flags override file values.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SolverConfig {
+    #[serde(default)]
+    pub search: SearchSection,
+    #[serde(default)]
+    pub evaluation: EvaluationSection,
+    #[serde(default)]
+    pub cache: CacheSection,
+    #[serde(default)]
+    pub spawn: SpawnSection,
+    #[serde(default)]
+    pub power_ups: PowerUps,
+    #[serde(default)]
+    pub output: OutputSection,
+    #[serde(default)]
+    pub tui: TuiSection,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SearchSection {
+    /// Fixed depth; adaptive depth when omitted.
+    pub max_depth: Option<u32>,
+    /// Per-move time budget in milliseconds.
+    pub time_limit_ms: Option<u64>,
+    /// Integer fixed-point evaluation, reproducible across platforms.
+    #[serde(default)]
+    pub fixed_point: bool,
+    /// Spawn cells averaged at chance nodes; the strategic top 8 when
+    /// omitted.
+    pub chance_sampling: Option<ChanceSampling>,
+    /// Standard deviations taken off chance-node expectations; 0 (expected
+    /// value) when omitted.
+    #[serde(default)]
+    pub risk_aversion: f32,
+    /// Resign games whose chance of surviving the next three spawns falls
+    /// below this; games are played out when omitted.
+    pub resign_threshold: Option<f32>,
+    /// Remaining depth from which chance nodes are expanded on several
+    /// threads; always serial when omitted.
+    pub parallel_chance_depth: Option<u32>,
+    /// Root moves evaluating this fraction or more below the best are not
+    /// searched; every legal move is when omitted.
+    pub root_prefilter: Option<f32>,
+    /// Model file written by `train`, valuing leaves by expected final
+    /// score; the heuristic evaluation when omitted.
+    pub score_model: Option<PathBuf>,
+    /// The model `score_model` names, loaded with the config.
+    #[serde(skip)]
+    pub model: Option<Arc<ScoreModel>>,
+}
+
+/// Multipliers on the stage-adaptive evaluation weights; omitted terms
+/// keep their built-in weight.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EvaluationSection {
+    pub monotonicity: Option<f32>,
+    pub smoothness: Option<f32>,
+    pub empty: Option<f32>,
+    pub corner: Option<f32>,
+    pub merge: Option<f32>,
+    pub position: Option<f32>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CacheSection {
+    /// Entries the transposition table is kept under; past them, its least
+    /// used entries are evicted.
+    pub max_entries: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpawnSection {
+    /// Chance that a spawned tile is a 4 rather than a 2.
+    pub four_probability: Option<f32>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutputSection {
+    pub verbosity: Option<Verbosity>,
+    /// Default for `--results`.
+    pub results: Option<PathBuf>,
+    /// Default for `--format`.
+    pub format: Option<ResultsFormat>,
+}
+
+/// Everything a game loop needs from the config, after CLI overrides.
+#[derive(Debug, Clone, Default)]
+pub struct GameSettings {
+    pub search: SearchConfig,
+    pub spawn: SpawnRules,
+    pub max_cache_entries: usize,
+    /// Charges each self-play game starts with.
+    pub power_ups: PowerUps,
+}
+
+impl GameSettings {
+    /// Self-play run of these settings; game `i` uses seed `seed + i`.
+    pub fn self_play(&self, seed: u64, threads: usize, max_moves: u32) -> SelfPlayConfig {
+        SelfPlayConfig {
+            search: self.search.clone(),
+            spawn: self.spawn,
+            max_moves,
+            max_cache_entries: self.max_cache_entries,
+            power_ups: self.power_ups,
+            seed,
+            threads: Some(threads),
+        }
+    }
+}
+
+/// Keys of `play` and the dashboard, and which dashboard panels show.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TuiSection {
+    /// Move key schemes; arrows and WASD when omitted.
+    pub keys: Option<Vec<KeyScheme>>,
+    /// AI hint in `play`; `h`, or `?` with vim keys, when omitted.
+    pub hint: Option<char>,
+    /// AI overlay toggle in `play`.
+    pub assist: Option<char>,
+    pub quit: Option<char>,
+    /// Search statistics panel.
+    pub stats: Option<bool>,
+    /// Move heatmap panel.
+    pub heatmap: Option<bool>,
+    /// Predicted board panel.
+    pub preview: Option<bool>,
+    /// Evaluation sparkline panel.
+    pub evaluation: Option<bool>,
+}
+
+/// Dashboard panels shown besides the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Panels {
+    pub stats: bool,
+    pub heatmap: bool,
+    pub preview: bool,
+    pub evaluation: bool,
+}
+
+impl Default for Panels {
+    fn default() -> Self {
+        Self { stats: true, heatmap: true, preview: true, evaluation: true }
+    }
+}
+
+/// Settings of the interactive front ends, resolved from the config.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TuiSettings {
+    pub keys: KeyBindings,
+    pub panels: Panels,
+}
+
+impl SolverConfig {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let mut config = Self::parse(&text, &path.display().to_string())?;
+        if let Some(model) = &config.search.score_model {
+            let file = File::open(model).and_then(|file| ScoreModel::read_from(BufReader::new(file)));
+            let model = file.map_err(|err| io::Error::new(err.kind(), format!("{}: {}", model.display(), err)))?;
+            config.search.model = Some(Arc::new(model));
+        }
+        Ok(config)
+    }
+
+    /// Loads `name` as a built-in preset, or as a file path otherwise.
+    pub fn load_or_preset(name: &Path) -> io::Result<Self> {
+        match PRESETS.iter().find(|(preset, _)| name == Path::new(preset)) {
+            Some((preset, text)) if !name.exists() => Self::parse(text, preset),
+            _ => Self::load(name),
+        }
+    }
+
+    fn parse(text: &str, source: &str) -> io::Result<Self> {
+        let config: Self = toml::from_str(text).map_err(|err| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", source, err))
+        })?;
+        if config.search.risk_aversion < 0.0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: search.risk_aversion must not be negative", source),
+            ));
+        }
+        if config.search.root_prefilter.is_some_and(|margin| margin < 0.0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: search.root_prefilter must not be negative", source),
+            ));
+        }
+        if let Some(key) = config.tui_settings().keys.conflict() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: tui key '{}' is bound to two actions", source, key),
+            ));
+        }
+        if config.tui.keys.as_ref().is_some_and(Vec::is_empty) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: tui.keys needs at least one scheme", source),
+            ));
+        }
+        if let Some(p) = config.spawn.four_probability {
+            if !(0.0..=1.0).contains(&p) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: spawn.four_probability must be between 0 and 1", source),
+                ));
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn search_config(&self) -> SearchConfig {
+        let e = &self.evaluation;
+        let scaled = [e.monotonicity, e.smoothness, e.empty, e.corner, e.merge, e.position]
+            .iter()
+            .any(Option::is_some);
+        SearchConfig {
+            max_depth: self.search.max_depth,
+            time_limit: self.search.time_limit_ms.map(Duration::from_millis),
+            weight_scale: scaled.then(|| OptimizedEvaluationWeights {
+                monotonicity: e.monotonicity.unwrap_or(1.0),
+                smoothness: e.smoothness.unwrap_or(1.0),
+                empty: e.empty.unwrap_or(1.0),
+                corner: e.corner.unwrap_or(1.0),
+                merge: e.merge.unwrap_or(1.0),
+                position: e.position.unwrap_or(1.0),
+            }),
+            fixed_point: self.search.fixed_point,
+            profile: false,
+            trace: false,
+            time_attack: None,
+            chance_sampling: self.search.chance_sampling.unwrap_or_default(),
+            spawn: self.spawn_rules(),
+            risk_aversion: self.search.risk_aversion,
+            resign_threshold: self.search.resign_threshold,
+            parallel_chance_depth: self.search.parallel_chance_depth,
+            root_prefilter: self.search.root_prefilter,
+            score_model: self.search.model.clone(),
+            // Charges belong to a game, see `GameSettings::power_ups`.
+            power_ups: PowerUps::default(),
+        }
+    }
+
+    pub fn spawn_rules(&self) -> SpawnRules {
+        match self.spawn.four_probability {
+            Some(four_probability) => SpawnRules { four_probability, ..SpawnRules::default() },
+            None => SpawnRules::default(),
+        }
+    }
+
+    pub fn tui_settings(&self) -> TuiSettings {
+        let tui = &self.tui;
+        let defaults = KeyBindings::default();
+        let schemes = tui.keys.clone().unwrap_or(defaults.schemes);
+        let vim = schemes.contains(&KeyScheme::Vim);
+        let panels = Panels::default();
+        TuiSettings {
+            keys: KeyBindings {
+                hint: tui.hint.unwrap_or(if vim { '?' } else { defaults.hint }),
+                assist: tui.assist.unwrap_or(defaults.assist),
+                quit: tui.quit.unwrap_or(defaults.quit),
+                schemes,
+            },
+            panels: Panels {
+                stats: tui.stats.unwrap_or(panels.stats),
+                heatmap: tui.heatmap.unwrap_or(panels.heatmap),
+                preview: tui.preview.unwrap_or(panels.preview),
+                evaluation: tui.evaluation.unwrap_or(panels.evaluation),
+            },
+        }
+    }
+
+    pub fn game_settings(&self) -> GameSettings {
+        GameSettings {
+            search: self.search_config(),
+            spawn: self.spawn_rules(),
+            max_cache_entries: self.cache.max_entries.unwrap_or(DEFAULT_MAX_CACHE_ENTRIES),
+            power_ups: self.power_ups,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_parse() {
+        for (name, _) in PRESETS {
+            let config = SolverConfig::load_or_preset(Path::new(name)).unwrap();
+            assert!(config.search.max_depth.is_some() || config.search.time_limit_ms.is_some());
+        }
+    }
+
+    #[test]
+    fn test_sections_map_to_settings() {
+        let config = SolverConfig::parse(
+            "[evaluation]\ncorner = 2.0\n[cache]\nmax_entries = 10\n[spawn]\nfour_probability = 0.25\n",
+            "test",
+        )
+        .unwrap();
+        let settings = config.game_settings();
+        let scale = settings.search.weight_scale.unwrap();
+        assert_eq!(scale.corner, 2.0);
+        assert_eq!(scale.empty, 1.0);
+        assert_eq!(settings.max_cache_entries, 10);
+        assert_eq!(settings.spawn.four_probability, 0.25);
+        assert_eq!(settings.search.spawn, settings.spawn);
+
+        assert!(SolverConfig::parse("[spawn]\nfour_probability = 2.0\n", "test").is_err());
+        assert!(SolverConfig::parse("[search]\ndepth = 3\n", "test").is_err());
+
+        let config = SolverConfig::parse("[search]\nchance_sampling = { random_k = { k = 4, seed = 9 } }\n", "test").unwrap();
+        assert_eq!(config.search_config().chance_sampling, ChanceSampling::RandomK { k: 4, seed: 9 });
+        let config = SolverConfig::parse("[search]\nchance_sampling = \"all\"\n", "test").unwrap();
+        assert_eq!(config.search_config().chance_sampling, ChanceSampling::All);
+        let config = SolverConfig::parse("[search]\nchance_sampling = \"strategic\"\n", "test").unwrap();
+        assert_eq!(config.search_config().chance_sampling, ChanceSampling::default());
+        assert!(SolverConfig::parse("[search]\nrisk_aversion = -1.0\n", "test").is_err());
+    }
+
+    #[test]
+    fn test_tui_section_sets_keys_and_panels() {
+        assert_eq!(SolverConfig::default().tui_settings(), TuiSettings::default());
+
+        let config = SolverConfig::parse("[tui]\nkeys = [\"vim\", \"arrows\"]\nheatmap = false\n", "test").unwrap();
+        let tui = config.tui_settings();
+        assert_eq!(tui.keys.schemes, [KeyScheme::Vim, KeyScheme::Arrows]);
+        assert_eq!(tui.keys.hint, '?');
+        assert_eq!(tui.panels, Panels { heatmap: false, ..Panels::default() });
+
+        assert!(SolverConfig::parse("[tui]\nkeys = [\"vim\"]\nhint = \"h\"\n", "test").is_err());
+        assert!(SolverConfig::parse("[tui]\nkeys = []\n", "test").is_err());
+        assert!(SolverConfig::parse("[tui]\nkeys = [\"emacs\"]\n", "test").is_err());
+    }
+}
@@ -0,0 +1,47 @@
+use std::fs::File;
+use std::io::{self, BufWriter};
+
+use serde::Serialize;
+use twenty_forty_eight::ai::dataset::{self, DatasetConfig, DatasetWriter};
+use twenty_forty_eight::SearchConfig;
+
+use super::progress::{games_bar, Verbosity};
+use super::{print_json, DatasetArgs, OutputFormat};
+
+/// `--output json` document of `dataset`.
+#[derive(Serialize)]
+struct DatasetReport {
+    seed: u64,
+    games: u64,
+    records: usize,
+}
+
+pub fn run(args: &DatasetArgs, verbosity: Verbosity, format: OutputFormat) -> io::Result<()> {
+    let config = DatasetConfig {
+        search: SearchConfig::with_depth(args.depth),
+        seed: args.seed.unwrap_or_else(rand::random),
+        max_moves: args.max_moves,
+        temperature: args.temperature,
+        augment: args.augment,
+    };
+    let mut writer = DatasetWriter::new(BufWriter::new(File::create(&args.out)?))?;
+    let bar = games_bar(args.games as usize, verbosity);
+    let mut records = 0;
+    for game in dataset::play_games(&config, args.games) {
+        for record in &game {
+            writer.write(record)?;
+        }
+        records += game.len();
+        bar.set_message(format!("{} records", records));
+        bar.inc(1);
+    }
+    bar.finish_and_clear();
+    io::Write::flush(&mut writer.into_inner())?;
+
+    let report = DatasetReport { seed: config.seed, games: args.games, records };
+    if format == OutputFormat::Json {
+        return print_json(&report);
+    }
+    println!("Wrote {} records from {} games (seed {}) to {}", report.records, report.games, report.seed, args.out.display());
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use std::io;
+
+use twenty_forty_eight::{PvStep, SearchConfig};
+
+use super::analyze::load_legal_position;
+use super::config::SolverConfig;
+use super::{print_json, DiffArgs, OutputFormat};
+
+fn search_config(path: &std::path::Path) -> io::Result<SearchConfig> {
+    Ok(SolverConfig::load_or_preset(path)?.game_settings().search)
+}
+
+fn line(pv: &[PvStep]) -> String {
+    let moves: Vec<String> = pv.iter().map(|step| format!("{:?}", step.direction)).collect();
+    if moves.is_empty() { "-".to_string() } else { moves.join(", ") }
+}
+
+pub fn run(args: &DiffArgs, format: OutputFormat) -> io::Result<()> {
+    let board = load_legal_position(&args.position, args.allow_illegal)?;
+    let diff = board.compare_configs(&search_config(&args.config_a)?, &search_config(&args.config_b)?);
+    if format == OutputFormat::Json {
+        return print_json(&diff);
+    }
+
+    println!("{:<8} {:>12} {:>12} {:>12}", "move", "A", "B", "B - A");
+    let cell = |score: Option<f32>| score.map_or_else(|| "-".to_string(), |score| format!("{:.1}", score));
+    for m in &diff.moves {
+        let best = match (Some(m.direction) == diff.best_a, Some(m.direction) == diff.best_b) {
+            (true, true) => "  best",
+            (true, false) => "  best for A",
+            (false, true) => "  best for B",
+            (false, false) => "",
+        };
+        println!("{:<8} {:>12} {:>12} {:>12}{}", format!("{:?}", m.direction), cell(m.a), cell(m.b), cell(m.delta()), best);
+    }
+    println!("\nA: depth {}, {} nodes, line {}", diff.depth_a, diff.nodes_a, line(&diff.pv_a));
+    println!("B: depth {}, {} nodes, line {}", diff.depth_b, diff.nodes_b, line(&diff.pv_b));
+    match diff.first_divergence {
+        Some(index) => {
+            let (a, b) = (diff.pv_a[index], diff.pv_b[index]);
+            println!(
+                "Lines part at move {}: A plays {:?} ({:.1}), B plays {:?} ({:.1})",
+                index + 1,
+                a.direction,
+                a.score,
+                b.direction,
+                b.score
+            );
+        }
+        None => println!("Lines agree"),
+    }
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter};
+use std::path::Path;
+
+use serde::Serialize;
+use twenty_forty_eight::export::{render_svg, select_frames, write_gif};
+use twenty_forty_eight::game::replay::{read_replay, replay_positions};
+
+use super::{print_json, ExportArgs, OutputFormat};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum ImageFormat {
+    Svg,
+    Gif,
+}
+
+impl ImageFormat {
+    fn from_path(path: &Path) -> io::Result<Self> {
+        match path.extension().and_then(|ext| ext.to_str()).map(str::to_ascii_lowercase).as_deref() {
+            Some("svg") => Ok(ImageFormat::Svg),
+            Some("gif") => Ok(ImageFormat::Gif),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}: expected a .svg or .gif file name", path.display()),
+            )),
+        }
+    }
+}
+
+/// `--output json` document of `export`.
+#[derive(Serialize)]
+struct ExportReport<'a> {
+    image: &'a Path,
+    format: ImageFormat,
+    moves: usize,
+    frames: usize,
+}
+
+pub fn run(args: &ExportArgs, format: OutputFormat) -> io::Result<()> {
+    let image_format = ImageFormat::from_path(&args.image)?;
+    let (header, steps) = read_replay(BufReader::new(File::open(&args.replay)?))?;
+    let positions = replay_positions(&header, &steps)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", args.replay.display(), err)))?;
+    let frames = select_frames(&positions, args.every);
+    match image_format {
+        ImageFormat::Svg => fs::write(&args.image, render_svg(&frames))?,
+        ImageFormat::Gif => {
+            let delay_cs = (args.frame_ms / 10).clamp(1, u16::MAX as u32) as u16;
+            write_gif(BufWriter::new(File::create(&args.image)?), &frames, delay_cs)?;
+        }
+    }
+
+    let report = ExportReport {
+        image: &args.image,
+        format: image_format,
+        moves: steps.len(),
+        frames: frames.len(),
+    };
+    if format == OutputFormat::Json {
+        return print_json(&report);
+    }
+    println!("Wrote {} boards of a {}-move game to {}", report.frames, report.moves, args.image.display());
+    Ok(())
+}
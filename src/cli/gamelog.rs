@@ -0,0 +1,72 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use serde::Serialize;
+use twenty_forty_eight::{Direction, GameBoard, GameObserver, MoveEvent, SpawnedTile};
+
+/// One line of a `--log` file: a played move with what the search knew
+/// and what followed.
+#[derive(Serialize)]
+pub struct MoveLogEntry {
+    pub seed: u64,
+    /// 1 for the first move of the game.
+    pub move_number: u32,
+    pub direction: Direction,
+    pub spawn: Option<SpawnedTile>,
+    /// Hash of the board after the spawn.
+    pub hash: u64,
+    /// Running score after the move.
+    pub score: u32,
+    pub depth: u32,
+    pub nodes: u64,
+    pub time_ms: f64,
+}
+
+/// Appends a [`MoveLogEntry`] line to a file for every move it observes,
+/// flushing each so the log is complete up to the last move even if the
+/// run is killed.
+pub struct MoveLog {
+    out: BufWriter<File>,
+    seed: u64,
+    /// The first write that failed; later moves are not logged.
+    error: Option<io::Error>,
+}
+
+impl MoveLog {
+    pub fn append(path: &Path, seed: u64) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { out: BufWriter::new(file), seed, error: None })
+    }
+
+    pub fn write(&mut self, entry: &MoveLogEntry) -> io::Result<()> {
+        serde_json::to_writer(&mut self.out, entry)?;
+        writeln!(self.out)?;
+        self.out.flush()
+    }
+
+    /// Reports a write that failed during the game.
+    pub fn finish(self) -> io::Result<()> {
+        self.error.map_or(Ok(()), Err)
+    }
+}
+
+impl GameObserver for MoveLog {
+    fn on_move(&mut self, board: &GameBoard, event: &MoveEvent) {
+        if self.error.is_some() {
+            return;
+        }
+        let entry = MoveLogEntry {
+            seed: self.seed,
+            move_number: event.move_number,
+            direction: event.direction,
+            spawn: event.result.spawned,
+            hash: board.board_hash(),
+            score: event.score,
+            depth: event.search.map_or(0, |search| search.depth),
+            nodes: event.search.map_or(0, |search| search.nodes),
+            time_ms: event.search.map_or(0.0, |search| search.elapsed.as_secs_f64() * 1000.0),
+        };
+        self.error = self.write(&entry).err();
+    }
+}
@@ -0,0 +1,34 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+
+use serde::Serialize;
+use twenty_forty_eight::game::import::parse_game_log;
+use twenty_forty_eight::game::replay::ReplayWriter;
+
+use super::{print_json, ImportArgs, OutputFormat};
+
+/// `--output json` document of `import`.
+#[derive(Serialize)]
+struct ImportReport {
+    moves: usize,
+    spawns: usize,
+}
+
+pub fn run(args: &ImportArgs, format: OutputFormat) -> io::Result<()> {
+    let (header, steps) = parse_game_log(&fs::read_to_string(&args.log)?)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", args.log.display(), err)))?;
+    let mut writer = ReplayWriter::new(BufWriter::new(File::create(&args.replay)?), &header)?;
+    for step in &steps {
+        writer.record(step)?;
+    }
+
+    let report = ImportReport {
+        moves: steps.len(),
+        spawns: steps.iter().filter(|step| step.spawn.is_some()).count(),
+    };
+    if format == OutputFormat::Json {
+        return print_json(&report);
+    }
+    println!("Imported {} moves to {}", report.moves, args.replay.display());
+    Ok(())
+}
@@ -0,0 +1,144 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+use serde::Deserialize;
+use twenty_forty_eight::Direction;
+
+/// A set of keys for the four moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyScheme {
+    /// The arrow keys.
+    Arrows,
+    /// W A S D.
+    Wasd,
+    /// h j k l, as in vi.
+    Vim,
+}
+
+impl KeyScheme {
+    fn direction(self, code: KeyCode) -> Option<Direction> {
+        let letter = |c: char| match self {
+            KeyScheme::Arrows => None,
+            KeyScheme::Wasd => match c {
+                'w' => Some(Direction::Up),
+                'a' => Some(Direction::Left),
+                's' => Some(Direction::Down),
+                'd' => Some(Direction::Right),
+                _ => None,
+            },
+            KeyScheme::Vim => match c {
+                'k' => Some(Direction::Up),
+                'h' => Some(Direction::Left),
+                'j' => Some(Direction::Down),
+                'l' => Some(Direction::Right),
+                _ => None,
+            },
+        };
+        match code {
+            KeyCode::Up if self == KeyScheme::Arrows => Some(Direction::Up),
+            KeyCode::Down if self == KeyScheme::Arrows => Some(Direction::Down),
+            KeyCode::Left if self == KeyScheme::Arrows => Some(Direction::Left),
+            KeyCode::Right if self == KeyScheme::Arrows => Some(Direction::Right),
+            KeyCode::Char(c) => letter(c.to_ascii_lowercase()),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            KeyScheme::Arrows => "arrows",
+            KeyScheme::Wasd => "WASD",
+            KeyScheme::Vim => "hjkl",
+        }
+    }
+}
+
+/// What a key press asks the interactive front ends to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAction {
+    Move(Direction),
+    Hint,
+    ToggleAssist,
+    Quit,
+}
+
+/// Keys of `play` and the dashboard. Letters match in either case; Esc
+/// and Ctrl-C always quit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyBindings {
+    pub schemes: Vec<KeyScheme>,
+    pub hint: char,
+    pub assist: char,
+    pub quit: char,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            schemes: vec![KeyScheme::Arrows, KeyScheme::Wasd],
+            hint: 'h',
+            assist: 'o',
+            quit: 'q',
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn action(&self, code: KeyCode, modifiers: KeyModifiers) -> Option<KeyAction> {
+        if code == KeyCode::Esc || (code == KeyCode::Char('c') && modifiers.contains(KeyModifiers::CONTROL)) {
+            return Some(KeyAction::Quit);
+        }
+        if let Some(direction) = self.schemes.iter().find_map(|scheme| scheme.direction(code)) {
+            return Some(KeyAction::Move(direction));
+        }
+        let KeyCode::Char(c) = code else { return None };
+        [(self.hint, KeyAction::Hint), (self.assist, KeyAction::ToggleAssist), (self.quit, KeyAction::Quit)]
+            .into_iter()
+            .find(|&(key, _)| key.eq_ignore_ascii_case(&c))
+            .map(|(_, action)| action)
+    }
+
+    /// A key bound to two actions, if any.
+    pub fn conflict(&self) -> Option<char> {
+        let commands = [self.hint, self.assist, self.quit];
+        commands.iter().enumerate().find_map(|(i, &key)| {
+            let moves = self.schemes.iter().any(|scheme| scheme.direction(KeyCode::Char(key)).is_some());
+            (moves || commands[..i].iter().any(|other| other.eq_ignore_ascii_case(&key))).then_some(key)
+        })
+    }
+
+    /// One-line key help, e.g. `arrows/WASD move  h hint  o AI overlay  q quit`.
+    pub fn help(&self, assist: bool) -> String {
+        let moves: Vec<&str> = self.schemes.iter().map(|scheme| scheme.label()).collect();
+        let mut help = format!("{} move  {} hint", moves.join("/"), self.hint);
+        if assist {
+            help += &format!("  {} AI overlay", self.assist);
+        }
+        help + &format!("  {} quit", self.quit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schemes_map_keys_to_moves_and_commands() {
+        let vim = KeyBindings { schemes: vec![KeyScheme::Vim], hint: '?', ..KeyBindings::default() };
+        let none = KeyModifiers::NONE;
+        assert_eq!(vim.action(KeyCode::Char('h'), none), Some(KeyAction::Move(Direction::Left)));
+        assert_eq!(vim.action(KeyCode::Char('K'), none), Some(KeyAction::Move(Direction::Up)));
+        assert_eq!(vim.action(KeyCode::Up, none), None);
+        assert_eq!(vim.action(KeyCode::Char('?'), none), Some(KeyAction::Hint));
+        assert_eq!(vim.action(KeyCode::Char('c'), KeyModifiers::CONTROL), Some(KeyAction::Quit));
+        assert_eq!(vim.conflict(), None);
+        assert_eq!(vim.help(true), "hjkl move  ? hint  o AI overlay  q quit");
+
+        let default = KeyBindings::default();
+        assert_eq!(default.action(KeyCode::Left, none), Some(KeyAction::Move(Direction::Left)));
+        assert_eq!(default.action(KeyCode::Char('S'), none), Some(KeyAction::Move(Direction::Down)));
+        assert_eq!(default.action(KeyCode::Char('h'), none), Some(KeyAction::Hint));
+        assert_eq!(default.conflict(), None);
+        assert_eq!(KeyBindings { schemes: vec![KeyScheme::Vim], ..KeyBindings::default() }.conflict(), Some('h'));
+        assert_eq!(KeyBindings { quit: 'O', ..KeyBindings::default() }.conflict(), Some('O'));
+    }
+}
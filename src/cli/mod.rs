@@ -0,0 +1,480 @@
+use std::io;
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use twenty_forty_eight::MergeBreakdown;
+
+use config::OutputSection;
+use results::ResultsFormat;
+
+pub mod analyze;
+pub mod bench;
+pub mod coach;
+pub mod config;
+pub mod dataset;
+pub mod diff;
+#[cfg(feature = "export")]
+pub mod export;
+pub mod gamelog;
+pub mod import;
+pub mod keys;
+pub mod play;
+pub mod progress;
+pub mod puzzle;
+pub mod results;
+pub mod savegame;
+#[cfg(feature = "web")]
+pub mod serve;
+pub mod simulate;
+pub mod solve;
+pub mod stats;
+pub mod tournament;
+pub mod train;
+pub mod trace;
+pub mod tui;
+
+#[derive(Parser)]
+#[command(name = "twenty-forty-eight", version, about = "AI solver and terminal player for 2048")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Solver settings: a TOML file or a preset (fast, strong, tuning)
+    #[arg(long, global = true, value_name = "PATH|PRESET")]
+    pub config: Option<PathBuf>,
+    /// Only print the final result
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+    /// Print more detail (boards, cache maintenance, per-game results)
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+    /// How to print the final result
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    /// The final result as a single JSON document on stdout; nothing else
+    /// is printed there
+    Json,
+}
+
+/// How a command that ran to completion ended; decides the exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Success,
+    /// `--target` was given and not reached.
+    TargetMissed,
+}
+
+/// Exit code for runtime errors (unreadable files, bad config, ...); clap
+/// reports invalid arguments with 2.
+pub const EXIT_ERROR: i32 = 1;
+/// Exit code when the run finished but missed its `--target` tile.
+pub const EXIT_TARGET_MISSED: i32 = 3;
+
+impl Outcome {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Outcome::Success => 0,
+            Outcome::TargetMissed => EXIT_TARGET_MISSED,
+        }
+    }
+
+    /// Checks `--target` against the lowest max tile of the run.
+    pub fn from_target(target: Option<u32>, max_tile: u32) -> Self {
+        match target {
+            Some(tile) if max_tile < tile => Outcome::TargetMissed,
+            _ => Outcome::Success,
+        }
+    }
+}
+
+/// End-of-game line shared by the interactive commands (`play`, `--tui`).
+#[derive(Debug, Serialize)]
+pub struct FinalScore {
+    pub seed: u64,
+    pub score: u32,
+    pub max_tile: u32,
+    pub moves: u32,
+    pub merges: MergeBreakdown,
+}
+
+impl FinalScore {
+    pub fn print(&self, format: OutputFormat) -> io::Result<()> {
+        match format {
+            OutputFormat::Text => {
+                println!("Seed: {}, Score: {}, Max tile: {}, Moves: {}", self.seed, self.score, self.max_tile, self.moves);
+                if self.merges.total() > 0 {
+                    println!("Merges by tile: {}", self.merges);
+                }
+                Ok(())
+            }
+            OutputFormat::Json => print_json(self),
+        }
+    }
+}
+
+/// Prints `report` as one line of JSON on stdout.
+pub fn print_json<T: Serialize>(report: &T) -> io::Result<()> {
+    println!("{}", serde_json::to_string(report)?);
+    Ok(())
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Let the AI play a full game (default)
+    Solve(SolveArgs),
+    /// Play interactively with arrow keys or WASD (h = AI hint, q = quit)
+    Play(PlayArgs),
+    /// Find-the-best-move puzzles from self-play, checked against a deep
+    /// search; streaks of right answers bring harder puzzles
+    Puzzle(PuzzleArgs),
+    /// Run many AI games in parallel and print aggregate statistics
+    Simulate(SimulateArgs),
+    /// Measure engine throughput on a fixed set of seeded positions
+    Bench(BenchArgs),
+    /// Compare two solver configurations on identical spawn seeds
+    Tournament(TournamentArgs),
+    /// Explain the AI's view of a position: evaluation terms, move scores,
+    /// principal variation and win probability
+    Analyze(AnalyzeArgs),
+    /// Search one position with two configurations and show where their
+    /// move scores and principal variations differ
+    Diff(DiffArgs),
+    /// Review a finished game (replay or save file): the engine's best
+    /// move and the evaluation lost at every move, with blunders flagged
+    Coach(CoachArgs),
+    /// Convert a game from another 2048 client (board snapshots, or a move
+    /// list with spawns) into a replay file
+    Import(ImportArgs),
+    /// Write self-play training examples for machine learning to a binary
+    /// dataset file
+    Dataset(DatasetArgs),
+    /// Fit an expected-final-score model to a dataset file, for use as
+    /// the search's leaf value (`score_model` in a config's [search])
+    Train(TrainArgs),
+    /// Record every table probe and store, cutoff, leaf evaluation and
+    /// deadline hit of one search to a trace file, or replay a trace and
+    /// report the first step that differs
+    Trace(TraceArgs),
+    /// Render a recorded game (see `solve --record`) as an SVG board
+    /// sequence or an animated GIF
+    #[cfg(feature = "export")]
+    Export(ExportArgs),
+    /// Serve games over a JSON HTTP API for a browser or remote client
+    #[cfg(feature = "web")]
+    Serve(ServeArgs),
+}
+
+impl Default for Command {
+    fn default() -> Self {
+        Command::Solve(SolveArgs::default())
+    }
+}
+
+#[derive(Args, Default)]
+pub struct SolveArgs {
+    /// Show a full-screen dashboard instead of printing progress
+    #[arg(long, conflicts_with_all = ["resume", "save_on_exit", "record", "log", "spawn_audit"])]
+    pub tui: bool,
+    /// Exit with status 3 unless the game reaches this tile
+    #[arg(long, value_name = "TILE")]
+    pub target: Option<u32>,
+    /// Spawn seed; the same seed replays the same game (random and printed when omitted)
+    #[arg(long, conflicts_with = "resume")]
+    pub seed: Option<u64>,
+    /// Write every move, spawn and board hash to this replay file
+    #[arg(long, value_name = "FILE", conflicts_with = "resume")]
+    pub record: Option<PathBuf>,
+    /// Append one JSON line per move (move, spawn, board hash, score, search depth, nodes and time) to this file
+    #[arg(long, value_name = "FILE")]
+    pub log: Option<PathBuf>,
+    /// Write every spawn of the game, with the RNG draw it came from, to this JSON file on exit
+    #[arg(long, value_name = "FILE")]
+    pub spawn_audit: Option<PathBuf>,
+    /// Time attack: play for the most points within this many moves
+    #[arg(long, value_name = "MOVES", conflicts_with_all = ["tui", "time_budget"])]
+    pub move_budget: Option<u32>,
+    /// Time attack: play for the most points within this many seconds
+    #[arg(long, value_name = "SECS", conflicts_with = "tui")]
+    pub time_budget: Option<f64>,
+    #[command(flatten)]
+    pub output: ResultsArgs,
+    #[command(flatten)]
+    pub save: SaveArgs,
+}
+
+#[derive(Args, Default)]
+pub struct PlayArgs {
+    /// Spawn seed; the same seed gives the same spawns for the same moves
+    #[arg(long, conflicts_with = "resume")]
+    pub seed: Option<u64>,
+    #[command(flatten)]
+    pub save: SaveArgs,
+}
+
+#[derive(Args)]
+pub struct PuzzleArgs {
+    /// Seed of the self-play games puzzles are drawn from (random by default)
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Depth of the search that verifies puzzles and judges answers
+    #[arg(long, default_value_t = 5)]
+    pub depth: u32,
+    /// Stop after this many answered puzzles (default: until quit)
+    #[arg(long)]
+    pub rounds: Option<u32>,
+}
+
+/// Saving a game in progress and picking it up again later.
+#[derive(Args, Default)]
+pub struct SaveArgs {
+    /// Save the game to this file when the command exits (including Ctrl-C)
+    #[arg(long, value_name = "FILE")]
+    pub save_on_exit: Option<PathBuf>,
+    /// Continue a game previously written with --save-on-exit
+    #[arg(long, value_name = "FILE")]
+    pub resume: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct SimulateArgs {
+    /// Number of games to play
+    #[arg(long, default_value_t = 100)]
+    pub games: usize,
+    /// Worker threads (defaults to the number of CPUs)
+    #[arg(long)]
+    pub threads: Option<usize>,
+    /// Base spawn seed; game i uses seed + i (random when omitted)
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Fixed search depth instead of the adaptive depth
+    #[arg(long)]
+    pub depth: Option<u32>,
+    /// Stop a game after this many moves
+    #[arg(long, default_value_t = 5000)]
+    pub max_moves: u32,
+    /// Exit with status 3 unless every game reaches this tile
+    #[arg(long, value_name = "TILE")]
+    pub target: Option<u32>,
+    /// Append this run's summary to a JSON Lines file and print totals over
+    /// every run recorded in it
+    #[arg(long, value_name = "PATH")]
+    pub stats_file: Option<PathBuf>,
+    /// Adapt the evaluation weights between games, resuming from and saving
+    /// to this JSON file. Games are then played one at a time
+    #[arg(long, value_name = "PATH", conflicts_with = "threads")]
+    pub adapt: Option<PathBuf>,
+    #[command(flatten)]
+    pub output: ResultsArgs,
+}
+
+#[derive(Args)]
+pub struct BenchArgs {
+    /// Search depth used for the nodes/sec measurement
+    #[arg(long, default_value_t = 6)]
+    pub depth: u32,
+    /// Number of seeded benchmark positions
+    #[arg(long, default_value_t = 16)]
+    pub positions: usize,
+    /// Time budget in milliseconds for each micro-benchmark
+    #[arg(long, default_value_t = 1000)]
+    pub millis: u64,
+    /// Also report search counters: nodes by type, evaluations, table
+    /// probes and stores, board clones and allocations
+    #[arg(long)]
+    pub profile: bool,
+}
+
+#[derive(Args)]
+pub struct TournamentArgs {
+    /// TOML configuration or preset for player A
+    #[arg(long, value_name = "PATH|PRESET")]
+    pub config_a: PathBuf,
+    /// TOML configuration or preset for player B
+    #[arg(long, value_name = "PATH|PRESET")]
+    pub config_b: PathBuf,
+    /// Games per configuration (each seed is played by both)
+    #[arg(long, default_value_t = 100)]
+    pub games: usize,
+    /// Worker threads (defaults to the number of CPUs)
+    #[arg(long)]
+    pub threads: Option<usize>,
+    /// Base spawn seed (random when omitted)
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Stop a game after this many moves
+    #[arg(long, default_value_t = 5000)]
+    pub max_moves: u32,
+}
+
+#[derive(Args)]
+pub struct AnalyzeArgs {
+    /// Position in compact notation ("2,4,8,16/0,0,2,4/...", rows top to
+    /// bottom, 0 = empty) or a file containing one
+    pub position: String,
+    /// Search depth (defaults to the config's, else adaptive)
+    #[arg(long)]
+    pub depth: Option<u32>,
+    /// Tile that counts as a win for the probability estimate
+    #[arg(long, default_value_t = 2048)]
+    pub win_tile: u32,
+    /// Seeded self-play games used to estimate the win probability
+    #[arg(long, default_value_t = 50)]
+    pub rollouts: usize,
+    /// Base seed for the rollouts (random when omitted)
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Also value each move by random playouts, up to this many per move,
+    /// stopping once the best move is separated at 95% confidence
+    #[arg(long, value_name = "MAX")]
+    pub monte_carlo: Option<u32>,
+    /// Analyze the position even if no legal game can reach it
+    #[arg(long)]
+    pub allow_illegal: bool,
+}
+
+#[derive(Args)]
+pub struct DiffArgs {
+    /// Position in compact notation or a file containing one
+    pub position: String,
+    /// TOML configuration or preset of the first search
+    #[arg(long, value_name = "PATH|PRESET")]
+    pub config_a: PathBuf,
+    /// TOML configuration or preset of the second search
+    #[arg(long, value_name = "PATH|PRESET")]
+    pub config_b: PathBuf,
+    /// Compare on the position even if no legal game can reach it
+    #[arg(long)]
+    pub allow_illegal: bool,
+}
+
+#[derive(Args)]
+pub struct CoachArgs {
+    /// Replay written by `solve --record`, or a save file from `--save-on-exit`
+    pub game: PathBuf,
+    /// Search depth of the review
+    #[arg(long, default_value_t = 3)]
+    pub depth: u32,
+    /// Evaluation loss, as a fraction of the best move's, that counts as a blunder
+    #[arg(long, default_value_t = 0.1)]
+    pub threshold: f32,
+}
+
+#[derive(Args)]
+pub struct ImportArgs {
+    /// Game log: the start position, then one position or one move (`L`,
+    /// or `L 3,0=2` with the spawn) per line; format in `game::import`
+    pub log: PathBuf,
+    /// Replay file to write
+    pub replay: PathBuf,
+}
+
+#[derive(Args)]
+pub struct TraceArgs {
+    /// Trace file to write, or to read with --replay
+    pub file: PathBuf,
+    /// Position to search, in compact notation or a file containing one
+    #[arg(long, required_unless_present = "replay")]
+    pub position: Option<String>,
+    /// Search the traced position again with the same --config and report
+    /// the first event that differs from the recording
+    #[arg(long, conflicts_with = "position")]
+    pub replay: bool,
+}
+
+#[derive(Args)]
+pub struct DatasetArgs {
+    /// Dataset file to write (format documented in `ai::dataset`)
+    pub out: PathBuf,
+    /// Number of games to play
+    #[arg(long, default_value_t = 10)]
+    pub games: u64,
+    /// Base spawn seed; game i uses seed + i (random when omitted)
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Search depth for every move
+    #[arg(long, default_value_t = 3)]
+    pub depth: u32,
+    /// Policy temperature; above 0 the played move is sampled from the policy
+    #[arg(long, default_value_t = 0.0)]
+    pub temperature: f32,
+    /// Also write the seven rotations and reflections of every position
+    #[arg(long)]
+    pub augment: bool,
+    /// Stop a game after this many moves
+    #[arg(long, default_value_t = 5000)]
+    pub max_moves: u32,
+}
+
+#[derive(Args)]
+pub struct TrainArgs {
+    /// Dataset file written by `dataset`
+    pub dataset: PathBuf,
+    /// Model file to write (format documented in `ai::score_model`)
+    pub model: PathBuf,
+    /// Passes over the dataset
+    #[arg(long, default_value_t = 5)]
+    pub epochs: u32,
+    /// Step size of the regression
+    #[arg(long, default_value_t = 0.01)]
+    pub learning_rate: f32,
+    /// Continue training this model instead of starting from scratch
+    #[arg(long, value_name = "MODEL")]
+    pub resume: Option<PathBuf>,
+}
+
+#[cfg(feature = "export")]
+#[derive(Args)]
+pub struct ExportArgs {
+    /// Replay file written by `solve --record`
+    pub replay: PathBuf,
+    /// Image to write; a .gif extension selects an animated GIF, .svg a
+    /// board sequence
+    pub image: PathBuf,
+    /// Draw the board every this many moves (the final board is always drawn)
+    #[arg(long, default_value_t = 100)]
+    pub every: usize,
+    /// How long each GIF frame is shown, in milliseconds
+    #[arg(long, default_value_t = 500)]
+    pub frame_ms: u32,
+}
+
+#[cfg(feature = "web")]
+#[derive(Args)]
+pub struct ServeArgs {
+    /// Port to listen on
+    #[arg(long, default_value_t = 8080)]
+    pub port: u16,
+    /// Address to listen on; 0.0.0.0 accepts clients from other machines
+    #[arg(long, default_value = "127.0.0.1")]
+    pub host: String,
+    /// Let pages from this origin call the API (e.g. http://localhost:3000,
+    /// or * for any page); by default only same-origin pages can
+    #[arg(long)]
+    pub allow_origin: Option<String>,
+}
+
+/// Where to write one record per finished game.
+#[derive(Args, Default)]
+pub struct ResultsArgs {
+    /// Write per-game results to this file
+    #[arg(long, value_name = "PATH")]
+    pub results: Option<PathBuf>,
+    /// Results format (inferred from a .csv extension, JSON Lines otherwise)
+    #[arg(long, value_enum)]
+    pub format: Option<ResultsFormat>,
+}
+
+impl ResultsArgs {
+    /// These flags, falling back to the config file's `[output]` section.
+    pub fn or_config(&self, output: &OutputSection) -> ResultsArgs {
+        ResultsArgs {
+            results: self.results.clone().or_else(|| output.results.clone()),
+            format: self.format.or(output.format),
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use std::io::{self, Write};
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyEvent, KeyEventKind};
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use twenty_forty_eight::utils::render::{tile_label, tile_rgb};
+use twenty_forty_eight::{Direction, GameBoard, GameSession};
+
+use super::config::{GameSettings, TuiSettings};
+use super::keys::{KeyAction, KeyBindings};
+use super::savegame;
+use super::{FinalScore, OutputFormat, PlayArgs};
+
+/// Restores the terminal even if the game loop returns early or panics.
+struct RawModeGuard;
+
+impl RawModeGuard {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, Hide)?;
+        Ok(RawModeGuard)
+    }
+}
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// How a played move compares with the AI's ranking of the position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveRating {
+    Best,
+    Ok,
+    Blunder,
+}
+
+/// Share of the best move's score a move may lose and still count as ok.
+const OK_SCORE_LOSS: f32 = 0.1;
+
+/// Rates `played` against root move scores sorted best first.
+pub fn rate_move(move_scores: &[(Direction, f32)], played: Direction) -> MoveRating {
+    let Some(&(best, best_score)) = move_scores.first() else {
+        return MoveRating::Best;
+    };
+    if played == best {
+        return MoveRating::Best;
+    }
+    match move_scores.iter().find(|&&(direction, _)| direction == played) {
+        Some(&(_, score)) if best_score - score <= best_score.abs() * OK_SCORE_LOSS => MoveRating::Ok,
+        _ => MoveRating::Blunder,
+    }
+}
+
+fn read_action(keys: &KeyBindings) -> io::Result<KeyAction> {
+    loop {
+        if let Event::Key(KeyEvent { code, modifiers, kind, .. }) = event::read()? {
+            if kind != KeyEventKind::Press {
+                continue;
+            }
+            if let Some(action) = keys.action(code, modifiers) {
+                return Ok(action);
+            }
+        }
+    }
+}
+
+pub fn tile_colors(value: u32) -> (Color, Color) {
+    let ([br, bg, bb], [fr, fg, fb]) = tile_rgb(value);
+    (Color::Rgb { r: fr, g: fg, b: fb }, Color::Rgb { r: br, g: bg, b: bb })
+}
+
+fn draw(out: &mut impl Write, game: &GameBoard, score: u32, status: &str, suggestions: Option<&[(Direction, f32)]>, keys: &KeyBindings) -> io::Result<()> {
+    queue!(out, MoveTo(0, 0), Clear(ClearType::All))?;
+    queue!(out, Print(format!("2048  |  Score: {}  |  Moves: {}\r\n\r\n", score, game.get_move_count())))?;
+    for row in game.get_board() {
+        for value in row {
+            let (foreground, background) = tile_colors(value);
+            queue!(
+                out,
+                SetForegroundColor(foreground),
+                SetBackgroundColor(background),
+                Print(format!("{:^7}", tile_label(value))),
+                ResetColor,
+                Print(" ")
+            )?;
+        }
+        queue!(out, Print("\r\n\r\n"))?;
+    }
+    if let Some(suggestions) = suggestions {
+        queue!(out, Print("AI ranking:"))?;
+        for (direction, move_score) in suggestions {
+            queue!(out, Print(format!("  {:?} {:.0}", direction, move_score)))?;
+        }
+        queue!(out, Print("\r\n"))?;
+    }
+    queue!(out, Print(format!("{}\r\n", keys.help(true))))?;
+    queue!(out, Print(format!("{}\r\n", status)))?;
+    out.flush()
+}
+
+/// Plays a game in the terminal. New games spawn by `settings`; resumed
+/// ones keep the spawns they were saved with, since their moves were played
+/// under them. Hints and the AI overlay search with `settings` either way.
+pub fn run(args: &PlayArgs, settings: &GameSettings, tui: &TuiSettings, format: OutputFormat) -> io::Result<()> {
+    let save = &args.save;
+    let mut session = match &save.resume {
+        Some(path) => {
+            let (saved, resumed, history) = savegame::resume(path)?;
+            GameSession::resume(saved.seed, resumed, history, saved.spawn_rules(), settings.search.clone())
+        }
+        None => GameSession::new(args.seed.unwrap_or_else(rand::random), settings.spawn, settings.search.clone()),
+    };
+    let mut status = String::new();
+    let mut assist = false;
+    // The AI's ranking and the board hash it was searched at, so redraws
+    // that leave the position alone (hints, toggles, illegal keys) reuse it.
+    let mut ranked: Option<(u64, Vec<(Direction, f32)>)> = None;
+    {
+        let _guard = RawModeGuard::enter()?;
+        let mut out = io::stdout();
+        loop {
+            // Ranked against the position before the move, so it can also
+            // rate the move the player picks next.
+            let hash = session.board().board_hash();
+            let ranking = if assist && !session.is_over() {
+                if ranked.as_ref().is_none_or(|&(searched, _)| searched != hash) {
+                    ranked = Some((hash, session.search().move_scores));
+                }
+                ranked.as_ref().map(|(_, scores)| scores.as_slice())
+            } else {
+                None
+            };
+            draw(&mut out, session.board(), session.score(), &status, ranking, &tui.keys)?;
+            if session.is_over() {
+                break;
+            }
+            match read_action(&tui.keys)? {
+                KeyAction::Move(direction) => match session.play_human_move(direction) {
+                    Ok(result) => {
+                        status = if result.score_gained > 0 {
+                            format!("+{}", result.score_gained)
+                        } else {
+                            String::new()
+                        };
+                        if let Some(ranking) = ranking {
+                            let rating = match rate_move(ranking, direction) {
+                                MoveRating::Best => "best",
+                                MoveRating::Ok => "ok",
+                                MoveRating::Blunder => "blunder",
+                            };
+                            status = format!("{:?} was {} {}", direction, rating, status).trim_end().to_string();
+                        }
+                    }
+                    Err(err) => status = format!("{:?}: {}", direction, err),
+                },
+                KeyAction::Hint => {
+                    status = match session.search().best_move {
+                        Some(direction) => format!("Hint: {:?}", direction),
+                        None => "No moves left".to_string(),
+                    };
+                }
+                KeyAction::ToggleAssist => {
+                    assist = !assist;
+                    status = format!("AI overlay {}", if assist { "on" } else { "off" });
+                }
+                KeyAction::Quit => break,
+            }
+        }
+    }
+    FinalScore {
+        seed: session.seed(),
+        score: session.score(),
+        max_tile: session.board().get_max_tile(),
+        moves: session.history().len() as u32,
+        merges: session.merges().clone(),
+    }
+    .print(format)?;
+    if let Some(path) = &save.save_on_exit {
+        savegame::save(path, &session.save_game())?;
+        if format == OutputFormat::Text {
+            println!("Game saved to {} (resume with --resume)", path.display());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_move_against_ranking() {
+        let ranking = [
+            (Direction::Left, 1000.0),
+            (Direction::Up, 950.0),
+            (Direction::Down, 400.0),
+        ];
+        assert_eq!(rate_move(&ranking, Direction::Left), MoveRating::Best);
+        assert_eq!(rate_move(&ranking, Direction::Up), MoveRating::Ok);
+        assert_eq!(rate_move(&ranking, Direction::Down), MoveRating::Blunder);
+        // A move the search did not consider legal cannot be rated ok.
+        assert_eq!(rate_move(&ranking, Direction::Right), MoveRating::Blunder);
+    }
+}
@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::Deserialize;
+
+/// How much a command prints, from `-q` (final result only) to `-v`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+impl Verbosity {
+    /// Level requested on the command line, if any.
+    pub fn from_flags(quiet: bool, verbose: u8) -> Option<Self> {
+        if quiet {
+            Some(Verbosity::Quiet)
+        } else if verbose > 0 {
+            Some(Verbosity::Verbose)
+        } else {
+            None
+        }
+    }
+}
+
+/// Spinner for a single game of unknown length; hidden with `-q`.
+pub fn move_spinner(verbosity: Verbosity) -> ProgressBar {
+    if verbosity == Verbosity::Quiet {
+        return ProgressBar::hidden();
+    }
+    let bar = ProgressBar::new_spinner();
+    bar.set_style(
+        ProgressStyle::with_template("{spinner} move {pos} [{elapsed_precise}] {msg}")
+            .expect("valid progress template"),
+    );
+    bar.enable_steady_tick(Duration::from_millis(120));
+    bar
+}
+
+/// Bar over a batch of games with ETA and a running message; hidden with `-q`.
+pub fn games_bar(total: usize, verbosity: Verbosity) -> ProgressBar {
+    if verbosity == Verbosity::Quiet {
+        return ProgressBar::hidden();
+    }
+    let bar = ProgressBar::new(total as u64);
+    bar.set_style(
+        ProgressStyle::with_template("{prefix}{bar:30} {pos}/{len} games [{elapsed_precise} < {eta}] {msg}")
+            .expect("valid progress template"),
+    );
+    bar
+}
+
+/// Prints above `bar`, or straight to stdout when the bar is not drawn
+/// (`-q`, or output is not a terminal).
+pub fn print_above(bar: &ProgressBar, message: impl AsRef<str>) {
+    if bar.is_hidden() {
+        println!("{}", message.as_ref());
+    } else {
+        bar.println(message);
+    }
+}
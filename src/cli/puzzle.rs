@@ -0,0 +1,222 @@
+use std::io::{self, BufRead, Write};
+
+use serde::Serialize;
+use twenty_forty_eight::utils::render;
+use twenty_forty_eight::{Direction, GameBoard, MovePuzzle, PuzzleGenerator, PuzzleGrade, SearchConfig, TranspositionState};
+
+use super::config::SolverConfig;
+use super::{print_json, OutputFormat, PuzzleArgs};
+
+/// Correct answers in a row that move the player up a grade.
+const PROMOTION_STREAK: u32 = 3;
+
+/// Puzzles each round of generation looks for.
+const BATCH: usize = 8;
+
+/// Rounds of generation to try for a puzzle of the player's grade before
+/// serving the closest one found.
+const MAX_BATCHES: u32 = 3;
+
+/// How a puzzle session is going: answers, the current streak, and the
+/// grade puzzles are served at. The grade rises after
+/// [`PROMOTION_STREAK`] correct answers in a row and drops after a miss.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PuzzleProgress {
+    pub grade: PuzzleGrade,
+    pub streak: u32,
+    pub best_streak: u32,
+    pub solved: u32,
+    pub attempted: u32,
+}
+
+impl PuzzleProgress {
+    pub fn new() -> Self {
+        Self {
+            grade: PuzzleGrade::Easy,
+            streak: 0,
+            best_streak: 0,
+            solved: 0,
+            attempted: 0,
+        }
+    }
+
+    /// Counts an answer; returns the new grade when it changes.
+    pub fn record(&mut self, correct: bool) -> Option<PuzzleGrade> {
+        self.attempted += 1;
+        let grade = if correct {
+            self.solved += 1;
+            self.streak += 1;
+            self.best_streak = self.best_streak.max(self.streak);
+            if self.streak.is_multiple_of(PROMOTION_STREAK) { harder(self.grade) } else { self.grade }
+        } else {
+            self.streak = 0;
+            easier(self.grade)
+        };
+        let changed = grade != self.grade;
+        self.grade = grade;
+        changed.then_some(grade)
+    }
+}
+
+fn harder(grade: PuzzleGrade) -> PuzzleGrade {
+    match grade {
+        PuzzleGrade::Easy => PuzzleGrade::Medium,
+        PuzzleGrade::Medium | PuzzleGrade::Hard => PuzzleGrade::Hard,
+    }
+}
+
+fn easier(grade: PuzzleGrade) -> PuzzleGrade {
+    match grade {
+        PuzzleGrade::Hard => PuzzleGrade::Medium,
+        PuzzleGrade::Medium | PuzzleGrade::Easy => PuzzleGrade::Easy,
+    }
+}
+
+/// Generated puzzles not served yet, topped up from fresh self-play games.
+struct PuzzleSource {
+    generator: PuzzleGenerator,
+    pool: Vec<MovePuzzle>,
+}
+
+impl PuzzleSource {
+    /// A puzzle of `grade`, or of the closest grade generated.
+    fn next(&mut self, grade: PuzzleGrade) -> Option<MovePuzzle> {
+        for _ in 0..MAX_BATCHES {
+            if let Some(index) = self.pool.iter().position(|puzzle| puzzle.grade == grade) {
+                return Some(self.pool.remove(index));
+            }
+            self.pool.extend(self.generator.generate(BATCH));
+            // The next round plays games none of this one's did.
+            self.generator.seed = self.generator.seed.wrapping_add(self.generator.max_games);
+        }
+        let closest = (0..self.pool.len()).min_by_key(|&index| (self.pool[index].grade as i32 - grade as i32).abs())?;
+        Some(self.pool.remove(closest))
+    }
+}
+
+enum Answer {
+    Move(Direction),
+    Skip,
+    Quit,
+}
+
+fn read_answer(input: &mut impl BufRead, out: &mut impl Write) -> io::Result<Answer> {
+    loop {
+        write!(out, "Best move? (up/down/left/right, skip, quit) ")?;
+        out.flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(Answer::Quit);
+        }
+        match line.trim().to_ascii_lowercase().as_str() {
+            "skip" => return Ok(Answer::Skip),
+            "q" | "quit" => return Ok(Answer::Quit),
+            text => match text.parse() {
+                Ok(direction) => return Ok(Answer::Move(direction)),
+                Err(err) => writeln!(out, "{}", err)?,
+            },
+        }
+    }
+}
+
+/// Serves puzzles until the player quits or `rounds` have been answered.
+/// Puzzles are drawn from games played with `search` at depth 2, and
+/// answers are judged by `search` at the puzzle depth.
+fn play(args: &PuzzleArgs, search: &SearchConfig, input: &mut impl BufRead, out: &mut impl Write) -> io::Result<PuzzleProgress> {
+    let mut source = PuzzleSource {
+        generator: PuzzleGenerator {
+            seed: args.seed.unwrap_or_else(rand::random),
+            play: SearchConfig { max_depth: Some(2), ..search.clone() },
+            verify_depth: args.depth,
+            ..PuzzleGenerator::default()
+        },
+        pool: Vec::new(),
+    };
+    let judge = SearchConfig { max_depth: Some(args.depth), ..search.clone() };
+    let mut progress = PuzzleProgress::new();
+    let mut tt = TranspositionState::new();
+    while args.rounds.is_none_or(|rounds| progress.attempted < rounds) {
+        writeln!(out, "Finding a{} {} puzzle...", if progress.grade == PuzzleGrade::Easy { "n" } else { "" }, progress.grade)?;
+        let Some(puzzle) = source.next(progress.grade) else {
+            writeln!(out, "No puzzle found; try another --seed")?;
+            break;
+        };
+        let board = GameBoard::from_compact(&puzzle.position).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        // Answers are graded by this one search, and the move it names is
+        // the one shown as best.
+        let deep = board.search_with(&judge, &mut tt);
+        tt.clear();
+        let Some(best_move) = deep.best_move else { continue };
+        writeln!(out, "\n{} puzzle, difficulty {:.2} (streak {})", puzzle.grade, puzzle.difficulty, progress.streak)?;
+        writeln!(out, "{}", render::unicode(&board.get_board()))?;
+        let answer = match read_answer(input, out)? {
+            Answer::Move(direction) => direction,
+            Answer::Skip => {
+                writeln!(out, "Skipped: {:?} was best", best_move)?;
+                continue;
+            }
+            Answer::Quit => break,
+        };
+
+        let correct = answer == best_move;
+        if correct {
+            writeln!(out, "Correct! {:?} leads the runner-up by {:.1}%", answer, deep.score_gap() * 100.0)?;
+        } else {
+            let best = deep.score;
+            match deep.move_scores.iter().find(|&&(direction, _)| direction == answer) {
+                Some(&(_, score)) => writeln!(
+                    out,
+                    "Not quite: {:?} was best; {:?} scores {:.1}% less",
+                    best_move,
+                    answer,
+                    (best - score) / best.abs().max(1.0) * 100.0
+                )?,
+                None => writeln!(out, "{:?} is not a legal move here; {:?} was best", answer, best_move)?,
+            }
+        }
+        if let Some(grade) = progress.record(correct) {
+            writeln!(out, "Puzzles are now {}", grade)?;
+        }
+    }
+    Ok(progress)
+}
+
+pub fn run(args: &PuzzleArgs, config: &SolverConfig, format: OutputFormat) -> io::Result<()> {
+    let search = config.game_settings().search;
+    let stdin = io::stdin();
+    if format == OutputFormat::Json {
+        // The prompts go to stderr so stdout holds only the summary.
+        let progress = play(args, &search, &mut stdin.lock(), &mut io::stderr())?;
+        return print_json(&progress);
+    }
+    let progress = play(args, &search, &mut stdin.lock(), &mut io::stdout())?;
+    println!(
+        "\nSolved {} of {} ({}), best streak {}, finished at {}",
+        progress.solved,
+        progress.attempted,
+        if progress.attempted == 0 { "-".to_string() } else { format!("{:.0}%", progress.solved as f32 / progress.attempted as f32 * 100.0) },
+        progress.best_streak,
+        progress.grade
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_streaks_raise_the_grade_and_misses_lower_it() {
+        let mut progress = PuzzleProgress::new();
+        assert_eq!(progress.record(false), None);
+        assert_eq!(progress.record(true), None);
+        assert_eq!(progress.record(true), None);
+        assert_eq!(progress.record(true), Some(PuzzleGrade::Medium));
+        for _ in 0..5 {
+            progress.record(true);
+        }
+        assert_eq!((progress.grade, progress.streak), (PuzzleGrade::Hard, 8));
+        assert_eq!(progress.record(false), Some(PuzzleGrade::Medium));
+        assert_eq!((progress.solved, progress.attempted, progress.best_streak), (8, 10, 8));
+    }
+}
@@ -0,0 +1,115 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+use twenty_forty_eight::SearchResult;
+
+pub use twenty_forty_eight::ai::selfplay::{DepthStats, GameRecord};
+
+const CSV_HEADER: &str = "seed,score,max_tile,moves,time_secs,mean_depth,max_depth";
+
+fn csv_row(record: &GameRecord) -> String {
+    format!(
+        "{},{},{},{},{:.3},{:.2},{}",
+        record.seed, record.score, record.max_tile, record.moves, record.time_secs, record.mean_depth, record.max_depth
+    )
+}
+
+/// Per-move search telemetry accumulated over one game: how deep each
+/// search got and whether the depth or time budget was the limit.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SearchTelemetry {
+    pub depth: DepthStats,
+    pub nodes: u64,
+    pub time: Duration,
+    pub max_time: Duration,
+    /// Searches that completed their target depth.
+    pub full_depth: u32,
+    /// Searches cut short by the time limit.
+    pub timed_out: u32,
+}
+
+impl SearchTelemetry {
+    pub fn record(&mut self, result: &SearchResult) {
+        self.depth.record(result.depth);
+        self.nodes += result.nodes;
+        self.time += result.elapsed;
+        self.max_time = self.max_time.max(result.elapsed);
+        if result.timed_out {
+            self.timed_out += 1;
+        } else if result.depth >= result.target_depth {
+            self.full_depth += 1;
+        }
+    }
+
+    pub fn searches(&self) -> u32 {
+        self.depth.count()
+    }
+
+    pub fn mean_time(&self) -> Duration {
+        self.time / self.searches().max(1)
+    }
+
+    pub fn print(&self) {
+        println!("Search depth: mean {:.2}, max {} (target reached on {} of {} moves)",
+                 self.depth.mean(),
+                 self.depth.max,
+                 self.full_depth,
+                 self.searches());
+        println!("Search time: mean {:.1} ms, max {:.1} ms per move; {} moves hit the time limit",
+                 self.mean_time().as_secs_f64() * 1000.0,
+                 self.max_time.as_secs_f64() * 1000.0,
+                 self.timed_out);
+        println!("Nodes searched: {} ({:.0}/s)",
+                 self.nodes,
+                 self.nodes as f64 / self.time.as_secs_f64().max(f64::EPSILON));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResultsFormat {
+    Csv,
+    /// One JSON object per line
+    Jsonl,
+}
+
+pub struct ResultsWriter {
+    out: BufWriter<File>,
+    format: ResultsFormat,
+}
+
+impl ResultsWriter {
+    /// Creates `path`, inferring the format from a `.csv` extension when
+    /// `format` is not given (JSON Lines otherwise).
+    pub fn create(path: &Path, format: Option<ResultsFormat>) -> io::Result<Self> {
+        let format = format.unwrap_or_else(|| {
+            match path.extension().and_then(|ext| ext.to_str()) {
+                Some(ext) if ext.eq_ignore_ascii_case("csv") => ResultsFormat::Csv,
+                _ => ResultsFormat::Jsonl,
+            }
+        });
+        let mut out = BufWriter::new(File::create(path)?);
+        if format == ResultsFormat::Csv {
+            writeln!(out, "{}", CSV_HEADER)?;
+        }
+        Ok(Self { out, format })
+    }
+
+    pub fn write(&mut self, record: &GameRecord) -> io::Result<()> {
+        match self.format {
+            ResultsFormat::Csv => writeln!(self.out, "{}", csv_row(record)),
+            ResultsFormat::Jsonl => {
+                serde_json::to_writer(&mut self.out, record)?;
+                writeln!(self.out)
+            }
+        }
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
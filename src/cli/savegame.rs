@@ -0,0 +1,27 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use twenty_forty_eight::game::replay::ResumedGame;
+use twenty_forty_eight::game::savegame::SaveGame;
+use twenty_forty_eight::Direction;
+
+fn invalid(path: &Path, err: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), err))
+}
+
+/// Reads a save file of any version, migrating older ones.
+pub fn load(path: &Path) -> io::Result<SaveGame> {
+    SaveGame::from_json(&fs::read_to_string(path)?).map_err(|err| invalid(path, err))
+}
+
+pub fn save(path: &Path, game: &SaveGame) -> io::Result<()> {
+    fs::write(path, game.to_json())
+}
+
+/// Loads and replays a save: the live game and its move history.
+pub fn resume(path: &Path) -> io::Result<(SaveGame, ResumedGame, Vec<Direction>)> {
+    let saved = load(path)?;
+    let (game, history) = saved.resume().map_err(|err| invalid(path, err))?;
+    Ok((saved, game, history))
+}
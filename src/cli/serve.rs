@@ -0,0 +1,18 @@
+use std::io;
+use std::net::TcpListener;
+
+use twenty_forty_eight::web::WebServer;
+
+use super::config::SolverConfig;
+use super::ServeArgs;
+
+pub fn run(args: &ServeArgs, config: &SolverConfig) -> io::Result<()> {
+    let settings = config.game_settings();
+    let listener = TcpListener::bind((args.host.as_str(), args.port))?;
+    println!("Serving the game API on http://{}", listener.local_addr()?);
+    let mut server = WebServer::new(settings.spawn, settings.search).with_max_cache_entries(settings.max_cache_entries);
+    if let Some(origin) = &args.allow_origin {
+        server = server.with_allowed_origin(origin);
+    }
+    server.serve(&listener)
+}
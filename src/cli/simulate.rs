@@ -0,0 +1,125 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+use std::thread;
+
+use indicatif::ProgressBar;
+use serde::Serialize;
+use twenty_forty_eight::ai::selfplay;
+use twenty_forty_eight::stats::GameStats;
+use twenty_forty_eight::{OptimizedEvaluationWeights, WeightAdapter};
+
+use super::config::SolverConfig;
+use super::progress::{games_bar, print_above, Verbosity};
+use super::results::{GameRecord, ResultsWriter};
+use super::stats::{self, Cumulative, Summary};
+use super::{print_json, Outcome, OutputFormat, SimulateArgs};
+
+/// `--output json` document of `simulate`.
+#[derive(Serialize)]
+struct SimulateReport<'a> {
+    #[serde(flatten)]
+    summary: &'a Summary,
+    /// Totals over the `--stats-file`, when one is given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cumulative: Option<Cumulative>,
+}
+
+/// Drives `bar` from finished games, showing the running mean score. With
+/// `-v` each game is also printed above the bar.
+pub fn game_progress(bar: &ProgressBar, verbosity: Verbosity) -> impl Fn(&GameRecord) + Sync + '_ {
+    let totals = Mutex::new((0u64, 0u64));
+    move |record| {
+        let mut totals = totals.lock().unwrap();
+        totals.0 += record.score as u64;
+        totals.1 += 1;
+        bar.set_message(format!("mean score {:.0}", totals.0 as f64 / totals.1 as f64));
+        if verbosity == Verbosity::Verbose {
+            print_above(bar, format!("seed {}: score {}, max tile {}, {} moves",
+                                record.seed, record.score, record.max_tile, record.moves));
+        }
+        bar.inc(1);
+    }
+}
+
+/// The adapter saved at `path`, or a new one starting from the configured
+/// weights.
+fn load_adapter(path: &Path, weights: &Option<OptimizedEvaluationWeights>, seed: u64) -> io::Result<WeightAdapter> {
+    match fs::read_to_string(path) {
+        Ok(text) => WeightAdapter::from_json(&text)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), err))),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            Ok(WeightAdapter::new(weights.clone().unwrap_or_else(|| OptimizedEvaluationWeights::uniform(1.0)), seed))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+pub fn run(args: &SimulateArgs, config: &SolverConfig, verbosity: Verbosity, format: OutputFormat) -> io::Result<Outcome> {
+    let seed = args.seed.unwrap_or_else(rand::random);
+    // Adapting games learn from each other, so they run one at a time.
+    let threads = match args.adapt {
+        Some(_) => 1,
+        None => args.threads.unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get())),
+    };
+    let mut settings = config.game_settings();
+    if args.depth.is_some() {
+        settings.search.max_depth = args.depth;
+    }
+    let output = args.output.or_config(&config.output);
+
+    if verbosity > Verbosity::Quiet {
+        println!("Simulating {} games on {} threads (seed {})", args.games, threads, seed);
+    }
+    let bar = games_bar(args.games, verbosity);
+    let config = settings.self_play(seed, threads, args.max_moves);
+    let results = match &args.adapt {
+        Some(path) => {
+            let mut adapter = load_adapter(path, &config.search.weight_scale, seed)?;
+            let progress = game_progress(&bar, verbosity);
+            let results = selfplay::run_adaptive_games(&config, args.games, &mut adapter, |record, adapter| {
+                progress(record);
+                fs::write(path, adapter.to_json())
+            })?;
+            if verbosity > Verbosity::Quiet {
+                let w = &adapter.weights;
+                print_above(&bar, format!(
+                    "Adapted weights after {} games, saved to {}: monotonicity {:.3}, smoothness {:.3}, empty {:.3}, corner {:.3}, merge {:.3}, position {:.3}",
+                    adapter.games, path.display(), w.monotonicity, w.smoothness, w.empty, w.corner, w.merge, w.position
+                ));
+            }
+            results
+        }
+        None => selfplay::run_games_with(&config, args.games, game_progress(&bar, verbosity)),
+    };
+    bar.finish_and_clear();
+    let records = results.records;
+    let summary = Summary::new(&GameStats::from_records(&records), seed, results.elapsed);
+    let cumulative = match &args.stats_file {
+        Some(path) => Some(stats::append_and_total(path, &summary)?),
+        None => None,
+    };
+    match format {
+        OutputFormat::Text => {
+            summary.print();
+            if let Some(cumulative) = &cumulative {
+                cumulative.print();
+            }
+        }
+        OutputFormat::Json => print_json(&SimulateReport { summary: &summary, cumulative })?,
+    }
+
+    if let Some(path) = &output.results {
+        let mut writer = ResultsWriter::create(path, output.format)?;
+        for record in &records {
+            writer.write(record)?;
+        }
+        writer.finish()?;
+        if verbosity > Verbosity::Quiet {
+            println!("Per-game results written to {}", path.display());
+        }
+    }
+    let lowest_max_tile = records.iter().map(|r| r.max_tile).min().unwrap_or(0);
+    Ok(Outcome::from_target(args.target, lowest_max_tile))
+}
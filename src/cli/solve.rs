@@ -0,0 +1,258 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use indicatif::ProgressBar;
+use serde::Serialize;
+use twenty_forty_eight::game::audit::SpawnAudit;
+use twenty_forty_eight::game::replay::{MoveAnnotation, ReplayHeader, ReplayStep, ReplayWriter};
+use twenty_forty_eight::game::savegame::SaveGame;
+use twenty_forty_eight::utils::render;
+use twenty_forty_eight::{AchievementTracker, Budget, GameBoard, GameObserver, GameSession, MoveEvent, TimeAttack, Unlocked, get_cache_stats, get_eval_cache_stats, maintain_cache};
+
+use super::config::SolverConfig;
+use super::gamelog::MoveLog;
+use super::progress::{move_spinner, print_above, Verbosity};
+use super::results::{GameRecord, ResultsWriter, SearchTelemetry};
+use super::savegame;
+use super::{print_json, Outcome, OutputFormat, SolveArgs};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Games stop after this many moves even if they are not over.
+const MAX_MOVES: u32 = 5000;
+
+/// Final result printed by `--output json`.
+#[derive(Serialize)]
+struct SolveReport<'a> {
+    #[serde(flatten)]
+    record: &'a GameRecord,
+    board: [[u32; 4]; 4],
+    nodes: u64,
+    mean_move_ms: f64,
+    /// Moves whose search was cut short by the time limit.
+    timed_out_moves: u32,
+    achievements: &'a [Unlocked],
+}
+
+/// Tracks achievements, printing each above the progress bar as it
+/// unlocks in verbose mode.
+struct AchievementFeed<'a> {
+    tracker: AchievementTracker,
+    progress: &'a ProgressBar,
+    verbosity: Verbosity,
+}
+
+impl GameObserver for AchievementFeed<'_> {
+    fn on_move(&mut self, board: &GameBoard, event: &MoveEvent) {
+        for achievement in self.tracker.observe(board) {
+            if self.verbosity == Verbosity::Verbose {
+                print_above(self.progress, format!("move {}: achievement unlocked: {}", event.move_number, achievement));
+            }
+        }
+    }
+}
+
+pub fn run(args: &SolveArgs, config: &SolverConfig, verbosity: Verbosity, format: OutputFormat) -> io::Result<Outcome> {
+    let start = Instant::now();
+    let mut settings = config.game_settings();
+    let output = args.output.or_config(&config.output);
+    let (seed, resumed) = match &args.save.resume {
+        Some(path) => {
+            let (saved, resumed, history) = savegame::resume(path)?;
+            if verbosity > Verbosity::Quiet {
+                println!("Resuming {} at move {}", path.display(), history.len());
+            }
+            // The game goes on with the spawns it was saved with.
+            settings.spawn = saved.spawn_rules();
+            settings.search.spawn = settings.spawn;
+            (saved.seed, Some((resumed, history)))
+        }
+        None => (args.seed.unwrap_or_else(rand::random), None),
+    };
+    let mut log = args.log.as_deref().map(|path| MoveLog::append(path, seed)).transpose()?;
+    if args.save.save_on_exit.is_some() {
+        // Let Ctrl-C end the loop normally so the game still gets saved.
+        ctrlc::set_handler(|| INTERRUPTED.store(true, Ordering::Relaxed))
+            .map_err(io::Error::other)?;
+    }
+    let time_attack = match (args.move_budget, args.time_budget) {
+        (Some(moves), _) => Some(TimeAttack::new(Budget::Moves(moves))),
+        (_, Some(secs)) => Some(TimeAttack::new(Budget::Time(Duration::try_from_secs_f64(secs).map_err(|err| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("--time-budget: {}", err))
+        })?))),
+        _ => None,
+    };
+    let mut telemetry = SearchTelemetry::default();
+
+    if verbosity > Verbosity::Quiet {
+        println!("Starting score-optimized 2048 solver with enhanced AI (seed {})...", seed);
+    }
+    let progress = move_spinner(verbosity);
+    let mut achievements = AchievementFeed { tracker: AchievementTracker::new(), progress: &progress, verbosity };
+    let mut session = match resumed {
+        Some((resumed, history)) => GameSession::resume(seed, resumed, history, settings.spawn, settings.search.clone()),
+        None => GameSession::new(seed, settings.spawn, settings.search.clone()),
+    }
+    .with_max_moves(MAX_MOVES);
+    if let Some(attack) = time_attack {
+        session = session.with_time_attack(attack);
+    }
+    session.add_observer(&mut achievements);
+    if let Some(log) = &mut log {
+        session.add_observer(log);
+    }
+    let mut recorder = match &args.record {
+        Some(path) => {
+            let header = ReplayHeader::new(seed, &settings.spawn, session.board());
+            Some(ReplayWriter::new(BufWriter::new(File::create(path)?), &header)?)
+        }
+        None => None,
+    };
+    progress.set_position(session.history().len() as u64);
+
+    while !session.is_over() && !INTERRUPTED.load(Ordering::Relaxed) {
+        let moves = session.history().len() as u32;
+        if verbosity == Verbosity::Verbose && moves.is_multiple_of(50) {
+            print_above(&progress, format!("\nMove {}\n{}", moves + 1, render::ascii(&session.board().get_board())));
+        }
+        let ai_move = match session.play_ai_move() {
+            Ok(ai_move) => ai_move,
+            Err(err) => {
+                print_above(&progress, format!("No move played: {}", err));
+                break;
+            }
+        };
+        let result = &ai_move.search;
+        telemetry.record(result);
+        progress.set_message(format!("score {}, max tile {}, empty {} | depth {}/{}, {} nodes, {:.1} ms",
+                                     session.score(),
+                                     session.board().get_max_tile(),
+                                     session.board().count_empty_cells(),
+                                     result.depth,
+                                     result.target_depth,
+                                     result.nodes,
+                                     result.elapsed.as_secs_f64() * 1000.0));
+        if verbosity == Verbosity::Verbose {
+            print_above(&progress, format!("move {}: depth {}/{}{}, {} nodes, {:.1} ms",
+                                           moves + 1,
+                                           result.depth,
+                                           result.target_depth,
+                                           if result.timed_out { " (time limit)" } else { "" },
+                                           result.nodes,
+                                           result.elapsed.as_secs_f64() * 1000.0));
+        }
+        let Some(played) = &ai_move.result else {
+            if verbosity > Verbosity::Quiet {
+                print_above(&progress, format!("Resigning at move {}: the position is lost", moves + 1));
+            }
+            break;
+        };
+        if let Some(recorder) = &mut recorder {
+            recorder.record(&ReplayStep {
+                direction: played.direction,
+                spawn: played.spawned,
+                hash: session.board().board_hash(),
+                annotation: Some(MoveAnnotation { eval: result.score, depth: result.depth }),
+            })?;
+        }
+        progress.inc(1);
+
+        if let Some(report) = maintain_cache(moves + 1, settings.max_cache_entries) {
+            if verbosity == Verbosity::Verbose {
+                print_above(&progress, format!("Cache size: {} entries, {:.1}% hit rate", report.entries, report.hit_rate * 100.0));
+                if report.evicted > 0 {
+                    print_above(&progress, format!("Cache evicted {} stale or shallow entries", report.evicted));
+                }
+            }
+        }
+    }
+    progress.finish_and_clear();
+    let game = session.board().clone();
+    let (score, history, merges) = (session.score(), session.history().to_vec(), session.merges().clone());
+    drop(session);
+    if let Some(log) = log {
+        log.finish()?;
+    }
+    let moves = history.len() as u32;
+    let record = GameRecord {
+        seed,
+        score,
+        max_tile: game.get_max_tile(),
+        moves,
+        time_secs: start.elapsed().as_secs_f64(),
+        mean_depth: telemetry.depth.mean(),
+        max_depth: telemetry.depth.max,
+        merges,
+    };
+    
+    if format == OutputFormat::Json {
+        print_json(&SolveReport {
+            record: &record,
+            board: game.get_board(),
+            nodes: telemetry.nodes,
+            mean_move_ms: telemetry.mean_time().as_secs_f64() * 1000.0,
+            timed_out_moves: telemetry.timed_out,
+            achievements: achievements.tracker.unlocked(),
+        })?;
+    } else {
+        if verbosity > Verbosity::Quiet {
+            println!("\nGame Over!");
+            println!("Final board state:");
+            println!("{}", render::ascii(&game.get_board()));
+        }
+        println!("Seed: {}", seed);
+        println!("Total moves: {}", moves);
+        println!("Highest tile: {}", game.get_max_tile());
+        println!("Final score: {}", record.score);
+        println!("Merges by tile: {}", record.merges);
+        for unlocked in achievements.tracker.unlocked() {
+            println!("Achievement: {} (move {})", unlocked.achievement, unlocked.move_number);
+        }
+    }
+    
+    if verbosity > Verbosity::Quiet {
+        telemetry.print();
+        // Final cache statistics
+        let (hits, misses, final_cache_size) = get_cache_stats();
+        println!("Final transposition table entries: {}", final_cache_size);
+        println!("Cache hits: {} | misses: {} | hit rate: {:.2}%", hits, misses, if hits + misses > 0 { (hits as f64 / (hits + misses) as f64) * 100.0 } else { 0.0 });
+        let (eval_hits, eval_misses, _) = get_eval_cache_stats();
+        println!("Evaluation cache hits: {} | misses: {} | hit rate: {:.2}%",
+                 eval_hits,
+                 eval_misses,
+                 if eval_hits + eval_misses > 0 { eval_hits as f64 / (eval_hits + eval_misses) as f64 * 100.0 } else { 0.0 });
+    }
+
+    if let Some(path) = &args.save.save_on_exit {
+        savegame::save(path, &SaveGame::new(seed, &settings.spawn, &game, &history, score))?;
+        if verbosity > Verbosity::Quiet {
+            println!("Game saved to {} (resume with --resume)", path.display());
+        }
+    }
+
+    if let Some(path) = &args.spawn_audit {
+        // Regenerated from the seed by the same spawn code the game used.
+        let audit = SpawnAudit::record(seed, &settings.spawn, game.variant, &history)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+        fs::write(path, audit.to_json())?;
+        if verbosity > Verbosity::Quiet {
+            println!("Spawn audit ({} spawns, {} RNG draws) written to {}", audit.spawns.len(), audit.draws, path.display());
+        }
+    }
+
+    if let (Some(path), true) = (&args.record, verbosity > Verbosity::Quiet) {
+        println!("Replay recorded to {}", path.display());
+    }
+
+    if let Some(path) = &output.results {
+        let mut writer = ResultsWriter::create(path, output.format)?;
+        writer.write(&record)?;
+        writer.finish()?;
+        if verbosity > Verbosity::Quiet {
+            println!("Game record written to {}", path.display());
+        }
+    }
+    Ok(Outcome::from_target(args.target, record.max_tile))
+}
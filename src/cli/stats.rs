@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use twenty_forty_eight::stats::{GameStats, StatsReport, MILESTONE_TILES};
+
+/// Aggregate statistics over a batch of games; also the `--output json`
+/// document of `simulate` and one line of the `--stats-file`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Summary {
+    pub seed: u64,
+    pub wall_time_secs: f64,
+    #[serde(flatten)]
+    pub stats: StatsReport,
+}
+
+impl Summary {
+    pub fn new(stats: &GameStats, seed: u64, wall_time: Duration) -> Self {
+        Self {
+            seed,
+            wall_time_secs: wall_time.as_secs_f64(),
+            stats: stats.report(),
+        }
+    }
+
+    pub fn print(&self) {
+        println!("Games: {}  (seed {}, wall time {:.1}s)", self.stats.games, self.seed, self.wall_time_secs);
+        print!("{}", self.stats);
+    }
+}
+
+impl<'de> Deserialize<'de> for Summary {
+    // `flatten` cannot read integer map keys back from JSON, so the run
+    // fields are split off by hand and the rest read as the report.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut value = serde_json::Value::deserialize(deserializer)?;
+        let object = value.as_object_mut().ok_or_else(|| D::Error::custom("summary is not an object"))?;
+        let mut field = |name| object.remove(name).ok_or_else(|| D::Error::missing_field(name));
+        let seed = serde_json::from_value(field("seed")?).map_err(D::Error::custom)?;
+        let wall_time_secs = serde_json::from_value(field("wall_time_secs")?).map_err(D::Error::custom)?;
+        let stats = serde_json::from_value(value).map_err(D::Error::custom)?;
+        Ok(Self { seed, wall_time_secs, stats })
+    }
+}
+
+/// One line of the cumulative stats file.
+#[derive(Debug, Serialize, Deserialize)]
+struct StatsEntry {
+    /// Seconds since the Unix epoch when the run finished.
+    timestamp: u64,
+    summary: Summary,
+}
+
+/// Totals over every run recorded in a stats file.
+#[derive(Debug, Serialize)]
+pub struct Cumulative {
+    pub runs: usize,
+    pub games: usize,
+    pub mean_score: f64,
+    pub reach_rates: BTreeMap<u32, f64>,
+}
+
+impl Cumulative {
+    pub fn print(&self) {
+        println!("All runs in stats file: {} runs, {} games, mean score {:.0}", self.runs, self.games, self.mean_score);
+        let milestones: Vec<String> = MILESTONE_TILES
+            .iter()
+            .map(|tile| format!("{} {:.1}%", tile, self.reach_rates.get(tile).copied().unwrap_or(0.0)))
+            .collect();
+        println!("  reach rates: {}", milestones.join(", "));
+    }
+}
+
+/// Appends `summary` to the JSON Lines file at `path` and returns the
+/// game-weighted totals over every run it now holds.
+pub fn append_and_total(path: &Path, summary: &Summary) -> io::Result<Cumulative> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    serde_json::to_writer(&mut file, &StatsEntry { timestamp, summary: summary.clone() })?;
+    writeln!(file)?;
+
+    let mut cumulative = Cumulative { runs: 0, games: 0, mean_score: 0.0, reach_rates: BTreeMap::new() };
+    let mut score_total = 0.0;
+    let mut reached: BTreeMap<u32, f64> = BTreeMap::new();
+    for (number, line) in fs::read_to_string(path)?.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: StatsEntry = serde_json::from_str(line).map_err(|err| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("{}:{}: {}", path.display(), number + 1, err))
+        })?;
+        let games = entry.summary.stats.games as f64;
+        cumulative.runs += 1;
+        cumulative.games += entry.summary.stats.games;
+        score_total += entry.summary.stats.mean_score * games;
+        for (tile, rate) in entry.summary.stats.reach_rates {
+            *reached.entry(tile).or_default() += rate / 100.0 * games;
+        }
+    }
+    let games = cumulative.games.max(1) as f64;
+    cumulative.mean_score = score_total / games;
+    cumulative.reach_rates = reached.into_iter().map(|(tile, count)| (tile, count / games * 100.0)).collect();
+    Ok(cumulative)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use twenty_forty_eight::stats::GameRecord;
+
+    fn record(score: u32, max_tile: u32) -> GameRecord {
+        GameRecord { seed: 0, score, max_tile, moves: 1, time_secs: 0.0, mean_depth: 1.0, max_depth: 1, merges: Default::default() }
+    }
+
+    #[test]
+    fn test_stats_file_accumulates_runs() {
+        let path = std::env::temp_dir().join(format!("2048-stats-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let first = Summary::new(&GameStats::from_records(&[record(1000, 2048)]), 1, Duration::ZERO);
+        let second = Summary::new(&GameStats::from_records(&[record(3000, 1024), record(5000, 1024), record(3000, 1024)]), 2, Duration::ZERO);
+        append_and_total(&path, &first).unwrap();
+        let total = append_and_total(&path, &second).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(total.runs, 2);
+        assert_eq!(total.games, 4);
+        assert_eq!(total.mean_score, 3000.0);
+        assert_eq!(total.reach_rates[&2048], 25.0);
+    }
+}
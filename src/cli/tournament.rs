@@ -0,0 +1,94 @@
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use serde::Serialize;
+use twenty_forty_eight::ai::arena::{Match, Side};
+
+use super::config::SolverConfig;
+use super::progress::{games_bar, Verbosity};
+use super::simulate::game_progress;
+use super::{print_json, OutputFormat, TournamentArgs};
+
+/// `--output json` document of `tournament`.
+#[derive(Serialize)]
+struct TournamentReport {
+    games: usize,
+    seed: u64,
+    mean_score_a: f64,
+    mean_score_b: f64,
+    wins: usize,
+    losses: usize,
+    ties: usize,
+    mean_diff: f64,
+    ci95: f64,
+    t: f64,
+    p_value: f64,
+    significant: bool,
+}
+
+pub fn run(args: &TournamentArgs, verbosity: Verbosity, format: OutputFormat) -> io::Result<()> {
+    let config_a = SolverConfig::load_or_preset(&args.config_a)?.game_settings();
+    let config_b = SolverConfig::load_or_preset(&args.config_b)?.game_settings();
+    if config_a.spawn != config_b.spawn {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "both configs must use the same spawn rules to play identical games",
+        ));
+    }
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let threads = args
+        .threads
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()));
+
+    if verbosity > Verbosity::Quiet {
+        println!("Tournament: {} games per config on {} threads (seed {})", args.games, threads, seed);
+    }
+    let bar = games_bar(args.games, verbosity);
+    bar.set_prefix("A ");
+    let progress = game_progress(&bar, verbosity);
+    let started_b = AtomicBool::new(false);
+    let result = Match::new(config_a.self_play(seed, threads, args.max_moves), config_b.self_play(seed, threads, args.max_moves))
+        .play_with(args.games, |side, record| {
+            if side == Side::B && !started_b.swap(true, Ordering::Relaxed) {
+                bar.reset();
+                bar.set_prefix("B ");
+                bar.set_message("");
+            }
+            progress(record);
+        });
+    bar.finish_and_clear();
+    let test = result.test;
+    let (mean_score_a, mean_score_b) = (result.stats_a.mean_score(), result.stats_b.mean_score());
+
+    if format == OutputFormat::Json {
+        return print_json(&TournamentReport {
+            games: result.records_a.len(),
+            seed,
+            mean_score_a,
+            mean_score_b,
+            wins: result.wins,
+            losses: result.losses,
+            ties: result.ties,
+            mean_diff: test.mean_diff,
+            ci95: test.ci95(),
+            t: test.t,
+            p_value: test.p_value,
+            significant: test.p_value < 0.05,
+        });
+    }
+    println!("A ({}): mean score {:.0}", args.config_a.display(), mean_score_a);
+    println!("B ({}): mean score {:.0}", args.config_b.display(), mean_score_b);
+    println!("Seed: {}", seed);
+    println!("A vs B: {} wins, {} losses, {} ties", result.wins, result.losses, result.ties);
+    println!("Mean difference: {:+.0} ± {:.0} (95% CI), t = {:.2}, p = {:.4}",
+             test.mean_diff,
+             test.ci95(),
+             test.t,
+             test.p_value);
+    match result.stronger(0.05) {
+        Some(side) => println!("Difference is significant at the 5% level: {:?} is stronger", side),
+        None => println!("No significant difference at the 5% level"),
+    }
+    Ok(())
+}
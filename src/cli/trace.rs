@@ -0,0 +1,74 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+
+use serde::Serialize;
+use twenty_forty_eight::{SearchConfig, SearchTrace, TranspositionState};
+
+use super::analyze::load_position;
+use super::config::SolverConfig;
+use super::{print_json, OutputFormat, TraceArgs};
+
+/// `--output json` document of `trace`.
+#[derive(Serialize)]
+struct TraceReport {
+    events: usize,
+    /// Index of the first event a replay did differently; absent when it
+    /// matched throughout, and when recording.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diverged_at: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expected: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    actual: Option<String>,
+}
+
+pub fn run(args: &TraceArgs, config: &SolverConfig, format: OutputFormat) -> io::Result<()> {
+    let search = SearchConfig { trace: true, ..config.game_settings().search };
+    let invalid = |err: &dyn std::fmt::Display| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", args.file.display(), err));
+    if !args.replay {
+        let position = args.position.as_deref().expect("clap requires a position unless replaying");
+        let board = load_position(position)?;
+        let result = board.search_with(&search, &mut TranspositionState::new());
+        let trace = result.trace.expect("tracing was on");
+        let mut out = BufWriter::new(File::create(&args.file)?);
+        trace.write_to(&mut out)?;
+        out.flush()?;
+        if format == OutputFormat::Json {
+            return print_json(&TraceReport { events: trace.events.len(), diverged_at: None, expected: None, actual: None });
+        }
+        println!(
+            "Traced {} events to {} (depth {}{})",
+            trace.events.len(),
+            args.file.display(),
+            result.depth,
+            if result.timed_out { ", timed out" } else { "" }
+        );
+        return Ok(());
+    }
+
+    let trace = SearchTrace::read_from(BufReader::new(File::open(&args.file)?))?;
+    let again = trace.replay(&search).map_err(|err| invalid(&err))?;
+    let divergence = trace.first_divergence(&again);
+    let show = |event: Option<_>| event.map_or_else(|| "end of search".to_string(), |event| format!("{}", event));
+    let report = TraceReport {
+        events: trace.events.len(),
+        diverged_at: divergence.as_ref().map(|d| d.index),
+        expected: divergence.as_ref().map(|d| show(d.expected)),
+        actual: divergence.as_ref().map(|d| show(d.actual)),
+    };
+    if format == OutputFormat::Json {
+        return print_json(&report);
+    }
+    match divergence {
+        None => println!("Replayed all {} events identically", trace.events.len()),
+        Some(divergence) => {
+            println!("Diverged at event {} of {}", divergence.index, trace.events.len());
+            println!("  recorded: {}", report.expected.unwrap());
+            println!("  replayed: {}", report.actual.unwrap());
+            if trace.table_entries > 0 {
+                println!("  (recorded on a table with {} entries; the replay starts empty)", trace.table_entries);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+
+use serde::Serialize;
+use twenty_forty_eight::ai::dataset::read_dataset;
+use twenty_forty_eight::ScoreModel;
+
+use super::progress::Verbosity;
+use super::{print_json, OutputFormat, TrainArgs};
+
+/// `--output json` document of `train`.
+#[derive(Serialize)]
+struct TrainReport {
+    records: usize,
+    /// Mean absolute error in points during each epoch.
+    errors: Vec<f32>,
+    examples: u64,
+}
+
+pub fn run(args: &TrainArgs, verbosity: Verbosity, format: OutputFormat) -> io::Result<()> {
+    let records = read_dataset(BufReader::new(File::open(&args.dataset)?))?;
+    let mut model = match &args.resume {
+        Some(path) => ScoreModel::read_from(BufReader::new(File::open(path)?))?,
+        None => ScoreModel::new(),
+    };
+    let errors = model.train(&records, args.epochs, args.learning_rate);
+    let mut out = BufWriter::new(File::create(&args.model)?);
+    model.write_to(&mut out)?;
+    out.flush()?;
+
+    let report = TrainReport { records: records.len(), errors, examples: model.examples() };
+    if format == OutputFormat::Json {
+        return print_json(&report);
+    }
+    if verbosity >= Verbosity::Verbose {
+        for (epoch, error) in report.errors.iter().enumerate() {
+            println!("epoch {}: mean error {:.0} points", epoch + 1, error);
+        }
+    }
+    println!(
+        "Trained on {} records for {} epochs (mean error {:.0} points) and wrote {}",
+        report.records,
+        report.errors.len(),
+        report.errors.last().copied().unwrap_or(0.0),
+        args.model.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,342 @@
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crossterm::event::{self, Event, KeyEventKind};
+use ratatui::layout::{Alignment, Constraint, Direction as LayoutDirection, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, Sparkline};
+use ratatui::{DefaultTerminal, Frame};
+use twenty_forty_eight::utils::render::tile_label;
+use twenty_forty_eight::{get_cache_stats, maintain_cache, Direction, GameBoard, GameObserver, GameSession, MergeBreakdown, MoveEvent, SearchConfig};
+
+use super::config::{GameSettings, Panels, TuiSettings};
+use super::keys::KeyAction;
+use super::{FinalScore, Outcome, OutputFormat};
+use super::play::{rate_move, tile_colors, MoveRating};
+
+/// Number of recent evaluations kept for the sparkline.
+const EVAL_HISTORY: usize = 200;
+/// Width of the strongest move's bar in the move heatmap.
+const HEAT_WIDTH: usize = 24;
+/// Moves ahead, and the search depth of each, of the predicted board.
+const PREVIEW_MOVES: usize = 5;
+const PREVIEW_DEPTH: u32 = 2;
+
+/// Snapshot sent from the AI thread after every move.
+struct Update {
+    board: [[u32; 4]; 4],
+    score: u32,
+    moves: u32,
+    merges: MergeBreakdown,
+    depth: u32,
+    target_depth: u32,
+    move_ms: f64,
+    /// Searches so far that were cut short by the time limit.
+    timed_out: u32,
+    nodes_per_sec: f64,
+    hit_rate: f64,
+    eval: f32,
+    /// Root move scores of the search that picked the last move, best first.
+    move_scores: Vec<(Direction, f32)>,
+    /// Where the engine expects the board to be `PREVIEW_MOVES` moves on.
+    preview: [[u32; 4]; 4],
+    game_over: bool,
+}
+
+struct Dashboard {
+    latest: Option<Update>,
+    evals: Vec<u64>,
+    finished: bool,
+}
+
+/// Sends the dashboard an [`Update`] after every move the AI plays.
+struct DashboardFeed {
+    tx: Sender<Update>,
+    merges: MergeBreakdown,
+    timed_out: u32,
+    preview_search: SearchConfig,
+}
+
+impl GameObserver for DashboardFeed {
+    fn on_move(&mut self, board: &GameBoard, event: &MoveEvent) {
+        self.merges.record(event.result);
+        let Some(search) = event.search else { return };
+        self.timed_out += search.timed_out as u32;
+        let (hits, misses, _) = get_cache_stats();
+        let secs = search.elapsed.as_secs_f64();
+        let update = Update {
+            board: board.get_board(),
+            score: event.score,
+            moves: board.get_move_count(),
+            merges: self.merges.clone(),
+            depth: search.depth,
+            target_depth: search.target_depth,
+            move_ms: secs * 1000.0,
+            timed_out: self.timed_out,
+            nodes_per_sec: if secs > 0.0 { search.nodes as f64 / secs } else { 0.0 },
+            hit_rate: if hits + misses > 0 { hits as f64 / (hits + misses) as f64 * 100.0 } else { 0.0 },
+            eval: search.score,
+            move_scores: search.move_scores.clone(),
+            preview: board.preview(&self.preview_search, PREVIEW_MOVES).board.get_board(),
+            game_over: board.is_game_over(),
+        };
+        // Fails once the dashboard has closed; the stop flag then ends the game.
+        let _ = self.tx.send(update);
+    }
+}
+
+fn spawn_ai(settings: GameSettings, seed: u64, stop: Arc<AtomicBool>) -> Receiver<Update> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut session = GameSession::new(seed, settings.spawn, settings.search.clone());
+        session.add_observer(DashboardFeed {
+            tx,
+            merges: MergeBreakdown::new(),
+            timed_out: 0,
+            preview_search: SearchConfig {
+                max_depth: Some(PREVIEW_DEPTH),
+                time_limit: None,
+                ..settings.search.clone()
+            },
+        });
+        while !stop.load(Ordering::Relaxed) && !session.is_over() {
+            if !session.play_ai_move().is_ok_and(|ai_move| ai_move.result.is_some()) {
+                break;
+            }
+            maintain_cache(session.history().len() as u32, settings.max_cache_entries);
+        }
+    });
+    rx
+}
+
+fn draw_board(frame: &mut Frame, area: Rect, board: &[[u32; 4]; 4], title: &str) {
+    let mut lines = Vec::new();
+    for row in board {
+        let spans: Vec<Span> = row
+            .iter()
+            .flat_map(|&value| {
+                let (foreground, background) = tile_colors(value);
+                [
+                    Span::styled(
+                        format!("{:^7}", tile_label(value)),
+                        Style::default()
+                            .fg(Color::from(foreground))
+                            .bg(Color::from(background))
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(" "),
+                ]
+            })
+            .collect();
+        lines.push(Line::from(spans));
+        lines.push(Line::raw(""));
+    }
+    let block = Block::default().borders(Borders::ALL).title(title);
+    frame.render_widget(Paragraph::new(lines).alignment(Alignment::Center).block(block), area);
+}
+
+/// Where `direction` falls between the worst (0.0) and best (1.0) legal
+/// root move, or `None` when it was not a legal move.
+fn move_heat(move_scores: &[(Direction, f32)], direction: Direction) -> Option<f32> {
+    let &(_, score) = move_scores.iter().find(|&&(d, _)| d == direction)?;
+    let best = move_scores.first().map_or(score, |&(_, s)| s);
+    let worst = move_scores.last().map_or(score, |&(_, s)| s);
+    Some(if best > worst { (score - worst) / (best - worst) } else { 1.0 })
+}
+
+fn draw_moves(frame: &mut Frame, area: Rect, move_scores: &[(Direction, f32)]) {
+    let lines: Vec<Line> = Direction::all()
+        .into_iter()
+        .map(|direction| {
+            let label = format!("{:<6}", format!("{:?}", direction));
+            let Some(heat) = move_heat(move_scores, direction) else {
+                return Line::from(vec![Span::raw(label), Span::styled("illegal", Style::default().fg(Color::DarkGray))]);
+            };
+            let color = match rate_move(move_scores, direction) {
+                MoveRating::Best => Color::Green,
+                MoveRating::Ok => Color::Yellow,
+                MoveRating::Blunder => Color::Red,
+            };
+            let score = move_scores.iter().find(|&&(d, _)| d == direction).map_or(0.0, |&(_, s)| s);
+            let width = ((heat * HEAT_WIDTH as f32).round() as usize).max(1);
+            Line::from(vec![
+                Span::raw(label),
+                Span::styled(format!("{:<w$}", "█".repeat(width), w = HEAT_WIDTH), Style::default().fg(color)),
+                Span::raw(format!(" {:.0}", score)),
+            ])
+        })
+        .collect();
+    let block = Block::default().borders(Borders::ALL).title(" Moves ");
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// Splits `area` into the panels of `panels`, in order, leaving out the
+/// hidden ones; `None` for a hidden panel.
+fn split<const N: usize>(area: Rect, direction: LayoutDirection, panels: [(bool, Constraint); N]) -> [Option<Rect>; N] {
+    let shown: Vec<Constraint> = panels.iter().filter(|(show, _)| *show).map(|&(_, constraint)| constraint).collect();
+    let areas = Layout::default().direction(direction).constraints(shown).split(area);
+    let mut areas = areas.iter().copied();
+    panels.map(|(show, _)| if show { areas.next() } else { None })
+}
+
+fn draw(frame: &mut Frame, dashboard: &Dashboard, panels: &Panels, quit: char) {
+    // Without the sparkline the free space goes below everything else.
+    let [top, heatmap, evaluation, _, footer] = split(
+        frame.area(),
+        LayoutDirection::Vertical,
+        [
+            (true, Constraint::Length(11)),
+            (panels.heatmap, Constraint::Length(6)),
+            (panels.evaluation, Constraint::Min(5)),
+            (!panels.evaluation, Constraint::Min(0)),
+            (true, Constraint::Length(1)),
+        ],
+    );
+    let [board_area, stats_area, preview_area] = split(
+        top.expect("always shown"),
+        LayoutDirection::Horizontal,
+        [
+            (true, Constraint::Length(36)),
+            (panels.stats, Constraint::Min(24)),
+            (panels.preview, Constraint::Length(36)),
+        ],
+    );
+
+    let empty = [[0; 4]; 4];
+    let board = dashboard.latest.as_ref().map_or(&empty, |u| &u.board);
+    draw_board(frame, board_area.expect("always shown"), board, " Board ");
+    if let Some(area) = preview_area {
+        let preview = dashboard.latest.as_ref().map_or(&empty, |u| &u.preview);
+        draw_board(frame, area, preview, &format!(" In {} moves ", PREVIEW_MOVES));
+    }
+
+    let stats = match &dashboard.latest {
+        Some(u) => vec![
+            Line::from(format!("Score:      {}", u.score)),
+            Line::from(format!("Move:       {}", u.moves)),
+            Line::from(format!("Depth:      {}/{}", u.depth, u.target_depth)),
+            Line::from(format!("Move time:  {:.1} ms", u.move_ms)),
+            Line::from(format!("Timed out:  {} moves", u.timed_out)),
+            Line::from(format!("Nodes/sec:  {:.0}", u.nodes_per_sec)),
+            Line::from(format!("Cache hits: {:.1}%", u.hit_rate)),
+            Line::from(format!("Eval:       {:.0}", u.eval)),
+        ],
+        None => vec![Line::from("Waiting for the first move...")],
+    };
+    if let Some(area) = stats_area {
+        let stats_block = Block::default().borders(Borders::ALL).title(" Search ");
+        frame.render_widget(Paragraph::new(stats).block(stats_block), area);
+    }
+
+    if let Some(area) = heatmap {
+        let move_scores = dashboard.latest.as_ref().map_or(&[][..], |u| &u.move_scores[..]);
+        draw_moves(frame, area, move_scores);
+    }
+
+    if let Some(area) = evaluation {
+        let evals = &dashboard.evals[dashboard.evals.len().saturating_sub(area.width as usize)..];
+        let sparkline = Sparkline::default()
+            .block(Block::default().borders(Borders::ALL).title(" Evaluation "))
+            .data(evals)
+            .style(Style::default().fg(Color::Yellow));
+        frame.render_widget(sparkline, area);
+    }
+
+    let text = if dashboard.finished { format!("Game over - press {} to exit", quit) } else { format!("{}: quit", quit) };
+    frame.render_widget(Paragraph::new(text), footer.expect("always shown"));
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, updates: Receiver<Update>, tui: &TuiSettings) -> io::Result<Dashboard> {
+    let mut dashboard = Dashboard {
+        latest: None,
+        evals: Vec::new(),
+        finished: false,
+    };
+    loop {
+        loop {
+            match updates.try_recv() {
+                Ok(update) => {
+                    dashboard.evals.push(update.eval.max(0.0) as u64);
+                    if dashboard.evals.len() > EVAL_HISTORY {
+                        dashboard.evals.remove(0);
+                    }
+                    dashboard.finished = update.game_over;
+                    dashboard.latest = Some(update);
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    dashboard.finished = true;
+                    break;
+                }
+            }
+        }
+        terminal.draw(|frame| draw(frame, &dashboard, &tui.panels, tui.keys.quit))?;
+
+        if event::poll(Duration::from_millis(50))? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && tui.keys.action(key.code, key.modifiers) == Some(KeyAction::Quit) {
+                    return Ok(dashboard);
+                }
+            }
+        }
+    }
+}
+
+/// Full-screen dashboard showing the AI play live.
+pub fn run(settings: &GameSettings, tui: &TuiSettings, seed: Option<u64>, target: Option<u32>, format: OutputFormat) -> io::Result<Outcome> {
+    let seed = seed.unwrap_or_else(rand::random);
+    let stop = Arc::new(AtomicBool::new(false));
+    let updates = spawn_ai(settings.clone(), seed, Arc::clone(&stop));
+
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, updates, tui);
+    ratatui::restore();
+    stop.store(true, Ordering::Relaxed);
+
+    let Some(update) = result?.latest else {
+        return Ok(Outcome::from_target(target, 0));
+    };
+    let max_tile = update.board.iter().flatten().max().copied().unwrap_or(0);
+    FinalScore {
+        seed,
+        score: update.score,
+        max_tile,
+        moves: update.moves,
+        merges: update.merges,
+    }
+    .print(format)?;
+    Ok(Outcome::from_target(target, max_tile))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_move_heat_spans_worst_to_best() {
+        let ranking = [(Direction::Left, 200.0), (Direction::Up, 150.0), (Direction::Down, 100.0)];
+        assert_eq!(move_heat(&ranking, Direction::Left), Some(1.0));
+        assert_eq!(move_heat(&ranking, Direction::Up), Some(0.5));
+        assert_eq!(move_heat(&ranking, Direction::Down), Some(0.0));
+        assert_eq!(move_heat(&ranking, Direction::Right), None);
+        assert_eq!(move_heat(&ranking[..1], Direction::Left), Some(1.0));
+    }
+
+    #[test]
+    fn test_hidden_panels_give_their_space_to_the_others() {
+        let area = Rect::new(0, 0, 100, 20);
+        let [board, stats, preview] = split(
+            area,
+            LayoutDirection::Horizontal,
+            [(true, Constraint::Length(36)), (false, Constraint::Min(24)), (true, Constraint::Length(36))],
+        );
+        assert_eq!(stats, None);
+        assert_eq!(board.map(|r| (r.x, r.width)), Some((0, 36)));
+        assert_eq!(preview.map(|r| r.x), Some(36));
+    }
+}
@@ -0,0 +1,244 @@
+//! Gym-style reinforcement-learning environment.
+//!
+//! [`Env`] is the reset/step interface RL loops are written against;
+//! [`GameEnv`] implements it for 2048, with the observation layout chosen
+//! by [`ObservationEncoding`] and the reward by [`RewardShaping`].
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::game::{Direction, GameBoard};
+
+/// An episodic environment.
+pub trait Env {
+    type Action;
+
+    /// Starts a new episode, seeded by `seed` when given, and returns the
+    /// first observation.
+    fn reset(&mut self, seed: Option<u64>) -> Vec<f32>;
+
+    /// Takes `action` and returns what followed it.
+    fn step(&mut self, action: Self::Action) -> Step;
+
+    /// The current state, encoded.
+    fn observation(&self) -> Vec<f32>;
+
+    /// Whether the episode has ended; `step` must not be called after.
+    fn done(&self) -> bool;
+
+    /// Length of every observation.
+    fn observation_len(&self) -> usize;
+}
+
+/// The outcome of one [`Env::step`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub observation: Vec<f32>,
+    pub reward: f32,
+    pub done: bool,
+    /// False when the action did not change the board (nothing spawned).
+    pub moved: bool,
+}
+
+/// How the 16 cells are laid out in an observation, row by row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObservationEncoding {
+    /// Tile values as they are (0 = empty): 16 entries.
+    Raw,
+    /// Tile ranks, log2 of the value for classic tiles (0 = empty): 16
+    /// entries.
+    Exponents,
+    /// One 4x4 plane per rank from 0 (empty) to `planes - 1`, with a 1
+    /// where a cell holds that rank; higher ranks share the last plane.
+    /// `16 * planes` entries.
+    OneHot { planes: usize },
+}
+
+impl ObservationEncoding {
+    /// Entries in every observation.
+    pub fn size(self) -> usize {
+        match self {
+            ObservationEncoding::Raw | ObservationEncoding::Exponents => 16,
+            ObservationEncoding::OneHot { planes } => 16 * planes,
+        }
+    }
+
+    /// `board` as an observation.
+    pub fn encode(self, board: &GameBoard) -> Vec<f32> {
+        let cells = board.board.iter().flatten();
+        match self {
+            ObservationEncoding::Raw => cells.map(|&value| value as f32).collect(),
+            ObservationEncoding::Exponents => cells.map(|&value| board.rules().rank(value) as f32).collect(),
+            ObservationEncoding::OneHot { planes } => {
+                let mut observation = vec![0.0; self.size()];
+                for (index, &value) in cells.enumerate() {
+                    let plane = board.rules().rank(value).min(planes - 1);
+                    observation[plane * 16 + index] = 1.0;
+                }
+                observation
+            }
+        }
+    }
+}
+
+/// The reward for a step, as the sum of weighted terms.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RewardShaping {
+    /// Per point scored by the step's merges.
+    pub score: f32,
+    /// Score as `log2(1 + points)` instead, which keeps late-game merges
+    /// from dwarfing everything else.
+    pub log_score: bool,
+    /// Per empty cell the step leaves.
+    pub empty_cell: f32,
+    /// For an action that does not change the board.
+    pub invalid_move: f32,
+    /// For the step that ends the game.
+    pub game_over: f32,
+}
+
+impl Default for RewardShaping {
+    /// The game's own score, with no shaping.
+    fn default() -> Self {
+        Self {
+            score: 1.0,
+            log_score: false,
+            empty_cell: 0.0,
+            invalid_move: 0.0,
+            game_over: 0.0,
+        }
+    }
+}
+
+/// Classic 2048 as an [`Env`] whose actions are [`Direction`]s.
+#[derive(Debug, Clone)]
+pub struct GameEnv {
+    pub encoding: ObservationEncoding,
+    pub reward: RewardShaping,
+    /// The episode ends after this many steps, legal or not, even if the
+    /// game could go on.
+    pub max_steps: Option<u32>,
+    board: GameBoard,
+    rng: StdRng,
+    steps: u32,
+    score: u32,
+}
+
+impl GameEnv {
+    /// An environment seeded with `seed`; call [`Env::reset`] to start
+    /// again.
+    pub fn new(seed: u64, encoding: ObservationEncoding, reward: RewardShaping) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let board = GameBoard::new_with_rng(&mut rng);
+        Self {
+            encoding,
+            reward,
+            max_steps: None,
+            board,
+            rng,
+            steps: 0,
+            score: 0,
+        }
+    }
+
+    pub fn board(&self) -> &GameBoard {
+        &self.board
+    }
+
+    /// The game's score so far this episode.
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    /// Actions that change the board, for masking a policy.
+    pub fn legal_actions(&self) -> impl Iterator<Item = Direction> + '_ {
+        Direction::all().into_iter().filter(|&direction| self.board.can_move(direction))
+    }
+}
+
+impl Env for GameEnv {
+    type Action = Direction;
+
+    fn reset(&mut self, seed: Option<u64>) -> Vec<f32> {
+        if let Some(seed) = seed {
+            self.rng = StdRng::seed_from_u64(seed);
+        }
+        self.board = GameBoard::new_with_rng(&mut self.rng);
+        self.steps = 0;
+        self.score = 0;
+        self.observation()
+    }
+
+    fn step(&mut self, action: Direction) -> Step {
+        self.steps += 1;
+        let shaping = self.reward;
+        let (moved, mut reward) = match self.board.play_move_with(action, &mut self.rng) {
+            Ok(result) => {
+                self.score += result.score_gained;
+                let points = result.score_gained as f32;
+                let points = if shaping.log_score { points.ln_1p() / std::f32::consts::LN_2 } else { points };
+                (true, shaping.score * points)
+            }
+            Err(_) => (false, shaping.invalid_move),
+        };
+        reward += shaping.empty_cell * self.board.count_empty_cells() as f32;
+        if self.board.is_game_over() {
+            reward += shaping.game_over;
+        }
+        Step {
+            observation: self.observation(),
+            reward,
+            done: self.done(),
+            moved,
+        }
+    }
+
+    fn observation(&self) -> Vec<f32> {
+        self.encoding.encode(&self.board)
+    }
+
+    fn done(&self) -> bool {
+        self.board.is_game_over() || self.max_steps.is_some_and(|max| self.steps >= max)
+    }
+
+    fn observation_len(&self) -> usize {
+        self.encoding.size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_episode_rewards_add_up_to_the_score() {
+        let mut env = GameEnv::new(3, ObservationEncoding::OneHot { planes: 16 }, RewardShaping::default());
+        let first = env.reset(Some(11));
+        assert_eq!(first.len(), env.observation_len());
+        assert_eq!(first.iter().sum::<f32>(), 16.0);
+        assert_eq!(env.reset(Some(11)), first);
+
+        let mut total = 0.0;
+        while !env.done() {
+            let action = env.legal_actions().next().unwrap();
+            let step = env.step(action);
+            assert!(step.moved);
+            assert_eq!(step.observation.iter().sum::<f32>(), 16.0);
+            total += step.reward;
+        }
+        assert_eq!(total, env.score() as f32);
+    }
+
+    #[test]
+    fn test_invalid_moves_are_penalized_without_changing_the_board() {
+        let shaping = RewardShaping { invalid_move: -5.0, ..RewardShaping::default() };
+        let mut env = GameEnv::new(0, ObservationEncoding::Exponents, shaping);
+        env.board.set_board([[2, 4, 8, 16], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]);
+        let before = env.observation();
+        assert_eq!(&before[..4], &[1.0, 2.0, 3.0, 4.0]);
+        let step = env.step(Direction::Up);
+        assert!(!step.moved);
+        assert_eq!(step.reward, -5.0);
+        assert_eq!(step.observation, before);
+    }
+}
@@ -0,0 +1,118 @@
+use std::io::{self, Write};
+
+use gif::{Encoder, Frame as GifFrame, Repeat};
+
+use super::{tile_rgb, Frame, BOARD, FRAME_RGB, GAP, PALETTE_TILES, TILE};
+
+/// Glyphs of the digits 0-9, five rows of three pixels (high bit left).
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+/// Largest pixel size of one glyph cell.
+const MAX_GLYPH_SCALE: u32 = 6;
+
+const FRAME_INDEX: u8 = 0;
+const DARK_TEXT_INDEX: u8 = 1;
+const LIGHT_TEXT_INDEX: u8 = 2;
+/// Palette index of the first tile color in `PALETTE_TILES` order.
+const FIRST_TILE_INDEX: u8 = 3;
+
+/// Global palette: frame, both text colors, then one color per tile.
+fn palette() -> Vec<u8> {
+    let mut colors = vec![FRAME_RGB, tile_rgb(2).1, tile_rgb(8).1];
+    colors.extend(PALETTE_TILES.iter().map(|&value| tile_rgb(value).0));
+    colors.concat()
+}
+
+fn tile_index(value: u32) -> u8 {
+    let position = PALETTE_TILES.iter().position(|&v| v == value).unwrap_or(PALETTE_TILES.len() - 1);
+    FIRST_TILE_INDEX + position as u8
+}
+
+fn fill(pixels: &mut [u8], x: u32, y: u32, width: u32, height: u32, index: u8) {
+    for row in y..y + height {
+        let start = (row * BOARD + x) as usize;
+        pixels[start..start + width as usize].fill(index);
+    }
+}
+
+/// Draws `value` centered in the tile whose top-left corner is `(x, y)`.
+fn draw_number(pixels: &mut [u8], x: u32, y: u32, value: u32, index: u8) {
+    let digits: Vec<usize> = value.to_string().bytes().map(|b| (b - b'0') as usize).collect();
+    let units = digits.len() as u32 * 4 - 1;
+    let scale = ((TILE - 12) / units).clamp(1, MAX_GLYPH_SCALE);
+    let left = x + (TILE - units * scale) / 2;
+    let top = y + (TILE - 5 * scale) / 2;
+    for (position, &digit) in digits.iter().enumerate() {
+        for (row, bits) in DIGITS[digit].iter().enumerate() {
+            for col in 0..3 {
+                if bits & (0b100 >> col) != 0 {
+                    let px = left + (position as u32 * 4 + col) * scale;
+                    fill(pixels, px, top + row as u32 * scale, scale, scale, index);
+                }
+            }
+        }
+    }
+}
+
+fn rasterize(board: &[[u32; 4]; 4]) -> Vec<u8> {
+    let mut pixels = vec![FRAME_INDEX; (BOARD * BOARD) as usize];
+    for (row, cells) in board.iter().enumerate() {
+        for (col, &value) in cells.iter().enumerate() {
+            let x = GAP + col as u32 * (TILE + GAP);
+            let y = GAP + row as u32 * (TILE + GAP);
+            fill(&mut pixels, x, y, TILE, TILE, tile_index(value));
+            if value != 0 {
+                let text = if value <= 4 { DARK_TEXT_INDEX } else { LIGHT_TEXT_INDEX };
+                draw_number(&mut pixels, x, y, value, text);
+            }
+        }
+    }
+    pixels
+}
+
+/// Writes `frames` as a looping animated GIF showing each board for
+/// `delay_cs` hundredths of a second; the final board is held three times
+/// as long.
+pub fn write_gif<W: Write>(out: W, frames: &[Frame], delay_cs: u16) -> io::Result<W> {
+    let side = BOARD as u16;
+    let mut encoder = Encoder::new(out, side, side, &palette()).map_err(io::Error::other)?;
+    encoder.set_repeat(Repeat::Infinite).map_err(io::Error::other)?;
+    for (index, frame) in frames.iter().enumerate() {
+        let mut image = GifFrame::from_indexed_pixels(side, side, rasterize(&frame.board), None);
+        image.delay = if index + 1 == frames.len() { delay_cs.saturating_mul(3) } else { delay_cs };
+        encoder.write_frame(&image).map_err(io::Error::other)?;
+    }
+    encoder.into_inner()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gif_has_header_and_trailer() {
+        let frame = Frame { move_number: 0, board: [[2, 4, 8, 16], [32, 64, 128, 256], [512, 1024, 2048, 4096], [8192, 16384, 0, 0]] };
+        let bytes = write_gif(Vec::new(), &[frame, frame], 50).unwrap();
+        assert!(bytes.starts_with(b"GIF89a"));
+        assert_eq!(bytes.last(), Some(&0x3b));
+    }
+
+    #[test]
+    fn test_five_digit_tiles_fit_inside_the_tile() {
+        let pixels = rasterize(&[[16384, 0, 0, 0], [0; 4], [0; 4], [0; 4]]);
+        // The gap column right of the first tile stays frame-colored.
+        let gap_x = GAP + TILE;
+        assert!((0..BOARD).all(|y| pixels[(y * BOARD + gap_x) as usize] == FRAME_INDEX));
+        assert!(pixels.contains(&LIGHT_TEXT_INDEX));
+    }
+}
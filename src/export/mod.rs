@@ -0,0 +1,55 @@
+mod animation;
+mod svg;
+
+pub use animation::write_gif;
+pub use svg::render_svg;
+
+use crate::game::GameBoard;
+use crate::utils::render::tile_rgb;
+
+/// Side of one tile in pixels.
+const TILE: u32 = 64;
+/// Gap between tiles and around the board in pixels.
+const GAP: u32 = 8;
+/// Side of a rendered board in pixels.
+const BOARD: u32 = 4 * TILE + 5 * GAP;
+
+/// Board frame color behind the tiles.
+const FRAME_RGB: [u8; 3] = [187, 173, 160];
+
+/// One board to draw, labelled with the number of moves played.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    pub move_number: usize,
+    pub board: [[u32; 4]; 4],
+}
+
+/// Picks the opening, every `every`-th position and the final board from
+/// the positions of a game (as returned by `replay_positions`).
+pub fn select_frames(positions: &[GameBoard], every: usize) -> Vec<Frame> {
+    let every = every.max(1);
+    let last = positions.len().saturating_sub(1);
+    positions
+        .iter()
+        .enumerate()
+        .filter(|&(index, _)| index % every == 0 || index == last)
+        .map(|(move_number, board)| Frame { move_number, board: board.get_board() })
+        .collect()
+}
+
+/// Every tile value drawn with its own color (higher tiles share one).
+const PALETTE_TILES: [u32; 13] = [0, 2, 4, 8, 16, 32, 64, 128, 256, 512, 1024, 2048, 4096];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_frames_keeps_interval_and_final_board() {
+        let positions = vec![GameBoard::new(); 251];
+        let moves: Vec<usize> = select_frames(&positions, 100).iter().map(|f| f.move_number).collect();
+        assert_eq!(moves, [0, 100, 200, 250]);
+        assert_eq!(select_frames(&positions[..201], 100).len(), 3);
+        assert!(select_frames(&[], 100).is_empty());
+    }
+}
@@ -0,0 +1,105 @@
+use std::fmt::Write;
+
+use super::{tile_rgb, Frame, BOARD, FRAME_RGB, GAP, TILE};
+
+/// Boards per row of the sequence.
+const COLUMNS: usize = 4;
+/// Height reserved above each board for its caption.
+const CAPTION: u32 = 28;
+/// Space between boards in the sequence.
+const MARGIN: u32 = 16;
+
+fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Renders `frames` as one SVG document, boards laid out left to right and
+/// top to bottom, each captioned with its move number.
+pub fn render_svg(frames: &[Frame]) -> String {
+    let columns = frames.len().clamp(1, COLUMNS) as u32;
+    let rows = frames.len().div_ceil(COLUMNS).max(1) as u32;
+    let width = columns * (BOARD + MARGIN) + MARGIN;
+    let height = rows * (BOARD + CAPTION + MARGIN) + MARGIN;
+
+    let mut svg = String::new();
+    // Writing to a String cannot fail.
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="Helvetica, Arial, sans-serif" font-weight="bold">"#,
+        w = width,
+        h = height
+    );
+    let _ = writeln!(svg, r##"<rect width="100%" height="100%" fill="#faf8ef"/>"##);
+    for (index, frame) in frames.iter().enumerate() {
+        let x = MARGIN + (index % COLUMNS) as u32 * (BOARD + MARGIN);
+        let y = MARGIN + (index / COLUMNS) as u32 * (BOARD + CAPTION + MARGIN);
+        let _ = writeln!(
+            svg,
+            r##"<text x="{}" y="{}" font-size="18" fill="#776e65">Move {}</text>"##,
+            x,
+            y + 18,
+            frame.move_number
+        );
+        let top = y + CAPTION;
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" rx="6" fill="{}"/>"#,
+            x,
+            top,
+            BOARD,
+            BOARD,
+            hex(FRAME_RGB)
+        );
+        for (row, cells) in frame.board.iter().enumerate() {
+            for (col, &value) in cells.iter().enumerate() {
+                let (background, foreground) = tile_rgb(value);
+                let tile_x = x + GAP + col as u32 * (TILE + GAP);
+                let tile_y = top + GAP + row as u32 * (TILE + GAP);
+                let _ = writeln!(
+                    svg,
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" rx="3" fill="{}"/>"#,
+                    tile_x,
+                    tile_y,
+                    TILE,
+                    TILE,
+                    hex(background)
+                );
+                if value != 0 {
+                    let font_size = match value {
+                        0..=99 => 28,
+                        100..=999 => 24,
+                        1000..=9999 => 19,
+                        _ => 15,
+                    };
+                    let _ = writeln!(
+                        svg,
+                        r#"<text x="{}" y="{}" font-size="{}" fill="{}" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+                        tile_x + TILE / 2,
+                        tile_y + TILE / 2,
+                        font_size,
+                        hex(foreground),
+                        value
+                    );
+                }
+            }
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_svg_draws_every_tile_of_every_frame() {
+        let frame = Frame { move_number: 7, board: [[2, 0, 0, 0], [0, 4, 0, 0], [0, 0, 2048, 0], [0, 0, 0, 0]] };
+        let svg = render_svg(&[frame, Frame { move_number: 8, ..frame }]);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches(r#"rx="3""#).count(), 32);
+        assert!(svg.contains(">Move 8<"));
+        assert!(svg.contains(">2048<"));
+    }
+}
@@ -0,0 +1,160 @@
+//! C API over the engine, for C, C++ and Swift front-ends. The header is
+//! `include/twenty_forty_eight.h`, generated by cbindgen when the crate is
+//! built with the `ffi` feature.
+//!
+//! A game is an opaque handle from [`tfe_game_new`], released with
+//! [`tfe_game_free`]. Each handle owns its board, spawn RNG and
+//! transposition table, so handles share no state and separate handles
+//! may be used from separate threads; one handle must not be used from
+//! two threads at once.
+
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::ai::SearchConfig;
+use crate::cache::TranspositionState;
+use crate::game::{Direction, GameBoard};
+
+/// Direction codes: the values taken by [`tfe_game_move`] and returned by
+/// [`tfe_game_hint`].
+pub const TFE_UP: i32 = 0;
+pub const TFE_DOWN: i32 = 1;
+pub const TFE_LEFT: i32 = 2;
+pub const TFE_RIGHT: i32 = 3;
+
+/// A game owned by the caller.
+pub struct TfeGame {
+    board: GameBoard,
+    rng: StdRng,
+    tt: TranspositionState,
+    score: u32,
+}
+
+fn direction(code: i32) -> Option<Direction> {
+    usize::try_from(code).ok().and_then(|index| Direction::all().get(index).copied())
+}
+
+fn code(direction: Direction) -> i32 {
+    Direction::all().iter().position(|&d| d == direction).unwrap() as i32
+}
+
+/// Starts a game whose spawns are fully determined by `seed`. Release it
+/// with [`tfe_game_free`].
+#[no_mangle]
+pub extern "C" fn tfe_game_new(seed: u64) -> *mut TfeGame {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let board = GameBoard::new_with_rng(&mut rng);
+    Box::into_raw(Box::new(TfeGame {
+        board,
+        rng,
+        tt: TranspositionState::new(),
+        score: 0,
+    }))
+}
+
+/// Releases a game. Null is ignored.
+///
+/// # Safety
+/// `game` must be null or a handle from [`tfe_game_new`] not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn tfe_game_free(game: *mut TfeGame) {
+    if !game.is_null() {
+        drop(Box::from_raw(game));
+    }
+}
+
+/// Copies the 16 cell values, row by row (0 = empty), into `out`.
+///
+/// # Safety
+/// `game` must be a live handle and `out` must point to 16 writable
+/// `uint32_t`s.
+#[no_mangle]
+pub unsafe extern "C" fn tfe_game_cells(game: *const TfeGame, out: *mut u32) {
+    let game = &*game;
+    let out = std::slice::from_raw_parts_mut(out, 16);
+    for (cell, &value) in out.iter_mut().zip(game.board.board.iter().flatten()) {
+        *cell = value;
+    }
+}
+
+/// Plays `direction` (a `TFE_*` code) and spawns a tile. Returns the score
+/// gained, or -1 if the move does not change the board or the code is not
+/// a direction.
+///
+/// # Safety
+/// `game` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn tfe_game_move(game: *mut TfeGame, direction: i32) -> i32 {
+    let game = &mut *game;
+    let Some(direction) = self::direction(direction) else { return -1 };
+    match game.board.play_move_with(direction, &mut game.rng) {
+        Ok(result) => {
+            game.score += result.score_gained;
+            result.score_gained as i32
+        }
+        Err(_) => -1,
+    }
+}
+
+/// Best move found within `time_limit_ms` milliseconds as a `TFE_*` code,
+/// or -1 when the game is over.
+///
+/// # Safety
+/// `game` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn tfe_game_hint(game: *mut TfeGame, time_limit_ms: u32) -> i32 {
+    let game = &mut *game;
+    let config = SearchConfig::with_time_limit(Duration::from_millis(time_limit_ms as u64));
+    game.board.search_with(&config, &mut game.tt).best_move.map_or(-1, code)
+}
+
+/// # Safety
+/// `game` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn tfe_game_score(game: *const TfeGame) -> u32 {
+    (*game).score
+}
+
+/// # Safety
+/// `game` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn tfe_game_max_tile(game: *const TfeGame) -> u32 {
+    (*game).board.get_max_tile()
+}
+
+/// # Safety
+/// `game` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn tfe_game_is_over(game: *const TfeGame) -> bool {
+    (*game).board.is_game_over()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_c_api_plays_a_game_to_the_end() {
+        unsafe {
+            let game = tfe_game_new(7);
+            let mut cells = [0u32; 16];
+            tfe_game_cells(game, cells.as_mut_ptr());
+            assert_eq!(cells.iter().filter(|&&v| v != 0).count(), 2);
+            assert_eq!(tfe_game_move(game, 9), -1);
+
+            let mut score = 0;
+            while !tfe_game_is_over(game) {
+                let hint = tfe_game_hint(game, 1);
+                let gained = tfe_game_move(game, hint);
+                assert!(gained >= 0);
+                score += gained as u32;
+            }
+            assert_eq!(tfe_game_hint(game, 1), -1);
+            assert_eq!(tfe_game_score(game), score);
+            assert!(tfe_game_max_tile(game) >= 64);
+            tfe_game_free(game);
+        }
+    }
+}
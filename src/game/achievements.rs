@@ -0,0 +1,140 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use super::board::GameBoard;
+
+/// Tiles whose first appearance is a milestone.
+const TILE_MILESTONES: [u32; 5] = [512, 1024, 2048, 4096, 8192];
+
+/// Tiles that count when three of them are on the board at once.
+const TRIPLE_TILES: [u32; 3] = [256, 512, 1024];
+
+/// Move counts worth surviving to.
+const SURVIVAL_MILESTONES: [u32; 3] = [500, 1000, 2000];
+
+/// Empty cells that count as recovered after being down to one.
+const COMEBACK_EMPTY_CELLS: usize = 6;
+
+/// Something notable that happened during a game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Achievement {
+    /// The first time this tile was made.
+    FirstTile(u32),
+    /// Three of this tile on the board at once.
+    ThreeOf(u32),
+    /// The game lasted this many moves.
+    Survived(u32),
+    /// Back to plenty of room after having one empty cell left.
+    Comeback,
+}
+
+impl fmt::Display for Achievement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Achievement::FirstTile(tile) => write!(f, "first {}", tile),
+            Achievement::ThreeOf(tile) => write!(f, "three {}s on the board", tile),
+            Achievement::Survived(moves) => write!(f, "survived {} moves", moves),
+            Achievement::Comeback => write!(f, "comeback from one empty cell"),
+        }
+    }
+}
+
+/// An achievement and the move that earned it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Unlocked {
+    pub achievement: Achievement,
+    pub move_number: u32,
+}
+
+/// Watches a game move by move and records each achievement once. Call
+/// [`AchievementTracker::observe`] with the board after every move.
+#[derive(Debug, Clone, Default)]
+pub struct AchievementTracker {
+    unlocked: Vec<Unlocked>,
+    /// Down to one empty cell since the last comeback.
+    cornered: bool,
+}
+
+impl AchievementTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks `board` and returns the achievements it newly unlocks.
+    pub fn observe(&mut self, board: &GameBoard) -> Vec<Achievement> {
+        let mut earned = Vec::new();
+        let max_tile = board.get_max_tile();
+        earned.extend(TILE_MILESTONES.iter().filter(|&&tile| max_tile >= tile).map(|&tile| Achievement::FirstTile(tile)));
+        for tile in TRIPLE_TILES {
+            if board.board.iter().flatten().filter(|&&value| value == tile).count() >= 3 {
+                earned.push(Achievement::ThreeOf(tile));
+            }
+        }
+        let moves = board.get_move_count();
+        earned.extend(SURVIVAL_MILESTONES.iter().filter(|&&target| moves >= target).map(|&target| Achievement::Survived(target)));
+
+        let empty = board.count_empty_cells();
+        if empty <= 1 {
+            self.cornered = true;
+        } else if self.cornered && empty >= COMEBACK_EMPTY_CELLS {
+            self.cornered = false;
+            earned.push(Achievement::Comeback);
+        }
+
+        earned.retain(|&achievement| !self.has(achievement));
+        self.unlocked.extend(earned.iter().map(|&achievement| Unlocked { achievement, move_number: moves }));
+        earned
+    }
+
+    pub fn has(&self, achievement: Achievement) -> bool {
+        self.unlocked.iter().any(|unlocked| unlocked.achievement == achievement)
+    }
+
+    /// Every achievement so far, in the order they were earned.
+    pub fn unlocked(&self) -> &[Unlocked] {
+        &self.unlocked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(cells: [[u32; 4]; 4], moves: u32) -> GameBoard {
+        let mut game = GameBoard::new();
+        game.set_board(cells);
+        game.move_count = moves;
+        game
+    }
+
+    #[test]
+    fn test_achievements_unlock_once_in_order() {
+        let mut tracker = AchievementTracker::new();
+        let crowded = board([
+            [1024, 512, 512, 512],
+            [2, 4, 8, 16],
+            [4, 8, 16, 32],
+            [8, 16, 32, 0],
+        ], 1000);
+        assert_eq!(tracker.observe(&crowded), [
+            Achievement::FirstTile(512),
+            Achievement::FirstTile(1024),
+            Achievement::ThreeOf(512),
+            Achievement::Survived(500),
+            Achievement::Survived(1000),
+        ]);
+        assert!(tracker.observe(&crowded).is_empty());
+
+        let roomy = board([
+            [1024, 1024, 0, 0],
+            [2, 4, 8, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0],
+        ], 1001);
+        assert_eq!(tracker.observe(&roomy), [Achievement::Comeback]);
+        assert_eq!(tracker.unlocked().last().unwrap().move_number, 1001);
+        assert_eq!(Achievement::ThreeOf(512).to_string(), "three 512s on the board");
+    }
+}
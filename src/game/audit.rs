@@ -0,0 +1,265 @@
+//! Spawn audit logs: every tile a seeded game spawned, where it landed and
+//! how many RNG draws came before it.
+//!
+//! A replay file records the spawns too, but a server checking a claimed
+//! game, or a developer chasing a determinism bug, needs to know the game
+//! drew them from the seed the same way: a log whose spawns all match but
+//! whose draw counts drift shows that some code path consumed randomness
+//! differently, even before it changes a tile.
+
+use std::fmt;
+
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use super::board::GameBoard;
+use super::move_result::{MoveError, MoveResult, SpawnedTile};
+use super::moves::Direction;
+use super::replay::ReplayHeader;
+use super::spawn::SpawnRules;
+use super::variant::Variant;
+
+/// Forwards to `R`, counting the draws: each `next_u32`, `next_u64` or
+/// `fill_bytes` call is one.
+#[derive(Debug, Clone)]
+pub struct CountingRng<R> {
+    inner: R,
+    draws: u64,
+}
+
+impl<R: RngCore> CountingRng<R> {
+    pub fn new(inner: R) -> Self {
+        Self { inner, draws: 0 }
+    }
+
+    /// Draws made so far.
+    pub fn draws(&self) -> u64 {
+        self.draws
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: RngCore> RngCore for CountingRng<R> {
+    fn next_u32(&mut self) -> u32 {
+        self.draws += 1;
+        self.inner.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.draws += 1;
+        self.inner.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.draws += 1;
+        self.inner.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.draws += 1;
+        self.inner.try_fill_bytes(dest)
+    }
+}
+
+/// One spawned tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpawnEntry {
+    /// The move the tile spawned after; 0 for the two opening tiles.
+    pub move_number: u32,
+    pub row: usize,
+    pub col: usize,
+    pub value: u32,
+    /// Draws the game's RNG had made before this spawn's first one.
+    pub draw: u64,
+}
+
+impl SpawnEntry {
+    fn new(move_number: u32, tile: SpawnedTile, draw: u64) -> Self {
+        Self { move_number, row: tile.row, col: tile.col, value: tile.value, draw }
+    }
+}
+
+/// Every spawn of a seeded game, in order, with the header needed to
+/// regenerate the game.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpawnAudit {
+    pub header: ReplayHeader,
+    pub spawns: Vec<SpawnEntry>,
+    /// Draws made by the whole game.
+    pub draws: u64,
+}
+
+/// How a game failed to match its audit log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditError {
+    /// The header names rules that are not registered.
+    UnknownRules,
+    /// Move `index` (0-based) does not change the board.
+    IllegalMove { index: usize, direction: Direction },
+    /// Spawn `index` differs; `None` where one side has no such spawn.
+    Spawn { index: usize, expected: Option<SpawnEntry>, recorded: Option<SpawnEntry> },
+    /// Every spawn matches, but the game drew more or less in total.
+    Draws { expected: u64, recorded: u64 },
+}
+
+impl fmt::Display for AuditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let describe = |entry: &Option<SpawnEntry>| match entry {
+            Some(entry) => format!(
+                "a {} at ({}, {}) after move {} from draw {}",
+                entry.value, entry.row, entry.col, entry.move_number, entry.draw
+            ),
+            None => "no spawn".to_string(),
+        };
+        match self {
+            AuditError::UnknownRules => write!(f, "audit rules are not a known variant"),
+            AuditError::IllegalMove { index, direction } => {
+                write!(f, "move {} ({:?}) does not change the board", index, direction)
+            }
+            AuditError::Spawn { index, expected, recorded } => {
+                write!(f, "spawn {}: the seed gives {}, the log has {}", index, describe(expected), describe(recorded))
+            }
+            AuditError::Draws { expected, recorded } => {
+                write!(f, "the seed gives {} RNG draws, the log has {}", expected, recorded)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AuditError {}
+
+/// A seeded game that logs its spawns as it is played.
+#[derive(Debug, Clone)]
+pub struct AuditedGame {
+    board: GameBoard,
+    rng: CountingRng<StdRng>,
+    rules: SpawnRules,
+    audit: SpawnAudit,
+}
+
+impl AuditedGame {
+    /// The game `seed` starts, spawning as [`super::replay::resume_game_with`]
+    /// does, so audited and unaudited games of a seed are the same game.
+    pub fn new(seed: u64, rules: &SpawnRules, variant: Variant) -> Self {
+        let mut rng = CountingRng::new(StdRng::seed_from_u64(seed));
+        // Variant openings spawn by the default rules, as in
+        // `GameBoard::new_with_variant`.
+        let opening = if variant == Variant::Classic { *rules } else { SpawnRules::default() };
+        let mut cells = [[0; 4]; 4];
+        let mut spawns = Vec::new();
+        for _ in 0..2 {
+            let draw = rng.draws();
+            if let Some(tile) = GameBoard::add_random_tile(&mut cells, &mut rng, &opening, variant) {
+                spawns.push(SpawnEntry::new(0, tile, draw));
+            }
+        }
+        let mut board = GameBoard::from_cells(cells);
+        if variant != Variant::Classic {
+            board.set_variant(variant);
+        }
+        let audit = SpawnAudit {
+            header: ReplayHeader::new(seed, rules, &board),
+            spawns,
+            draws: rng.draws(),
+        };
+        Self { board, rng, rules: *rules, audit }
+    }
+
+    /// Plays a full turn, logging its spawn.
+    pub fn play_move(&mut self, direction: Direction) -> Result<MoveResult, MoveError> {
+        let draw = self.rng.draws();
+        let result = self.board.play_move_with_rules(direction, &mut self.rng, &self.rules)?;
+        if let Some(tile) = result.spawned {
+            self.audit.spawns.push(SpawnEntry::new(self.board.get_move_count(), tile, draw));
+        }
+        self.audit.draws = self.rng.draws();
+        Ok(result)
+    }
+
+    pub fn board(&self) -> &GameBoard {
+        &self.board
+    }
+
+    pub fn audit(&self) -> &SpawnAudit {
+        &self.audit
+    }
+
+    pub fn into_audit(self) -> SpawnAudit {
+        self.audit
+    }
+}
+
+impl SpawnAudit {
+    /// The log of the game `seed` plays with `moves`.
+    pub fn record(seed: u64, rules: &SpawnRules, variant: Variant, moves: &[Direction]) -> Result<Self, AuditError> {
+        let mut game = AuditedGame::new(seed, rules, variant);
+        for (index, &direction) in moves.iter().enumerate() {
+            game.play_move(direction).map_err(|_| AuditError::IllegalMove { index, direction })?;
+        }
+        Ok(game.into_audit())
+    }
+
+    /// Replays `moves` from the header's seed and checks every spawn, and
+    /// the draw it came from, against this log.
+    pub fn verify(&self, moves: &[Direction]) -> Result<(), AuditError> {
+        let variant = self.header.variant().map_err(|_| AuditError::UnknownRules)?;
+        let expected = Self::record(self.header.seed, &self.header.spawn_rules(), variant, moves)?;
+        let longest = expected.spawns.len().max(self.spawns.len());
+        for index in 0..longest {
+            let (expected, recorded) = (expected.spawns.get(index).copied(), self.spawns.get(index).copied());
+            if expected != recorded {
+                return Err(AuditError::Spawn { index, expected, recorded });
+            }
+        }
+        if expected.draws != self.draws {
+            return Err(AuditError::Draws { expected: expected.draws, recorded: self.draws });
+        }
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("an audit serializes")
+    }
+
+    pub fn from_json(text: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::replay::resume_game_with;
+
+    #[test]
+    fn test_audited_games_match_the_seed_and_catch_tampering() {
+        let rules = SpawnRules::default();
+        let mut game = AuditedGame::new(31, &rules, Variant::Classic);
+        let mut moves = Vec::new();
+        for direction in Direction::all().into_iter().cycle().take(80) {
+            if game.play_move(direction).is_ok() {
+                moves.push(direction);
+            }
+        }
+        let resumed = resume_game_with(31, &moves, &rules, Variant::Classic).unwrap();
+        assert_eq!(game.board().get_board(), resumed.board.get_board());
+
+        let audit = SpawnAudit::from_json(&game.audit().to_json()).unwrap();
+        assert_eq!(&audit, game.audit());
+        assert_eq!(audit.spawns.len(), moves.len() + 2);
+        assert!(audit.spawns.windows(2).all(|pair| pair[0].draw < pair[1].draw));
+        assert_eq!(audit.verify(&moves), Ok(()));
+
+        let mut moved = audit.clone();
+        moved.spawns[10].row = (moved.spawns[10].row + 1) % 4;
+        assert!(matches!(moved.verify(&moves), Err(AuditError::Spawn { index: 10, .. })));
+        let mut drifted = audit.clone();
+        drifted.spawns[5].draw += 1;
+        assert!(matches!(drifted.verify(&moves), Err(AuditError::Spawn { index: 5, .. })));
+        assert!(matches!(audit.verify(&moves[..moves.len() - 1]), Err(AuditError::Spawn { .. })));
+    }
+}
@@ -0,0 +1,138 @@
+use rand::Rng;
+
+use super::board::GameBoard;
+use super::move_result::{MoveError, MoveResult, SpawnedTile};
+use super::moves::Direction;
+
+/// Merges creating at least this tile send garbage by default.
+pub const DEFAULT_GARBAGE_THRESHOLD: u32 = 64;
+
+/// Value of a garbage tile dropped on the opponent's board.
+pub const GARBAGE_TILE: u32 = 2;
+
+/// Two players on two classic boards, taking turns. Every merge that
+/// creates a tile of at least `garbage_threshold` drops a garbage 2 on a
+/// random empty cell of the opponent's board. A player with no legal move
+/// on their turn loses.
+#[derive(Debug, Clone)]
+pub struct BattleGame {
+    pub boards: [GameBoard; 2],
+    /// Index of the player whose turn it is.
+    pub to_move: usize,
+    pub garbage_threshold: u32,
+    pub scores: [u32; 2],
+}
+
+/// One played battle turn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BattleTurn {
+    pub player: usize,
+    pub result: MoveResult,
+    /// Garbage tiles that landed on the opponent's board.
+    pub garbage: Vec<SpawnedTile>,
+}
+
+impl BattleGame {
+    /// Starts a battle with both openings drawn from `rng`.
+    pub fn new_with_rng<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        Self::from_boards([GameBoard::new_with_rng(rng), GameBoard::new_with_rng(rng)])
+    }
+
+    /// A battle on the given boards, player 0 to move.
+    pub fn from_boards(boards: [GameBoard; 2]) -> Self {
+        Self {
+            boards,
+            to_move: 0,
+            garbage_threshold: DEFAULT_GARBAGE_THRESHOLD,
+            scores: [0; 2],
+        }
+    }
+
+    pub fn opponent(&self) -> usize {
+        1 - self.to_move
+    }
+
+    /// Garbage tiles a move's merges send at `threshold`.
+    pub fn garbage_sent(result: &MoveResult, threshold: u32) -> usize {
+        result.merged.iter().filter(|tile| tile.value >= threshold).count()
+    }
+
+    /// The loser, once the player to move has no legal move.
+    pub fn loser(&self) -> Option<usize> {
+        self.boards[self.to_move].is_game_over().then_some(self.to_move)
+    }
+
+    /// Plays `direction` for the player to move: the move and its spawn,
+    /// then garbage on the opponent's board, then the turn passes. Fails
+    /// (nothing changed) if the move does not slide anything.
+    pub fn play_move_with<R: Rng + ?Sized>(&mut self, direction: Direction, rng: &mut R) -> Result<BattleTurn, MoveError> {
+        let player = self.to_move;
+        let result = self.boards[player].play_move_with(direction, rng)?;
+        self.scores[player] += result.score_gained;
+        let opponent = &mut self.boards[1 - player];
+        let mut garbage = Vec::new();
+        for _ in 0..Self::garbage_sent(&result, self.garbage_threshold) {
+            let mut empty = opponent.empty_cells();
+            if empty.len() == 0 {
+                break;
+            }
+            let index = rng.gen_range(0..empty.len());
+            let Some((row, col)) = empty.nth(index) else { break };
+            opponent.place_tile(row, col, GARBAGE_TILE);
+            garbage.push(SpawnedTile { row, col, value: GARBAGE_TILE });
+        }
+        self.to_move = 1 - player;
+        Ok(BattleTurn { player, result, garbage })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    fn board(cells: [[u32; 4]; 4]) -> GameBoard {
+        let mut game = GameBoard::new();
+        game.set_board(cells);
+        game
+    }
+
+    #[test]
+    fn test_big_merges_send_garbage_and_pass_the_turn() {
+        let mut battle = BattleGame::from_boards([
+            board([
+                [32, 32, 64, 64],
+                [0, 0, 0, 0],
+                [0, 0, 0, 0],
+                [0, 0, 0, 0],
+            ]),
+            board([
+                [2, 4, 8, 16],
+                [0, 0, 0, 0],
+                [0, 0, 0, 0],
+                [0, 0, 0, 0],
+            ]),
+        ]);
+        let mut rng = StdRng::seed_from_u64(1);
+        let turn = battle.play_move_with(Direction::Left, &mut rng).unwrap();
+        assert_eq!(turn.player, 0);
+        assert_eq!(turn.garbage.len(), 2);
+        assert_eq!(battle.boards[1].count_empty_cells(), 10);
+        assert_eq!(battle.scores, [64 + 128, 0]);
+        assert_eq!(battle.to_move, 1);
+    }
+
+    #[test]
+    fn test_player_without_moves_loses() {
+        let stuck = board([
+            [2, 4, 2, 4],
+            [4, 2, 4, 2],
+            [2, 4, 2, 4],
+            [4, 2, 4, 2],
+        ]);
+        let battle = BattleGame::from_boards([stuck, GameBoard::new()]);
+        assert_eq!(battle.loser(), Some(0));
+    }
+}
@@ -1,23 +1,31 @@
-use rand::prelude::SliceRandom;
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
+use super::move_result::{MergedTile, MoveError, MoveResult, SpawnedTile};
 use super::moves::Direction;
-
-/// Zobrist keys: 16 cells × 16 value classes (0=empty, 1–15=log2 of tile).
-/// Deterministic seed so same position always hashes the same.
-fn zobrist_table() -> [[u64; 16]; 16] {
+use super::rules::{self, GameRules};
+use super::spawn::SpawnRules;
+use super::variant::Variant;
+
+/// Zobrist keys: 16 cells × 32 value classes (0=empty, else the tile's
+/// `GameRules::rank`, log2 for classic tiles). Deterministic seed so same
+/// position always hashes the same.
+fn zobrist_table() -> [[u64; 32]; 16] {
     let mut rng = StdRng::seed_from_u64(0x2048_2048);
-    let mut t = [[0u64; 16]; 16];
-    for i in 0..16 {
-        for j in 0..16 {
-            t[i][j] = rng.gen();
+    let mut t = [[0u64; 32]; 16];
+    // Classes 16 and up are drawn last, so keys of the lower classes (and
+    // hashes recorded in replays) are the same as with 16 classes.
+    for classes in [0..16, 16..32] {
+        for row in t.iter_mut() {
+            for key in row[classes.clone()].iter_mut() {
+                *key = rng.gen();
+            }
         }
     }
     t
 }
 
 lazy_static::lazy_static! {
-    static ref ZOBRIST: [[u64; 16]; 16] = zobrist_table();
+    static ref ZOBRIST: [[u64; 32]; 16] = zobrist_table();
 }
 
 #[derive(Debug, Clone)]
@@ -26,8 +34,46 @@ pub struct GameBoard {
     pub move_count: u32,
     pub empty_mask: u16,  // Bitmask of empty cells
     pub max_tile: u32,    // Cached max tile
+    pub legal_moves: u8,  // Cached Direction::bit of every move that changes the board
+    pub variant: Variant,
 }
 
+/// State saved by [`GameBoard::apply`] and [`GameBoard::place_tile`] so
+/// [`GameBoard::undo`] can restore the position without recomputing masks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UndoInfo {
+    board: [[u32; 4]; 4],
+    move_count: u32,
+    empty_mask: u16,
+    max_tile: u32,
+    legal_moves: u8,
+}
+
+/// (row, col) of each set bit of a cell mask (bit `row * 4 + col`), in
+/// row-major order. Used for empty cells without allocating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmptyCells(pub u16);
+
+impl Iterator for EmptyCells {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        if self.0 == 0 {
+            return None;
+        }
+        let index = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Some((index / 4, index % 4))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for EmptyCells {}
+
 impl Default for GameBoard {
     fn default() -> Self {
         Self::new()
@@ -36,17 +82,45 @@ impl Default for GameBoard {
 
 impl GameBoard {
     pub fn new() -> Self {
+        Self::new_with_rng(&mut rand::thread_rng())
+    }
+
+    /// Starts a game whose two opening tiles come from `rng`. Pass a seeded
+    /// RNG (and keep using it for spawns) to get a reproducible game.
+    pub fn new_with_rng<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        Self::new_with_rules(rng, &SpawnRules::default())
+    }
+
+    /// Same as [`GameBoard::new_with_rng`] with non-standard spawn rules.
+    pub fn new_with_rules<R: Rng + ?Sized>(rng: &mut R, rules: &SpawnRules) -> Self {
+        let mut board = [[0; 4]; 4];
+        Self::add_random_tile(&mut board, rng, rules, Variant::Classic);
+        Self::add_random_tile(&mut board, rng, rules, Variant::Classic);
+        Self::from_cells(board)
+    }
+
+    /// Starts a game of `variant` whose opening tiles come from `rng`.
+    pub fn new_with_variant<R: Rng + ?Sized>(rng: &mut R, variant: Variant) -> Self {
         let mut board = [[0; 4]; 4];
-        Self::add_random_tile(&mut board);
-        Self::add_random_tile(&mut board);
-        let empty_mask = Self::calculate_empty_mask(&board);
-        let max_tile = Self::calculate_max_tile(&board);
-        GameBoard {
+        Self::add_random_tile(&mut board, rng, &SpawnRules::default(), variant);
+        Self::add_random_tile(&mut board, rng, &SpawnRules::default(), variant);
+        let mut game = Self::from_cells(board);
+        game.set_variant(variant);
+        game
+    }
+
+    /// A fresh game (move 0) on `board`, with every cached field computed.
+    pub(crate) fn from_cells(board: [[u32; 4]; 4]) -> Self {
+        let mut game = GameBoard {
             board,
             move_count: 0,
-            empty_mask,
-            max_tile,
-        }
+            empty_mask: 0,
+            max_tile: 0,
+            legal_moves: 0,
+            variant: Variant::Classic,
+        };
+        game.refresh_caches();
+        game
     }
 
     pub fn get_board(&self) -> [[u32; 4]; 4] {
@@ -55,8 +129,30 @@ impl GameBoard {
 
     pub fn set_board(&mut self, board: [[u32; 4]; 4]) {
         self.board = board;
-        self.empty_mask = Self::calculate_empty_mask(&board);
-        self.max_tile = Self::calculate_max_tile(&board);
+        self.refresh_caches();
+    }
+
+    /// Switches the position to `variant`'s rules.
+    pub fn set_variant(&mut self, variant: Variant) {
+        self.variant = variant;
+        self.refresh_caches();
+    }
+
+    /// The rules of the position's variant.
+    pub fn rules(&self) -> &'static dyn GameRules {
+        self.variant.rules()
+    }
+
+    /// Whether the board holds the variant's winning tile.
+    pub fn has_won(&self) -> bool {
+        self.max_tile >= self.rules().win_tile()
+    }
+
+    /// Recomputes the empty mask, max tile and legal moves from `board`.
+    fn refresh_caches(&mut self) {
+        self.empty_mask = Self::calculate_empty_mask(&self.board);
+        self.max_tile = Self::calculate_max_tile(&self.board);
+        self.legal_moves = Self::calculate_legal_moves(&self.board, self.max_tile, self.variant);
     }
 
     pub fn get_move_count(&self) -> u32 {
@@ -79,89 +175,181 @@ impl GameBoard {
         board.iter().flatten().max().copied().unwrap_or(0)
     }
 
-    fn add_random_tile(board: &mut [[u32; 4]; 4]) {
-        let mut empty_cells = Vec::new();
-        for (i, row) in board.iter().enumerate() {
-            for (j, &cell) in row.iter().enumerate() {
-                if cell == 0 {
-                    empty_cells.push((i, j));
-                }
+    /// Spawns on a random empty cell: by `rules` in a classic or joker game,
+    /// by the variant's own spawns otherwise.
+    pub(crate) fn add_random_tile<R: Rng + ?Sized>(board: &mut [[u32; 4]; 4], rng: &mut R, rules: &SpawnRules, variant: Variant) -> Option<SpawnedTile> {
+        let mut empty_cells = EmptyCells(Self::calculate_empty_mask(board));
+        if empty_cells.len() == 0 {
+            return None;
+        }
+        // Same draw as `SliceRandom::choose`, so seeded games keep their spawns.
+        let (row, col) = empty_cells.nth(rng.gen_range(0..empty_cells.len() as u32) as usize)?;
+        let value = match variant {
+            Variant::Classic => rules.sample_value(rng),
+            Variant::Joker => rules.sample_joker_value(rng),
+            _ => rules::sample_spawn(variant.rules(), rng),
+        };
+        board[row][col] = value;
+        Some(SpawnedTile { row, col, value })
+    }
+
+    /// Slides tiles without spawning. The cached empty mask, max tile and
+    /// legal moves are kept valid, so callers never need to recompute them.
+    pub fn move_tiles(&mut self, direction: Direction) -> bool {
+        match self.slide(direction, |_, _, _| {}) {
+            Some(new_board) => {
+                self.commit_move(new_board);
+                true
             }
+            None => false,
         }
-        if let Some((i, j)) = empty_cells.choose(&mut rand::thread_rng()) {
-            board[*i][*j] = if rand::random::<f32>() < 0.9 { 2 } else { 4 };
+    }
+
+    /// The position after sliding `direction` (no spawn), with every cached
+    /// field consistent, or `None` if the move changes nothing.
+    pub fn child(&self, direction: Direction) -> Option<GameBoard> {
+        let new_board = self.slide(direction, |_, _, _| {})?;
+        let mut child = self.clone();
+        child.commit_move(new_board);
+        Some(child)
+    }
+
+    /// Slides tiles in place, without spawning, and returns what `undo`
+    /// needs to take the move back. `None` (board untouched) if nothing moved.
+    pub fn apply(&mut self, direction: Direction) -> Option<UndoInfo> {
+        let new_board = self.slide(direction, |_, _, _| {})?;
+        let undo = self.undo_info();
+        self.commit_move(new_board);
+        Some(undo)
+    }
+
+    /// Puts `value` on the empty cell at (`row`, `col`), as a chance node
+    /// does, updating the cached mask and max tile incrementally.
+    pub fn place_tile(&mut self, row: usize, col: usize, value: u32) -> UndoInfo {
+        debug_assert_eq!(self.board[row][col], 0, "place_tile on an occupied cell");
+        let undo = self.undo_info();
+        self.board[row][col] = value;
+        self.empty_mask &= !(1 << (row * 4 + col));
+        self.max_tile = self.max_tile.max(value);
+        self.legal_moves = Self::calculate_legal_moves(&self.board, self.max_tile, self.variant);
+        undo
+    }
+
+    /// Restores the position saved by the matching `apply` or `place_tile`.
+    pub fn undo(&mut self, undo: UndoInfo) {
+        self.board = undo.board;
+        self.move_count = undo.move_count;
+        self.empty_mask = undo.empty_mask;
+        self.max_tile = undo.max_tile;
+        self.legal_moves = undo.legal_moves;
+    }
+
+    fn undo_info(&self) -> UndoInfo {
+        UndoInfo {
+            board: self.board,
+            move_count: self.move_count,
+            empty_mask: self.empty_mask,
+            max_tile: self.max_tile,
+            legal_moves: self.legal_moves,
         }
     }
 
-    pub fn move_tiles(&mut self, direction: Direction) -> bool {
-        let mut moved = false;
-        let mut new_board = self.board;
+    /// Plays a full turn: moves, then spawns a random tile. Fails (leaving
+    /// the board untouched) if the move does not change anything.
+    pub fn play_move(&mut self, direction: Direction) -> Result<MoveResult, MoveError> {
+        self.play_move_with(direction, &mut rand::thread_rng())
+    }
+
+    /// Same as [`GameBoard::play_move`], drawing the spawn from `rng`.
+    pub fn play_move_with<R: Rng + ?Sized>(&mut self, direction: Direction, rng: &mut R) -> Result<MoveResult, MoveError> {
+        self.play_move_with_rules(direction, rng, &SpawnRules::default())
+    }
+
+    /// Same as [`GameBoard::play_move_with`], spawning by `rules`.
+    pub fn play_move_with_rules<R: Rng + ?Sized>(&mut self, direction: Direction, rng: &mut R, rules: &SpawnRules) -> Result<MoveResult, MoveError> {
+        let mut result = self.play_move_without_spawn(direction)?;
+        result.spawned = Self::add_random_tile(&mut self.board, rng, rules, self.variant);
+        self.refresh_caches();
+        Ok(result)
+    }
+
+    /// A puzzle-mode turn: the move alone, with no tile spawned after it.
+    /// Fails (leaving the board untouched) if nothing moves.
+    pub fn play_move_without_spawn(&mut self, direction: Direction) -> Result<MoveResult, MoveError> {
+        let mut merged = Vec::new();
+        let Some(new_board) = self.slide(direction, |row, col, value| {
+            merged.push(MergedTile { row, col, value });
+        }) else {
+            return Err(if self.is_game_over() { MoveError::GameAlreadyOver } else { MoveError::NoTilesMoved });
+        };
+        self.commit_move(new_board);
+        Ok(MoveResult {
+            direction,
+            score_gained: merged.iter().map(|m| self.rules().merge_score(m.value)).sum(),
+            merged,
+            spawned: None,
+        })
+    }
+
+    fn commit_move(&mut self, new_board: [[u32; 4]; 4]) {
+        self.board = new_board;
+        self.move_count += 1;
+        self.refresh_caches();
+    }
+
+    /// Board coordinates of line `index`, ordered from the edge tiles slide
+    /// towards, so every direction can reuse the same left-merge logic.
+    fn line_cells(direction: Direction, index: usize) -> [(usize, usize); 4] {
         match direction {
-            Direction::Left => {
-                for (row, board_row) in self.board.iter().enumerate() {
-                    let (new_row, row_moved) = Self::merge_row(board_row);
-                    new_board[row] = new_row;
-                    moved |= row_moved;
-                }
-            }
-            Direction::Right => {
-                for (row, board_row) in self.board.iter().enumerate() {
-                    let mut reversed_row = *board_row;
-                    reversed_row.reverse();
-                    let (merged_row, row_moved) = Self::merge_row(&reversed_row);
-                    new_board[row] = {
-                        let mut result = merged_row;
-                        result.reverse();
-                        result
-                    };
-                    moved |= row_moved;
-                }
-            }
-            Direction::Up => {
-                for col in 0..4 {
-                    let column: [u32; 4] = [
-                        self.board[0][col],
-                        self.board[1][col],
-                        self.board[2][col],
-                        self.board[3][col],
-                    ];
-                    let (merged_col, col_moved) = Self::merge_row(&column);
-                    for (row, &value) in merged_col.iter().enumerate() {
-                        new_board[row][col] = value;
-                    }
-                    moved |= col_moved;
-                }
-            }
-            Direction::Down => {
-                for col in 0..4 {
-                    let mut column: [u32; 4] = [
-                        self.board[0][col],
-                        self.board[1][col],
-                        self.board[2][col],
-                        self.board[3][col],
-                    ];
-                    column.reverse();
-                    let (merged_col, col_moved) = Self::merge_row(&column);
-                    let mut result_col = merged_col;
-                    result_col.reverse();
-                    for (row, &value) in result_col.iter().enumerate() {
-                        new_board[row][col] = value;
-                    }
-                    moved |= col_moved;
+            Direction::Left => [(index, 0), (index, 1), (index, 2), (index, 3)],
+            Direction::Right => [(index, 3), (index, 2), (index, 1), (index, 0)],
+            Direction::Up => [(0, index), (1, index), (2, index), (3, index)],
+            Direction::Down => [(3, index), (2, index), (1, index), (0, index)],
+        }
+    }
+
+    /// Computes the board after sliding, calling `on_merge(row, col, value)`
+    /// for every merged tile. Returns `None` if nothing moved.
+    fn slide<F>(&self, direction: Direction, mut on_merge: F) -> Option<[[u32; 4]; 4]>
+    where
+        F: FnMut(usize, usize, u32),
+    {
+        if !self.can_move(direction) {
+            return None;
+        }
+        let mut new_board = self.board;
+        let mut moved = false;
+        let rules = self.rules();
+        for index in 0..4 {
+            let cells = Self::line_cells(direction, index);
+            let line = cells.map(|(row, col)| self.board[row][col]);
+            let (merged_line, line_moved, merge_mask) = rules.slide_line(&line);
+            for (k, &(row, col)) in cells.iter().enumerate() {
+                new_board[row][col] = merged_line[k];
+                if merge_mask & (1 << k) != 0 {
+                    on_merge(row, col, merged_line[k]);
                 }
             }
+            moved |= line_moved;
         }
         if moved {
-            self.board = new_board;
-            self.move_count += 1;
-            self.empty_mask = Self::calculate_empty_mask(&self.board);
-            self.max_tile = Self::calculate_max_tile(&self.board);
+            Some(new_board)
+        } else {
+            None
         }
-        moved
     }
 
+    #[cfg(test)]
     fn merge_row(row: &[u32; 4]) -> ([u32; 4], bool) {
+        let (new_row, moved, _) = Self::merge_line(row);
+        (new_row, moved)
+    }
+
+    /// Left-merges one line. The returned mask has bit `k` set when output
+    /// slot `k` holds a freshly merged tile.
+    pub(super) fn merge_line(row: &[u32; 4]) -> ([u32; 4], bool, u8) {
         let mut new_row = [0; 4];
+        let mut merge_mask = 0u8;
         let mut write_pos = 0;
         let mut i = 0;
         let mut moved = false;
@@ -172,6 +360,7 @@ impl GameBoard {
             }
             if i + 1 < 4 && row[i] == row[i + 1] && row[i] != 0 {
                 new_row[write_pos] = row[i] * 2;
+                merge_mask |= 1 << write_pos;
                 write_pos += 1;
                 i += 2;
                 moved = true;
@@ -184,28 +373,25 @@ impl GameBoard {
                 }
             }
         }
-        (new_row, moved)
+        (new_row, moved, merge_mask)
     }
 
     pub fn is_game_over(&self) -> bool {
-        if self.count_empty_cells() > 0 {
-            return false;
-        }
-        for i in 0..4 {
-            for j in 0..4 {
-                let value = self.board[i][j];
-                if (i < 3 && self.board[i + 1][j] == value)
-                    || (j < 3 && self.board[i][j + 1] == value)
-                {
-                    return false;
-                }
-            }
-        }
-        true
+        self.legal_moves == 0
+    }
+
+    /// Whether sliding `direction` changes the board, from the cached mask.
+    pub fn can_move(&self, direction: Direction) -> bool {
+        self.legal_moves & direction.bit() != 0
     }
 
     pub fn count_empty_cells(&self) -> usize {
-        self.board.iter().flatten().filter(|&&x| x == 0).count()
+        self.empty_mask.count_ones() as usize
+    }
+
+    /// Every empty cell as (row, col), read from the cached mask.
+    pub fn empty_cells(&self) -> EmptyCells {
+        EmptyCells(self.empty_mask)
     }
 
     pub fn get_max_tile(&self) -> u32 {
@@ -216,38 +402,34 @@ impl GameBoard {
         self.board.iter().flatten().sum()
     }
 
-    pub fn add_random_tile_self(&mut self) {
-        Self::add_random_tile(&mut self.board);
-        self.empty_mask = Self::calculate_empty_mask(&self.board);
-        self.max_tile = Self::calculate_max_tile(&self.board);
+    pub fn add_random_tile_self(&mut self) -> Option<SpawnedTile> {
+        self.add_random_tile_with(&mut rand::thread_rng())
     }
 
-    /// List of (row, col) for every empty cell. Used by AI chance nodes.
-    pub(crate) fn get_empty_cells(&self) -> Vec<(usize, usize)> {
-        let mut cells = Vec::new();
-        for i in 0..4 {
-            for j in 0..4 {
-                if (self.empty_mask & (1 << (i * 4 + j))) != 0 {
-                    cells.push((i, j));
-                }
-            }
-        }
-        cells
+    pub fn add_random_tile_with<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Option<SpawnedTile> {
+        let spawned = Self::add_random_tile(&mut self.board, rng, &SpawnRules::default(), self.variant);
+        self.refresh_caches();
+        spawned
     }
 
     /// 64-bit Zobrist hash for transposition table. Low collision rate so
     /// we keep more useful entries and get better cache hit rate.
-    pub(crate) fn board_hash(&self) -> u64 {
+    pub fn board_hash(&self) -> u64 {
         let mut hash = 0u64;
         for i in 0..4 {
             for j in 0..4 {
                 let pos = i * 4 + j;
                 let value = self.board[i][j];
-                let value_index = if value == 0 { 0 } else { value.trailing_zeros() as usize };
-                hash ^= ZOBRIST[pos][value_index];
+                let rank = self.rules().rank(value);
+                hash ^= ZOBRIST[pos][rank];
+                if rank == 31 {
+                    // Tiles past the last class (only in custom rules) share
+                    // it, so mix the value in to keep them apart.
+                    hash ^= (value as u64).wrapping_mul(ZOBRIST[pos][0] | 1);
+                }
             }
         }
-        hash
+        hash ^ self.rules().hash_key()
     }
 }
 
@@ -314,6 +496,21 @@ mod tests {
         assert_eq!(mask, expected);
     }
 
+    #[test]
+    fn test_empty_cells_follow_mask_in_row_major_order() {
+        let mut board = GameBoard::new();
+        board.set_board([
+            [2, 0, 4, 0],
+            [8, 8, 8, 8],
+            [8, 8, 8, 8],
+            [0, 8, 8, 8]
+        ]);
+        let cells = board.empty_cells();
+        assert_eq!(cells.len(), 3);
+        assert_eq!(cells.collect::<Vec<_>>(), vec![(0, 1), (0, 3), (3, 0)]);
+        assert_eq!(board.count_empty_cells(), 3);
+    }
+
     #[test]
     fn test_calculate_max_tile() {
         let board = [
@@ -416,4 +613,158 @@ mod tests {
         ];
         assert_eq!(board.board, expected);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_play_move_reports_merges_and_spawn() {
+        let mut board = GameBoard::new();
+        board.set_board([
+            [2, 2, 4, 4],
+            [0, 0, 0, 0],
+            [0, 8, 8, 0],
+            [0, 0, 0, 16]
+        ]);
+
+        let result = board.play_move(Direction::Left).expect("move is legal");
+        assert_eq!(result.score_gained, 4 + 8 + 16);
+        assert_eq!(
+            result.merged,
+            vec![
+                MergedTile { row: 0, col: 0, value: 4 },
+                MergedTile { row: 0, col: 1, value: 8 },
+                MergedTile { row: 2, col: 0, value: 16 },
+            ]
+        );
+
+        let spawned = result.spawned.expect("board had empty cells");
+        assert!(spawned.value == 2 || spawned.value == 4);
+        assert_eq!(board.board[spawned.row][spawned.col], spawned.value);
+        assert_eq!(board.count_empty_cells(), 16 - 5);
+    }
+
+    #[test]
+    fn test_play_move_illegal_leaves_board_unchanged() {
+        let mut board = GameBoard::new();
+        let layout = [
+            [2, 4, 8, 16],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0]
+        ];
+        board.set_board(layout);
+
+        assert_eq!(board.play_move(Direction::Up), Err(MoveError::NoTilesMoved));
+        assert_eq!(board.board, layout);
+        assert_eq!(board.get_move_count(), 0);
+
+        let full = [
+            [2, 4, 2, 4],
+            [4, 2, 4, 2],
+            [2, 4, 2, 4],
+            [4, 2, 4, 2]
+        ];
+        board.set_board(full);
+        assert_eq!(board.play_move_without_spawn(Direction::Left), Err(MoveError::GameAlreadyOver));
+        assert_eq!(board.board, full);
+    }
+
+    #[test]
+    fn test_directions_parse_from_names_and_letters() {
+        assert_eq!("left".parse(), Ok(Direction::Left));
+        assert_eq!(" Up ".parse(), Ok(Direction::Up));
+        assert_eq!("r".parse(), Ok(Direction::Right));
+        assert_eq!("D".parse(), Ok(Direction::Down));
+        assert_eq!("sideways".parse::<Direction>(), Err(MoveError::InvalidDirection("sideways".to_string())));
+        assert!("".parse::<Direction>().is_err());
+    }
+
+    #[test]
+    fn test_spawn_rules_control_spawned_value() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let rules = SpawnRules { four_probability: 1.0, ..SpawnRules::default() };
+        let mut board = GameBoard::new_with_rules(&mut rng, &rules);
+        assert!(board.board.iter().flatten().all(|&v| v == 0 || v == 4));
+
+        board.set_board([
+            [2, 0, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0]
+        ]);
+        let result = board.play_move_with_rules(Direction::Right, &mut rng, &rules).unwrap();
+        assert_eq!(result.spawned.map(|s| s.value), Some(4));
+    }
+
+    #[test]
+    fn test_apply_and_place_tile_undo_exactly() {
+        let mut board = GameBoard::new();
+        board.set_board([
+            [2, 2, 0, 4],
+            [0, 0, 0, 0],
+            [0, 8, 0, 0],
+            [0, 0, 0, 0]
+        ]);
+        let before = board.clone();
+
+        let moved = board.apply(Direction::Left).unwrap();
+        assert_eq!(board.board[0], [4, 4, 0, 0]);
+        assert_eq!(board.empty_mask, GameBoard::calculate_empty_mask(&board.board));
+        let placed = board.place_tile(3, 3, 16);
+        assert_eq!(board.empty_mask, GameBoard::calculate_empty_mask(&board.board));
+        assert_eq!(board.get_max_tile(), 16);
+
+        board.undo(placed);
+        board.undo(moved);
+        assert_eq!(board.board, before.board);
+        assert_eq!(board.empty_mask, before.empty_mask);
+        assert_eq!(board.get_max_tile(), before.get_max_tile());
+        assert_eq!(board.get_move_count(), before.get_move_count());
+        assert!(board.apply(Direction::Up).is_some());
+    }
+
+    #[test]
+    fn test_child_keeps_cached_fields_consistent() {
+        let mut board = GameBoard::new();
+        board.set_board([
+            [0, 0, 0, 2],
+            [0, 0, 0, 2],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0]
+        ]);
+        let child = board.child(Direction::Up).unwrap();
+        assert_eq!(child.board[0][3], 4);
+        assert_eq!(child.empty_mask, GameBoard::calculate_empty_mask(&child.board));
+        assert_eq!(child.get_max_tile(), 4);
+        assert_eq!(child.get_move_count(), board.get_move_count() + 1);
+        assert!(board.child(Direction::Right).is_none());
+        assert_eq!(board.board[0][3], 2);
+    }
+
+    #[test]
+    fn test_legal_moves_follow_moves_spawns_and_undo() {
+        let mut board = GameBoard::new();
+        board.set_board([
+            [2, 4, 2, 4],
+            [4, 2, 4, 2],
+            [2, 4, 2, 4],
+            [4, 2, 4, 0]
+        ]);
+        assert_eq!(board.legal_moves, Direction::Down.bit() | Direction::Right.bit());
+        let undo = board.place_tile(3, 3, 2);
+        assert!(board.is_game_over());
+        board.undo(undo);
+        assert!(board.can_move(Direction::Right) && !board.can_move(Direction::Left));
+
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut game = GameBoard::new_with_rng(&mut rng);
+        while let Some(&direction) = Direction::all().iter().find(|&&d| game.can_move(d)) {
+            game.play_move_with(direction, &mut rng).unwrap();
+            // Sliding with the mask forced open shows which moves really change the board.
+            let sliding = Direction::all().into_iter().filter(|&d| {
+                let mut copy = game.clone();
+                copy.legal_moves = 0xF;
+                copy.move_tiles(d)
+            });
+            assert_eq!(sliding.fold(0, |mask, d| mask | d.bit()), game.legal_moves);
+        }
+    }
+}
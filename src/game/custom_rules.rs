@@ -0,0 +1,236 @@
+use std::fmt;
+
+use super::rules::GameRules;
+
+/// Distinct tiles that get their own Zobrist class; larger ones share the
+/// last class and are told apart by value in [`crate::GameBoard::board_hash`].
+const RANKED_TILES: usize = 31;
+
+/// Largest value checked exactly for [`MergeRule::Sum`] tiles; above it a
+/// value counts as a tile when the spawns' greatest common divisor divides it.
+const SUM_CHECK_LIMIT: u32 = 1 << 16;
+
+/// How two tiles merge in a [`CustomRules`] game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeRule {
+    /// Equal tiles merge into their value times the factor (2 is 2048).
+    Multiply(u32),
+    /// Any two tiles merge into their sum.
+    Sum,
+}
+
+/// Why a custom rule set was rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidRules {
+    NoSpawns,
+    /// A spawn of 0, which is the empty cell.
+    ZeroTile,
+    /// A spawn with weight 0.
+    ZeroWeight(u32),
+    DuplicateSpawn(u32),
+    /// A [`MergeRule::Multiply`] factor below 2.
+    MergeFactor(u32),
+    /// The win tile cannot be built from the spawns.
+    WinTileUnreachable(u32),
+    /// A variant with this name is already registered.
+    NameTaken(String),
+    /// No room for another custom variant.
+    RegistryFull,
+}
+
+impl fmt::Display for InvalidRules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidRules::NoSpawns => write!(f, "at least one tile must spawn"),
+            InvalidRules::ZeroTile => write!(f, "0 is the empty cell, not a spawnable tile"),
+            InvalidRules::ZeroWeight(value) => write!(f, "spawn {} has weight 0", value),
+            InvalidRules::DuplicateSpawn(value) => write!(f, "{} is listed as a spawn twice", value),
+            InvalidRules::MergeFactor(factor) => write!(f, "merge factor {} is below 2", factor),
+            InvalidRules::WinTileUnreachable(tile) => write!(f, "win tile {} cannot be built from the spawns", tile),
+            InvalidRules::NameTaken(name) => write!(f, "a variant named '{}' already exists", name),
+            InvalidRules::RegistryFull => write!(f, "too many custom variants"),
+        }
+    }
+}
+
+impl std::error::Error for InvalidRules {}
+
+/// A user-defined rule set: which values spawn (with relative weights),
+/// how tiles merge and which tile wins, checked when it is built. Lines
+/// slide as in 2048. Register it with [`crate::Variant::register`] to play
+/// it on a [`crate::GameBoard`].
+#[derive(Debug, Clone)]
+pub struct CustomRules {
+    name: String,
+    spawns: Vec<(u32, u32)>,
+    merge: MergeRule,
+    win_tile: u32,
+    /// The smallest buildable tiles, ascending; a tile's rank is its
+    /// position here plus one.
+    ranked: Vec<u32>,
+    hash_key: u64,
+}
+
+impl CustomRules {
+    pub fn new(name: &str, spawns: &[(u32, u32)], merge: MergeRule, win_tile: u32) -> Result<Self, InvalidRules> {
+        if spawns.is_empty() {
+            return Err(InvalidRules::NoSpawns);
+        }
+        for (i, &(value, weight)) in spawns.iter().enumerate() {
+            if value == 0 {
+                return Err(InvalidRules::ZeroTile);
+            }
+            if weight == 0 {
+                return Err(InvalidRules::ZeroWeight(value));
+            }
+            if spawns[..i].iter().any(|&(other, _)| other == value) {
+                return Err(InvalidRules::DuplicateSpawn(value));
+            }
+        }
+        if let MergeRule::Multiply(factor) = merge {
+            if factor < 2 {
+                return Err(InvalidRules::MergeFactor(factor));
+            }
+        }
+        if !buildable(spawns, merge, win_tile) {
+            return Err(InvalidRules::WinTileUnreachable(win_tile));
+        }
+        Ok(CustomRules {
+            name: name.to_string(),
+            spawns: spawns.to_vec(),
+            merge,
+            win_tile,
+            ranked: smallest_tiles(spawns, merge),
+            hash_key: name.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100_0000_01b3)),
+        })
+    }
+}
+
+/// The [`RANKED_TILES`] smallest tiles `spawns` can build.
+fn smallest_tiles(spawns: &[(u32, u32)], merge: MergeRule) -> Vec<u32> {
+    let mut tiles: Vec<u32> = spawns.iter().map(|&(value, _)| value).collect();
+    tiles.sort_unstable();
+    tiles.truncate(RANKED_TILES);
+    loop {
+        let built: Vec<u32> = match merge {
+            MergeRule::Multiply(factor) => tiles.iter().filter_map(|&tile| tile.checked_mul(factor)).collect(),
+            MergeRule::Sum => tiles.iter().flat_map(|&a| tiles.iter().filter_map(move |&b| a.checked_add(b))).collect(),
+        };
+        let before = tiles.clone();
+        tiles.extend(built);
+        tiles.sort_unstable();
+        tiles.dedup();
+        tiles.truncate(RANKED_TILES);
+        if tiles == before {
+            return tiles;
+        }
+    }
+}
+
+/// Whether `spawns` can build `value` by `merge`.
+fn buildable(spawns: &[(u32, u32)], merge: MergeRule, value: u32) -> bool {
+    if value == 0 {
+        return false;
+    }
+    match merge {
+        MergeRule::Multiply(factor) => spawns.iter().any(|&(spawn, _)| {
+            let mut tile = spawn;
+            while tile < value {
+                let Some(next) = tile.checked_mul(factor) else { return false };
+                tile = next;
+            }
+            tile == value
+        }),
+        MergeRule::Sum if value > SUM_CHECK_LIMIT => {
+            value.is_multiple_of(spawns.iter().fold(0, |d, &(spawn, _)| gcd(d, spawn)))
+        }
+        MergeRule::Sum => {
+            // Which totals up to `value` some spawns add up to.
+            let mut sums = vec![false; value as usize + 1];
+            sums[0] = true;
+            for total in 1..=value as usize {
+                sums[total] = spawns.iter().any(|&(spawn, _)| spawn as usize <= total && sums[total - spawn as usize]);
+            }
+            sums[value as usize]
+        }
+    }
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+impl GameRules for CustomRules {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn is_tile(&self, value: u32) -> bool {
+        buildable(&self.spawns, self.merge, value)
+    }
+
+    /// Position among the smallest buildable tiles; larger tiles all share
+    /// the last rank.
+    fn rank(&self, value: u32) -> usize {
+        if value == 0 {
+            return 0;
+        }
+        match self.ranked.binary_search(&value) {
+            Ok(index) => index + 1,
+            Err(index) => (index + 1).min(RANKED_TILES),
+        }
+    }
+
+    fn merge(&self, first: u32, second: u32) -> Option<u32> {
+        if first == 0 || second == 0 {
+            return None;
+        }
+        match self.merge {
+            MergeRule::Multiply(factor) if first == second => first.checked_mul(factor),
+            MergeRule::Multiply(_) => None,
+            MergeRule::Sum => first.checked_add(second),
+        }
+    }
+
+    fn spawns(&self) -> &[(u32, u32)] {
+        &self.spawns
+    }
+
+    fn win_tile(&self) -> u32 {
+        self.win_tile
+    }
+
+    fn hash_key(&self) -> u64 {
+        self.hash_key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_rules_are_validated() {
+        let rules = |spawns: &[(u32, u32)], merge, win| CustomRules::new("test", spawns, merge, win).map(|_| ());
+        assert_eq!(rules(&[], MergeRule::Sum, 8), Err(InvalidRules::NoSpawns));
+        assert_eq!(rules(&[(0, 1)], MergeRule::Sum, 8), Err(InvalidRules::ZeroTile));
+        assert_eq!(rules(&[(2, 0)], MergeRule::Sum, 8), Err(InvalidRules::ZeroWeight(2)));
+        assert_eq!(rules(&[(2, 1), (2, 3)], MergeRule::Sum, 8), Err(InvalidRules::DuplicateSpawn(2)));
+        assert_eq!(rules(&[(2, 1)], MergeRule::Multiply(1), 8), Err(InvalidRules::MergeFactor(1)));
+        assert_eq!(rules(&[(2, 1)], MergeRule::Multiply(3), 8), Err(InvalidRules::WinTileUnreachable(8)));
+        assert_eq!(rules(&[(2, 1), (3, 1)], MergeRule::Multiply(3), 54), Ok(()));
+    }
+
+    #[test]
+    fn test_custom_merges_and_ranks() {
+        let triples = CustomRules::new("triples", &[(2, 1), (4, 1), (8, 1)], MergeRule::Multiply(3), 216).unwrap();
+        assert_eq!(triples.slide_line(&[2, 2, 4, 8]), ([6, 4, 8, 0], true, 0b1));
+        assert_eq!([2, 4, 6, 8, 12].map(|v| triples.rank(v)), [1, 2, 3, 4, 5]);
+        assert!(!triples.is_tile(10));
+
+        let sums = CustomRules::new("sums", &[(2, 3), (3, 1)], MergeRule::Sum, 100).unwrap();
+        assert_eq!(sums.slide_line(&[2, 3, 5, 0]), ([5, 5, 0, 0], true, 0b1));
+        assert!(sums.is_tile(5) && !sums.is_tile(1));
+        assert_eq!(sums.rank(1_000_000), RANKED_TILES);
+    }
+}
@@ -0,0 +1,138 @@
+//! Differential tests: `GameBoard` against a plain reference slide.
+//!
+//! A classic move goes through the packed merge table (or the array
+//! `merge_line` past the packed range), and legality comes from a separate
+//! per-line table kept in the board's caches. The reference here slides the
+//! raw array line by line with `merge_line` and recomputes everything from
+//! scratch, so a divergence in any of those paths shows up as a mismatch
+//! over random games and boards.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use super::board::GameBoard;
+use super::moves::Direction;
+
+type Cells = [[u32; 4]; 4];
+
+/// The board after sliding `direction` and the points its merges score, or
+/// `None` if nothing changes.
+fn reference_slide(board: &Cells, direction: Direction) -> Option<(Cells, u32)> {
+    let mut out = [[0; 4]; 4];
+    let mut score = 0;
+    for i in 0..4 {
+        // Line `i`, read from the edge the tiles slide towards.
+        let at = |k: usize| match direction {
+            Direction::Left => (i, k),
+            Direction::Right => (i, 3 - k),
+            Direction::Up => (k, i),
+            Direction::Down => (3 - k, i),
+        };
+        let line = std::array::from_fn(|k| {
+            let (row, col) = at(k);
+            board[row][col]
+        });
+        let (merged, _, merge_mask) = GameBoard::merge_line(&line);
+        for (k, &value) in merged.iter().enumerate() {
+            let (row, col) = at(k);
+            out[row][col] = value;
+            if merge_mask & (1 << k) != 0 {
+                score += value;
+            }
+        }
+    }
+    (out != *board).then_some((out, score))
+}
+
+/// Checks every cached and derived fact about `game` against the reference.
+fn assert_matches_reference(game: &GameBoard) {
+    let cells = game.get_board();
+    let empty = cells.iter().flatten().filter(|&&value| value == 0).count();
+    let max = cells.iter().flatten().copied().max().unwrap();
+    assert_eq!(game.count_empty_cells(), empty, "{:?}", cells);
+    assert_eq!(game.empty_cells().count(), empty, "{:?}", cells);
+    assert_eq!(game.get_max_tile(), max, "{:?}", cells);
+
+    let mut any_move = false;
+    for direction in Direction::all() {
+        let expected = reference_slide(&cells, direction);
+        assert_eq!(game.can_move(direction), expected.is_some(), "{:?} {:?}", direction, cells);
+        let mut played = game.clone();
+        let result = played.play_move_without_spawn(direction);
+        assert_eq!(
+            result.ok().map(|result| (played.get_board(), result.score_gained)),
+            expected,
+            "{:?} {:?}",
+            direction,
+            cells
+        );
+        any_move |= expected.is_some();
+    }
+    assert_eq!(game.is_game_over(), !any_move, "{:?}", cells);
+}
+
+#[test]
+fn test_random_games_match_the_reference() {
+    for seed in 0..40 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut game = GameBoard::new_with_rng(&mut rng);
+        while !game.is_game_over() {
+            assert_matches_reference(&game);
+            // Illegal moves included: they must leave the board alone.
+            let direction = Direction::all()[rng.gen_range(0..4)];
+            let before = game.get_board();
+            match game.play_move_with(direction, &mut rng) {
+                Ok(result) => {
+                    let (after, gained) = reference_slide(&before, direction).expect("played an illegal move");
+                    assert_eq!(result.score_gained, gained);
+                    let spawned = result.spawned.expect("a move frees a cell");
+                    assert_eq!(after[spawned.row][spawned.col], 0);
+                }
+                Err(_) => {
+                    assert_eq!(reference_slide(&before, direction), None);
+                    assert_eq!(game.get_board(), before);
+                }
+            }
+        }
+        assert_matches_reference(&game);
+    }
+}
+
+#[test]
+fn test_random_boards_past_the_packed_range_match_the_reference() {
+    let mut rng = StdRng::seed_from_u64(2048);
+    for _ in 0..5000 {
+        // Tiles up to 2^17 reach past the packed line keys, and few
+        // distinct values make merges and locked boards common.
+        let top = rng.gen_range(2..=17);
+        let cells = std::array::from_fn(|_| {
+            std::array::from_fn(|_| match rng.gen_range(0..=top) {
+                0 => 0,
+                exponent => 1 << exponent.max(top - 3),
+            })
+        });
+        let mut game = GameBoard::new();
+        game.set_board(cells);
+        assert_matches_reference(&game);
+    }
+}
+
+#[test]
+fn test_apply_place_and_undo_keep_caches_exact() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut game = GameBoard::new_with_rng(&mut rng);
+    let mut undos = Vec::new();
+    for _ in 0..2000 {
+        let moves: Vec<_> = Direction::all().into_iter().filter(|&d| game.can_move(d)).collect();
+        if moves.is_empty() || (undos.len() > 6 && rng.gen_bool(0.5)) {
+            let Some(undo) = undos.pop() else { break };
+            game.undo(undo);
+        } else {
+            undos.push(game.apply(moves[rng.gen_range(0..moves.len())]).unwrap());
+            let cells: Vec<_> = game.empty_cells().collect();
+            let (row, col) = cells[rng.gen_range(0..cells.len())];
+            undos.push(game.place_tile(row, col, if rng.gen_bool(0.9) { 2 } else { 4 }));
+        }
+        assert_matches_reference(&game);
+    }
+}
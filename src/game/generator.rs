@@ -0,0 +1,149 @@
+//! Seeded random positions for benchmarks, tests and puzzles: a given
+//! largest tile and number of tiles, either scattered or laid out the way
+//! a corner strategy leaves a mid-game board.
+
+use std::fmt;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use super::board::GameBoard;
+
+/// Attempts at a position with a legal move before a locked full board is
+/// returned anyway.
+const MAX_ATTEMPTS: u32 = 100;
+
+/// Why a random position could not be generated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RandomBoardError {
+    /// The largest tile is not a classic tile (a power of two from 2).
+    InvalidMaxTile(u32),
+    /// Positions hold 1 to 16 tiles.
+    FilledCells(usize),
+}
+
+impl fmt::Display for RandomBoardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RandomBoardError::InvalidMaxTile(tile) => write!(f, "{} is not a valid tile", tile),
+            RandomBoardError::FilledCells(cells) => write!(f, "{} tiles do not fit a board (1 to 16)", cells),
+        }
+    }
+}
+
+impl std::error::Error for RandomBoardError {}
+
+impl GameBoard {
+    /// A classic position holding `max_tile` once and `filled_cells - 1`
+    /// smaller tiles on random cells, each tile as likely to be a 2 as
+    /// everything larger. The same arguments always give the same position,
+    /// which has a legal move unless none of many full boards drawn did.
+    pub fn random_with(max_tile: u32, filled_cells: usize, seed: u64) -> Result<GameBoard, RandomBoardError> {
+        Self::random_board(max_tile, filled_cells, seed, |rng| {
+            let mut cells = [[0; 4]; 4];
+            let mut order: Vec<usize> = (0..16).collect();
+            let (chosen, _) = order.partial_shuffle(rng, filled_cells);
+            for (i, &cell) in chosen.iter().enumerate() {
+                cells[cell / 4][cell % 4] = if i == 0 { max_tile } else { small_tile(rng, max_tile) };
+            }
+            cells
+        })
+    }
+
+    /// [`GameBoard::random_with`], but shaped like a game that could have
+    /// got there: `max_tile` in a corner and the tiles falling off along a
+    /// snake from it, with the odd freshly spawned 2 or 4 out of order and
+    /// the empty cells at the far end.
+    pub fn random_monotone(max_tile: u32, filled_cells: usize, seed: u64) -> Result<GameBoard, RandomBoardError> {
+        Self::random_board(max_tile, filled_cells, seed, |rng| {
+            // Which corner, and whether the snake starts along a row or a column.
+            let (flip_row, flip_col, transpose) = (rng.gen::<bool>(), rng.gen::<bool>(), rng.gen::<bool>());
+            let mut cells = [[0; 4]; 4];
+            let mut previous = max_tile;
+            for step in 0..filled_cells {
+                let row = step / 4;
+                let col = if row % 2 == 0 { step % 4 } else { 3 - step % 4 };
+                let (row, col) = if transpose { (col, row) } else { (row, col) };
+                let (row, col) = (if flip_row { 3 - row } else { row }, if flip_col { 3 - col } else { col });
+                cells[row][col] = if step == 0 {
+                    max_tile
+                } else if rng.gen_ratio(1, 8) {
+                    (2 << rng.gen_range(0..2)).min(max_tile)
+                } else {
+                    previous = next_down(rng, previous);
+                    previous
+                };
+            }
+            cells
+        })
+    }
+
+    fn random_board<F>(max_tile: u32, filled_cells: usize, seed: u64, mut layout: F) -> Result<GameBoard, RandomBoardError>
+    where
+        F: FnMut(&mut StdRng) -> [[u32; 4]; 4],
+    {
+        if max_tile < 2 || !max_tile.is_power_of_two() {
+            return Err(RandomBoardError::InvalidMaxTile(max_tile));
+        }
+        if !(1..=16).contains(&filled_cells) {
+            return Err(RandomBoardError::FilledCells(filled_cells));
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut board = GameBoard::from_cells(layout(&mut rng));
+        for _ in 1..MAX_ATTEMPTS {
+            if !board.is_game_over() {
+                break;
+            }
+            board = GameBoard::from_cells(layout(&mut rng));
+        }
+        Ok(board)
+    }
+}
+
+/// A tile below `max_tile` (2 when `max_tile` is 2): 2 half the time, each
+/// larger tile half as likely as the one before.
+fn small_tile<R: Rng + ?Sized>(rng: &mut R, max_tile: u32) -> u32 {
+    let mut tile = 2;
+    while tile * 2 < max_tile && rng.gen::<bool>() {
+        tile *= 2;
+    }
+    tile
+}
+
+/// The next tile along a snake after `tile`: usually half of it, sometimes
+/// a quarter, and an equal tile waiting to merge while tiles are small.
+fn next_down<R: Rng + ?Sized>(rng: &mut R, tile: u32) -> u32 {
+    match rng.gen_range(0..4) {
+        0 if tile <= 8 => tile,
+        3 => (tile / 4).max(2),
+        _ => (tile / 2).max(2),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_boards_hold_the_requested_tiles() {
+        for seed in 0..20 {
+            for filled in [1, 6, 12, 16] {
+                let board = GameBoard::random_with(512, filled, seed).unwrap();
+                assert_eq!(board.get_board(), GameBoard::random_with(512, filled, seed).unwrap().get_board());
+                assert_eq!(board.count_empty_cells(), 16 - filled);
+                assert_eq!(board.get_board().iter().flatten().filter(|&&tile| tile == 512).count(), 1);
+                assert_eq!(board.get_max_tile(), 512);
+
+                let shaped = GameBoard::random_monotone(512, filled, seed).unwrap();
+                let cells = shaped.get_board();
+                assert_eq!(shaped.count_empty_cells(), 16 - filled);
+                assert!([cells[0][0], cells[0][3], cells[3][0], cells[3][3]].contains(&512));
+            }
+        }
+        assert_ne!(GameBoard::random_with(512, 8, 1).unwrap().get_board(), GameBoard::random_with(512, 8, 2).unwrap().get_board());
+        assert_eq!(GameBoard::random_with(2, 5, 0).unwrap().get_max_tile(), 2);
+        assert_eq!(GameBoard::random_with(96, 5, 0).err(), Some(RandomBoardError::InvalidMaxTile(96)));
+        assert_eq!(GameBoard::random_monotone(64, 17, 0).err(), Some(RandomBoardError::FilledCells(17)));
+    }
+}
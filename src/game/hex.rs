@@ -0,0 +1,220 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::move_result::MoveError;
+use super::rules::{self, GameRules};
+use super::variant::Variant;
+
+/// The six ways tiles slide on a [`HexBoard`]. Cells are axial (row, col)
+/// coordinates of a 4×4 rhombus, each row drawn half a cell right of the
+/// one above, so a cell touches its row neighbours, two cells above and
+/// two below.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum HexDirection {
+    Left,
+    Right,
+    /// Towards (row - 1, col).
+    UpLeft,
+    /// Towards (row - 1, col + 1).
+    UpRight,
+    /// Towards (row + 1, col - 1).
+    DownLeft,
+    /// Towards (row + 1, col).
+    DownRight,
+}
+
+impl HexDirection {
+    pub const ALL: [HexDirection; 6] = [
+        HexDirection::Left,
+        HexDirection::Right,
+        HexDirection::UpLeft,
+        HexDirection::UpRight,
+        HexDirection::DownLeft,
+        HexDirection::DownRight,
+    ];
+
+    /// Code used in compact move lists (`L`, `R`, `UL`, `UR`, `DL`, `DR`).
+    pub fn code(self) -> &'static str {
+        match self {
+            HexDirection::Left => "L",
+            HexDirection::Right => "R",
+            HexDirection::UpLeft => "UL",
+            HexDirection::UpRight => "UR",
+            HexDirection::DownLeft => "DL",
+            HexDirection::DownRight => "DR",
+        }
+    }
+}
+
+/// A hex-grid game: 16 cells in a rhombus, six move directions, and the
+/// merge, spawn and scoring rules of any [`Variant`]. Lines along the
+/// rhombus' short diagonal hold one to four cells.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HexBoard {
+    /// `cells[row][col]`, 0 for an empty cell.
+    pub cells: [[u32; 4]; 4],
+    pub variant: Variant,
+    pub move_count: u32,
+    pub score: u32,
+}
+
+impl HexBoard {
+    /// Starts a game of `variant` on the hex grid with two spawned tiles.
+    pub fn new_with_variant<R: Rng + ?Sized>(rng: &mut R, variant: Variant) -> Self {
+        let mut board = Self::from_cells([[0; 4]; 4], variant);
+        board.add_random_tile(rng);
+        board.add_random_tile(rng);
+        board
+    }
+
+    /// A fresh game (move 0) on `cells`.
+    pub fn from_cells(cells: [[u32; 4]; 4], variant: Variant) -> Self {
+        HexBoard { cells, variant, move_count: 0, score: 0 }
+    }
+
+    pub fn rules(&self) -> &'static dyn GameRules {
+        self.variant.rules()
+    }
+
+    /// Coordinates of every line `direction` slides, each ordered from the
+    /// edge its tiles slide towards.
+    pub fn lines(direction: HexDirection) -> impl Iterator<Item = Vec<(usize, usize)>> {
+        (0..7).filter_map(move |index| {
+            let cells: Vec<(usize, usize)> = match direction {
+                HexDirection::Left if index < 4 => (0..4).map(|col| (index, col)).collect(),
+                HexDirection::Right if index < 4 => (0..4).rev().map(|col| (index, col)).collect(),
+                HexDirection::UpLeft if index < 4 => (0..4).map(|row| (row, index)).collect(),
+                HexDirection::DownRight if index < 4 => (0..4).rev().map(|row| (row, index)).collect(),
+                // Short diagonals: the cells whose row + col is `index`.
+                HexDirection::UpRight => (0..4).filter_map(|row| Some((row, index.checked_sub(row).filter(|&col| col < 4)?))).collect(),
+                HexDirection::DownLeft => (0..4).rev().filter_map(|row| Some((row, index.checked_sub(row).filter(|&col| col < 4)?))).collect(),
+                _ => return None,
+            };
+            Some(cells)
+        })
+    }
+
+    /// The cells after sliding `direction` and the points its merges earn,
+    /// or `None` if nothing moves.
+    pub fn slide(&self, direction: HexDirection) -> Option<([[u32; 4]; 4], u32)> {
+        let rules = self.rules();
+        let mut cells = self.cells;
+        let mut moved = false;
+        let mut points = 0;
+        for line in Self::lines(direction) {
+            // Short lines are padded at the far end; slides never reach it.
+            let mut values = [0; 4];
+            for (k, &(row, col)) in line.iter().enumerate() {
+                values[k] = self.cells[row][col];
+            }
+            let (slid, line_moved, merge_mask) = rules.slide_line(&values);
+            for (k, &(row, col)) in line.iter().enumerate() {
+                cells[row][col] = slid[k];
+                if merge_mask & (1 << k) != 0 {
+                    points += rules.merge_score(slid[k]);
+                }
+            }
+            moved |= line_moved;
+        }
+        moved.then_some((cells, points))
+    }
+
+    pub fn can_move(&self, direction: HexDirection) -> bool {
+        self.slide(direction).is_some()
+    }
+
+    pub fn legal_moves(&self) -> impl Iterator<Item = HexDirection> + '_ {
+        HexDirection::ALL.into_iter().filter(|&direction| self.can_move(direction))
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.legal_moves().next().is_none()
+    }
+
+    /// Plays a full turn: slides, then spawns one of the variant's tiles.
+    /// Returns the points gained, or fails (board untouched) if nothing
+    /// moved.
+    pub fn play_move_with<R: Rng + ?Sized>(&mut self, direction: HexDirection, rng: &mut R) -> Result<u32, MoveError> {
+        let Some((cells, points)) = self.slide(direction) else {
+            return Err(if self.is_game_over() { MoveError::GameAlreadyOver } else { MoveError::NoTilesMoved });
+        };
+        self.cells = cells;
+        self.move_count += 1;
+        self.score += points;
+        self.add_random_tile(rng);
+        Ok(points)
+    }
+
+    /// Every empty cell as (row, col), row-major.
+    pub fn empty_cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..16).map(|index| (index / 4, index % 4)).filter(|&(row, col)| self.cells[row][col] == 0)
+    }
+
+    pub fn get_max_tile(&self) -> u32 {
+        self.cells.iter().flatten().copied().max().unwrap_or(0)
+    }
+
+    pub fn has_won(&self) -> bool {
+        self.get_max_tile() >= self.rules().win_tile()
+    }
+
+    fn add_random_tile<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let empty: Vec<_> = self.empty_cells().collect();
+        if empty.is_empty() {
+            return;
+        }
+        let (row, col) = empty[rng.gen_range(0..empty.len())];
+        self.cells[row][col] = rules::sample_spawn(self.rules(), rng);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_diagonals_slide_and_merge() {
+        let board = HexBoard::from_cells([
+            [0, 0, 2, 0],
+            [0, 2, 0, 0],
+            [4, 0, 0, 0],
+            [0, 0, 0, 0],
+        ], Variant::Classic);
+        // Row + col = 2: (0, 2), (1, 1), (2, 0) hold 2, 2, 4.
+        let (cells, points) = board.slide(HexDirection::UpRight).unwrap();
+        assert_eq!(cells[0][2], 4);
+        assert_eq!(cells[1][1], 4);
+        assert_eq!(cells[2][0], 0);
+        assert_eq!(points, 4);
+        let (cells, _) = board.slide(HexDirection::DownLeft).unwrap();
+        assert_eq!((cells[2][0], cells[1][1], cells[0][2]), (4, 4, 0));
+        assert_eq!(HexBoard::lines(HexDirection::UpRight).map(|line| line.len()).collect::<Vec<_>>(), [1, 2, 3, 4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn test_six_directions_are_legal_on_an_open_board() {
+        let board = HexBoard::from_cells([
+            [0, 0, 0, 0],
+            [0, 2, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0],
+        ], Variant::Fibonacci);
+        assert_eq!(board.legal_moves().count(), 6);
+        let stuck = HexBoard::from_cells([
+            [2, 4, 2, 4],
+            [8, 16, 8, 16],
+            [2, 4, 2, 4],
+            [8, 16, 8, 16],
+        ], Variant::Classic);
+        assert!(stuck.is_game_over());
+        // Rows and columns never repeat; each short diagonal holds one value.
+        let diagonal = HexBoard::from_cells([
+            [2, 4, 8, 16],
+            [4, 8, 16, 32],
+            [8, 16, 32, 64],
+            [16, 32, 64, 128],
+        ], Variant::Classic);
+        let moves: Vec<_> = diagonal.legal_moves().collect();
+        assert_eq!(moves, [HexDirection::UpRight, HexDirection::DownLeft]);
+    }
+}
@@ -0,0 +1,198 @@
+//! Games played in other 2048 clients, turned into replays.
+//!
+//! Two inputs are accepted: a move list with the tile spawned after each
+//! move, or successive board snapshots from which the moves and spawns are
+//! inferred. Either way the result is a [`ReplayHeader`] and its steps,
+//! ready for [`super::replay::ReplayWriter`], [`super::replay::replay_positions`]
+//! and the coach. Imported games have no seed: the header's is 0 and the
+//! game is rebuilt from its start position and recorded spawns.
+
+use std::fmt;
+
+use super::board::GameBoard;
+use super::legality::IllegalPosition;
+use super::move_result::SpawnedTile;
+use super::moves::Direction;
+use super::replay::{ReplayHeader, ReplayStep};
+use super::spawn::SpawnRules;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportError {
+    /// Line `line` (1-based) of a text log could not be read.
+    Parse { line: usize, message: String },
+    /// The start position cannot arise in a legal game.
+    IllegalStart(IllegalPosition),
+    /// Move `index` does not change the board.
+    IllegalMove { index: usize, direction: Direction },
+    /// The spawn after move `index` is not a 2 or 4 on an empty cell.
+    InvalidSpawn { index: usize },
+    /// No move and single spawn lead from snapshot `index` to the next.
+    NoMatchingMove { index: usize },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            ImportError::IllegalStart(reason) => write!(f, "illegal start position: {}", reason),
+            ImportError::IllegalMove { index, direction } => {
+                write!(f, "move {} ({:?}) does not change the board", index + 1, direction)
+            }
+            ImportError::InvalidSpawn { index } => write!(f, "spawn after move {} is not a 2 or 4 on an empty cell", index + 1),
+            ImportError::NoMatchingMove { index } => {
+                write!(f, "no move and spawn turn snapshot {} into snapshot {}", index + 1, index + 2)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+fn header(start: &GameBoard) -> ReplayHeader {
+    ReplayHeader::new(0, &SpawnRules::default(), start)
+}
+
+/// Plays `moves` from `start`, placing each recorded spawn. `start` must
+/// pass [`GameBoard::check_legality`].
+pub fn import_moves(start: &GameBoard, moves: &[(Direction, Option<SpawnedTile>)]) -> Result<(ReplayHeader, Vec<ReplayStep>), ImportError> {
+    start.check_legality().map_err(ImportError::IllegalStart)?;
+    let mut board = start.clone();
+    let mut steps = Vec::with_capacity(moves.len());
+    for (index, &(direction, spawn)) in moves.iter().enumerate() {
+        if !board.move_tiles(direction) {
+            return Err(ImportError::IllegalMove { index, direction });
+        }
+        if let Some(spawn) = spawn {
+            let valid = spawn.row < 4 && spawn.col < 4 && matches!(spawn.value, 2 | 4) && board.board[spawn.row][spawn.col] == 0;
+            if !valid {
+                return Err(ImportError::InvalidSpawn { index });
+            }
+            board.place_tile(spawn.row, spawn.col, spawn.value);
+        }
+        steps.push(ReplayStep { direction, spawn, hash: board.board_hash(), annotation: None });
+    }
+    Ok((header(start), steps))
+}
+
+/// The move and spawn that turn `before` into `after`: a direction whose
+/// slide matches `after` except for at most one new 2 or 4.
+fn infer_step(before: &GameBoard, after: &[[u32; 4]; 4]) -> Option<(Direction, Option<SpawnedTile>)> {
+    Direction::all().into_iter().find_map(|direction| {
+        let slid = before.child(direction)?;
+        let mut spawn = None;
+        for (row, col) in (0..4).flat_map(|row| (0..4).map(move |col| (row, col))) {
+            let (was, is) = (slid.board[row][col], after[row][col]);
+            if was == is {
+                continue;
+            }
+            if was != 0 || !matches!(is, 2 | 4) || spawn.is_some() {
+                return None;
+            }
+            spawn = Some(SpawnedTile { row, col, value: is });
+        }
+        Some((direction, spawn))
+    })
+}
+
+/// Infers the moves and spawns between successive board snapshots, the
+/// first being the start position.
+pub fn import_snapshots(snapshots: &[GameBoard]) -> Result<(ReplayHeader, Vec<ReplayStep>), ImportError> {
+    let Some(start) = snapshots.first() else {
+        return Err(ImportError::Parse { line: 1, message: "no boards".to_string() });
+    };
+    let moves = snapshots
+        .windows(2)
+        .enumerate()
+        .map(|(index, pair)| infer_step(&pair[0], &pair[1].board).ok_or(ImportError::NoMatchingMove { index }))
+        .collect::<Result<Vec<_>, _>>()?;
+    import_moves(start, &moves)
+}
+
+/// Reads a text game log, one entry per line (blank lines and `#` comments
+/// are skipped). The first line is the start position in compact notation
+/// (see [`GameBoard::from_compact`]). The rest are either more positions,
+/// read as snapshots, or moves: a direction letter (`U`, `D`, `L`, `R`)
+/// optionally followed by the spawn as `row,col=value` with 0-based
+/// coordinates, e.g. `L 3,0=2`.
+pub fn parse_game_log(text: &str) -> Result<(ReplayHeader, Vec<ReplayStep>), ImportError> {
+    let lines: Vec<(usize, &str)> = text
+        .lines()
+        .enumerate()
+        .map(|(number, line)| (number + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+    let parse_board = |&(line, text): &(usize, &str)| {
+        GameBoard::from_compact(text).map_err(|err| ImportError::Parse { line, message: err.to_string() })
+    };
+    let Some(first) = lines.first() else {
+        return Err(ImportError::Parse { line: 1, message: "empty game log".to_string() });
+    };
+    let start = parse_board(first)?;
+    let is_board = |text: &str| text.contains('/') || text.split(',').count() > 4;
+    if lines.get(1).is_some_and(|&(_, text)| is_board(text)) {
+        let snapshots = lines.iter().map(parse_board).collect::<Result<Vec<_>, _>>()?;
+        return import_snapshots(&snapshots);
+    }
+    let moves = lines[1..].iter().map(|&(line, text)| parse_move(text).ok_or_else(|| ImportError::Parse {
+        line,
+        message: format!("'{}' is not a move such as `L` or `L 3,0=2`", text),
+    }));
+    import_moves(&start, &moves.collect::<Result<Vec<_>, _>>()?)
+}
+
+fn parse_move(text: &str) -> Option<(Direction, Option<SpawnedTile>)> {
+    let mut chars = text.chars();
+    let direction = Direction::from_letter(chars.next()?.to_ascii_uppercase())?;
+    let spawn = chars.as_str().trim();
+    if spawn.is_empty() {
+        return Some((direction, None));
+    }
+    let (cell, value) = spawn.split_once('=')?;
+    let (row, col) = cell.split_once(',')?;
+    Some((
+        direction,
+        Some(SpawnedTile {
+            row: row.trim().parse().ok()?,
+            col: col.trim().parse().ok()?,
+            value: value.trim().parse().ok()?,
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::game::replay::replay_positions;
+
+    #[test]
+    fn test_snapshots_and_move_logs_import_the_same_replay() {
+        let mut rng = StdRng::seed_from_u64(21);
+        let mut board = GameBoard::new_with_rng(&mut rng);
+        let mut snapshots = vec![board.to_compact()];
+        let mut log = vec![board.to_compact()];
+        for direction in Direction::all().into_iter().cycle().take(60) {
+            if let Ok(result) = board.play_move_with(direction, &mut rng) {
+                let spawn = result.spawned.unwrap();
+                snapshots.push(board.to_compact());
+                log.push(format!("{} {},{}={}", direction.letter(), spawn.row, spawn.col, spawn.value));
+            }
+        }
+
+        let (header, steps) = parse_game_log(&snapshots.join("\n")).unwrap();
+        let from_log = parse_game_log(&log.join("\n")).unwrap();
+        assert_eq!(from_log, (header.clone(), steps.clone()));
+        let positions = replay_positions(&header, &steps).unwrap();
+        assert_eq!(positions.last().unwrap().get_board(), board.get_board());
+
+        log.push("U 0,0=8".to_string());
+        assert!(matches!(parse_game_log(&log.join("\n")), Err(ImportError::IllegalMove { .. } | ImportError::InvalidSpawn { .. })));
+        assert!(matches!(parse_game_log("2,0,0,0/0,0,0,0/0,0,0,0/0,0,0,2\nsideways"), Err(ImportError::Parse { line: 2, .. })));
+        assert_eq!(
+            parse_game_log("8,0,0,0/0,0,0,0/0,0,0,0/0,0,0,16\nL"),
+            Err(ImportError::IllegalStart(IllegalPosition::NoSpawnedTile))
+        );
+    }
+}
@@ -0,0 +1,113 @@
+//! Whether a classic position can arise in a legal game.
+//!
+//! Every turn ends with a 2 or 4 spawned on a board that has just slid, so
+//! a position other than an opening must hold a 2 or 4 whose removal
+//! leaves every tile packed against one side. Positions typed in by hand
+//! or taken from a broken client often fail this, and the engine would
+//! otherwise search them as if they were real games.
+
+use std::fmt;
+
+use super::board::GameBoard;
+use super::moves::Direction;
+use super::variant::Variant;
+
+/// Why a position cannot arise in a legal game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IllegalPosition {
+    /// Games start with two tiles and never drop below two.
+    TooFewTiles(usize),
+    /// No 2 or 4 for the last turn to have spawned.
+    NoSpawnedTile,
+    /// No 2 or 4 whose removal leaves a board some move could have slid.
+    NoPreviousMove,
+}
+
+impl fmt::Display for IllegalPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IllegalPosition::TooFewTiles(tiles) => write!(f, "{} tiles, but every game holds at least 2", tiles),
+            IllegalPosition::NoSpawnedTile => write!(f, "no 2 or 4 for the last move to have spawned"),
+            IllegalPosition::NoPreviousMove => write!(f, "no move followed by a spawn leads to this position"),
+        }
+    }
+}
+
+impl std::error::Error for IllegalPosition {}
+
+impl GameBoard {
+    /// Checks that the position can arise in a classic game, as an opening
+    /// or after a move and its spawn. Other variants spawn and slide by
+    /// their own rules and are not checked.
+    pub fn check_legality(&self) -> Result<(), IllegalPosition> {
+        if self.variant != Variant::Classic {
+            return Ok(());
+        }
+        let tiles = 16 - self.count_empty_cells();
+        if tiles < 2 {
+            return Err(IllegalPosition::TooFewTiles(tiles));
+        }
+        let spawnable: Vec<(usize, usize)> = (0..16)
+            .map(|cell| (cell / 4, cell % 4))
+            .filter(|&(row, col)| matches!(self.board[row][col], 2 | 4))
+            .collect();
+        if spawnable.is_empty() {
+            return Err(IllegalPosition::NoSpawnedTile);
+        }
+        // The opening: two spawns anywhere.
+        if tiles == 2 && spawnable.len() == 2 {
+            return Ok(());
+        }
+        let slid = spawnable.iter().any(|&(row, col)| {
+            let mut cells = self.board;
+            cells[row][col] = 0;
+            Direction::all().into_iter().any(|direction| is_packed(&cells, direction))
+        });
+        if slid {
+            Ok(())
+        } else {
+            Err(IllegalPosition::NoPreviousMove)
+        }
+    }
+}
+
+/// Whether every line of `cells` has its tiles against the side `direction`
+/// slides them to, as any position just after that move does.
+fn is_packed(cells: &[[u32; 4]; 4], direction: Direction) -> bool {
+    (0..4).all(|line| {
+        // Cells of the line, starting from the side tiles slide to.
+        let cell = |k: usize| match direction {
+            Direction::Left => cells[line][k],
+            Direction::Right => cells[line][3 - k],
+            Direction::Up => cells[k][line],
+            Direction::Down => cells[3 - k][line],
+        };
+        let tiles = (0..4).filter(|&k| cell(k) != 0).count();
+        (0..tiles).all(|k| cell(k) != 0)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn test_played_positions_are_legal_and_made_up_ones_are_not() {
+        let mut rng = StdRng::seed_from_u64(9);
+        let mut board = GameBoard::new_with_rng(&mut rng);
+        for direction in Direction::all().into_iter().cycle().take(200) {
+            assert_eq!(board.check_legality(), Ok(()), "{}", board.to_compact());
+            let _ = board.play_move_with(direction, &mut rng);
+        }
+
+        let illegal = |text: &str| GameBoard::from_compact(text).unwrap().check_legality();
+        assert_eq!(illegal("0,0,0,0/0,2048,0,0/0,0,0,0/0,0,0,0"), Err(IllegalPosition::TooFewTiles(1)));
+        assert_eq!(illegal("8,0,0,0/0,0,0,0/0,0,0,0/0,0,0,16"), Err(IllegalPosition::NoSpawnedTile));
+        assert_eq!(illegal("0,0,0,0/0,64,0,0/0,0,2,0/0,32,0,0"), Err(IllegalPosition::NoPreviousMove));
+        assert_eq!(illegal("0,0,0,0/0,4,0,0/0,0,0,0/0,0,2,0"), Ok(()));
+        assert_eq!(illegal("512,256,128,64/8,16,32,4/2,0,0,2/0,0,0,0"), Ok(()));
+    }
+}
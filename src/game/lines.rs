@@ -0,0 +1,207 @@
+use super::board::GameBoard;
+use super::moves::Direction;
+use super::variant::Variant;
+
+/// Number of distinct lines: four cells of 4-bit tile exponents.
+pub(crate) const LINES: usize = 1 << 16;
+
+/// Largest tile a packed line can hold.
+pub(crate) const MAX_PACKED_TILE: u32 = 1 << 15;
+
+/// Packed keys of the four rows and the four columns of a board. A line is
+/// packed as four tile exponents (0 = empty, first cell in the low nibble);
+/// rows are read left to right and columns top to bottom.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LineKeys {
+    pub rows: [u16; 4],
+    pub columns: [u16; 4],
+}
+
+fn exponent(value: u32) -> u16 {
+    if value == 0 {
+        0
+    } else {
+        value.trailing_zeros() as u16
+    }
+}
+
+pub(crate) fn pack(line: [u32; 4]) -> u16 {
+    line.iter().enumerate().fold(0, |key, (i, &value)| key | exponent(value) << (4 * i))
+}
+
+pub(crate) fn unpack(key: usize) -> [u32; 4] {
+    std::array::from_fn(|i| match (key >> (4 * i)) & 0xF {
+        0 => 0,
+        e => 1 << e,
+    })
+}
+
+/// Bit 0: sliding the line towards its first cell changes it; bit 1: the
+/// same towards its last cell.
+fn line_moves(line: [u32; 4]) -> u8 {
+    let changes = |line: [u32; 4]| {
+        line.windows(2).any(|pair| (pair[0] == 0 && pair[1] != 0) || (pair[0] != 0 && pair[0] == pair[1]))
+    };
+    let mut reversed = line;
+    reversed.reverse();
+    changes(line) as u8 | (changes(reversed) as u8) << 1
+}
+
+/// Exponent in cell `i` of a packed line; 0 past the end.
+const fn nibble(key: u16, i: usize) -> u16 {
+    if i < 4 {
+        (key >> (4 * i)) & 0xF
+    } else {
+        0
+    }
+}
+
+/// Left-merges a packed line exactly like `GameBoard::merge_line`: the
+/// merged key and a bit per output cell that holds a freshly merged tile.
+/// Merging two 32768s wraps to an empty nibble, so callers only use the
+/// result below that tile.
+const fn merge_key(key: u16) -> (u16, u8) {
+    let mut merged = 0u16;
+    let mut merge_mask = 0u8;
+    let mut write = 0;
+    let mut i = 0;
+    while i < 4 {
+        let e = nibble(key, i);
+        if e == 0 {
+            i += 1;
+        } else if e == nibble(key, i + 1) {
+            merged |= ((e + 1) & 0xF) << (4 * write);
+            merge_mask |= 1 << write;
+            write += 1;
+            i += 2;
+        } else {
+            merged |= e << (4 * write);
+            write += 1;
+            i += 1;
+        }
+    }
+    (merged, merge_mask)
+}
+
+const fn reverse_key(key: u16) -> u16 {
+    (key & 0xF) << 12 | (key >> 4 & 0xF) << 8 | (key >> 8 & 0xF) << 4 | key >> 12
+}
+
+const fn merge_table() -> [(u16, u8); LINES] {
+    let mut table = [(0, 0); LINES];
+    let mut key = 0;
+    while key < LINES {
+        table[key] = merge_key(key as u16);
+        key += 1;
+    }
+    table
+}
+
+const fn line_moves_table() -> [u8; LINES] {
+    let mut table = [0; LINES];
+    let mut key = 0;
+    while key < LINES {
+        let towards_first = merge_key(key as u16).0 != key as u16;
+        let reversed = reverse_key(key as u16);
+        let towards_last = merge_key(reversed).0 != reversed;
+        table[key] = towards_first as u8 | (towards_last as u8) << 1;
+        key += 1;
+    }
+    table
+}
+
+/// [`merge_key`] of every line, built at compile time so it sits in
+/// read-only data rather than being computed at startup.
+pub(crate) static MERGE_LEFT: [(u16, u8); LINES] = merge_table();
+
+/// [`line_moves`] of every line, also built at compile time.
+static LINE_MOVES: [u8; LINES] = line_moves_table();
+
+impl Direction {
+    /// This direction's bit in [`GameBoard::legal_moves`].
+    pub fn bit(self) -> u8 {
+        match self {
+            Direction::Up => 1,
+            Direction::Down => 2,
+            Direction::Left => 4,
+            Direction::Right => 8,
+        }
+    }
+}
+
+impl GameBoard {
+    pub(crate) fn line_keys(&self) -> LineKeys {
+        LineKeys {
+            rows: std::array::from_fn(|i| pack(self.board[i])),
+            columns: std::array::from_fn(|j| pack(self.board.map(|row| row[j]))),
+        }
+    }
+
+    /// Directions that change `board`, one [`Direction::bit`] each: eight
+    /// table lookups, one per row and column, for a classic board.
+    pub(crate) fn calculate_legal_moves(board: &[[u32; 4]; 4], max_tile: u32, variant: Variant) -> u8 {
+        if variant != Variant::Classic {
+            // Other rules have no table: slide each line both ways.
+            let moves = |line: [u32; 4]| {
+                let mut reversed = line;
+                reversed.reverse();
+                variant.rules().slide_line(&line).1 as u8 | (variant.rules().slide_line(&reversed).1 as u8) << 1
+            };
+            return Self::direction_bits_by(board, moves);
+        }
+        if max_tile > MAX_PACKED_TILE {
+            // Beyond what a nibble holds: check each line directly.
+            return Self::direction_bits_by(board, line_moves);
+        }
+        let mut rows = 0;
+        let mut columns = 0;
+        for i in 0..4 {
+            rows |= LINE_MOVES[pack(board[i]) as usize];
+            columns |= LINE_MOVES[pack(board.map(|row| row[i])) as usize];
+        }
+        Self::direction_bits(rows, columns)
+    }
+
+    /// [`GameBoard::direction_bits`] of `board` with `moves` computing each
+    /// line's bits.
+    fn direction_bits_by(board: &[[u32; 4]; 4], moves: impl Fn([u32; 4]) -> u8) -> u8 {
+        let rows = board.iter().fold(0, |bits, &row| bits | moves(row));
+        let columns = (0..4).fold(0, |bits, j| bits | moves(board.map(|row| row[j])));
+        Self::direction_bits(rows, columns)
+    }
+
+    fn direction_bits(rows: u8, columns: u8) -> u8 {
+        let bit = |moves: u8, towards_first: Direction, towards_last: Direction| {
+            (if moves & 1 != 0 { towards_first.bit() } else { 0 }) | (if moves & 2 != 0 { towards_last.bit() } else { 0 })
+        };
+        bit(rows, Direction::Left, Direction::Right) | bit(columns, Direction::Up, Direction::Down)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_table_matches_merge_line() {
+        for key in 0..LINES {
+            let line = unpack(key);
+            if line.contains(&MAX_PACKED_TILE) {
+                continue;
+            }
+            let (merged, _, merge_mask) = GameBoard::merge_line(&line);
+            assert_eq!(MERGE_LEFT[key], (pack(merged), merge_mask), "line {:?}", line);
+            assert_eq!(LINE_MOVES[key], line_moves(line));
+        }
+    }
+
+    #[test]
+    fn test_line_moves() {
+        assert_eq!(line_moves([0, 0, 0, 0]), 0);
+        assert_eq!(line_moves([2, 4, 8, 16]), 0);
+        assert_eq!(line_moves([2, 0, 0, 0]), 2);
+        assert_eq!(line_moves([0, 0, 0, 2]), 1);
+        assert_eq!(line_moves([4, 4, 2, 8]), 3);
+        assert_eq!(unpack(pack([0, 2, 1024, 32768]) as usize), [0, 2, 1024, 32768]);
+    }
+}
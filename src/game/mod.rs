@@ -1,5 +1,36 @@
+mod achievements;
+mod battle;
 mod board;
+mod custom_rules;
+#[cfg(test)]
+mod differential;
+mod generator;
+mod hex;
+mod legality;
+pub(crate) mod lines;
 mod moves;
+mod move_result;
+mod notation;
+mod power_ups;
+mod rules;
+mod spawn;
+mod variant;
+pub mod audit;
+pub mod import;
+pub mod replay;
+pub mod savegame;
 
-pub use board::GameBoard;
-pub use moves::Direction; 
\ No newline at end of file
+pub use achievements::{Achievement, AchievementTracker, Unlocked};
+pub use battle::{BattleGame, BattleTurn, DEFAULT_GARBAGE_THRESHOLD, GARBAGE_TILE};
+pub use board::{EmptyCells, GameBoard, UndoInfo};
+pub use custom_rules::{CustomRules, InvalidRules, MergeRule};
+pub use generator::RandomBoardError;
+pub use hex::{HexBoard, HexDirection};
+pub use legality::IllegalPosition;
+pub use moves::Direction;
+pub use move_result::{MergeBreakdown, MergedTile, MoveError, MoveResult, SpawnedTile};
+pub use notation::ParseBoardError;
+pub use power_ups::{PowerUp, PowerUpError, PowerUpGame, PowerUps};
+pub use spawn::SpawnRules;
+pub use rules::{Classic2048, Fibonacci, GameRules, Joker, Threes, JOKER};
+pub use variant::{CustomId, UnknownVariant, Variant};
@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::moves::Direction;
+
+/// A cell that received a merged tile during a move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MergedTile {
+    pub row: usize,
+    pub col: usize,
+    /// Value of the tile produced by the merge (also the score it earned).
+    pub value: u32,
+}
+
+/// The random tile added after a move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpawnedTile {
+    pub row: usize,
+    pub col: usize,
+    pub value: u32,
+}
+
+/// Everything that changed during one played move, so a front-end can
+/// animate merges and show the score popup without diffing boards.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoveResult {
+    pub direction: Direction,
+    /// Points for the merges: the sum of merged tile values in 2048, the
+    /// variant's [`crate::GameRules::merge_score`] otherwise.
+    pub score_gained: u32,
+    pub merged: Vec<MergedTile>,
+    /// `None` only if the board had no empty cell left to spawn into, or in
+    /// puzzle mode, where nothing spawns.
+    pub spawned: Option<SpawnedTile>,
+}
+
+/// How many merges made each tile value over a game, e.g. three 512s:
+/// where the score came from, since each merge earns its tile's points.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct MergeBreakdown(BTreeMap<u32, u64>);
+
+impl MergeBreakdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts the merges of one played move.
+    pub fn record(&mut self, result: &MoveResult) {
+        for merged in &result.merged {
+            *self.0.entry(merged.value).or_default() += 1;
+        }
+    }
+
+    /// Adds every merge of `other`.
+    pub fn add(&mut self, other: &MergeBreakdown) {
+        for (&tile, &count) in &other.0 {
+            *self.0.entry(tile).or_default() += count;
+        }
+    }
+
+    /// Merges that made `tile`.
+    pub fn count(&self, tile: u32) -> u64 {
+        self.0.get(&tile).copied().unwrap_or(0)
+    }
+
+    pub fn total(&self) -> u64 {
+        self.0.values().sum()
+    }
+
+    /// (tile, merges) from the smallest tile up.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, u64)> + '_ {
+        self.0.iter().map(|(&tile, &count)| (tile, count))
+    }
+}
+
+impl fmt::Display for MergeBreakdown {
+    /// `4: 120, 8: 57, ...`, tile then merges.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let counts: Vec<String> = self.iter().map(|(tile, count)| format!("{}: {}", tile, count)).collect();
+        write!(f, "{}", counts.join(", "))
+    }
+}
+
+/// Why a move was not played. The board is left untouched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoveError {
+    /// The move would not slide or merge anything, though another would.
+    NoTilesMoved,
+    /// No move changes the board any more.
+    GameAlreadyOver,
+    /// Text that names no direction, see [`Direction::from_str`].
+    ///
+    /// [`Direction::from_str`]: std::str::FromStr::from_str
+    InvalidDirection(String),
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::NoTilesMoved => write!(f, "the move does not change the board"),
+            MoveError::GameAlreadyOver => write!(f, "the game is over"),
+            MoveError::InvalidDirection(text) => write!(f, "'{}' is not a direction (up, down, left, right or U, D, L, R)", text),
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
@@ -1,4 +1,11 @@
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use super::move_result::MoveError;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen::prelude::wasm_bindgen)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Direction {
     Up,
     Down,
@@ -10,4 +17,42 @@ impl Direction {
     pub fn all() -> [Direction; 4] {
         [Direction::Up, Direction::Down, Direction::Left, Direction::Right]
     }
-} 
\ No newline at end of file
+
+    /// One-letter code (`U`, `D`, `L`, `R`) used in compact move lists.
+    pub fn letter(self) -> char {
+        match self {
+            Direction::Up => 'U',
+            Direction::Down => 'D',
+            Direction::Left => 'L',
+            Direction::Right => 'R',
+        }
+    }
+
+    pub fn from_letter(letter: char) -> Option<Direction> {
+        match letter.to_ascii_uppercase() {
+            'U' => Some(Direction::Up),
+            'D' => Some(Direction::Down),
+            'L' => Some(Direction::Left),
+            'R' => Some(Direction::Right),
+            _ => None,
+        }
+    }
+} 
+
+impl FromStr for Direction {
+    type Err = MoveError;
+
+    /// A direction by name or letter, in any case: `left`, `Left` or `l`.
+    fn from_str(text: &str) -> Result<Self, MoveError> {
+        let text = text.trim();
+        let named = Direction::all()
+            .into_iter()
+            .find(|direction| format!("{:?}", direction).eq_ignore_ascii_case(text));
+        let mut chars = text.chars();
+        let lettered = match (chars.next(), chars.next()) {
+            (Some(letter), None) => Direction::from_letter(letter),
+            _ => None,
+        };
+        named.or(lettered).ok_or_else(|| MoveError::InvalidDirection(text.to_string()))
+    }
+}
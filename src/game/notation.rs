@@ -0,0 +1,118 @@
+use std::fmt;
+
+use super::board::GameBoard;
+use super::variant::Variant;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseBoardError {
+    /// The text did not contain exactly four rows.
+    RowCount(usize),
+    /// Row `row` did not contain exactly four cells.
+    CellCount { row: usize, cells: usize },
+    /// A cell was not a number.
+    InvalidNumber(String),
+    /// A cell was neither 0 nor a tile of the variant.
+    InvalidTile(u32),
+}
+
+impl fmt::Display for ParseBoardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseBoardError::RowCount(rows) => write!(f, "expected 4 rows, found {}", rows),
+            ParseBoardError::CellCount { row, cells } => {
+                write!(f, "row {} has {} cells, expected 4", row + 1, cells)
+            }
+            ParseBoardError::InvalidNumber(cell) => write!(f, "'{}' is not a number", cell),
+            ParseBoardError::InvalidTile(value) => write!(f, "{} is not a valid tile", value),
+        }
+    }
+}
+
+impl std::error::Error for ParseBoardError {}
+
+impl GameBoard {
+    /// Parses a position in compact notation, `2,4,8,16/0,0,2,4/...`: rows
+    /// top to bottom separated by `/` or newlines, cells by commas or
+    /// whitespace, `0` for empty. Move count starts at zero.
+    pub fn from_compact(text: &str) -> Result<GameBoard, ParseBoardError> {
+        Self::from_compact_with(text, Variant::Classic)
+    }
+
+    /// [`GameBoard::from_compact`] for a position of `variant`, whose tiles
+    /// it accepts.
+    pub fn from_compact_with(text: &str, variant: Variant) -> Result<GameBoard, ParseBoardError> {
+        let rows: Vec<&str> = text
+            .split(['/', '\n'])
+            .map(str::trim)
+            .filter(|row| !row.is_empty())
+            .collect();
+        if rows.len() != 4 {
+            return Err(ParseBoardError::RowCount(rows.len()));
+        }
+        let mut board = [[0; 4]; 4];
+        for (i, row) in rows.iter().enumerate() {
+            let cells: Vec<&str> = row
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|cell| !cell.is_empty())
+                .collect();
+            if cells.len() != 4 {
+                return Err(ParseBoardError::CellCount { row: i, cells: cells.len() });
+            }
+            for (j, cell) in cells.iter().enumerate() {
+                let value: u32 = cell
+                    .parse()
+                    .map_err(|_| ParseBoardError::InvalidNumber(cell.to_string()))?;
+                if value != 0 && !variant.rules().is_tile(value) {
+                    return Err(ParseBoardError::InvalidTile(value));
+                }
+                board[i][j] = value;
+            }
+        }
+        let mut board = GameBoard::from_cells(board);
+        board.set_variant(variant);
+        Ok(board)
+    }
+
+    /// The board in the compact notation read by [`GameBoard::from_compact`].
+    pub fn to_compact(&self) -> String {
+        self.board
+            .iter()
+            .map(|row| row.map(|v| v.to_string()).join(","))
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compact_round_trip() {
+        let text = "2,4,8,16/0,0,0,0/0,2,0,0/2048,0,0,4";
+        let board = GameBoard::from_compact(text).unwrap();
+        assert_eq!(board.board[3][0], 2048);
+        assert_eq!(board.get_max_tile(), 2048);
+        assert_eq!(board.to_compact(), text);
+
+        let multiline = "2 4 8 16\n0 0 0 0\n0 2 0 0\n2048 0 0 4\n";
+        assert_eq!(GameBoard::from_compact(multiline).unwrap().board, board.board);
+    }
+
+    #[test]
+    fn test_compact_rejects_bad_input() {
+        assert!(matches!(GameBoard::from_compact("2,4/0,0"), Err(ParseBoardError::RowCount(2))));
+        assert!(matches!(
+            GameBoard::from_compact("2,4,8/0,0,0,0/0,0,0,0/0,0,0,0"),
+            Err(ParseBoardError::CellCount { row: 0, cells: 3 })
+        ));
+        assert!(matches!(
+            GameBoard::from_compact("3,0,0,0/0,0,0,0/0,0,0,0/0,0,0,0"),
+            Err(ParseBoardError::InvalidTile(3))
+        ));
+        assert!(matches!(
+            GameBoard::from_compact("x,0,0,0/0,0,0,0/0,0,0,0/0,0,0,0"),
+            Err(ParseBoardError::InvalidNumber(_))
+        ));
+    }
+}
@@ -0,0 +1,265 @@
+//! Power-ups: swapping two tiles, deleting a tile and undoing the last
+//! move, each with a limited number of charges per game. Using one does
+//! not count as a move and spawns nothing.
+
+use std::fmt;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::board::GameBoard;
+use super::move_result::{MergeBreakdown, MoveError, MoveResult};
+use super::moves::Direction;
+use super::spawn::SpawnRules;
+
+/// One use of a power-up. Cells are (row, col).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerUp {
+    /// Exchanges two tiles.
+    Swap((usize, usize), (usize, usize)),
+    /// Removes a tile.
+    Delete(usize, usize),
+    /// Takes back the last move and the tile it spawned.
+    Undo,
+}
+
+impl fmt::Display for PowerUp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PowerUp::Swap(a, b) => write!(f, "swap ({}, {}) with ({}, {})", a.0, a.1, b.0, b.1),
+            PowerUp::Delete(row, col) => write!(f, "delete ({}, {})", row, col),
+            PowerUp::Undo => write!(f, "undo"),
+        }
+    }
+}
+
+/// Charges left of each power-up; none by default, which is plain 2048.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PowerUps {
+    pub swap: u32,
+    pub delete: u32,
+    pub undo: u32,
+}
+
+impl PowerUps {
+    pub fn is_empty(&self) -> bool {
+        self.swap == 0 && self.delete == 0 && self.undo == 0
+    }
+
+    /// Charges left for `power_up`'s kind.
+    pub fn charges(&self, power_up: &PowerUp) -> u32 {
+        match power_up {
+            PowerUp::Swap(..) => self.swap,
+            PowerUp::Delete(..) => self.delete,
+            PowerUp::Undo => self.undo,
+        }
+    }
+
+    fn spend(&mut self, power_up: &PowerUp) {
+        let charges = match power_up {
+            PowerUp::Swap(..) => &mut self.swap,
+            PowerUp::Delete(..) => &mut self.delete,
+            PowerUp::Undo => &mut self.undo,
+        };
+        *charges -= 1;
+    }
+}
+
+/// Why a power-up could not be used. Nothing changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PowerUpError {
+    NoCharges,
+    /// A cell off the board.
+    OutOfBounds,
+    /// A swap or delete of an empty cell.
+    EmptyCell,
+    /// A swap of two equal tiles, which changes nothing.
+    SameTiles,
+    /// No move to undo, or the last one was already undone.
+    NothingToUndo,
+}
+
+impl fmt::Display for PowerUpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PowerUpError::NoCharges => write!(f, "no charges left"),
+            PowerUpError::OutOfBounds => write!(f, "the cell is off the board"),
+            PowerUpError::EmptyCell => write!(f, "the cell is empty"),
+            PowerUpError::SameTiles => write!(f, "the tiles are equal"),
+            PowerUpError::NothingToUndo => write!(f, "there is no move to undo"),
+        }
+    }
+}
+
+impl std::error::Error for PowerUpError {}
+
+impl GameBoard {
+    fn occupied(&self, (row, col): (usize, usize)) -> Result<u32, PowerUpError> {
+        match self.board.get(row).and_then(|cells| cells.get(col)) {
+            None => Err(PowerUpError::OutOfBounds),
+            Some(0) => Err(PowerUpError::EmptyCell),
+            Some(&value) => Ok(value),
+        }
+    }
+
+    /// The position after a swap or delete, or why it cannot be used here.
+    /// Undo needs the game's history; see [`PowerUpGame`].
+    pub fn with_power_up(&self, power_up: &PowerUp) -> Result<GameBoard, PowerUpError> {
+        let mut cells = self.board;
+        match *power_up {
+            PowerUp::Swap(a, b) => {
+                let (first, second) = (self.occupied(a)?, self.occupied(b)?);
+                if first == second {
+                    return Err(PowerUpError::SameTiles);
+                }
+                cells[a.0][a.1] = second;
+                cells[b.0][b.1] = first;
+            }
+            PowerUp::Delete(row, col) => {
+                self.occupied((row, col))?;
+                cells[row][col] = 0;
+            }
+            PowerUp::Undo => return Err(PowerUpError::NothingToUndo),
+        }
+        let mut board = self.clone();
+        board.set_board(cells);
+        Ok(board)
+    }
+
+    /// Every swap and delete usable on this position: deletes of each tile,
+    /// then swaps of each pair of unequal tiles, in row-major order.
+    pub fn power_up_options(&self, charges: &PowerUps) -> Vec<PowerUp> {
+        let tiles: Vec<(usize, usize)> =
+            (0..16).map(|cell| (cell / 4, cell % 4)).filter(|&(row, col)| self.board[row][col] != 0).collect();
+        let mut options = Vec::new();
+        if charges.delete > 0 {
+            options.extend(tiles.iter().map(|&(row, col)| PowerUp::Delete(row, col)));
+        }
+        if charges.swap > 0 {
+            for (i, &a) in tiles.iter().enumerate() {
+                for &b in &tiles[i + 1..] {
+                    if self.board[a.0][a.1] != self.board[b.0][b.1] {
+                        options.push(PowerUp::Swap(a, b));
+                    }
+                }
+            }
+        }
+        options
+    }
+}
+
+/// The part of a [`PowerUpGame`] an undo restores.
+#[derive(Debug, Clone)]
+struct Snapshot {
+    board: GameBoard,
+    score: u32,
+    merges: MergeBreakdown,
+}
+
+/// A game played with power-ups: the board, the charges left and the
+/// position before the last move, for undo.
+#[derive(Debug, Clone)]
+pub struct PowerUpGame {
+    board: GameBoard,
+    charges: PowerUps,
+    score: u32,
+    merges: MergeBreakdown,
+    /// The game before the last move, until it is undone.
+    previous: Option<Snapshot>,
+}
+
+impl PowerUpGame {
+    pub fn new(board: GameBoard, charges: PowerUps) -> Self {
+        Self { board, charges, score: 0, merges: MergeBreakdown::new(), previous: None }
+    }
+
+    pub fn board(&self) -> &GameBoard {
+        &self.board
+    }
+
+    pub fn charges(&self) -> &PowerUps {
+        &self.charges
+    }
+
+    /// Points from merges, less those of undone moves.
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    /// Merges of the moves that stand.
+    pub fn merges(&self) -> &MergeBreakdown {
+        &self.merges
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.charges.undo > 0 && self.previous.is_some()
+    }
+
+    /// Plays a full turn, remembering the position before it for undo.
+    pub fn play_move_with_rules<R: Rng + ?Sized>(&mut self, direction: Direction, rng: &mut R, rules: &SpawnRules) -> Result<MoveResult, MoveError> {
+        let before = Snapshot { board: self.board.clone(), score: self.score, merges: self.merges.clone() };
+        let result = self.board.play_move_with_rules(direction, rng, rules)?;
+        self.score += result.score_gained;
+        self.merges.record(&result);
+        self.previous = Some(before);
+        Ok(result)
+    }
+
+    /// Uses a charge of `power_up`.
+    pub fn use_power_up(&mut self, power_up: PowerUp) -> Result<(), PowerUpError> {
+        if self.charges.charges(&power_up) == 0 {
+            return Err(PowerUpError::NoCharges);
+        }
+        if power_up == PowerUp::Undo {
+            let snapshot = self.previous.take().ok_or(PowerUpError::NothingToUndo)?;
+            self.board = snapshot.board;
+            self.score = snapshot.score;
+            self.merges = snapshot.merges;
+        } else {
+            self.board = self.board.with_power_up(&power_up)?;
+        }
+        self.charges.spend(&power_up);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn test_power_ups_spend_charges_and_undo_restores_the_position() {
+        let board = GameBoard::from_compact("2,4,0,0/0,0,0,0/0,0,0,0/0,0,0,8").unwrap();
+        let mut game = PowerUpGame::new(board.clone(), PowerUps { swap: 1, delete: 1, undo: 1 });
+        assert_eq!(game.use_power_up(PowerUp::Undo), Err(PowerUpError::NothingToUndo));
+        assert_eq!(game.use_power_up(PowerUp::Delete(1, 1)), Err(PowerUpError::EmptyCell));
+        assert_eq!(game.use_power_up(PowerUp::Swap((0, 0), (4, 0))), Err(PowerUpError::OutOfBounds));
+
+        game.use_power_up(PowerUp::Swap((0, 0), (3, 3))).unwrap();
+        assert_eq!(game.board().to_compact(), "8,4,0,0/0,0,0,0/0,0,0,0/0,0,0,2");
+        assert_eq!(game.use_power_up(PowerUp::Swap((0, 0), (0, 1))), Err(PowerUpError::NoCharges));
+        game.use_power_up(PowerUp::Delete(0, 1)).unwrap();
+        assert_eq!(game.board().get_max_tile(), 8);
+        assert_eq!(game.board().count_empty_cells(), 14);
+
+        let before = game.board().clone();
+        let mut rng = StdRng::seed_from_u64(4);
+        game.play_move_with_rules(Direction::Down, &mut rng, &SpawnRules::default()).unwrap();
+        assert!(game.can_undo());
+        game.use_power_up(PowerUp::Undo).unwrap();
+        assert_eq!(game.board().get_board(), before.get_board());
+        assert_eq!(game.board().get_move_count(), before.get_move_count());
+        assert_eq!((game.score(), game.merges().total()), (0, 0));
+        assert_eq!(*game.charges(), PowerUps::default());
+
+        // Every tile can go, but only unequal tiles swap.
+        let options = board.power_up_options(&PowerUps { swap: 1, delete: 1, undo: 0 });
+        assert_eq!(options.len(), 3 + 3);
+        assert!(options.iter().all(|option| board.with_power_up(option).is_ok()));
+    }
+}
@@ -0,0 +1,444 @@
+use std::fmt;
+use std::io::{self, BufRead, Lines, Write};
+use std::iter::Enumerate;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use super::board::GameBoard;
+use super::move_result::{MergeBreakdown, SpawnedTile};
+use super::moves::Direction;
+use super::spawn::SpawnRules;
+use super::variant::{UnknownVariant, Variant};
+
+/// Version written in the header of replay files. Version 2 added the
+/// rules, the joker probability and move annotations.
+pub const REPLAY_VERSION: u32 = 2;
+
+/// First line of a replay file: everything needed to regenerate the game
+/// from its moves.
+///
+/// Readers ignore fields they do not know, so a file written by a newer
+/// version still reads as long as the fields below keep their meaning;
+/// fields added since version 1 default to a classic game.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayHeader {
+    pub version: u32,
+    pub seed: u64,
+    /// Name of the variant the game was played by, see [`Variant`].
+    #[serde(default = "classic_rules")]
+    pub rules: String,
+    pub four_probability: f32,
+    #[serde(default = "default_joker_probability")]
+    pub joker_probability: f32,
+    /// Opening position in compact notation, for readers that do not
+    /// regenerate it from the seed.
+    pub start: String,
+}
+
+fn classic_rules() -> String {
+    Variant::Classic.name().to_string()
+}
+
+fn default_joker_probability() -> f32 {
+    SpawnRules::default().joker_probability
+}
+
+impl ReplayHeader {
+    pub fn new(seed: u64, rules: &SpawnRules, start: &GameBoard) -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            seed,
+            rules: start.variant.name().to_string(),
+            four_probability: rules.four_probability,
+            joker_probability: rules.joker_probability,
+            start: start.to_compact(),
+        }
+    }
+
+    /// The variant named by [`ReplayHeader::rules`]; custom rule sets must
+    /// be registered before their replays are read.
+    pub fn variant(&self) -> Result<Variant, UnknownVariant> {
+        self.rules.parse()
+    }
+
+    pub fn spawn_rules(&self) -> SpawnRules {
+        SpawnRules {
+            four_probability: self.four_probability,
+            joker_probability: self.joker_probability,
+        }
+    }
+}
+
+/// What the engine thought of a recorded move.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MoveAnnotation {
+    /// Expectimax score of the move.
+    pub eval: f32,
+    /// Depth the search completed.
+    pub depth: u32,
+}
+
+/// One played move: the direction, the tile spawned after it, and the hash
+/// of the resulting position for spot-checking a replay.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayStep {
+    pub direction: Direction,
+    pub spawn: Option<SpawnedTile>,
+    pub hash: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotation: Option<MoveAnnotation>,
+}
+
+/// Writes a replay as JSON Lines: the header, then one step per move.
+/// Every line is flushed so a recording survives an interrupted game.
+pub struct ReplayWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> ReplayWriter<W> {
+    pub fn new(mut out: W, header: &ReplayHeader) -> io::Result<Self> {
+        serde_json::to_writer(&mut out, header)?;
+        writeln!(out)?;
+        Ok(Self { out })
+    }
+
+    pub fn record(&mut self, step: &ReplayStep) -> io::Result<()> {
+        serde_json::to_writer(&mut self.out, step)?;
+        writeln!(self.out)?;
+        self.out.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+fn invalid_line(line: usize, err: impl fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("replay line {}: {}", line, err))
+}
+
+/// Reads a replay written by [`ReplayWriter`] one step at a time, so a long
+/// game never has to be held in memory.
+pub struct ReplayReader<R: BufRead> {
+    header: ReplayHeader,
+    lines: Enumerate<Lines<R>>,
+}
+
+impl<R: BufRead> ReplayReader<R> {
+    /// Reads the header. Fails on files without one and on version 0,
+    /// which was never written.
+    pub fn new(input: R) -> io::Result<Self> {
+        let mut lines = input.lines().enumerate();
+        let header: ReplayHeader = loop {
+            match lines.next() {
+                Some((_, Ok(line))) if line.trim().is_empty() => continue,
+                Some((number, line)) => break serde_json::from_str(&line?).map_err(|err| invalid_line(number + 1, err))?,
+                None => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "empty replay")),
+            }
+        };
+        if header.version == 0 {
+            return Err(invalid_line(1, "replay version 0 is not supported"));
+        }
+        Ok(Self { header, lines })
+    }
+
+    pub fn header(&self) -> &ReplayHeader {
+        &self.header
+    }
+}
+
+impl<R: BufRead> Iterator for ReplayReader<R> {
+    type Item = io::Result<ReplayStep>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (number, line) = self.lines.find(|(_, line)| !matches!(line, Ok(l) if l.trim().is_empty()))?;
+        Some(line.and_then(|line| serde_json::from_str(&line).map_err(|err| invalid_line(number + 1, err))))
+    }
+}
+
+/// Reads a whole replay written by [`ReplayWriter`].
+pub fn read_replay<R: BufRead>(input: R) -> io::Result<(ReplayHeader, Vec<ReplayStep>)> {
+    let reader = ReplayReader::new(input)?;
+    let header = reader.header().clone();
+    let steps = reader.collect::<io::Result<_>>()?;
+    Ok((header, steps))
+}
+
+/// Every position of a recorded game, from the opening to the final board,
+/// rebuilt from the header's start position and the recorded spawns.
+pub fn replay_positions(header: &ReplayHeader, steps: &[ReplayStep]) -> Result<Vec<GameBoard>, ReplayError> {
+    let variant = header.variant().map_err(|_| ReplayError::UnknownRules)?;
+    let mut board = GameBoard::from_compact_with(&header.start, variant).map_err(|_| ReplayError::InvalidStart)?;
+    let mut positions = vec![board.clone()];
+    for (index, step) in steps.iter().enumerate() {
+        if !board.move_tiles(step.direction) {
+            return Err(ReplayError::IllegalMove { index, direction: step.direction });
+        }
+        if let Some(spawn) = step.spawn {
+            if spawn.row >= 4 || spawn.col >= 4 || board.board[spawn.row][spawn.col] != 0 {
+                return Err(ReplayError::InvalidSpawn { index });
+            }
+            board.place_tile(spawn.row, spawn.col, spawn.value);
+        }
+        if board.board_hash() != step.hash {
+            return Err(ReplayError::HashMismatch { index });
+        }
+        positions.push(board.clone());
+    }
+    Ok(positions)
+}
+
+/// Result of re-simulating a seeded game from its move list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayOutcome {
+    /// Standard 2048 score: the sum of every merged tile.
+    pub score: u32,
+    pub max_tile: u32,
+    pub moves_played: usize,
+    pub board: [[u32; 4]; 4],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayError {
+    /// Move `index` did not change the board, so it could not have been played.
+    IllegalMove { index: usize, direction: Direction },
+    /// The replay header names rules that are not registered.
+    UnknownRules,
+    /// The replay header's start position is not a valid board.
+    InvalidStart,
+    /// The tile recorded after move `index` does not land on an empty cell.
+    InvalidSpawn { index: usize },
+    /// The board after move `index` does not match the recorded hash.
+    HashMismatch { index: usize },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::IllegalMove { index, direction } => {
+                write!(f, "move {} ({:?}) does not change the board", index, direction)
+            }
+            ReplayError::UnknownRules => write!(f, "replay rules are not a known variant"),
+            ReplayError::InvalidStart => write!(f, "replay start position is not a valid board"),
+            ReplayError::InvalidSpawn { index } => write!(f, "spawn after move {} is not on an empty cell", index),
+            ReplayError::HashMismatch { index } => {
+                write!(f, "board after move {} does not match the recorded hash", index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+/// A seeded game rebuilt from its moves, ready to continue: `rng` is in
+/// exactly the state it had after the last recorded spawn.
+#[derive(Debug, Clone)]
+pub struct ResumedGame {
+    pub board: GameBoard,
+    pub rng: StdRng,
+    pub score: u32,
+    /// Merges made by the recorded moves.
+    pub merges: MergeBreakdown,
+}
+
+/// Replays `moves` on the game generated by `seed` and hands back the live
+/// game so play can continue where the recording stopped.
+pub fn resume_game(seed: u64, moves: &[Direction]) -> Result<ResumedGame, ReplayError> {
+    resume_game_with(seed, moves, &SpawnRules::default(), Variant::Classic)
+}
+
+/// Same as [`resume_game`] for a game of `variant` played with `rules`.
+pub fn resume_game_with(seed: u64, moves: &[Direction], rules: &SpawnRules, variant: Variant) -> Result<ResumedGame, ReplayError> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut board = match variant {
+        Variant::Classic => GameBoard::new_with_rules(&mut rng, rules),
+        _ => GameBoard::new_with_variant(&mut rng, variant),
+    };
+    let mut score = 0;
+    let mut merges = MergeBreakdown::new();
+    for (index, &direction) in moves.iter().enumerate() {
+        let result = board
+            .play_move_with_rules(direction, &mut rng, rules)
+            .map_err(|_| ReplayError::IllegalMove { index, direction })?;
+        score += result.score_gained;
+        merges.record(&result);
+    }
+    Ok(ResumedGame { board, rng, score, merges })
+}
+
+/// Replays `moves` on the game generated by `seed`. Spawns come from the
+/// same seeded RNG the game was played with, so the result is exact.
+pub fn simulate_replay(seed: u64, moves: &[Direction]) -> Result<ReplayOutcome, ReplayError> {
+    let game = resume_game(seed, moves)?;
+    Ok(ReplayOutcome {
+        score: game.score,
+        max_tile: game.board.get_max_tile(),
+        moves_played: moves.len(),
+        board: game.board.get_board(),
+    })
+}
+
+/// `true` if `moves` is a legal game for `seed` that ends on `claimed_score`.
+pub fn verify_replay(seed: u64, moves: &[Direction], claimed_score: u32) -> bool {
+    matches!(simulate_replay(seed, moves), Ok(outcome) if outcome.score == claimed_score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Plays a short seeded game, cycling directions and skipping illegal ones.
+    fn record_game(seed: u64, turns: usize) -> (Vec<Direction>, u32) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut game = GameBoard::new_with_rng(&mut rng);
+        let mut moves = Vec::new();
+        let mut score = 0;
+        for turn in 0..turns {
+            for offset in 0..4 {
+                let direction = Direction::all()[(turn + offset) % 4];
+                if let Ok(result) = game.play_move_with(direction, &mut rng) {
+                    moves.push(direction);
+                    score += result.score_gained;
+                    break;
+                }
+            }
+        }
+        (moves, score)
+    }
+
+    #[test]
+    fn test_replay_matches_recorded_game() {
+        let (moves, score) = record_game(42, 40);
+        let outcome = simulate_replay(42, &moves).unwrap();
+        assert_eq!(outcome.score, score);
+        assert_eq!(outcome.moves_played, moves.len());
+        assert!(verify_replay(42, &moves, score));
+        assert!(!verify_replay(42, &moves, score + 4));
+    }
+
+    #[test]
+    fn test_resumed_game_continues_identically() {
+        let (moves, _) = record_game(9, 30);
+        let (first, rest) = moves.split_at(moves.len() / 2);
+
+        let mut resumed = resume_game(9, first).unwrap();
+        for &direction in rest {
+            let result = resumed.board.play_move_with(direction, &mut resumed.rng).unwrap();
+            resumed.score += result.score_gained;
+        }
+        let full = simulate_replay(9, &moves).unwrap();
+        assert_eq!(resumed.board.get_board(), full.board);
+        assert_eq!(resumed.score, full.score);
+    }
+
+    #[test]
+    fn test_replay_rejects_illegal_move() {
+        // Find a seed whose opening position has a direction that moves nothing.
+        let (seed, direction) = (0..100u64)
+            .find_map(|seed| {
+                let start = GameBoard::new_with_rng(&mut StdRng::seed_from_u64(seed));
+                Direction::all()
+                    .into_iter()
+                    .find(|&d| start.clone().play_move(d).is_err())
+                    .map(|d| (seed, d))
+            })
+            .expect("some opening has an illegal move");
+
+        assert_eq!(
+            simulate_replay(seed, &[direction]),
+            Err(ReplayError::IllegalMove { index: 0, direction })
+        );
+        assert!(!verify_replay(seed, &[direction], 0));
+    }
+
+    #[test]
+    fn test_replay_writer_emits_header_and_steps() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut game = GameBoard::new_with_rng(&mut rng);
+        let header = ReplayHeader::new(5, &SpawnRules::default(), &game);
+        let mut writer = ReplayWriter::new(Vec::new(), &header).unwrap();
+        let direction = Direction::all()
+            .into_iter()
+            .find(|&d| game.child(d).is_some())
+            .unwrap();
+        let result = game.play_move_with(direction, &mut rng).unwrap();
+        let step = ReplayStep { direction, spawn: result.spawned, hash: game.board_hash(), annotation: None };
+        writer.record(&step).unwrap();
+
+        let text = String::from_utf8(writer.into_inner()).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(serde_json::from_str::<ReplayHeader>(lines[0]).unwrap(), header);
+        assert_eq!(serde_json::from_str::<ReplayStep>(lines[1]).unwrap(), step);
+    }
+
+    #[test]
+    fn test_read_replay_rebuilds_positions() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut game = GameBoard::new_with_rng(&mut rng);
+        let mut writer = ReplayWriter::new(Vec::new(), &ReplayHeader::new(11, &SpawnRules::default(), &game)).unwrap();
+        for turn in 0..30 {
+            let Some(direction) = (0..4).map(|o| Direction::all()[(turn + o) % 4]).find(|&d| game.child(d).is_some()) else {
+                break;
+            };
+            let result = game.play_move_with(direction, &mut rng).unwrap();
+            writer.record(&ReplayStep { direction, spawn: result.spawned, hash: game.board_hash(), annotation: None }).unwrap();
+        }
+
+        let bytes = writer.into_inner();
+        let (header, mut steps) = read_replay(&bytes[..]).unwrap();
+        let positions = replay_positions(&header, &steps).unwrap();
+        assert_eq!(positions.len(), steps.len() + 1);
+        assert_eq!(positions.last().unwrap().get_board(), game.get_board());
+
+        steps[3].hash ^= 1;
+        assert_eq!(replay_positions(&header, &steps).unwrap_err(), ReplayError::HashMismatch { index: 3 });
+        steps[3].hash ^= 1;
+        let recorded = steps[2].spawn.unwrap();
+        steps[2].spawn = Some(SpawnedTile { row: 9, ..recorded });
+        assert_eq!(replay_positions(&header, &steps).unwrap_err(), ReplayError::InvalidSpawn { index: 2 });
+        // A cell move 2's slide left occupied.
+        let occupied = (0..16)
+            .find(|&cell| positions[3].board[cell / 4][cell % 4] != 0 && (cell / 4, cell % 4) != (recorded.row, recorded.col))
+            .unwrap();
+        steps[2].spawn = Some(SpawnedTile { row: occupied / 4, col: occupied % 4, ..recorded });
+        assert_eq!(replay_positions(&header, &steps).unwrap_err(), ReplayError::InvalidSpawn { index: 2 });
+        assert!(read_replay(&b"{}\n"[..]).is_err());
+    }
+
+    #[test]
+    fn test_replay_format_reads_other_versions_and_variants() {
+        let v1 = r#"{"version":1,"seed":3,"four_probability":0.1,"start":"2,0,0,0/0,0,0,0/0,0,0,0/0,0,0,2"}"#;
+        let (header, steps) = read_replay(v1.as_bytes()).unwrap();
+        assert_eq!(header.variant(), Ok(Variant::Classic));
+        assert_eq!(header.spawn_rules(), SpawnRules::default());
+        assert!(steps.is_empty());
+
+        let mut game = GameBoard::from_compact_with("3,0,0,0/0,0,0,0/0,0,0,0/0,0,0,1", Variant::Threes).unwrap();
+        let header = ReplayHeader::new(3, &SpawnRules::default(), &game);
+        game.move_tiles(Direction::Right);
+        let step = ReplayStep {
+            direction: Direction::Right,
+            spawn: None,
+            hash: game.board_hash(),
+            annotation: Some(MoveAnnotation { eval: 12.5, depth: 3 }),
+        };
+        let mut writer = ReplayWriter::new(Vec::new(), &header).unwrap();
+        writer.record(&step).unwrap();
+        let bytes = writer.into_inner();
+        let mut reader = ReplayReader::new(&bytes[..]).unwrap();
+        assert_eq!(reader.header().rules, "threes");
+        assert_eq!(reader.next().unwrap().unwrap(), step);
+        assert!(reader.next().is_none());
+        assert_eq!(replay_positions(&header, &[step]).unwrap()[1].get_board(), game.get_board());
+
+        // A newer writer's extra fields are skipped.
+        let newer = r#"{"version":9,"seed":3,"rules":"classic","four_probability":0.1,"start":"2,0,0,0/0,0,0,0/0,0,0,0/0,0,0,2","clock":"x"}
+{"direction":"Up","spawn":null,"hash":1,"comment":"opening"}"#;
+        let (header, steps) = read_replay(newer.as_bytes()).unwrap();
+        assert_eq!((header.version, steps.len()), (9, 1));
+        assert!(read_replay(&br#"{"version":0,"seed":3,"four_probability":0.1,"start":""}"#[..]).is_err());
+    }
+}
@@ -0,0 +1,369 @@
+use rand::Rng;
+
+use super::board::GameBoard;
+use super::lines::{pack, unpack, MAX_PACKED_TILE, MERGE_LEFT};
+
+/// Everything that differs between 2048 and its variants: which tiles
+/// exist, how a line slides and merges, what spawns, when the game is won
+/// and how merges score. The board, its cached fields, the search and the
+/// variant evaluation only go through these methods, so a new rule set is
+/// one implementation plus an entry in [`crate::Variant`].
+pub trait GameRules: Sync {
+    /// Name in the variant registry, e.g. `classic`.
+    fn name(&self) -> &str;
+
+    /// Whether `value` is a tile of this game (0, an empty cell, is not).
+    fn is_tile(&self, value: u32) -> bool;
+
+    /// Position of `value` in the game's tile sequence: 0 for an empty
+    /// cell, 1 for the smallest tile, one more for each tile up (below 32).
+    /// Keys the Zobrist hash and stands in for tile size when evaluating.
+    fn rank(&self, value: u32) -> usize;
+
+    /// The tile that `first` and `second` merge into when `second` slides
+    /// onto `first`, if they merge at all.
+    fn merge(&self, first: u32, second: u32) -> Option<u32>;
+
+    /// Slides one line towards its first cell: the new line, whether it
+    /// changed, and a bit per output cell that holds a freshly merged tile.
+    /// By default tiles slide as far as they go and each pair that
+    /// [`GameRules::merge`] allows merges once, as in 2048.
+    fn slide_line(&self, line: &[u32; 4]) -> ([u32; 4], bool, u8) {
+        let mut tiles = line.iter().copied().filter(|&value| value != 0).peekable();
+        let mut new_line = [0; 4];
+        let mut merge_mask = 0u8;
+        let mut write = 0;
+        while let Some(tile) = tiles.next() {
+            match tiles.peek().and_then(|&next| self.merge(tile, next)) {
+                Some(merged) => {
+                    tiles.next();
+                    new_line[write] = merged;
+                    merge_mask |= 1 << write;
+                }
+                None => new_line[write] = tile,
+            }
+            write += 1;
+        }
+        (new_line, new_line != *line, merge_mask)
+    }
+
+    /// Tiles that can spawn with their relative weights; a chance node
+    /// weighs each placement by `weight / sum of weights`.
+    fn spawns(&self) -> &[(u32, u32)];
+
+    /// Reaching this tile wins the game.
+    fn win_tile(&self) -> u32;
+
+    /// Points for creating `merged`.
+    fn merge_score(&self, merged: u32) -> u32 {
+        merged
+    }
+
+    /// Mixed into the board hash so equal grids of different rules do not
+    /// share transposition entries.
+    fn hash_key(&self) -> u64;
+}
+
+/// Draws a spawned tile from `rules`' [`GameRules::spawns`].
+pub(crate) fn sample_spawn<R: Rng + ?Sized>(rules: &dyn GameRules, rng: &mut R) -> u32 {
+    let spawns = rules.spawns();
+    let total: u32 = spawns.iter().map(|&(_, weight)| weight).sum();
+    let mut draw = rng.gen_range(0..total);
+    for &(value, weight) in spawns {
+        if draw < weight {
+            return value;
+        }
+        draw -= weight;
+    }
+    unreachable!("draw is below the total weight")
+}
+
+/// 2048: tiles slide as far as they can and equal tiles merge into their
+/// sum. A 2 spawns, or a 4 with `SpawnRules::four_probability`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Classic2048;
+
+impl GameRules for Classic2048 {
+    fn name(&self) -> &str {
+        "classic"
+    }
+
+    fn is_tile(&self, value: u32) -> bool {
+        value >= 2 && value.is_power_of_two()
+    }
+
+    fn rank(&self, value: u32) -> usize {
+        if value == 0 {
+            0
+        } else {
+            value.trailing_zeros() as usize
+        }
+    }
+
+    fn merge(&self, first: u32, second: u32) -> Option<u32> {
+        (first != 0 && first == second).then(|| first * 2)
+    }
+
+    /// Looks the line up in the merge table while its tiles fit a packed
+    /// line key.
+    fn slide_line(&self, line: &[u32; 4]) -> ([u32; 4], bool, u8) {
+        if line.iter().all(|&value| value < MAX_PACKED_TILE) {
+            let key = pack(*line);
+            let (merged, merge_mask) = MERGE_LEFT[key as usize];
+            (unpack(merged as usize), merged != key, merge_mask)
+        } else {
+            GameBoard::merge_line(line)
+        }
+    }
+
+    fn spawns(&self) -> &[(u32, u32)] {
+        &[(2, 9), (4, 1)]
+    }
+
+    fn win_tile(&self) -> u32 {
+        2048
+    }
+
+    /// Zero, which keeps classic hashes (and the replay files that record
+    /// them) the same as before variants existed.
+    fn hash_key(&self) -> u64 {
+        0
+    }
+}
+
+/// Threes!: a 1 and a 2 merge into a 3, equal tiles from 3 up merge into
+/// their sum, and a move shifts each line by at most one cell, taking at
+/// most one merge per line. A 1, 2 or 3 spawns with equal chance on any
+/// empty cell (rather than at the trailing edge as in the original).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Threes;
+
+impl GameRules for Threes {
+    fn name(&self) -> &str {
+        "threes"
+    }
+
+    fn is_tile(&self, value: u32) -> bool {
+        value == 1 || value == 2 || (value.is_multiple_of(3) && (value / 3).is_power_of_two())
+    }
+
+    fn rank(&self, value: u32) -> usize {
+        match value {
+            0..=2 => value as usize,
+            _ => 3 + (value / 3).trailing_zeros() as usize,
+        }
+    }
+
+    fn merge(&self, first: u32, second: u32) -> Option<u32> {
+        match (first, second) {
+            (1, 2) | (2, 1) => Some(3),
+            (a, b) if a >= 3 && a == b => Some(a * 2),
+            _ => None,
+        }
+    }
+
+    /// Moves a line by one cell: the first gap or mergeable pair from the
+    /// front takes the move, and everything behind it steps forward.
+    fn slide_line(&self, line: &[u32; 4]) -> ([u32; 4], bool, u8) {
+        for i in 0..3 {
+            let (front, merge_mask) = if line[i] == 0 {
+                if line[i + 1..].iter().all(|&value| value == 0) {
+                    break;
+                }
+                (line[i + 1], 0)
+            } else if let Some(merged) = self.merge(line[i], line[i + 1]) {
+                (merged, 1 << i)
+            } else {
+                continue;
+            };
+            let mut shifted = *line;
+            shifted[i] = front;
+            shifted.copy_within(i + 2.., i + 1);
+            shifted[3] = 0;
+            return (shifted, true, merge_mask);
+        }
+        (*line, false, 0)
+    }
+
+    fn spawns(&self) -> &[(u32, u32)] {
+        &[(1, 1), (2, 1), (3, 1)]
+    }
+
+    /// The 6144, rarely seen in the original game.
+    fn win_tile(&self) -> u32 {
+        6144
+    }
+
+    /// The tile's points in Threes: 3 for a 3, tripling with every merge
+    /// after (9 for a 6, 27 for a 12, ...).
+    fn merge_score(&self, merged: u32) -> u32 {
+        3u32.saturating_pow(self.rank(merged) as u32 - 2)
+    }
+
+    fn hash_key(&self) -> u64 {
+        0x7423_e5a1_c0de_3333
+    }
+}
+
+/// 2584: tiles are Fibonacci numbers, slide as in 2048, and two neighbours
+/// of the sequence (1 and 1, 1 and 2, 2 and 3, 3 and 5, ...) merge into the
+/// next one. A 1 spawns, or a 2 one time in ten.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Fibonacci;
+
+impl Fibonacci {
+    /// Rank of `value` in 1, 2, 3, 5, 8, ... (1 for the 1), if it is one;
+    /// `None` for 0.
+    fn rank_of(value: u32) -> Option<usize> {
+        let (mut tile, mut next, mut rank) = (1u32, 2u32, 1);
+        while tile < value {
+            (tile, next) = (next, tile.saturating_add(next));
+            rank += 1;
+        }
+        (tile == value).then_some(rank)
+    }
+}
+
+impl GameRules for Fibonacci {
+    fn name(&self) -> &str {
+        "fibonacci"
+    }
+
+    fn is_tile(&self, value: u32) -> bool {
+        Self::rank_of(value).is_some()
+    }
+
+    fn rank(&self, value: u32) -> usize {
+        Self::rank_of(value).unwrap_or(0)
+    }
+
+    fn merge(&self, first: u32, second: u32) -> Option<u32> {
+        let (low, high) = (first.min(second), first.max(second));
+        let neighbours = match (Self::rank_of(low), Self::rank_of(high)) {
+            (Some(1), Some(1)) => true,
+            (Some(low), Some(high)) => high == low + 1,
+            _ => false,
+        };
+        neighbours.then(|| low + high)
+    }
+
+    fn spawns(&self) -> &[(u32, u32)] {
+        &[(1, 9), (2, 1)]
+    }
+
+    fn win_tile(&self) -> u32 {
+        2584
+    }
+
+    fn hash_key(&self) -> u64 {
+        0x2584_1b87_9e37_79b9
+    }
+}
+
+/// Value of the joker tile in [`Joker`] games, below every classic tile.
+pub const JOKER: u32 = 1;
+
+/// 2048 with a joker: an X tile that merges with any numbered tile into
+/// twice that tile, as if it were a copy of it. Two jokers do not merge.
+/// A joker spawns with `SpawnRules::joker_probability` (5% by default,
+/// which is also what the search assumes); otherwise 2s and 4s spawn as
+/// in 2048.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Joker;
+
+impl GameRules for Joker {
+    fn name(&self) -> &str {
+        "joker"
+    }
+
+    fn is_tile(&self, value: u32) -> bool {
+        value == JOKER || Classic2048.is_tile(value)
+    }
+
+    /// The joker ranks below a 2, one under every numbered tile.
+    fn rank(&self, value: u32) -> usize {
+        match value {
+            0 => 0,
+            JOKER => 1,
+            _ => value.trailing_zeros() as usize + 1,
+        }
+    }
+
+    fn merge(&self, first: u32, second: u32) -> Option<u32> {
+        match (first, second) {
+            (0, _) | (_, 0) | (JOKER, JOKER) => None,
+            (JOKER, tile) | (tile, JOKER) => Some(tile * 2),
+            _ => Classic2048.merge(first, second),
+        }
+    }
+
+    fn spawns(&self) -> &[(u32, u32)] {
+        &[(2, 171), (4, 19), (JOKER, 10)]
+    }
+
+    fn win_tile(&self) -> u32 {
+        2048
+    }
+
+    fn hash_key(&self) -> u64 {
+        0x0b0e_5a17_1ce5_0001
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_threes_shifts_by_one_cell_with_one_merge() {
+        let slide = |line| Threes.slide_line(&line);
+        assert_eq!(slide([0, 3, 0, 6]), ([3, 0, 6, 0], true, 0));
+        assert_eq!(slide([1, 2, 3, 3]), ([3, 3, 3, 0], true, 0b1));
+        assert_eq!(slide([6, 3, 3, 1]), ([6, 6, 1, 0], true, 0b10));
+        assert_eq!(slide([1, 1, 2, 2]), ([1, 3, 2, 0], true, 0b10));
+        assert_eq!(slide([3, 6, 1, 1]), ([3, 6, 1, 1], false, 0));
+        assert_eq!(slide([2, 0, 0, 0]), ([2, 0, 0, 0], false, 0));
+        assert_eq!([3, 6, 12].map(|tile| Threes.merge_score(tile)), [3, 9, 27]);
+    }
+
+    #[test]
+    fn test_fibonacci_neighbours_merge_once_per_slide() {
+        let slide = |line| Fibonacci.slide_line(&line);
+        assert_eq!(slide([1, 1, 1, 0]), ([2, 1, 0, 0], true, 0b1));
+        assert_eq!(slide([2, 0, 3, 5]), ([5, 5, 0, 0], true, 0b1));
+        assert_eq!(slide([8, 5, 2, 2]), ([13, 2, 2, 0], true, 0b1));
+        assert_eq!(slide([2, 5, 2, 5]), ([2, 5, 2, 5], false, 0));
+        assert_eq!(Fibonacci.merge(1597, 987), Some(2584));
+        assert_eq!(Fibonacci.merge(2, 2), None);
+        assert_eq!(Fibonacci.merge(0, 1), None);
+    }
+
+    #[test]
+    fn test_joker_doubles_any_numbered_tile() {
+        let slide = |line| Joker.slide_line(&line);
+        assert_eq!(slide([JOKER, 8, 4, 4]), ([16, 8, 0, 0], true, 0b11));
+        assert_eq!(slide([2, 0, JOKER, 0]), ([4, 0, 0, 0], true, 0b1));
+        assert_eq!(slide([JOKER, JOKER, 2, 0]), ([JOKER, 4, 0, 0], true, 0b10));
+        let total: u32 = Joker.spawns().iter().map(|&(_, weight)| weight).sum();
+        assert_eq!(Joker.spawns()[2], (JOKER, total / 20));
+    }
+
+    #[test]
+    fn test_classic_table_slide_matches_merge_line() {
+        for line in [[2, 2, 4, 4], [0, 2, 0, 2], [2, 4, 8, 16], [32768, 32768, 2, 0]] {
+            assert_eq!(Classic2048.slide_line(&line), GameBoard::merge_line(&line));
+        }
+    }
+
+    #[test]
+    fn test_ranks_are_distinct() {
+        let threes = [0, 1, 2, 3, 6, 12, 24, 48, 96, 192, 384, 768, 1536, 3072, 6144, 12288];
+        let ranks: Vec<_> = threes.iter().map(|&v| Threes.rank(v)).collect();
+        assert_eq!(ranks, (0..16).collect::<Vec<_>>());
+        assert!(threes[1..].iter().all(|&v| Threes.is_tile(v)));
+        assert_eq!(Classic2048.rank(2048), 11);
+        assert_eq!(Fibonacci.rank(2584), 17);
+        assert!(!Fibonacci.is_tile(4) && !Classic2048.is_tile(6) && !Threes.is_tile(4));
+        assert_eq!([JOKER, 2, 2048].map(|v| Joker.rank(v)), [1, 2, 12]);
+    }
+}
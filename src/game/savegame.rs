@@ -0,0 +1,221 @@
+//! Saved games in progress, as versioned JSON.
+//!
+//! A save holds the spawn seed and the moves played rather than the RNG
+//! itself: replaying the moves from the seed restores the board and the
+//! RNG state exactly, and checks the file along the way. Files written by
+//! older versions are migrated on load, one version at a time, so old
+//! saves keep resuming as the format grows.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::board::GameBoard;
+use super::moves::Direction;
+use super::replay::{resume_game, resume_game_with, ReplayError, ResumedGame};
+use super::spawn::SpawnRules;
+use super::variant::Variant;
+
+/// Version written by [`SaveGame::to_json`]. Version 1 files had only the
+/// seed, the moves and the score, and no version field; version 2 added
+/// the version, the rules the game is played by and the final board.
+pub const SAVE_VERSION: u32 = 2;
+
+/// A game in progress.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SaveGame {
+    pub version: u32,
+    pub seed: u64,
+    /// Name of the variant, see [`Variant`].
+    pub rules: String,
+    pub four_probability: f32,
+    pub joker_probability: f32,
+    /// One letter per move (`U`, `D`, `L`, `R`).
+    pub moves: String,
+    pub score: u32,
+    /// The position after the last move in compact notation, checked
+    /// against the replayed one on resume.
+    pub board: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SaveError {
+    /// The text is not JSON of a saved game.
+    Json(String),
+    /// A version this program cannot read: newer than [`SAVE_VERSION`], or
+    /// one no save was ever written with, such as 0.
+    UnsupportedVersion(u32),
+    InvalidMove(char),
+    UnknownRules(String),
+    /// The moves do not replay from the seed.
+    Replay(ReplayError),
+    /// The moves replay, but not to the saved score or board.
+    Mismatch(&'static str),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Json(err) => write!(f, "not a saved game: {}", err),
+            SaveError::UnsupportedVersion(version) => {
+                write!(f, "save version {} is not supported (this program reads 1 to {})", version, SAVE_VERSION)
+            }
+            SaveError::InvalidMove(letter) => write!(f, "invalid move letter '{}'", letter),
+            SaveError::UnknownRules(rules) => write!(f, "unknown rules '{}'", rules),
+            SaveError::Replay(err) => err.fmt(f),
+            SaveError::Mismatch(field) => write!(f, "saved {} does not match the replayed game", field),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(err: serde_json::Error) -> Self {
+        SaveError::Json(err.to_string())
+    }
+}
+
+impl SaveGame {
+    /// Saves `game`, reached from `seed` by `moves` under `rules`.
+    pub fn new(seed: u64, rules: &SpawnRules, game: &GameBoard, moves: &[Direction], score: u32) -> Self {
+        Self {
+            version: SAVE_VERSION,
+            seed,
+            rules: game.variant.name().to_string(),
+            four_probability: rules.four_probability,
+            joker_probability: rules.joker_probability,
+            moves: moves.iter().map(|d| d.letter()).collect(),
+            score,
+            board: game.to_compact(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a save serializes")
+    }
+
+    /// Reads a save written by this or any earlier version.
+    pub fn from_json(text: &str) -> Result<Self, SaveError> {
+        let mut value: Value = serde_json::from_str(text)?;
+        let mut version = match value.get("version") {
+            None => 1,
+            Some(version) => version
+                .as_u64()
+                .and_then(|version| u32::try_from(version).ok())
+                .ok_or_else(|| SaveError::Json("version is not a number".to_string()))?,
+        };
+        if version > SAVE_VERSION {
+            return Err(SaveError::UnsupportedVersion(version));
+        }
+        while version < SAVE_VERSION {
+            migrate(&mut value, version)?;
+            version += 1;
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    pub fn variant(&self) -> Result<Variant, SaveError> {
+        self.rules.parse().map_err(|_| SaveError::UnknownRules(self.rules.clone()))
+    }
+
+    pub fn spawn_rules(&self) -> SpawnRules {
+        SpawnRules {
+            four_probability: self.four_probability,
+            joker_probability: self.joker_probability,
+        }
+    }
+
+    pub fn directions(&self) -> Result<Vec<Direction>, SaveError> {
+        self.moves
+            .chars()
+            .map(|c| Direction::from_letter(c).ok_or(SaveError::InvalidMove(c)))
+            .collect()
+    }
+
+    /// Rebuilds the live game and its move history, rejecting saves whose
+    /// moves do not replay to the saved score and board.
+    pub fn resume(&self) -> Result<(ResumedGame, Vec<Direction>), SaveError> {
+        let moves = self.directions()?;
+        let game = resume_game_with(self.seed, &moves, &self.spawn_rules(), self.variant()?).map_err(SaveError::Replay)?;
+        if game.score != self.score {
+            return Err(SaveError::Mismatch("score"));
+        }
+        if game.board.to_compact() != self.board {
+            return Err(SaveError::Mismatch("board"));
+        }
+        Ok((game, moves))
+    }
+}
+
+/// Upgrades `value` from `version` to the next version.
+fn migrate(value: &mut Value, version: u32) -> Result<(), SaveError> {
+    let Value::Object(save) = value else {
+        return Err(SaveError::Json("expected an object".to_string()));
+    };
+    match version {
+        1 => {
+            // Version 1 only saved classic games with the standard spawns;
+            // the board it lacked comes from replaying its moves.
+            let v1: SaveV1 = serde_json::from_value(Value::Object(save.clone()))?;
+            let moves = v1
+                .moves
+                .chars()
+                .map(|c| Direction::from_letter(c).ok_or(SaveError::InvalidMove(c)))
+                .collect::<Result<Vec<_>, _>>()?;
+            let game = resume_game(v1.seed, &moves).map_err(SaveError::Replay)?;
+            let rules = SpawnRules::default();
+            save.insert("version".to_string(), 2.into());
+            save.insert("rules".to_string(), Variant::Classic.name().into());
+            save.insert("four_probability".to_string(), rules.four_probability.into());
+            save.insert("joker_probability".to_string(), rules.joker_probability.into());
+            save.insert("board".to_string(), game.board.to_compact().into());
+        }
+        _ => return Err(SaveError::UnsupportedVersion(version)),
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct SaveV1 {
+    seed: u64,
+    moves: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn test_version_1_saves_migrate_and_resume() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut game = GameBoard::new_with_rng(&mut rng);
+        let (mut moves, mut score) = (Vec::new(), 0);
+        for direction in Direction::all().into_iter().cycle().take(40) {
+            if let Ok(result) = game.play_move_with(direction, &mut rng) {
+                moves.push(direction);
+                score += result.score_gained;
+            }
+        }
+        let letters: String = moves.iter().map(|d| d.letter()).collect();
+        let v1 = format!(r#"{{"seed": 5, "moves": "{}", "score": {}}}"#, letters, score);
+
+        let save = SaveGame::from_json(&v1).unwrap();
+        assert_eq!(save, SaveGame::new(5, &SpawnRules::default(), &game, &moves, score));
+        assert_eq!(SaveGame::from_json(&save.to_json()).unwrap(), save);
+        let (resumed, history) = save.resume().unwrap();
+        assert_eq!(resumed.board.get_board(), game.get_board());
+        assert_eq!(history, moves);
+
+        let tampered = format!(r#"{{"seed": 5, "moves": "{}", "score": {}}}"#, letters, score + 4);
+        assert_eq!(SaveGame::from_json(&tampered).unwrap().resume().unwrap_err(), SaveError::Mismatch("score"));
+        let future = save.to_json().replace("\"version\": 2", "\"version\": 99");
+        assert_eq!(SaveGame::from_json(&future), Err(SaveError::UnsupportedVersion(99)));
+        let zero = save.to_json().replace("\"version\": 2", "\"version\": 0");
+        assert_eq!(SaveGame::from_json(&zero), Err(SaveError::UnsupportedVersion(0)));
+    }
+}
@@ -0,0 +1,80 @@
+use arrayvec::ArrayVec;
+use rand::Rng;
+
+use super::rules::JOKER;
+use super::variant::Variant;
+
+/// Which tile appears after each move: a 2, or a 4 with `four_probability`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpawnRules {
+    pub four_probability: f32,
+    /// Chance of a joker instead, in [`crate::Variant::Joker`] games only.
+    pub joker_probability: f32,
+}
+
+impl Default for SpawnRules {
+    fn default() -> Self {
+        Self { four_probability: 0.1, joker_probability: 0.05 }
+    }
+}
+
+impl SpawnRules {
+    pub(crate) fn sample_value<R: Rng + ?Sized>(&self, rng: &mut R) -> u32 {
+        if rng.gen::<f32>() < 1.0 - self.four_probability { 2 } else { 4 }
+    }
+
+    /// A joker game's spawn: a joker with `joker_probability`, otherwise
+    /// [`SpawnRules::sample_value`].
+    pub(crate) fn sample_joker_value<R: Rng + ?Sized>(&self, rng: &mut R) -> u32 {
+        if rng.gen::<f32>() < self.joker_probability {
+            JOKER
+        } else {
+            self.sample_value(rng)
+        }
+    }
+
+    /// The tiles a `variant` game spawns under these rules, with integer
+    /// weights in proportion to their chances (reduced, so the default
+    /// classic rules give `[(2, 9), (4, 1)]`). Variants other than classic
+    /// and joker spawn by their own [`crate::GameRules::spawns`].
+    pub fn weights(&self, variant: Variant) -> ArrayVec<(u32, u32), 3> {
+        let per_mille = |p: f32| (p.clamp(0.0, 1.0) * 1000.0).round() as u32;
+        let four = per_mille(self.four_probability);
+        let mut weights: ArrayVec<(u32, u32), 3> = match variant {
+            Variant::Classic => [(2, 1000 - four), (4, four)].into_iter().collect(),
+            Variant::Joker => {
+                let joker = per_mille(self.joker_probability);
+                [(2, (1000 - joker) * (1000 - four)), (4, (1000 - joker) * four), (JOKER, joker * 1000)]
+                    .into_iter()
+                    .collect()
+            }
+            _ => variant.rules().spawns().iter().copied().collect(),
+        };
+        weights.retain(|&mut (_, weight)| weight > 0);
+        let divisor = weights.iter().fold(0, |a, &(_, weight)| gcd(a, weight));
+        for (_, weight) in &mut weights {
+            *weight /= divisor;
+        }
+        weights
+    }
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_weights_match_the_variant_tables() {
+        for variant in [Variant::Classic, Variant::Joker, Variant::Fibonacci] {
+            assert_eq!(SpawnRules::default().weights(variant).as_slice(), variant.rules().spawns());
+        }
+        let twos_only = SpawnRules { four_probability: 0.0, ..SpawnRules::default() };
+        assert_eq!(twos_only.weights(Variant::Classic).as_slice(), &[(2, 1)]);
+        let even = SpawnRules { four_probability: 0.5, ..SpawnRules::default() };
+        assert_eq!(even.weights(Variant::Classic).as_slice(), &[(2, 1), (4, 1)]);
+    }
+}
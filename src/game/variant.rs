@@ -0,0 +1,197 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::RwLock;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::custom_rules::{CustomRules, InvalidRules};
+use super::rules::{Classic2048, Fibonacci, GameRules, Joker, Threes};
+
+/// Rule sets added with [`Variant::register`], by [`Variant::Custom`] index.
+static CUSTOM: RwLock<Vec<&'static CustomRules>> = RwLock::new(Vec::new());
+
+/// Registry of the rule sets a [`crate::GameBoard`] can play by. Every
+/// variant shares the board, its cached fields and the search; its
+/// [`GameRules`] decide how lines slide, what spawns and how it scores.
+/// Variants serialize as their names and deserialize only from registered
+/// ones.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Variant {
+    /// 2048, see [`Classic2048`].
+    #[default]
+    Classic,
+    /// Threes!-style merges and one-cell moves, see [`Threes`].
+    Threes,
+    /// 2584, merging Fibonacci neighbours, see [`Fibonacci`].
+    Fibonacci,
+    /// 2048 with a wildcard tile, see [`Joker`].
+    Joker,
+    /// A user-defined rule set added with [`Variant::register`].
+    Custom(CustomId),
+}
+
+/// The registry slot of a [`Variant::Custom`]. Only
+/// [`Variant::register`] hands these out, so every one names registered
+/// rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CustomId(u8);
+
+impl Variant {
+    /// Every built-in variant.
+    pub const ALL: [Variant; 4] = [Variant::Classic, Variant::Threes, Variant::Fibonacci, Variant::Joker];
+
+    pub fn rules(self) -> &'static dyn GameRules {
+        match self {
+            Variant::Classic => &Classic2048,
+            Variant::Threes => &Threes,
+            Variant::Fibonacci => &Fibonacci,
+            Variant::Joker => &Joker,
+            Variant::Custom(CustomId(index)) => CUSTOM.read().unwrap()[index as usize],
+        }
+    }
+
+    /// Adds `rules` to the registry, after which the returned variant plays
+    /// by them and parses from their name. Registered rules live for the
+    /// rest of the program.
+    pub fn register(rules: CustomRules) -> Result<Variant, InvalidRules> {
+        // Checked under the write guard so two threads cannot both register
+        // the same name.
+        let mut custom = CUSTOM.write().unwrap();
+        let mut names = Variant::ALL.iter().map(|variant| variant.name()).chain(custom.iter().map(|other| other.name()));
+        if names.any(|name| name.eq_ignore_ascii_case(rules.name())) {
+            return Err(InvalidRules::NameTaken(rules.name().to_string()));
+        }
+        let index = u8::try_from(custom.len()).map_err(|_| InvalidRules::RegistryFull)?;
+        custom.push(Box::leak(Box::new(rules)));
+        Ok(Variant::Custom(CustomId(index)))
+    }
+
+    /// The built-in variants followed by every registered one.
+    pub fn registered() -> Vec<Variant> {
+        let custom = CUSTOM.read().unwrap().len() as u8;
+        Variant::ALL.into_iter().chain((0..custom).map(|index| Variant::Custom(CustomId(index)))).collect()
+    }
+
+    pub fn name(self) -> &'static str {
+        self.rules().name()
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Serialize for Variant {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for Variant {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// A name that is not in the registry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownVariant(pub String);
+
+impl fmt::Display for UnknownVariant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = Variant::registered().iter().map(|variant| variant.name()).collect();
+        write!(f, "unknown variant '{}' (expected one of: {})", self.0, names.join(", "))
+    }
+}
+
+impl std::error::Error for UnknownVariant {}
+
+impl FromStr for Variant {
+    type Err = UnknownVariant;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Variant::registered()
+            .into_iter()
+            .find(|variant| variant.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| UnknownVariant(name.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::ai::SearchConfig;
+    use crate::game::MergeRule;
+    use crate::cache::TranspositionState;
+    use crate::game::{Direction, GameBoard};
+
+    #[test]
+    fn test_registry_names_round_trip() {
+        for variant in Variant::ALL {
+            assert_eq!(variant.name().parse::<Variant>(), Ok(variant));
+            assert_eq!(serde_json::to_string(&variant).unwrap(), format!("\"{}\"", variant));
+        }
+        assert_eq!("Threes".parse::<Variant>(), Ok(Variant::Threes));
+        assert_eq!(serde_json::from_str::<Variant>("\"fibonacci\"").unwrap(), Variant::Fibonacci);
+        assert!(serde_json::from_str::<Variant>("{\"custom\": 9}").is_err());
+        assert!(serde_json::from_str::<Variant>("\"hex\"").is_err());
+        assert!("hex".parse::<Variant>().unwrap_err().to_string().contains("classic, threes, fibonacci, joker"));
+    }
+
+    #[test]
+    fn test_registered_rules_parse_and_play() {
+        let rules = CustomRules::new("trinity", &[(3, 2), (9, 1)], MergeRule::Multiply(3), 729).unwrap();
+        let variant = Variant::register(rules).unwrap();
+        assert_eq!("Trinity".parse::<Variant>(), Ok(variant));
+        let json = serde_json::to_string(&variant).unwrap();
+        assert_eq!((json.as_str(), serde_json::from_str::<Variant>(&json).unwrap()), ("\"trinity\"", variant));
+        assert!(Variant::registered().contains(&variant));
+        let again = CustomRules::new("classic", &[(2, 1)], MergeRule::Multiply(2), 2048).unwrap();
+        assert_eq!(Variant::register(again).unwrap_err(), InvalidRules::NameTaken("classic".into()));
+        let racers: Vec<_> = (0..4)
+            .map(|_| std::thread::spawn(|| Variant::register(CustomRules::new("Racer", &[(2, 1)], MergeRule::Multiply(2), 8).unwrap())))
+            .collect();
+        let won = racers.into_iter().filter_map(|racer| racer.join().unwrap().ok()).count();
+        assert_eq!(won, 1);
+
+        let mut game = GameBoard::new();
+        game.set_board([
+            [3, 3, 3, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0],
+        ]);
+        game.set_variant(variant);
+        let result = game.play_move_without_spawn(Direction::Left).unwrap();
+        assert_eq!(game.board[0], [9, 3, 0, 0]);
+        assert_eq!(result.score_gained, 9);
+        assert!(game.search_with(&SearchConfig::with_depth(2), &mut TranspositionState::new()).best_move.is_some());
+    }
+
+    #[test]
+    fn test_variant_games_spawn_their_own_tiles_and_search() {
+        for variant in [Variant::Threes, Variant::Fibonacci, Variant::Joker] {
+            let mut rng = StdRng::seed_from_u64(3);
+            let mut game = GameBoard::new_with_variant(&mut rng, variant);
+            let mut tt = TranspositionState::new();
+            for _ in 0..20 {
+                let Some(direction) = game.search_with(&SearchConfig::with_depth(2), &mut tt).best_move else { break };
+                let result = game.play_move_with(direction, &mut rng).unwrap();
+                assert!(result.spawned.is_none_or(|tile| variant.rules().spawns().iter().any(|&(v, _)| v == tile.value)));
+                let sliding = Direction::all().into_iter().filter(|&d| {
+                    let mut copy = game.clone();
+                    copy.legal_moves = 0xF;
+                    copy.move_tiles(d)
+                });
+                assert_eq!(sliding.fold(0, |mask, d| mask | d.bit()), game.legal_moves);
+            }
+            assert_eq!(game.variant, variant);
+            assert!(game.board.iter().flatten().all(|&v| v == 0 || variant.rules().is_tile(v)));
+        }
+    }
+}
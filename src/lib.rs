@@ -1,7 +1,22 @@
 pub mod game;
 pub mod ai;
 pub mod cache;
+pub mod utils;
+pub mod stats;
+pub mod env;
+pub mod observer;
+pub mod session;
+#[cfg(feature = "export")]
+pub mod export;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "web")]
+pub mod web;
  
-pub use game::{GameBoard, Direction};
-pub use cache::{clear_cache, get_cache_stats, with_thread_tt, TranspositionState};
-pub use ai::EvaluationWeights; 
\ No newline at end of file
+pub use game::{Achievement, AchievementTracker, Unlocked, BattleGame, BattleTurn, CustomRules, GameBoard, GameRules, IllegalPosition, InvalidRules, JOKER, MergeRule, Direction, MergeBreakdown, MoveError, MoveResult, MergedTile, PowerUp, PowerUpError, PowerUpGame, PowerUps, RandomBoardError, SpawnRules, SpawnedTile, UndoInfo, EmptyCells, HexBoard, HexDirection, UnknownVariant, Variant};
+pub use observer::{GameObserver, MoveEvent, Observers};
+pub use session::{AiMove, GameSession};
+pub use cache::{clear_cache, get_cache_stats, get_eval_cache_stats, maintain_cache, with_thread_tt, EntryQuality, Maintenance, TranspositionState, MAINTENANCE_INTERVAL};
+pub use ai::{rate_difficulty, rate_difficulty_with, Coach, ConfigDiff, MoveDelta, PvStep, Difficulty, GameReview, MoveReview, EvaluationTerm, EvaluationWeights, OptimizedEvaluationWeights, MovePuzzle, Preview, PuzzleGenerator, PuzzleGoal, PuzzleGrade, PuzzleSolution, MoveEstimate, RolloutConfig, RolloutResult, ScoreModel, ChanceSampling, OrderingStats, PowerUpPlayer, PowerUpTurn, SearchConfig, SearchProfile, SearchResult, Budget, TimeAttack, SearchTrace, TraceEvent, WeightAdapter};
\ No newline at end of file
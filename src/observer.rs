@@ -0,0 +1,209 @@
+//! Hooks on a game as it is played: a [`GameObserver`] hears about every
+//! move, merge, spawn and milestone and the end of the game, so logging,
+//! achievements and live displays hang off the game loop instead of being
+//! written into it.
+
+use crate::ai::SearchResult;
+use crate::game::{AchievementTracker, Direction, GameBoard, MergedTile, MoveResult, SpawnedTile};
+
+/// Smallest tile whose first merge is a milestone; every larger tile after
+/// it is one too.
+pub const MILESTONE_TILE: u32 = 256;
+
+/// A played move, as observers see it.
+#[derive(Debug, Clone, Copy)]
+pub struct MoveEvent<'a> {
+    /// 1 for the first move of the game.
+    pub move_number: u32,
+    pub direction: Direction,
+    pub result: &'a MoveResult,
+    /// Points scored so far, this move's included.
+    pub score: u32,
+    /// The search that chose the move, when the AI played it.
+    pub search: Option<&'a SearchResult>,
+}
+
+/// Callbacks on a game's events; each defaults to doing nothing. `board`
+/// is always the position after the move and its spawn.
+pub trait GameObserver {
+    /// A move was played. Called before the move's other events.
+    fn on_move(&mut self, _board: &GameBoard, _event: &MoveEvent) {}
+
+    /// One merge of the move.
+    fn on_merge(&mut self, _board: &GameBoard, _merge: &MergedTile) {}
+
+    /// The tile spawned after the move.
+    fn on_spawn(&mut self, _board: &GameBoard, _tile: &SpawnedTile) {}
+
+    /// A merge made `tile`, the largest tile merged so far and at least
+    /// [`MILESTONE_TILE`].
+    fn on_milestone(&mut self, _board: &GameBoard, _tile: u32) {}
+
+    /// The move left no legal move.
+    fn on_game_over(&mut self, _board: &GameBoard) {}
+}
+
+impl<T: GameObserver + ?Sized> GameObserver for &mut T {
+    fn on_move(&mut self, board: &GameBoard, event: &MoveEvent) {
+        (**self).on_move(board, event)
+    }
+
+    fn on_merge(&mut self, board: &GameBoard, merge: &MergedTile) {
+        (**self).on_merge(board, merge)
+    }
+
+    fn on_spawn(&mut self, board: &GameBoard, tile: &SpawnedTile) {
+        (**self).on_spawn(board, tile)
+    }
+
+    fn on_milestone(&mut self, board: &GameBoard, tile: u32) {
+        (**self).on_milestone(board, tile)
+    }
+
+    fn on_game_over(&mut self, board: &GameBoard) {
+        (**self).on_game_over(board)
+    }
+}
+
+impl GameObserver for AchievementTracker {
+    fn on_move(&mut self, board: &GameBoard, _event: &MoveEvent) {
+        self.observe(board);
+    }
+}
+
+/// The observers registered on a game, told about each move in the order
+/// they were added. Register a `&mut` observer to read it back once the
+/// game is done.
+#[derive(Default)]
+pub struct Observers<'a> {
+    observers: Vec<Box<dyn GameObserver + 'a>>,
+    /// Largest tile merged so far, for milestones.
+    best_merge: u32,
+}
+
+impl<'a> Observers<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add<O: GameObserver + 'a>(&mut self, observer: O) {
+        self.observers.push(Box::new(observer));
+    }
+
+    pub fn len(&self) -> usize {
+        self.observers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.observers.is_empty()
+    }
+
+    /// Reports a played move: `on_move`, then `on_merge` for each merge,
+    /// `on_spawn`, `on_milestone` and, when `board` is locked,
+    /// `on_game_over`.
+    pub fn notify_move(&mut self, board: &GameBoard, event: &MoveEvent) {
+        let milestone = event.result.merged.iter().map(|merge| merge.value).max().filter(|&tile| {
+            tile >= MILESTONE_TILE && tile > self.best_merge
+        });
+        for merge in &event.result.merged {
+            self.best_merge = self.best_merge.max(merge.value);
+        }
+        for observer in &mut self.observers {
+            observer.on_move(board, event);
+            for merge in &event.result.merged {
+                observer.on_merge(board, merge);
+            }
+            if let Some(tile) = &event.result.spawned {
+                observer.on_spawn(board, tile);
+            }
+            if let Some(tile) = milestone {
+                observer.on_milestone(board, tile);
+            }
+            if board.is_game_over() {
+                observer.on_game_over(board);
+            }
+        }
+    }
+
+    /// Reports a game that ended with legal moves left, e.g. by
+    /// resignation or a spent move limit; [`Observers::notify_move`]
+    /// reports locked boards.
+    pub fn notify_game_over(&mut self, board: &GameBoard) {
+        for observer in &mut self.observers {
+            observer.on_game_over(board);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Counts {
+        moves: u32,
+        merges: u64,
+        spawns: u32,
+        milestones: Vec<u32>,
+        game_over: bool,
+    }
+
+    impl GameObserver for Counts {
+        fn on_move(&mut self, _board: &GameBoard, event: &MoveEvent) {
+            assert_eq!(event.move_number, self.moves + 1);
+            self.moves += 1;
+        }
+
+        fn on_merge(&mut self, _board: &GameBoard, _merge: &MergedTile) {
+            self.merges += 1;
+        }
+
+        fn on_spawn(&mut self, _board: &GameBoard, _tile: &SpawnedTile) {
+            self.spawns += 1;
+        }
+
+        fn on_milestone(&mut self, _board: &GameBoard, tile: u32) {
+            self.milestones.push(tile);
+        }
+
+        fn on_game_over(&mut self, _board: &GameBoard) {
+            self.game_over = true;
+        }
+    }
+
+    #[test]
+    fn test_observers_hear_every_event_of_a_game() {
+        let mut counts = Counts::default();
+        let mut tracker = AchievementTracker::new();
+        let mut observers = Observers::new();
+        observers.add(&mut counts);
+        observers.add(&mut tracker);
+
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut board = GameBoard::from_compact("128,128,0,0/0,0,0,0/0,0,0,0/256,256,0,0").unwrap();
+        let mut score = 0;
+        let mut merges = crate::game::MergeBreakdown::new();
+        // The opening move makes a 256 and a 512: one milestone, the larger.
+        let mut first = Some(Direction::Left);
+        while !board.is_game_over() {
+            let direction = first.take().unwrap_or_else(|| Direction::all().into_iter().find(|&d| board.can_move(d)).unwrap());
+            let result = board.play_move_with(direction, &mut rng).unwrap();
+            score += result.score_gained;
+            merges.record(&result);
+            let event = MoveEvent { move_number: board.get_move_count(), direction, result: &result, score, search: None };
+            observers.notify_move(&board, &event);
+        }
+        drop(observers);
+
+        assert_eq!(counts.moves, board.get_move_count());
+        assert_eq!(counts.merges, merges.total());
+        assert_eq!(counts.spawns, counts.moves);
+        assert_eq!(counts.milestones[0], 512);
+        assert!(counts.milestones.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(counts.game_over);
+        assert!(tracker.has(crate::game::Achievement::FirstTile(512)));
+    }
+}
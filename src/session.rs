@@ -0,0 +1,350 @@
+//! One game from start to finish: the board, its seeded RNG, the moves
+//! played, the clock and the AI's settings, behind the move, spawn and
+//! game-over steps every front-end needs. Registered [`GameObserver`]s hear
+//! about each move whoever played it.
+
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::ai::{SearchConfig, SearchResult, TimeAttack};
+use crate::cache::maintain_cache;
+use crate::game::replay::ResumedGame;
+use crate::game::savegame::SaveGame;
+use crate::game::{Direction, GameBoard, MergeBreakdown, MoveError, MoveResult, SpawnRules, Variant};
+use crate::observer::{GameObserver, MoveEvent, Observers};
+
+/// Entries [`GameSession::run_to_completion`] keeps the transposition table
+/// under, as self-play does by default.
+pub const DEFAULT_MAX_CACHE_ENTRIES: usize = 1_000_000;
+
+/// A move the AI chose and played.
+#[derive(Debug, Clone)]
+pub struct AiMove {
+    pub search: SearchResult,
+    /// `None` when the search found the position lost and the session
+    /// resigned instead (see [`SearchResult::hopeless`]).
+    pub result: Option<MoveResult>,
+}
+
+/// A game being played, by a person, the AI or both.
+pub struct GameSession<'a> {
+    seed: u64,
+    board: GameBoard,
+    rng: StdRng,
+    spawn: SpawnRules,
+    search: SearchConfig,
+    history: Vec<Direction>,
+    last_move: Option<MoveResult>,
+    score: u32,
+    merges: MergeBreakdown,
+    started: Instant,
+    /// When the last move was played, or the session started; a human
+    /// move's time counts from here.
+    turn_started: Instant,
+    time_attack: Option<TimeAttack>,
+    max_moves: u32,
+    max_cache_entries: usize,
+    resigned: bool,
+    observers: Observers<'a>,
+}
+
+impl<'a> GameSession<'a> {
+    /// A new game whose spawns all come from `seed`. The AI searches with
+    /// `search`, expecting the spawns of `spawn`.
+    pub fn new(seed: u64, spawn: SpawnRules, search: SearchConfig) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let board = GameBoard::new_with_rules(&mut rng, &spawn);
+        Self::start(seed, board, rng, spawn, search)
+    }
+
+    /// [`GameSession::new`] for a game of `variant`.
+    pub fn new_with_variant(seed: u64, variant: Variant, spawn: SpawnRules, search: SearchConfig) -> Self {
+        if variant == Variant::Classic {
+            return Self::new(seed, spawn, search);
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        let board = GameBoard::new_with_variant(&mut rng, variant);
+        Self::start(seed, board, rng, spawn, search)
+    }
+
+    /// Goes on with a game rebuilt from its `history` (see
+    /// [`crate::game::replay::resume_game_with`]).
+    pub fn resume(seed: u64, resumed: ResumedGame, history: Vec<Direction>, spawn: SpawnRules, search: SearchConfig) -> Self {
+        let mut session = Self::start(seed, resumed.board, resumed.rng, spawn, search);
+        session.history = history;
+        session.score = resumed.score;
+        session.merges = resumed.merges;
+        session
+    }
+
+    fn start(seed: u64, board: GameBoard, rng: StdRng, spawn: SpawnRules, search: SearchConfig) -> Self {
+        Self {
+            seed,
+            board,
+            rng,
+            spawn,
+            search: SearchConfig { spawn, ..search },
+            history: Vec::new(),
+            last_move: None,
+            score: 0,
+            merges: MergeBreakdown::new(),
+            started: Instant::now(),
+            turn_started: Instant::now(),
+            time_attack: None,
+            max_moves: u32::MAX,
+            max_cache_entries: DEFAULT_MAX_CACHE_ENTRIES,
+            resigned: false,
+            observers: Observers::new(),
+        }
+    }
+
+    /// Plays against a budget of moves or time; the AI paces its searches
+    /// to it and the game is over once it is spent.
+    pub fn with_time_attack(mut self, time_attack: TimeAttack) -> Self {
+        self.time_attack = Some(time_attack);
+        self
+    }
+
+    /// Ends the game after `max_moves` moves, counting resumed ones.
+    pub fn with_max_moves(mut self, max_moves: u32) -> Self {
+        self.max_moves = max_moves;
+        self
+    }
+
+    /// Entries [`GameSession::run_to_completion`] keeps the transposition
+    /// table under.
+    pub fn with_max_cache_entries(mut self, entries: usize) -> Self {
+        self.max_cache_entries = entries;
+        self
+    }
+
+    pub fn add_observer<O: GameObserver + 'a>(&mut self, observer: O) {
+        self.observers.add(observer);
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn board(&self) -> &GameBoard {
+        &self.board
+    }
+
+    pub fn spawn_rules(&self) -> &SpawnRules {
+        &self.spawn
+    }
+
+    pub fn search_config(&self) -> &SearchConfig {
+        &self.search
+    }
+
+    /// Every move played, resumed ones included.
+    pub fn history(&self) -> &[Direction] {
+        &self.history
+    }
+
+    /// What the latest move played in this session changed; `None` before
+    /// the first one.
+    pub fn last_move(&self) -> Option<&MoveResult> {
+        self.last_move.as_ref()
+    }
+
+    /// Points from merges.
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    pub fn merges(&self) -> &MergeBreakdown {
+        &self.merges
+    }
+
+    pub fn time_attack(&self) -> Option<&TimeAttack> {
+        self.time_attack.as_ref()
+    }
+
+    /// Wall-clock time since the session started.
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// The AI gave the game up as lost.
+    pub fn resigned(&self) -> bool {
+        self.resigned
+    }
+
+    /// No move is left, the AI resigned, or the move limit or time-attack
+    /// budget is spent.
+    pub fn is_over(&self) -> bool {
+        self.board.is_game_over()
+            || self.resigned
+            || self.history.len() >= self.max_moves as usize
+            || self.time_attack.is_some_and(|attack| attack.is_over())
+    }
+
+    /// Plays `direction` and its spawn.
+    pub fn play_human_move(&mut self, direction: Direction) -> Result<MoveResult, MoveError> {
+        if self.is_over() {
+            return Err(MoveError::GameAlreadyOver);
+        }
+        let result = self.board.play_move_with_rules(direction, &mut self.rng, &self.spawn)?;
+        self.record(&result, None, self.turn_started.elapsed());
+        Ok(result)
+    }
+
+    /// The AI's search of the current position.
+    pub fn search(&self) -> SearchResult {
+        match self.time_attack {
+            Some(attack) => self.board.search(&SearchConfig { time_attack: Some(attack), ..self.search.clone() }),
+            None => self.board.search(&self.search),
+        }
+    }
+
+    /// Searches the position and plays the best move, or resigns when the
+    /// search finds the game lost.
+    pub fn play_ai_move(&mut self) -> Result<AiMove, MoveError> {
+        if self.is_over() {
+            return Err(MoveError::GameAlreadyOver);
+        }
+        let move_start = Instant::now();
+        let search = self.search();
+        if search.hopeless {
+            self.resigned = true;
+            self.observers.notify_game_over(&self.board);
+            return Ok(AiMove { search, result: None });
+        }
+        let direction = search.best_move.ok_or(MoveError::GameAlreadyOver)?;
+        let result = self.board.play_move_with_rules(direction, &mut self.rng, &self.spawn)?;
+        self.record(&result, Some(&search), move_start.elapsed());
+        Ok(AiMove { search, result: Some(result) })
+    }
+
+    /// Lets the AI play until the game is over; returns the moves it played.
+    pub fn run_to_completion(&mut self) -> u32 {
+        let mut played = 0;
+        while let Ok(ai_move) = self.play_ai_move() {
+            if ai_move.result.is_none() {
+                break;
+            }
+            played += 1;
+            maintain_cache(self.history.len() as u32, self.max_cache_entries);
+        }
+        played
+    }
+
+    /// The game as a save file.
+    pub fn save_game(&self) -> SaveGame {
+        SaveGame::new(self.seed, &self.spawn, &self.board, &self.history, self.score)
+    }
+
+    /// Counts a move that took `took` against the score, the history and
+    /// any time-attack budget, and reports it. Observers hear of the game
+    /// ending once, whether the board locked or a limit was reached.
+    fn record(&mut self, result: &MoveResult, search: Option<&SearchResult>, took: Duration) {
+        if let Some(attack) = &mut self.time_attack {
+            attack.record_move(took);
+        }
+        self.score += result.score_gained;
+        self.merges.record(result);
+        self.history.push(result.direction);
+        let event = MoveEvent {
+            move_number: self.history.len() as u32,
+            direction: result.direction,
+            result,
+            score: self.score,
+            search,
+        };
+        self.observers.notify_move(&self.board, &event);
+        // notify_move already reported a locked board.
+        if !self.board.is_game_over() && self.is_over() {
+            self.observers.notify_game_over(&self.board);
+        }
+        self.last_move = Some(result.clone());
+        self.turn_started = Instant::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Budget;
+    use crate::game::replay::resume_game_with;
+
+    #[derive(Default)]
+    struct Moves(Vec<(u32, bool)>);
+
+    impl GameObserver for Moves {
+        fn on_move(&mut self, _board: &GameBoard, event: &MoveEvent) {
+            self.0.push((event.move_number, event.search.is_some()));
+        }
+    }
+
+    #[test]
+    fn test_sessions_play_seeded_games_and_report_every_move() {
+        let spawn = SpawnRules::default();
+        let mut moves = Moves::default();
+        let mut session = GameSession::new(12, spawn, SearchConfig::with_depth(1)).with_max_moves(60);
+        session.add_observer(&mut moves);
+        let direction = Direction::all().into_iter().find(|&d| session.board().can_move(d)).unwrap();
+        session.play_human_move(direction).unwrap();
+        let ai_move = session.play_ai_move().unwrap();
+        assert_eq!(Some(ai_move.result.unwrap().direction), ai_move.search.best_move);
+        let played = session.run_to_completion();
+        assert!(session.is_over());
+        assert_eq!(session.history().len(), 2 + played as usize);
+        assert_eq!(session.play_ai_move().err(), Some(MoveError::GameAlreadyOver));
+
+        // The same seed and moves rebuild the same game.
+        let resumed = resume_game_with(12, session.history(), &spawn, Variant::Classic).unwrap();
+        assert_eq!(resumed.board.get_board(), session.board().get_board());
+        assert_eq!(resumed.score, session.score());
+        let (board, score, history) = (session.board().get_board(), session.score(), session.history().to_vec());
+        drop(session);
+        assert_eq!(moves.0.len(), history.len());
+        assert_eq!(moves.0[..2], [(1, false), (2, true)]);
+
+        let mut resumed = GameSession::resume(12, resumed, history, spawn, SearchConfig::with_depth(1));
+        assert_eq!((resumed.board().get_board(), resumed.score()), (board, score));
+        if !resumed.board().is_game_over() {
+            resumed.play_ai_move().unwrap();
+            assert_eq!(resumed.history().len(), 61);
+        }
+    }
+
+    #[derive(Default)]
+    struct GameOvers(u32);
+
+    impl GameObserver for GameOvers {
+        fn on_game_over(&mut self, _board: &GameBoard) {
+            self.0 += 1;
+        }
+    }
+
+    #[test]
+    fn test_limits_count_human_moves_and_end_the_game_once() {
+        let spawn = SpawnRules::default();
+        let mut over = GameOvers::default();
+        let mut session = GameSession::new(5, spawn, SearchConfig::with_depth(1)).with_time_attack(TimeAttack::new(Budget::Moves(2)));
+        session.add_observer(&mut over);
+        for _ in 0..2 {
+            let direction = Direction::all().into_iter().find(|&d| session.board().can_move(d)).unwrap();
+            session.play_human_move(direction).unwrap();
+        }
+        assert!(session.is_over());
+        assert_eq!(session.time_attack().unwrap().moves_left(), Some(0));
+        drop(session);
+        assert_eq!(over.0, 1);
+
+        let mut over = GameOvers::default();
+        let mut session = GameSession::new(5, spawn, SearchConfig::with_depth(1)).with_max_moves(3);
+        session.add_observer(&mut over);
+        assert_eq!(session.run_to_completion(), 3);
+        drop(session);
+        assert_eq!(over.0, 1);
+    }
+}
@@ -0,0 +1,352 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::MergeBreakdown;
+
+/// Tiles whose reach rate is always reported, even when no game got there.
+pub const MILESTONE_TILES: [u32; 4] = [1024, 2048, 4096, 8192];
+/// Number of equal-width score bins in the report histogram.
+const HISTOGRAM_BINS: usize = 10;
+/// Width of the longest histogram bar in characters.
+const HISTOGRAM_WIDTH: usize = 40;
+/// Move-count percentiles in the report.
+const MOVE_PERCENTILES: [u32; 5] = [10, 25, 50, 75, 90];
+
+/// One finished game.
+#[derive(Debug, Clone, Serialize)]
+pub struct GameRecord {
+    pub seed: u64,
+    pub score: u32,
+    pub max_tile: u32,
+    pub moves: u32,
+    pub time_secs: f64,
+    /// Mean of the depth reached by each move's search.
+    pub mean_depth: f64,
+    pub max_depth: u32,
+    pub merges: MergeBreakdown,
+}
+
+/// Linear-interpolated quantile `q` (0..=1) of sorted values.
+pub fn quantile(sorted: &[u32], q: f64) -> f64 {
+    match sorted.len() {
+        0 => 0.0,
+        n => {
+            let position = q.clamp(0.0, 1.0) * (n - 1) as f64;
+            let low = position.floor() as usize;
+            let high = position.ceil() as usize;
+            let fraction = position - low as f64;
+            sorted[low] as f64 + (sorted[high] as f64 - sorted[low] as f64) * fraction
+        }
+    }
+}
+
+/// Score range `[from, to)` and how many games ended in it (the last bin
+/// also includes `to`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistogramBin {
+    pub from: u32,
+    pub to: u32,
+    pub count: usize,
+}
+
+/// Up to `bins` equal-width bins over sorted values.
+pub fn histogram(sorted: &[u32], bins: usize) -> Vec<HistogramBin> {
+    let (Some(&min), Some(&max)) = (sorted.first(), sorted.last()) else {
+        return Vec::new();
+    };
+    let width = ((max - min) / bins.max(1) as u32).max(1);
+    let mut histogram: Vec<HistogramBin> = (0..bins as u32)
+        .map(|i| HistogramBin { from: min + i * width, to: min + (i + 1) * width, count: 0 })
+        .take_while(|bin| bin.from <= max)
+        .collect();
+    if let Some(last) = histogram.last_mut() {
+        last.to = last.to.max(max);
+    }
+    for &value in sorted {
+        let index = (((value - min) / width) as usize).min(histogram.len() - 1);
+        histogram[index].count += 1;
+    }
+    histogram
+}
+
+/// Statistics over any number of games. It keeps every score and move count,
+/// so two sets merge into exactly the statistics of all their games.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GameStats {
+    /// Sorted.
+    scores: Vec<u32>,
+    /// Sorted.
+    moves: Vec<u32>,
+    /// Games ending with each max tile.
+    max_tiles: BTreeMap<u32, usize>,
+    depth_total: f64,
+    max_depth: u32,
+    time_total: f64,
+    max_time_secs: f64,
+    #[serde(default)]
+    merges: MergeBreakdown,
+}
+
+impl GameStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_records<'a>(records: impl IntoIterator<Item = &'a GameRecord>) -> Self {
+        let mut stats = Self::new();
+        for record in records {
+            stats.add(record);
+        }
+        stats
+    }
+
+    pub fn add(&mut self, record: &GameRecord) {
+        insert_sorted(&mut self.scores, record.score);
+        insert_sorted(&mut self.moves, record.moves);
+        *self.max_tiles.entry(record.max_tile).or_default() += 1;
+        self.depth_total += record.mean_depth;
+        self.max_depth = self.max_depth.max(record.max_depth);
+        self.time_total += record.time_secs;
+        self.max_time_secs = self.max_time_secs.max(record.time_secs);
+        self.merges.add(&record.merges);
+    }
+
+    /// Adds every game of `other`.
+    pub fn merge(&mut self, other: &GameStats) {
+        self.scores = merge_sorted(&self.scores, &other.scores);
+        self.moves = merge_sorted(&self.moves, &other.moves);
+        for (&tile, &count) in &other.max_tiles {
+            *self.max_tiles.entry(tile).or_default() += count;
+        }
+        self.depth_total += other.depth_total;
+        self.max_depth = self.max_depth.max(other.max_depth);
+        self.time_total += other.time_total;
+        self.max_time_secs = self.max_time_secs.max(other.max_time_secs);
+        self.merges.add(&other.merges);
+    }
+
+    pub fn games(&self) -> usize {
+        self.scores.len()
+    }
+
+    fn per_game(&self, total: f64) -> f64 {
+        total / self.games().max(1) as f64
+    }
+
+    pub fn mean_score(&self) -> f64 {
+        self.per_game(self.scores.iter().map(|&s| s as f64).sum())
+    }
+
+    /// Quantile `q` (0..=1) of the scores.
+    pub fn score_quantile(&self, q: f64) -> f64 {
+        quantile(&self.scores, q)
+    }
+
+    /// Percentile `p` (0..=100) of the move counts.
+    pub fn move_percentile(&self, p: u32) -> f64 {
+        quantile(&self.moves, p as f64 / 100.0)
+    }
+
+    pub fn mean_moves(&self) -> f64 {
+        self.per_game(self.moves.iter().map(|&m| m as f64).sum())
+    }
+
+    /// Games ending with each max tile.
+    pub fn max_tile_histogram(&self) -> &BTreeMap<u32, usize> {
+        &self.max_tiles
+    }
+
+    /// Fraction of games whose max tile reached `tile`.
+    pub fn reach_rate(&self, tile: u32) -> f64 {
+        let reached: usize = self.max_tiles.range(tile..).map(|(_, &count)| count).sum();
+        self.per_game(reached as f64)
+    }
+
+    pub fn score_histogram(&self, bins: usize) -> Vec<HistogramBin> {
+        histogram(&self.scores, bins)
+    }
+
+    /// Mean over games of each game's mean search depth.
+    pub fn mean_depth(&self) -> f64 {
+        self.per_game(self.depth_total)
+    }
+
+    pub fn max_depth(&self) -> u32 {
+        self.max_depth
+    }
+
+    pub fn mean_time_secs(&self) -> f64 {
+        self.per_game(self.time_total)
+    }
+
+    pub fn max_time_secs(&self) -> f64 {
+        self.max_time_secs
+    }
+
+    /// Merges making each tile, summed over the games.
+    pub fn merges(&self) -> &MergeBreakdown {
+        &self.merges
+    }
+
+    /// The summary figures, for JSON export and printing.
+    pub fn report(&self) -> StatsReport {
+        let best = self.max_tiles.keys().last().copied().unwrap_or(0);
+        let last_tile = best.max(MILESTONE_TILES[MILESTONE_TILES.len() - 1]);
+        StatsReport {
+            games: self.games(),
+            mean_score: self.mean_score(),
+            min_score: self.scores.first().copied().unwrap_or(0),
+            q1_score: self.score_quantile(0.25),
+            median_score: self.score_quantile(0.5),
+            q3_score: self.score_quantile(0.75),
+            max_score: self.scores.last().copied().unwrap_or(0),
+            mean_moves: self.mean_moves(),
+            move_percentiles: MOVE_PERCENTILES.iter().map(|&p| (p, self.move_percentile(p))).collect(),
+            mean_time_secs: self.mean_time_secs(),
+            max_time_secs: self.max_time_secs,
+            mean_depth: self.mean_depth(),
+            max_depth: self.max_depth,
+            reach_rates: std::iter::successors(Some(256u32), |tile| tile.checked_mul(2))
+                .take_while(|&tile| tile <= last_tile)
+                .map(|tile| (tile, self.reach_rate(tile) * 100.0))
+                .collect(),
+            max_tiles: self.max_tiles.clone(),
+            mean_merges: self.merges.iter().map(|(tile, count)| (tile, self.per_game(count as f64))).collect(),
+            histogram: self.score_histogram(HISTOGRAM_BINS),
+        }
+    }
+}
+
+fn insert_sorted(values: &mut Vec<u32>, value: u32) {
+    let index = values.partition_point(|&v| v <= value);
+    values.insert(index, value);
+}
+
+fn merge_sorted(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut merged = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] <= b[j] {
+            merged.push(a[i]);
+            i += 1;
+        } else {
+            merged.push(b[j]);
+            j += 1;
+        }
+    }
+    merged.extend_from_slice(&a[i..]);
+    merged.extend_from_slice(&b[j..]);
+    merged
+}
+
+/// The figures of a [`GameStats`] at one point. Printing it gives the
+/// text summary; serializing it gives the JSON one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsReport {
+    pub games: usize,
+    pub mean_score: f64,
+    pub min_score: u32,
+    pub q1_score: f64,
+    pub median_score: f64,
+    pub q3_score: f64,
+    pub max_score: u32,
+    pub mean_moves: f64,
+    /// Move count at each percentile.
+    #[serde(default)]
+    pub move_percentiles: BTreeMap<u32, f64>,
+    pub mean_time_secs: f64,
+    #[serde(default)]
+    pub max_time_secs: f64,
+    #[serde(default)]
+    pub mean_depth: f64,
+    #[serde(default)]
+    pub max_depth: u32,
+    /// Percentage of games reaching each tile, from 256 up to at least 8192.
+    pub reach_rates: BTreeMap<u32, f64>,
+    /// Games ending with each max tile.
+    #[serde(default)]
+    pub max_tiles: BTreeMap<u32, usize>,
+    /// Mean merges per game making each tile.
+    #[serde(default)]
+    pub mean_merges: BTreeMap<u32, f64>,
+    pub histogram: Vec<HistogramBin>,
+}
+
+impl fmt::Display for StatsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Score: mean {:.0}, median {:.0}, min {}, max {}",
+                 self.mean_score,
+                 self.median_score,
+                 self.min_score,
+                 self.max_score)?;
+        writeln!(f, "Score quartiles: Q1 {:.0}, Q2 {:.0}, Q3 {:.0}", self.q1_score, self.median_score, self.q3_score)?;
+        writeln!(f, "Moves per game: {:.0}, time per game: {:.1}s (max {:.1}s)", self.mean_moves, self.mean_time_secs, self.max_time_secs)?;
+        let percentiles: Vec<String> = self.move_percentiles.iter().map(|(p, moves)| format!("p{} {:.0}", p, moves)).collect();
+        writeln!(f, "Move percentiles: {}", percentiles.join(", "))?;
+        writeln!(f, "Search depth: mean {:.2}, max {}", self.mean_depth, self.max_depth)?;
+        writeln!(f, "Max tile reached:")?;
+        for (tile, rate) in &self.reach_rates {
+            let ended = self.max_tiles.get(tile).copied().unwrap_or(0);
+            writeln!(f, "  {:>6}: {:>5.1}%  ({} ended here)", tile, rate, ended)?;
+        }
+        if !self.mean_merges.is_empty() {
+            let merges: Vec<String> = self.mean_merges.iter().map(|(tile, count)| format!("{}: {:.1}", tile, count)).collect();
+            writeln!(f, "Merges per game: {}", merges.join(", "))?;
+        }
+        writeln!(f, "Score histogram:")?;
+        let tallest = self.histogram.iter().map(|bin| bin.count).max().unwrap_or(0).max(1);
+        for bin in &self.histogram {
+            let bar = "#".repeat(bin.count * HISTOGRAM_WIDTH / tallest);
+            writeln!(f, "  {:>6} - {:<6} {:>5} {}", bin.from, bin.to, bin.count, bar)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Direction, MergedTile, MoveResult};
+
+    fn record(score: u32, max_tile: u32, moves: u32) -> GameRecord {
+        let mut merges = MergeBreakdown::new();
+        merges.record(&MoveResult {
+            direction: Direction::Left,
+            score_gained: max_tile,
+            merged: vec![MergedTile { row: 0, col: 0, value: max_tile }],
+            spawned: None,
+        });
+        GameRecord { seed: 0, score, max_tile, moves, time_secs: 1.0, mean_depth: 2.0, max_depth: 3, merges }
+    }
+
+    #[test]
+    fn test_quantiles_interpolate() {
+        let sorted = [10, 20, 30, 40];
+        assert_eq!(quantile(&sorted, 0.0), 10.0);
+        assert_eq!(quantile(&sorted, 0.5), 25.0);
+        assert_eq!(quantile(&sorted, 0.25), 17.5);
+        assert_eq!(quantile(&sorted, 1.0), 40.0);
+        assert_eq!(quantile(&[], 0.5), 0.0);
+    }
+
+    #[test]
+    fn test_merged_stats_match_stats_of_all_games() {
+        let records = [record(1000, 512, 300), record(5000, 1024, 500), record(20000, 2048, 900), record(20000, 2048, 1000)];
+        let mut merged = GameStats::from_records(&records[..1]);
+        merged.merge(&GameStats::from_records(&records[1..]));
+        assert_eq!(merged, GameStats::from_records(records.iter().rev()));
+
+        let report = merged.report();
+        assert_eq!(report.reach_rates[&2048], 50.0);
+        assert_eq!(report.reach_rates[&8192], 0.0);
+        assert_eq!(report.max_tiles[&2048], 2);
+        assert_eq!(report.move_percentiles[&50], 700.0);
+        assert_eq!(report.histogram.iter().map(|bin| bin.count).sum::<usize>(), records.len());
+        assert_eq!(report.histogram.last().unwrap().count, 2);
+        assert_eq!((report.mean_depth, report.max_depth), (2.0, 3));
+        assert_eq!(merged.merges().count(2048), 2);
+        assert_eq!(report.mean_merges[&2048], 0.5);
+    }
+}
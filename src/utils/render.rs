@@ -0,0 +1,114 @@
+use std::fmt::Write;
+
+/// Text shown on a tile: its value, or nothing for an empty cell.
+pub fn tile_label(value: u32) -> String {
+    if value == 0 {
+        String::new()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Classic 2048 background and text colors for a tile, as RGB.
+pub fn tile_rgb(value: u32) -> ([u8; 3], [u8; 3]) {
+    let background = match value {
+        0 => [205, 193, 180],
+        2 => [238, 228, 218],
+        4 => [237, 224, 200],
+        8 => [242, 177, 121],
+        16 => [245, 149, 99],
+        32 => [246, 124, 95],
+        64 => [246, 94, 59],
+        128 => [237, 207, 114],
+        256 => [237, 204, 97],
+        512 => [237, 200, 80],
+        1024 => [237, 197, 63],
+        2048 => [237, 194, 46],
+        _ => [60, 58, 50],
+    };
+    let foreground = if value <= 4 { [119, 110, 101] } else { [249, 246, 242] };
+    (background, foreground)
+}
+
+/// Plain fixed-width grid, one row per line, empty cells shown as 0.
+pub fn ascii(board: &[[u32; 4]; 4]) -> String {
+    board
+        .iter()
+        .map(|row| format!("{:>4} {:>4} {:>4} {:>4}", row[0], row[1], row[2], row[3]))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Grid drawn with box-drawing characters; cells widen to fit the
+/// largest tile and empty cells are blank.
+pub fn unicode(board: &[[u32; 4]; 4]) -> String {
+    let width = board.iter().flatten().map(|&v| tile_label(v).len()).max().unwrap_or(0).max(4) + 2;
+    let rule = |left: char, middle: char, right: char| {
+        let segment = "─".repeat(width);
+        format!("{}{}{}", left, [segment.as_str(); 4].join(&middle.to_string()), right)
+    };
+    let mut text = rule('┌', '┬', '┐');
+    for (index, row) in board.iter().enumerate() {
+        text.push('\n');
+        text.push('│');
+        for &value in row {
+            // Writing to a String cannot fail.
+            let _ = write!(text, "{:^w$}│", tile_label(value), w = width);
+        }
+        text.push('\n');
+        text.push_str(&if index == 3 { rule('└', '┴', '┘') } else { rule('├', '┼', '┤') });
+    }
+    text
+}
+
+/// `<table>` with one styled cell per tile, for embedding in a web page.
+/// Cells carry `tile tile-<value>` classes so a stylesheet can override
+/// the inline colors.
+pub fn html(board: &[[u32; 4]; 4]) -> String {
+    let mut html = String::from("<table class=\"board\">\n");
+    for row in board {
+        html.push_str("  <tr>");
+        for &value in row {
+            let ([br, bg, bb], [fr, fg, fb]) = tile_rgb(value);
+            let _ = write!(
+                html,
+                "<td class=\"tile tile-{}\" style=\"background:#{:02x}{:02x}{:02x};color:#{:02x}{:02x}{:02x}\">{}</td>",
+                value,
+                br,
+                bg,
+                bb,
+                fr,
+                fg,
+                fb,
+                tile_label(value)
+            );
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOARD: [[u32; 4]; 4] = [[2, 0, 0, 0], [0, 16, 0, 0], [0, 0, 0, 0], [0, 0, 0, 16384]];
+
+    #[test]
+    fn test_renderers_show_every_row() {
+        assert_eq!(ascii(&BOARD).lines().next(), Some("   2    0    0    0"));
+        assert_eq!(ascii(&BOARD).lines().count(), 4);
+
+        let boxed = unicode(&BOARD);
+        let lines: Vec<&str> = boxed.lines().collect();
+        assert_eq!(lines.len(), 9);
+        assert!(lines.iter().all(|line| line.chars().count() == lines[0].chars().count()));
+        assert!(lines[7].contains("16384"));
+
+        let table = html(&BOARD);
+        assert_eq!(table.matches("<tr>").count(), 4);
+        assert_eq!(table.matches("<td").count(), 16);
+        assert!(table.contains("class=\"tile tile-16\""));
+    }
+}
@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use wasm_bindgen::prelude::*;
+
+use crate::ai::SearchConfig;
+use crate::cache::TranspositionState;
+use crate::game::{Direction, GameBoard, ParseBoardError, Variant};
+use crate::utils::render;
+
+/// Row-major cells (16 entries, 0 = empty) as a classic 2048 board.
+fn board_from_cells(cells: &[u32]) -> Result<GameBoard, JsError> {
+    if cells.len() != 16 {
+        return Err(JsError::new(&format!("expected 16 cells, found {}", cells.len())));
+    }
+    let mut board = [[0; 4]; 4];
+    for (index, &value) in cells.iter().enumerate() {
+        if value != 0 && !Variant::Classic.rules().is_tile(value) {
+            return Err(JsError::new(&ParseBoardError::InvalidTile(value).to_string()));
+        }
+        board[index / 4][index % 4] = value;
+    }
+    let mut game = GameBoard::new();
+    game.set_board(board);
+    Ok(game)
+}
+
+/// Best move on a board kept by the caller, found within `time_limit_ms`
+/// milliseconds, for front-ends that run their own game and only want
+/// hints. `undefined` when no move is possible.
+#[wasm_bindgen(js_name = bestMove)]
+pub fn best_move(cells: &[u32], time_limit_ms: u32) -> Result<Option<Direction>, JsError> {
+    let board = board_from_cells(cells)?;
+    let config = SearchConfig::with_time_limit(Duration::from_millis(time_limit_ms as u64));
+    Ok(board.search_with(&config, &mut TranspositionState::new()).best_move)
+}
+
+/// A game owned by JavaScript: the board, its spawn RNG, the running score
+/// and a private transposition table (no thread-local state is used).
+#[wasm_bindgen(js_name = GameBoard)]
+pub struct WasmGame {
+    board: GameBoard,
+    rng: StdRng,
+    tt: TranspositionState,
+    score: u32,
+}
+
+#[wasm_bindgen(js_class = GameBoard)]
+impl WasmGame {
+    /// Starts a game whose spawns are fully determined by `seed`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(seed: u64) -> WasmGame {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let board = GameBoard::new_with_rng(&mut rng);
+        WasmGame {
+            board,
+            rng,
+            tt: TranspositionState::new(),
+            score: 0,
+        }
+    }
+
+    /// Continues from `cells` (row-major, 16 entries, 0 = empty) with a
+    /// score of 0; later spawns are determined by `seed`.
+    #[wasm_bindgen(js_name = fromCells)]
+    pub fn from_cells(cells: &[u32], seed: u64) -> Result<WasmGame, JsError> {
+        Ok(WasmGame {
+            board: board_from_cells(cells)?,
+            rng: StdRng::seed_from_u64(seed),
+            tt: TranspositionState::new(),
+            score: 0,
+        })
+    }
+
+    /// Cell values in row-major order (16 entries, 0 = empty).
+    pub fn cells(&self) -> Vec<u32> {
+        self.board.get_board().iter().flatten().copied().collect()
+    }
+
+    /// The board as an HTML `<table>` with colored `tile tile-<value>` cells.
+    #[wasm_bindgen(js_name = toHtml)]
+    pub fn to_html(&self) -> String {
+        render::html(&self.board.get_board())
+    }
+
+    /// Moves and spawns a tile. Returns the score gained, or `-1` if the
+    /// move does not change the board.
+    #[wasm_bindgen(js_name = moveTiles)]
+    pub fn move_tiles(&mut self, direction: Direction) -> i32 {
+        match self.board.play_move_with(direction, &mut self.rng) {
+            Ok(result) => {
+                self.score += result.score_gained;
+                result.score_gained as i32
+            }
+            Err(_) => -1,
+        }
+    }
+
+    /// Best move found within `time_limit_ms` milliseconds.
+    #[wasm_bindgen(js_name = findBestMove)]
+    pub fn find_best_move(&mut self, time_limit_ms: u32) -> Option<Direction> {
+        let config = SearchConfig::with_time_limit(Duration::from_millis(time_limit_ms as u64));
+        self.board.search_with(&config, &mut self.tt).best_move
+    }
+
+    /// Whether moving in `direction` would change the board.
+    #[wasm_bindgen(js_name = canMove)]
+    pub fn can_move(&self, direction: Direction) -> bool {
+        self.board.can_move(direction)
+    }
+
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    #[wasm_bindgen(js_name = maxTile)]
+    pub fn max_tile(&self) -> u32 {
+        self.board.get_max_tile()
+    }
+
+    #[wasm_bindgen(js_name = isGameOver)]
+    pub fn is_game_over(&self) -> bool {
+        self.board.is_game_over()
+    }
+}
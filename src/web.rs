@@ -0,0 +1,587 @@
+//! A JSON-over-HTTP front end: the server keeps each game as a
+//! [`GameSession`] under an id, and a browser or remote client plays it
+//! through these routes:
+//!
+//! - `GET /api/variants` lists the rule sets games can be played by
+//! - `POST /api/new-game` with `{"variant": "threes", "seed": 7}` (both
+//!   optional) starts a game
+//! - `GET /api/game/{id}` is the game's state
+//! - `POST /api/game/{id}/move` with `{"direction": "left"}` plays a move
+//! - `POST /api/game/{id}/ai-move` lets the AI play one
+//!
+//! Every other answer is a [`GameState`] or `{"error": "..."}`. It runs on
+//! `std::net` alone and serves one connection at a time, which is plenty
+//! for a local UI; a client gets [`IO_TIMEOUT`] to send its whole request,
+//! however it trickles in, so a slow or idle connection holds the others up
+//! for at most that long.
+//!
+//! Pages on other sites cannot drive the server: a POST must say
+//! `Content-Type: application/json`, which a browser only sends
+//! cross-origin after a preflight this server refuses, a request whose
+//! `Origin` is another site is refused with 403, and a server on a
+//! loopback address only answers requests addressed to `localhost`, so a
+//! rebound DNS name cannot reach it either. [`WebServer::with_allowed_origin`]
+//! lets a page from another origin in.
+
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ai::SearchConfig;
+use crate::cache::maintain_cache;
+use crate::game::{Direction, MoveError, MoveResult, SpawnRules, Variant};
+use crate::session::{GameSession, DEFAULT_MAX_CACHE_ENTRIES};
+
+/// Games kept at once; starting another drops the oldest.
+pub const MAX_GAMES: usize = 256;
+
+/// Largest request body read, in bytes.
+const MAX_BODY: usize = 64 * 1024;
+
+/// Largest request line plus headers read, in bytes.
+const MAX_HEAD: u64 = 8 * 1024;
+
+/// How long a client may take to send its whole request, and to read the
+/// answer.
+pub const IO_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A game as the API reports it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GameState {
+    pub id: u64,
+    pub seed: u64,
+    pub variant: String,
+    pub board: [[u32; 4]; 4],
+    pub score: u32,
+    pub moves: usize,
+    pub max_tile: u32,
+    pub over: bool,
+    pub resigned: bool,
+    /// The move the request played, if it played one.
+    pub played: Option<Direction>,
+    /// The latest move's score gained, merged cells and spawned tile, for
+    /// animating it.
+    pub last_move: Option<MoveResult>,
+}
+
+/// A rule set as `/api/variants` lists it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VariantInfo {
+    /// What `/api/new-game` takes as `variant`.
+    pub name: String,
+    /// Cells per side; every variant plays on the 4x4 board.
+    pub board_size: usize,
+    pub win_tile: u32,
+    /// Each tile that spawns with its chance, under the server's spawn
+    /// rules.
+    pub spawns: Vec<(u32, f32)>,
+}
+
+/// An HTTP status and its JSON body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+impl Response {
+    fn json<T: Serialize>(value: &T) -> Self {
+        Self {
+            status: 200,
+            body: serde_json::to_string(value).expect("API values serialize"),
+        }
+    }
+
+    fn error(status: u16, message: impl ToString) -> Self {
+        Self {
+            status,
+            body: serde_json::json!({ "error": message.to_string() }).to_string(),
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            204 => "No Content",
+            400 => "Bad Request",
+            404 => "Not Found",
+            403 => "Forbidden",
+            405 => "Method Not Allowed",
+            408 => "Request Timeout",
+            409 => "Conflict",
+            413 => "Payload Too Large",
+            415 => "Unsupported Media Type",
+            431 => "Request Header Fields Too Large",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+/// A request as read off a connection.
+struct Request {
+    method: String,
+    path: String,
+    content_type: Option<String>,
+    origin: Option<String>,
+    host: Option<String>,
+    body: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct NewGame {
+    variant: Option<String>,
+    seed: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MoveRequest {
+    direction: String,
+}
+
+/// The games being played and the rules and AI settings new ones get.
+pub struct WebServer {
+    games: BTreeMap<u64, GameSession<'static>>,
+    next_id: u64,
+    spawn: SpawnRules,
+    search: SearchConfig,
+    max_cache_entries: usize,
+    allowed_origin: Option<String>,
+    timeout: Duration,
+}
+
+impl WebServer {
+    /// A server whose games spawn by `spawn` and whose AI searches with
+    /// `search`.
+    pub fn new(spawn: SpawnRules, search: SearchConfig) -> Self {
+        Self {
+            games: BTreeMap::new(),
+            next_id: 1,
+            spawn,
+            search,
+            max_cache_entries: DEFAULT_MAX_CACHE_ENTRIES,
+            allowed_origin: None,
+            timeout: IO_TIMEOUT,
+        }
+    }
+
+    /// Entries the AI's transposition table is kept under.
+    pub fn with_max_cache_entries(mut self, entries: usize) -> Self {
+        self.max_cache_entries = entries;
+        self
+    }
+
+    /// Lets pages from `origin` (e.g. `http://localhost:3000`, or `*` for
+    /// any page) call the API; by default only same-origin pages can.
+    pub fn with_allowed_origin(mut self, origin: impl Into<String>) -> Self {
+        self.allowed_origin = Some(origin.into());
+        self
+    }
+
+    /// How long a client may take over its request, instead of
+    /// [`IO_TIMEOUT`].
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Answers one request; `path` may carry a query string, which is
+    /// ignored.
+    pub fn handle(&mut self, method: &str, path: &str, body: &str) -> Response {
+        let path = path.split('?').next().unwrap_or_default();
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        match (method, &segments[..]) {
+            ("GET", ["api", "variants"]) => Response::json(&self.variants()),
+            (_, ["api", "variants"]) => Response::error(405, "use GET"),
+            ("POST", ["api", "new-game"]) => self.new_game(body),
+            (_, ["api", "new-game"]) => Response::error(405, "use POST"),
+            (method, ["api", "game", id, action @ ..]) => {
+                let Some((id, session)) = id.parse().ok().and_then(|id| Some((id, self.games.get_mut(&id)?))) else {
+                    return Response::error(404, format!("no game {}", id));
+                };
+                match (method, action) {
+                    ("GET", []) => Response::json(&state(id, session, None)),
+                    ("POST", ["move"]) => human_move(id, session, body),
+                    ("POST", ["ai-move"]) => {
+                        let response = ai_move(id, session);
+                        maintain_cache(session.history().len() as u32, self.max_cache_entries);
+                        response
+                    }
+                    (_, [] | ["move"] | ["ai-move"]) => Response::error(405, "method not allowed"),
+                    _ => Response::error(404, format!("no route {}", path)),
+                }
+            }
+            _ => Response::error(404, format!("no route {}", path)),
+        }
+    }
+
+    /// Serves requests from `listener` until accepting a connection fails.
+    /// A client that drops its connection or sends garbage only loses its
+    /// own request.
+    pub fn serve(&mut self, listener: &TcpListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            let _ = self.respond(stream?);
+        }
+        Ok(())
+    }
+
+    fn respond(&mut self, stream: TcpStream) -> io::Result<()> {
+        stream.set_write_timeout(Some(self.timeout))?;
+        let connection = Deadline {
+            stream: stream.try_clone()?,
+            deadline: Instant::now() + self.timeout,
+        };
+        let loopback = stream.local_addr()?.ip().is_loopback();
+        let response = match read_request(&mut BufReader::new(connection)) {
+            Ok(request) => match self.refuse_cross_site(&request, loopback) {
+                Some(refusal) => refusal,
+                // A cross-origin preflight, answered by the headers alone.
+                None if request.method == "OPTIONS" && self.allowed_origin.is_some() => Response {
+                    status: 204,
+                    body: String::new(),
+                },
+                None => self.handle(&request.method, &request.path, &request.body),
+            },
+            Err(response) => response,
+        };
+        let cors = match &self.allowed_origin {
+            Some(origin) => format!(
+                "Access-Control-Allow-Origin: {}\r\nAccess-Control-Allow-Methods: GET, POST\r\nAccess-Control-Allow-Headers: Content-Type\r\n",
+                origin
+            ),
+            None => String::new(),
+        };
+        let mut stream = stream;
+        write!(
+            stream,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n{}",
+            response.status,
+            response.reason(),
+            response.body.len(),
+            cors,
+            response.body
+        )?;
+        stream.flush()
+    }
+
+    /// The refusal for a request a page on another site could have sent;
+    /// see the module docs. `loopback` is whether the server listens on a
+    /// loopback address.
+    fn refuse_cross_site(&self, request: &Request, loopback: bool) -> Option<Response> {
+        let host = request.host.as_deref().unwrap_or_default();
+        if loopback {
+            // "localhost:8080" or "[::1]:8080", without the port.
+            let name = match host.strip_prefix('[') {
+                Some(rest) => rest.split(']').next().unwrap_or_default(),
+                None => host.split(':').next().unwrap_or_default(),
+            };
+            if !["localhost", "127.0.0.1", "::1"].contains(&name) {
+                return Some(Response::error(403, format!("this server only answers localhost, not {:?}", host)));
+            }
+        }
+        if let Some(origin) = &request.origin {
+            let allowed = self.allowed_origin.as_deref().is_some_and(|allowed| allowed == "*" || allowed == origin);
+            if !allowed && *origin != format!("http://{}", host) {
+                return Some(Response::error(403, format!("requests from {} are not allowed", origin)));
+            }
+        }
+        let json = request
+            .content_type
+            .as_deref()
+            .is_some_and(|content_type| content_type.split(';').next().unwrap_or_default().trim().eq_ignore_ascii_case("application/json"));
+        if request.method == "POST" && !json {
+            return Some(Response::error(415, "POST requests must be sent as Content-Type: application/json"));
+        }
+        None
+    }
+
+    /// Every registered variant, built-in ones first.
+    pub fn variants(&self) -> Vec<VariantInfo> {
+        Variant::registered()
+            .into_iter()
+            .map(|variant| {
+                let weights = self.spawn.weights(variant);
+                let total: u32 = weights.iter().map(|&(_, weight)| weight).sum();
+                VariantInfo {
+                    name: variant.name().to_string(),
+                    board_size: 4,
+                    win_tile: variant.rules().win_tile(),
+                    spawns: weights.iter().map(|&(tile, weight)| (tile, weight as f32 / total as f32)).collect(),
+                }
+            })
+            .collect()
+    }
+
+    fn new_game(&mut self, body: &str) -> Response {
+        let request: NewGame = if body.trim().is_empty() {
+            NewGame::default()
+        } else {
+            match serde_json::from_str(body) {
+                Ok(request) => request,
+                Err(err) => return Response::error(400, err),
+            }
+        };
+        let variant = match request.variant.as_deref().map(str::parse::<Variant>).transpose() {
+            Ok(variant) => variant.unwrap_or_default(),
+            Err(err) => return Response::error(400, err),
+        };
+        let seed = request.seed.unwrap_or_else(rand::random);
+        let session = GameSession::new_with_variant(seed, variant, self.spawn, self.search.clone());
+        let id = self.next_id;
+        self.next_id += 1;
+        if self.games.len() >= MAX_GAMES {
+            self.games.pop_first();
+        }
+        let response = Response::json(&state(id, &session, None));
+        self.games.insert(id, session);
+        response
+    }
+}
+
+/// A connection read against one deadline for the whole request: each
+/// read may only wait out what is left of it, so a client sending a byte
+/// at a time cannot keep the connection past it.
+struct Deadline {
+    stream: TcpStream,
+    deadline: Instant,
+}
+
+impl Read for Deadline {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let left = self.deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        self.stream.set_read_timeout(Some(left))?;
+        self.stream.read(buf)
+    }
+}
+
+/// Reads a request, or the error answer when it is too large, too slow or
+/// not HTTP.
+fn read_request(reader: &mut impl BufRead) -> Result<Request, Response> {
+    let failed = |err: io::Error| match err.kind() {
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => Response::error(408, "the request took too long"),
+        _ => Response::error(400, err),
+    };
+    // The request line and headers share one budget, so a client cannot
+    // grow a line without end.
+    let mut budget = MAX_HEAD;
+    let mut next_line = |reader: &mut dyn BufRead| {
+        let mut line = String::new();
+        let read = reader.take(budget).read_line(&mut line).map_err(failed)?;
+        budget -= read as u64;
+        if budget == 0 && !line.ends_with('\n') {
+            return Err(Response::error(431, format!("request headers are limited to {} bytes", MAX_HEAD)));
+        }
+        Ok(line)
+    };
+    let request_line = next_line(reader)?;
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or_default().to_string(), parts.next().unwrap_or_default().to_string());
+    let (mut length, mut content_type, mut origin, mut host) = (0, None, None, None);
+    loop {
+        let header = next_line(reader)?;
+        if header.trim().is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else { continue };
+        let value = value.trim();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "content-length" => length = value.parse().map_err(|_| Response::error(400, format!("bad Content-Length {:?}", value)))?,
+            "content-type" => content_type = Some(value.to_string()),
+            "origin" => origin = Some(value.to_string()),
+            "host" => host = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    if length > MAX_BODY {
+        return Err(Response::error(413, format!("request bodies are limited to {} bytes", MAX_BODY)));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).map_err(failed)?;
+    match String::from_utf8(body) {
+        Ok(body) => Ok(Request { method, path, content_type, origin, host, body }),
+        Err(_) => Err(Response::error(400, "the body is not UTF-8")),
+    }
+}
+
+fn state(id: u64, session: &GameSession, played: Option<Direction>) -> GameState {
+    let board = session.board();
+    GameState {
+        id,
+        seed: session.seed(),
+        variant: board.variant.name().to_string(),
+        board: board.get_board(),
+        score: session.score(),
+        moves: session.history().len(),
+        max_tile: board.get_max_tile(),
+        over: session.is_over(),
+        resigned: session.resigned(),
+        played,
+        last_move: session.last_move().cloned(),
+    }
+}
+
+fn human_move(id: u64, session: &mut GameSession, body: &str) -> Response {
+    let direction = match serde_json::from_str::<MoveRequest>(body) {
+        Ok(request) => request.direction.parse::<Direction>(),
+        Err(err) => return Response::error(400, err),
+    };
+    match direction.and_then(|direction| session.play_human_move(direction)) {
+        Ok(result) => Response::json(&state(id, session, Some(result.direction))),
+        Err(err) => move_error(err),
+    }
+}
+
+fn ai_move(id: u64, session: &mut GameSession) -> Response {
+    match session.play_ai_move() {
+        Ok(ai_move) => Response::json(&state(id, session, ai_move.result.map(|result| result.direction))),
+        Err(err) => move_error(err),
+    }
+}
+
+fn move_error(err: MoveError) -> Response {
+    match err {
+        MoveError::GameAlreadyOver => Response::error(409, err),
+        _ => Response::error(400, err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(response: &Response) -> serde_json::Value {
+        serde_json::from_str(&response.body).unwrap()
+    }
+
+    #[test]
+    fn test_games_are_played_through_the_routes() {
+        let mut server = WebServer::new(SpawnRules::default(), SearchConfig::with_depth(1));
+        let created = server.handle("POST", "/api/new-game", r#"{"seed": 5}"#);
+        assert_eq!(created.status, 200);
+        let id = body(&created)["id"].as_u64().unwrap();
+        assert_eq!(body(&created)["seed"], 5);
+
+        let fetched = server.handle("GET", &format!("/api/game/{}?fresh=1", id), "");
+        assert_eq!(fetched.body, created.body);
+
+        let ai = server.handle("POST", &format!("/api/game/{}/ai-move", id), "");
+        assert_eq!(body(&ai)["moves"], 1);
+        let played = body(&ai)["played"].as_str().unwrap().to_string();
+        assert!(played.parse::<Direction>().is_ok());
+
+        let session = &server.games[&id];
+        let direction = Direction::all().into_iter().find(|&d| session.board().can_move(d)).unwrap();
+        let moved = server.handle("POST", &format!("/api/game/{}/move", id), &format!(r#"{{"direction": "{:?}"}}"#, direction));
+        assert_eq!((moved.status, body(&moved)["moves"].as_u64()), (200, Some(2)));
+        let last = &body(&moved)["last_move"];
+        let merged: u64 = last["merged"].as_array().unwrap().iter().map(|tile| tile["value"].as_u64().unwrap()).sum();
+        assert_eq!(last["score_gained"].as_u64(), Some(merged));
+        assert_eq!(body(&moved)["score"].as_u64().unwrap() - body(&ai)["score"].as_u64().unwrap(), merged);
+        assert!(last["spawned"]["value"].as_u64().is_some());
+
+        assert_eq!(server.handle("POST", &format!("/api/game/{}/move", id), r#"{"direction": "sideways"}"#).status, 400);
+        assert_eq!(server.handle("POST", &format!("/api/game/{}/move", id), "not json").status, 400);
+        assert_eq!(server.handle("GET", "/api/game/999", "").status, 404);
+        assert_eq!(server.handle("DELETE", &format!("/api/game/{}", id), "").status, 405);
+        assert_eq!(server.handle("GET", "/nowhere", "").status, 404);
+    }
+
+    #[test]
+    fn test_games_start_with_any_listed_variant() {
+        let mut server = WebServer::new(SpawnRules::default(), SearchConfig::with_depth(1));
+        let variants = body(&server.handle("GET", "/api/variants", ""));
+        let names: Vec<&str> = variants.as_array().unwrap().iter().map(|variant| variant["name"].as_str().unwrap()).collect();
+        assert_eq!(names[..3], ["classic", "threes", "fibonacci"]);
+        assert_eq!(variants[0]["spawns"], serde_json::json!([[2, 0.9], [4, 0.1]]));
+
+        for name in names {
+            let created = body(&server.handle("POST", "/api/new-game", &format!(r#"{{"variant": "{}", "seed": 4}}"#, name)));
+            assert_eq!(created["variant"], name);
+            let id = created["id"].as_u64().unwrap();
+            let played = body(&server.handle("POST", &format!("/api/game/{}/ai-move", id), ""));
+            assert_eq!((played["variant"].as_str(), played["moves"].as_u64()), (Some(name), Some(1)));
+        }
+        let mut same = |request: &str| server.handle("POST", "/api/new-game", request).body.split_once(',').unwrap().1.to_string();
+        assert_eq!(same(r#"{"variant": "threes", "seed": 4}"#), same(r#"{"variant": "Threes", "seed": 4}"#));
+        assert_eq!(server.handle("POST", "/api/new-game", r#"{"variant": "hexagonal"}"#).status, 400);
+    }
+
+    #[test]
+    fn test_oversized_and_slow_requests_are_refused() {
+        let read = |request: &str| read_request(&mut io::Cursor::new(request.as_bytes()));
+        let request = read("POST /api/new-game HTTP/1.1\r\nContent-Length: 11\r\n\r\n{\"seed\": 3}").ok().unwrap();
+        assert_eq!((request.method.as_str(), request.path.as_str(), request.body.as_str()), ("POST", "/api/new-game", "{\"seed\": 3}"));
+        let endless = format!("GET /api/variants HTTP/1.1\r\nX-Padding: {}\r\n\r\n", "a".repeat(MAX_HEAD as usize));
+        assert_eq!(read(&endless).err().unwrap().status, 431);
+        assert_eq!(read(&format!("POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY + 1)).err().unwrap().status, 413);
+        assert_eq!(read("POST /api/new-game HTTP/1.1\r\nContent-Length: ten\r\n\r\n{}").err().unwrap().status, 400);
+        assert_eq!(read("POST /api/new-game HTTP/1.1\r\nContent-Length: -2\r\n\r\n{}").err().unwrap().status, 400);
+
+        // A client that connects and says nothing is timed out, and the
+        // answer names no other origin.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let mut server = WebServer::new(SpawnRules::default(), SearchConfig::with_depth(1)).with_timeout(Duration::from_millis(50));
+        server.respond(listener.accept().unwrap().0).unwrap();
+        let mut answer = String::new();
+        client.read_to_string(&mut answer).unwrap();
+        assert!(answer.starts_with("HTTP/1.1 408"), "{}", answer);
+        assert!(!answer.contains("Access-Control-Allow-Origin"));
+
+        // Nor can one that sends a byte well within the timeout each time.
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let drip = std::thread::spawn(move || {
+            for byte in b"GET /api/variants HTTP/1.1\r\nX-Slow: ".iter().chain([b'a'; 64].iter()) {
+                if client.write_all(&[*byte]).is_err() {
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            let mut answer = String::new();
+            let _ = client.read_to_string(&mut answer);
+            answer
+        });
+        let started = Instant::now();
+        server.respond(listener.accept().unwrap().0).unwrap();
+        assert!(started.elapsed() < Duration::from_millis(500));
+        // Closing on unread bytes may reset the connection before the
+        // client reads the 408, so only a wrong answer fails.
+        let answer = drip.join().unwrap();
+        assert!(answer.is_empty() || answer.starts_with("HTTP/1.1 408"), "{}", answer);
+    }
+
+    #[test]
+    fn test_requests_other_sites_could_send_are_refused() {
+        let request = |method: &str, content_type: Option<&str>, origin: Option<&str>, host: &str| Request {
+            method: method.to_string(),
+            path: "/api/new-game".to_string(),
+            content_type: content_type.map(str::to_string),
+            origin: origin.map(str::to_string),
+            host: Some(host.to_string()),
+            body: String::new(),
+        };
+        let status = |server: &WebServer, request: &Request| server.refuse_cross_site(request, true).map(|refusal| refusal.status);
+        let server = WebServer::new(SpawnRules::default(), SearchConfig::with_depth(1));
+        let json = Some("application/json; charset=utf-8");
+        assert_eq!(status(&server, &request("POST", json, None, "localhost:8080")), None);
+        assert_eq!(status(&server, &request("POST", json, Some("http://localhost:8080"), "localhost:8080")), None);
+        assert_eq!(status(&server, &request("GET", None, None, "[::1]:8080")), None);
+        // A no-cors form or text/plain POST, a foreign page, a rebound name.
+        assert_eq!(status(&server, &request("POST", Some("text/plain"), None, "localhost:8080")), Some(415));
+        assert_eq!(status(&server, &request("POST", None, None, "127.0.0.1:8080")), Some(415));
+        assert_eq!(status(&server, &request("POST", json, Some("https://evil.example"), "localhost:8080")), Some(403));
+        assert_eq!(status(&server, &request("GET", None, Some("http://evil.example:8080"), "evil.example:8080")), Some(403));
+        assert_eq!(server.refuse_cross_site(&request("GET", None, None, "game.example:8080"), false), None);
+
+        let server = server.with_allowed_origin("http://localhost:3000");
+        assert_eq!(status(&server, &request("POST", json, Some("http://localhost:3000"), "localhost:8080")), None);
+        assert_eq!(status(&server, &request("POST", json, Some("https://evil.example"), "localhost:8080")), Some(403));
+    }
+}